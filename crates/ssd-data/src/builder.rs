@@ -0,0 +1,508 @@
+//! Builders to construct models programmatically.
+//!
+//! ```
+//! use ssd_data::builder::{DataTypeBuilder, FunctionBuilder, ServiceBuilder, SsdModuleBuilder};
+//!
+//! let module = SsdModuleBuilder::new("example::shapes")
+//!     .import("std::error::Error")
+//!     .data_type(
+//!         "Rect",
+//!         DataTypeBuilder::new()
+//!             .property("x", "i32")
+//!             .property("points", "list of i32")
+//!             .attribute("test"),
+//!     )
+//!     .service(
+//!         "Shapes",
+//!         ServiceBuilder::new()
+//!             .depends_on("some::Logger")
+//!             .function("area", FunctionBuilder::new().argument("rect", "Rect").returns("f64")),
+//!     )
+//!     .build();
+//!
+//! assert!(module.data_types["Rect"].properties["points"].is_list());
+//! ```
+
+use crate::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
+    OrderedMap, Service, SsdModule, TypeName,
+};
+
+/// Parses types the same way as they are written in a description file,
+/// e.g. `"i32"`, `"list of u8"`, `"5 of u8"` or `"list of 4 of f32"`.
+impl From<&str> for TypeName {
+    fn from(typ: &str) -> Self {
+        let mut lists = Vec::new();
+        let mut typ = typ.trim();
+        while let Some((list, element)) = typ.split_once(" of ") {
+            match list.trim() {
+                "list" => lists.push(None),
+                count => match count.parse() {
+                    Ok(count) => lists.push(Some(count)),
+                    Err(_) => break,
+                },
+            }
+            typ = element.trim();
+        }
+        TypeName::new(Namespace::new(typ), lists, Vec::new())
+    }
+}
+
+/// An attribute without parameters, e.g. `"test"` for `#[test]`.
+impl From<&str> for Attribute {
+    fn from(name: &str) -> Self {
+        Attribute::new(Namespace::new(name), Vec::new())
+    }
+}
+
+pub struct SsdModuleBuilder {
+    module: SsdModule,
+}
+
+impl SsdModuleBuilder {
+    #[must_use]
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            module: SsdModule::new(
+                Namespace::new(namespace),
+                Vec::new(),
+                OrderedMap::new(),
+                OrderedMap::new(),
+                OrderedMap::new(),
+            ),
+        }
+    }
+
+    /// Declares the version of the module, e.g. `2.1.0`.
+    #[must_use]
+    pub fn version(mut self, version: &str) -> Self {
+        self.module.version = Some(version.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn import(self, path: &str) -> Self {
+        self.import_with(Import::new(Namespace::new(path), Vec::new()))
+    }
+
+    #[must_use]
+    pub fn import_with(mut self, import: Import) -> Self {
+        self.module.imports.push(import);
+        self
+    }
+
+    #[must_use]
+    pub fn data_type(mut self, name: &str, data_type: impl Into<DataType>) -> Self {
+        self.module
+            .data_types
+            .insert(name.to_owned(), data_type.into());
+        self
+    }
+
+    #[must_use]
+    pub fn enumeration(mut self, name: &str, en: impl Into<Enum>) -> Self {
+        self.module.enums.insert(name.to_owned(), en.into());
+        self
+    }
+
+    #[must_use]
+    pub fn service(mut self, name: &str, service: impl Into<Service>) -> Self {
+        self.module.services.insert(name.to_owned(), service.into());
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> SsdModule {
+        self.module
+    }
+}
+
+#[derive(Default)]
+pub struct DataTypeBuilder {
+    properties: OrderedMap<TypeName>,
+    attributes: Vec<Attribute>,
+    docs: Vec<String>,
+}
+
+impl DataTypeBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn property(mut self, name: &str, typ: impl Into<TypeName>) -> Self {
+        self.properties.insert(name.to_owned(), typ.into());
+        self
+    }
+
+    #[must_use]
+    pub fn attribute(mut self, attribute: impl Into<Attribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    /// Adds a line of the doc comment.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.push(doc.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn build(mut self) -> DataType {
+        DataType::new(self.properties, self.attributes).with_docs(&mut self.docs)
+    }
+}
+
+impl From<DataTypeBuilder> for DataType {
+    fn from(builder: DataTypeBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+pub struct EnumBuilder {
+    values: OrderedMap<EnumValue>,
+    attributes: Vec<Attribute>,
+    docs: Vec<String>,
+    is_flags: bool,
+}
+
+impl EnumBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value, e.g. `.value("A", 5)` or `.value("B", None)`.
+    #[must_use]
+    pub fn value(mut self, name: &str, value: impl Into<Option<i64>>) -> Self {
+        self.values
+            .insert(name.to_owned(), EnumValue::new(value.into(), Vec::new()));
+        self
+    }
+
+    #[must_use]
+    pub fn attribute(mut self, attribute: impl Into<Attribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    /// Adds a line of the doc comment.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.push(doc.to_owned());
+        self
+    }
+
+    /// Makes it a `flags` enum, values without a number become powers of two (see
+    /// [`Enum::assign_flag_values`]).
+    #[must_use]
+    pub fn flags(mut self) -> Self {
+        self.is_flags = true;
+        self
+    }
+
+    #[must_use]
+    pub fn build(mut self) -> Enum {
+        let mut en = Enum::new(self.values, self.attributes)
+            .with_docs(&mut self.docs)
+            .with_flags(self.is_flags);
+        if en.is_flags {
+            en.assign_flag_values();
+        }
+        en
+    }
+}
+
+impl From<EnumBuilder> for Enum {
+    fn from(builder: EnumBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+pub struct ServiceBuilder {
+    dependencies: Vec<Dependency>,
+    functions: OrderedMap<Function>,
+    events: OrderedMap<Event>,
+    attributes: Vec<Attribute>,
+    docs: Vec<String>,
+}
+
+impl ServiceBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn depends_on(mut self, name: &str) -> Self {
+        self.dependencies
+            .push(Dependency::new(Namespace::new(name), Vec::new()));
+        self
+    }
+
+    #[must_use]
+    pub fn function(mut self, name: &str, function: impl Into<Function>) -> Self {
+        self.functions.insert(name.to_owned(), function.into());
+        self
+    }
+
+    #[must_use]
+    pub fn event(mut self, name: &str, event: impl Into<Event>) -> Self {
+        self.events.insert(name.to_owned(), event.into());
+        self
+    }
+
+    #[must_use]
+    pub fn attribute(mut self, attribute: impl Into<Attribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    /// Adds a line of the doc comment.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.push(doc.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn build(mut self) -> Service {
+        Service::new(
+            self.dependencies,
+            self.functions,
+            self.events,
+            self.attributes,
+        )
+        .with_docs(&mut self.docs)
+    }
+}
+
+impl From<ServiceBuilder> for Service {
+    fn from(builder: ServiceBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+pub struct FunctionBuilder {
+    arguments: OrderedMap<TypeName>,
+    return_type: Option<TypeName>,
+    error_type: Option<TypeName>,
+    is_oneway: bool,
+    attributes: Vec<Attribute>,
+    comments: Vec<String>,
+    docs: Vec<String>,
+}
+
+impl FunctionBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn argument(mut self, name: &str, typ: impl Into<TypeName>) -> Self {
+        self.arguments.insert(name.to_owned(), typ.into());
+        self
+    }
+
+    #[must_use]
+    pub fn returns(mut self, typ: impl Into<TypeName>) -> Self {
+        self.return_type = Some(typ.into());
+        self
+    }
+
+    /// Returns `result of ok, error`.
+    #[must_use]
+    pub fn returns_result(mut self, ok: impl Into<TypeName>, error: impl Into<TypeName>) -> Self {
+        self.return_type = Some(ok.into());
+        self.error_type = Some(error.into());
+        self
+    }
+
+    /// Makes the function `oneway` (a notification without a response).
+    #[must_use]
+    pub fn oneway(mut self) -> Self {
+        self.is_oneway = true;
+        self
+    }
+
+    #[must_use]
+    pub fn attribute(mut self, attribute: impl Into<Attribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    #[must_use]
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comments.push(comment.to_owned());
+        self
+    }
+
+    /// Adds a line of the doc comment.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.push(doc.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn build(mut self) -> Function {
+        Function::new(self.arguments, self.return_type, self.attributes)
+            .with_error_type(self.error_type)
+            .with_oneway(self.is_oneway)
+            .with_comments(&mut self.comments)
+            .with_docs(&mut self.docs)
+    }
+}
+
+impl From<FunctionBuilder> for Function {
+    fn from(builder: FunctionBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+pub struct EventBuilder {
+    arguments: OrderedMap<TypeName>,
+    attributes: Vec<Attribute>,
+    comments: Vec<String>,
+    docs: Vec<String>,
+}
+
+impl EventBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn argument(mut self, name: &str, typ: impl Into<TypeName>) -> Self {
+        self.arguments.insert(name.to_owned(), typ.into());
+        self
+    }
+
+    #[must_use]
+    pub fn attribute(mut self, attribute: impl Into<Attribute>) -> Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    #[must_use]
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comments.push(comment.to_owned());
+        self
+    }
+
+    /// Adds a line of the doc comment.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.push(doc.to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn build(mut self) -> Event {
+        Event::new(self.arguments, self.attributes)
+            .with_comments(&mut self.comments)
+            .with_docs(&mut self.docs)
+    }
+}
+
+impl From<EventBuilder> for Event {
+    fn from(builder: EventBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[test]
+fn test_type_names() {
+    let typ = TypeName::from(" list of 4 of some::Vec3 ");
+    assert_eq!(typ.typ.to_string(), "some::Vec3");
+    assert_eq!(typ.lists, [None, Some(4)]);
+    assert!(TypeName::from("u8").lists.is_empty());
+    // only `list` and counts make a list
+    let typ = TypeName::from("result of u8");
+    assert_eq!(typ.typ.to_string(), "result of u8");
+    assert!(typ.lists.is_empty());
+}
+
+#[test]
+fn test_builders() {
+    let module = SsdModuleBuilder::new("app::files")
+        .version("1.2.0")
+        .data_type(
+            "File",
+            DataTypeBuilder::new()
+                .doc("A file")
+                .property("name", "string"),
+        )
+        .enumeration(
+            "Mode",
+            EnumBuilder::new()
+                .flags()
+                .value("None", 0)
+                .value("Read", None)
+                .value("Write", None)
+                .value("All", 3)
+                .value("Execute", None),
+        )
+        .enumeration("Kind", EnumBuilder::new().value("Text", None))
+        .service(
+            "Files",
+            ServiceBuilder::new()
+                .attribute("http")
+                .function(
+                    "open",
+                    FunctionBuilder::new()
+                        .argument("path", "string")
+                        .returns_result("File", "Error"),
+                )
+                .function(
+                    "touch",
+                    FunctionBuilder::new().oneway().comment("fire and forget"),
+                )
+                .event("changed", EventBuilder::new().argument("file", "File")),
+        )
+        .build();
+
+    assert_eq!(module.namespace.to_string(), "app::files");
+    assert_eq!(module.version.as_deref(), Some("1.2.0"));
+    assert_eq!(module.data_types["File"].docs, ["A file"]);
+    let mode = &module.enums["Mode"];
+    assert!(mode.is_flags);
+    let values = mode
+        .values
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [
+            ("None", Some(0)),
+            ("Read", Some(1)),
+            ("Write", Some(2)),
+            ("All", Some(3)),
+            ("Execute", Some(4)),
+        ]
+    );
+    // values of normal enums stay without a number
+    assert!(module.enums["Kind"].values["Text"].value.is_none());
+
+    let files = &module.services["Files"];
+    assert_eq!(files.attributes[0].name.to_string(), "http");
+    let open = &files.functions["open"];
+    assert_eq!(open.return_type.as_ref().unwrap().typ.to_string(), "File");
+    assert_eq!(open.error_type.as_ref().unwrap().typ.to_string(), "Error");
+    let touch = &files.functions["touch"];
+    assert!(touch.is_oneway && touch.return_type.is_none());
+    assert_eq!(touch.comments, ["fire and forget"]);
+    assert_eq!(
+        files.events["changed"].arguments["file"].typ.to_string(),
+        "File"
+    );
+}
@@ -1,46 +1,103 @@
+// the constructors generated by pyo3 0.20 for `#[new]` trigger this lint
+#![cfg_attr(feature = "_python", allow(non_local_definitions))]
+
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "_python")]
 use pyo3::prelude::*;
 
-#[cfg(feature = "_access_functions")]
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use std::collections::BTreeMap;
 
-use std::collections::HashMap;
+pub mod builder;
+pub mod lookup;
+pub mod merge;
+pub mod ordered_map;
 #[cfg(feature = "_access_functions")]
-use std::io::Write;
-
-pub type OrderedMap<T> = Vec<(String, T)>;
+pub mod shared;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod visit;
+
+pub use lookup::{sort_data_types_topologically, TypeCycle, TypeRef};
+pub use merge::{MergeConflict, MergeError};
+pub use ordered_map::OrderedMap;
+#[cfg(feature = "_access_functions")]
+pub use shared::Shared;
+pub use visit::{Visitor, VisitorMut};
+
+/// Values passed to generators with `-D` or `--define-file`, which can be nested.
+///
+/// Sorted by name, so generators iterate them in the same order on every run.
+pub type Defines = BTreeMap<String, serde_value::Value>;
+
+/// Information about the generator run, passed to generators as `meta` (e.g. for "generated by"
+/// headers).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Meta {
+    /// The version of ssd.
+    pub tool_version: String,
+    /// The input file as given on the command line.
+    pub source: String,
+    /// The namespace of the module, empty for raw data.
+    pub namespace: String,
+    /// The arguments ssd was called with, without the program name.
+    pub arguments: Vec<String>,
+    /// The time of the run in UTC (RFC 3339), only set with `--timestamp` so the output stays
+    /// reproducible by default.
+    pub timestamp: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawModel {
     pub raw: serde_value::Value,
-    pub defines: HashMap<String, String>,
+    pub defines: Defines,
+    #[serde(default)]
+    pub meta: Meta,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SsdModel {
     pub module: SsdModule,
-    pub defines: HashMap<String, String>,
+    pub defines: Defines,
+    #[serde(default)]
+    pub meta: Meta,
+    /// The elements of the description file in their original order, including comments
+    /// (`--with-ast`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ast: Option<serde_value::Value>,
+}
+
+/// A finding of a lint rule (`ssd check --lint`), as returned by the `lint` export of wasm
+/// plugins or reported by rhai scripts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LintDiagnostic {
+    /// `warn` or `deny`, which fails the check.
+    pub level: String,
+    /// The element the finding is about, e.g. `Orders.get`, or empty for the whole module.
+    #[serde(default)]
+    pub span: String,
+    pub message: String,
 }
 
 #[cfg(feature = "_python")]
 macro_rules! Struct {
-    ($name: ident, $($prop_name: ident : $typ: ty),+) => {
+    ($(#[$struct_attr: meta])* $name: ident, $($(#[$attr: meta])* $prop_name: ident : $typ: ty),+) => {
         #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+        $(#[$struct_attr])*
         #[pyclass]
         pub struct $name {
-            $(#[pyo3(get)] pub $prop_name: $typ),+
+            $($(#[$attr])* #[pyo3(get, set)] pub $prop_name: $typ),+
         }
     };
 }
 
 #[cfg(not(feature = "_python"))]
 macro_rules! Struct {
-    ($name: ident, $($prop_name: ident : $typ: ty),+) => {
+    ($(#[$struct_attr: meta])* $name: ident, $($(#[$attr: meta])* $prop_name: ident : $typ: ty),+) => {
         #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+        $(#[$struct_attr])*
         pub struct $name {
-            $(pub $prop_name: $typ),+
+            $($(#[$attr])* pub $prop_name: $typ),+
         }
     };
 }
@@ -50,12 +107,28 @@ Struct!(SsdModule,
     imports: Vec<Import>,
     data_types: OrderedMap<DataType>,
     enums: OrderedMap<Enum>,
-    services: OrderedMap<Service>
+    services: OrderedMap<Service>,
+    /// The version declared with `version "2.1.0";`.
+    #[serde(default)]
+    version: Option<String>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl SsdModule {
+    #[new]
+    #[pyo3(signature = (namespace, imports = Vec::new(), data_types = OrderedMap::new(), enums = OrderedMap::new(), services = OrderedMap::new(), version = None))]
+    fn py_new(
+        namespace: Namespace,
+        imports: Vec<Import>,
+        data_types: OrderedMap<DataType>,
+        enums: OrderedMap<Enum>,
+        services: OrderedMap<Service>,
+        version: Option<String>,
+    ) -> Self {
+        Self::new(namespace, imports, data_types, enums, services).with_version(version)
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -76,41 +149,43 @@ impl SsdModule {
             data_types,
             enums,
             services,
+            version: None,
         }
     }
-}
-
-#[cfg(feature = "_access_functions")]
-impl SsdModule {
-    pub fn namespace(&mut self) -> Namespace {
-        self.namespace.clone()
-    }
 
-    pub fn imports(&mut self) -> Vec<Import> {
-        self.imports.clone()
-    }
-
-    pub fn data_types(&mut self) -> OrderedMap<DataType> {
-        self.data_types.clone()
-    }
-
-    pub fn enums(&mut self) -> OrderedMap<Enum> {
-        self.enums.clone()
-    }
-
-    pub fn services(&mut self) -> OrderedMap<Service> {
-        self.services.clone()
+    #[must_use]
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version;
+        self
     }
 }
 
 Struct!(Import,
     path: Namespace,
-    attributes: Vec<Attribute>
+    attributes: Vec<Attribute>,
+    alias: Option<String>,
+    items: Option<Vec<String>>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Import {
+    #[new]
+    #[pyo3(signature = (path, attributes = Vec::new(), alias = None, items = None))]
+    fn py_new(
+        path: Namespace,
+        attributes: Vec<Attribute>,
+        alias: Option<String>,
+        items: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            path,
+            attributes,
+            alias,
+            items,
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -119,7 +194,26 @@ impl Import {
 impl Import {
     #[must_use]
     pub fn new(path: Namespace, attributes: Vec<Attribute>) -> Self {
-        Import { path, attributes }
+        Import {
+            path,
+            attributes,
+            alias: None,
+            items: None,
+        }
+    }
+
+    /// Imports the path under another name, e.g. `import foo::bar as fb;`.
+    #[must_use]
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Imports only the given names from the path, e.g. `import foo::{User, Role};`.
+    #[must_use]
+    pub fn with_items(mut self, items: Vec<String>) -> Self {
+        self.items = Some(items);
+        self
     }
 }
 
@@ -132,17 +226,45 @@ impl Import {
     pub fn attributes(&mut self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    pub fn alias(&mut self) -> Option<String> {
+        self.alias.clone()
+    }
+
+    pub fn items(&mut self) -> Option<Vec<String>> {
+        self.items.clone()
+    }
 }
 
 Struct!(Dependency,
     name: Namespace,
     attributes: Vec<Attribute>,
-    comments: Vec<String>
+    /// Free comments (`//` and `/* */`) before the element.
+    comments: Vec<String>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Dependency {
+    #[new]
+    #[pyo3(signature = (name, attributes = Vec::new(), comments = Vec::new(), docs = Vec::new()))]
+    fn py_new(
+        name: Namespace,
+        attributes: Vec<Attribute>,
+        comments: Vec<String>,
+        docs: Vec<String>,
+    ) -> Self {
+        Self {
+            name,
+            attributes,
+            comments,
+            docs,
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -155,6 +277,7 @@ impl Dependency {
             name,
             attributes,
             comments: Vec::new(),
+            docs: Vec::new(),
         }
     }
 
@@ -162,6 +285,11 @@ impl Dependency {
         self.comments.append(comments);
         self
     }
+
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
+    }
 }
 
 #[cfg(feature = "_access_functions")]
@@ -173,6 +301,10 @@ impl Dependency {
     pub fn attributes(&mut self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    pub fn docs(&mut self) -> Vec<String> {
+        self.docs.clone()
+    }
 }
 
 Struct!(Parameter,
@@ -183,6 +315,12 @@ Struct!(Parameter,
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Parameter {
+    #[new]
+    #[pyo3(signature = (name, value = None))]
+    fn py_new(name: String, value: Option<String>) -> Self {
+        Self { name, value }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -207,6 +345,12 @@ Struct!(Attribute,
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Attribute {
+    #[new]
+    #[pyo3(signature = (name, parameters = Vec::new()))]
+    fn py_new(name: Namespace, parameters: Vec<Parameter>) -> Self {
+        Self { name, parameters }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -223,6 +367,18 @@ impl Attribute {
                 .collect(),
         }
     }
+
+    /// Returns the parameter with the given name, if it exists.
+    #[must_use]
+    pub fn find_parameter(&self, name: &str) -> Option<&Parameter> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+}
+
+/// Returns the attribute with the given name (e.g. `"test"` or `"some::attr"`), if it exists.
+#[must_use]
+pub fn find_attribute<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attributes.iter().find(|a| a.name.to_string() == name)
 }
 
 #[cfg(feature = "_access_functions")]
@@ -236,14 +392,57 @@ impl Attribute {
     }
 }
 
+/// Whether a data type, enum or service is part of the published API (`pub`) or a helper
+/// (`internal`). Declarations without a marker count as published.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "_python", pyclass)]
+pub enum Visibility {
+    #[serde(rename = "pub")]
+    Public,
+    #[serde(rename = "internal")]
+    Internal,
+}
+
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Public => write!(f, "pub"),
+            Visibility::Internal => write!(f, "internal"),
+        }
+    }
+}
+
+/// Returns the marker of the visibility as written in descriptions, or an empty string.
+#[cfg(feature = "_access_functions")]
+fn visibility_marker(visibility: Option<Visibility>) -> String {
+    visibility.map(|v| v.to_string()).unwrap_or_default()
+}
+
 Struct!(DataType,
     properties: OrderedMap<TypeName>,
-    attributes: Vec<Attribute>
+    attributes: Vec<Attribute>,
+    visibility: Option<Visibility>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl DataType {
+    #[new]
+    #[pyo3(signature = (properties = OrderedMap::new(), attributes = Vec::new(), visibility = None, docs = Vec::new()))]
+    fn py_new(
+        properties: OrderedMap<TypeName>,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+        mut docs: Vec<String>,
+    ) -> Self {
+        Self::new(properties, attributes)
+            .with_visibility(visibility)
+            .with_docs(&mut docs)
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -255,29 +454,54 @@ impl DataType {
         Self {
             properties,
             attributes,
+            visibility: None,
+            docs: Vec::new(),
         }
     }
-}
 
-#[cfg(feature = "_access_functions")]
-impl DataType {
-    pub fn properties(&mut self) -> OrderedMap<TypeName> {
-        self.properties.clone()
+    #[must_use]
+    pub fn with_visibility(mut self, visibility: Option<Visibility>) -> Self {
+        self.visibility = visibility;
+        self
     }
 
-    pub fn attributes(&mut self) -> Vec<Attribute> {
-        self.attributes.clone()
+    #[must_use]
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
     }
 }
 
 Struct!(Enum,
     values: OrderedMap<EnumValue>,
-    attributes: Vec<Attribute>
+    attributes: Vec<Attribute>,
+    visibility: Option<Visibility>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>,
+    /// Declared with `flags`: the values are bits, which can be combined.
+    #[serde(default)]
+    is_flags: bool
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Enum {
+    #[new]
+    #[pyo3(signature = (values = OrderedMap::new(), attributes = Vec::new(), visibility = None, docs = Vec::new(), is_flags = false))]
+    fn py_new(
+        values: OrderedMap<EnumValue>,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+        mut docs: Vec<String>,
+        is_flags: bool,
+    ) -> Self {
+        Self::new(values, attributes)
+            .with_visibility(visibility)
+            .with_docs(&mut docs)
+            .with_flags(is_flags)
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -286,18 +510,60 @@ impl Enum {
 impl Enum {
     #[must_use]
     pub fn new(values: OrderedMap<EnumValue>, attributes: Vec<Attribute>) -> Self {
-        Self { values, attributes }
+        Self {
+            values,
+            attributes,
+            visibility: None,
+            docs: Vec::new(),
+            is_flags: false,
+        }
     }
-}
 
-#[cfg(feature = "_access_functions")]
-impl Enum {
-    pub fn values(&mut self) -> OrderedMap<EnumValue> {
-        self.values.clone()
+    #[must_use]
+    pub fn with_visibility(mut self, visibility: Option<Visibility>) -> Self {
+        self.visibility = visibility;
+        self
     }
 
-    pub fn attributes(&mut self) -> Vec<Attribute> {
-        self.attributes.clone()
+    #[must_use]
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
+    }
+
+    #[must_use]
+    pub fn with_flags(mut self, is_flags: bool) -> Self {
+        self.is_flags = is_flags;
+        self
+    }
+
+    /// Gives the values without a number the bit after the highest bit of the values before them,
+    /// starting with 1: `None = 0, Read, Write, All = 3, Execute` becomes
+    /// `None = 0, Read = 1, Write = 2, All = 3, Execute = 4`.
+    ///
+    /// Values after a negative value or the highest bit keep having no number.
+    pub fn assign_flag_values(&mut self) {
+        let mut used = 0i64;
+        for (_, value) in &mut self.values {
+            if value.value.is_none() {
+                value.value = 1i64
+                    .checked_shl(64 - used.leading_zeros())
+                    .filter(|bit| *bit > 0);
+            }
+            used |= value.value.unwrap_or(-1);
+        }
+    }
+
+    /// Returns whether the number is a combination of the values of the enum (as flags), e.g.
+    /// 5 for `Read = 1, Write = 2, Execute = 4`.
+    #[must_use]
+    pub fn is_flag_combination(&self, number: i64) -> bool {
+        let all = self
+            .values
+            .iter()
+            .filter_map(|(_, value)| value.value)
+            .fold(0, |all, value| all | value);
+        number >= 0 && number & !all == 0
     }
 }
 
@@ -305,12 +571,31 @@ Struct!(Service,
     dependencies: Vec<Dependency>,
     functions: OrderedMap<Function>,
     events: OrderedMap<Event>,
-    attributes: Vec<Attribute>
+    attributes: Vec<Attribute>,
+    visibility: Option<Visibility>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Service {
+    #[new]
+    #[pyo3(signature = (dependencies = Vec::new(), functions = OrderedMap::new(), events = OrderedMap::new(), attributes = Vec::new(), visibility = None, docs = Vec::new()))]
+    fn py_new(
+        dependencies: Vec<Dependency>,
+        functions: OrderedMap<Function>,
+        events: OrderedMap<Event>,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+        mut docs: Vec<String>,
+    ) -> Self {
+        Self::new(dependencies, functions, events, attributes)
+            .with_visibility(visibility)
+            .with_docs(&mut docs)
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -329,55 +614,67 @@ impl Service {
             functions,
             events,
             attributes,
+            visibility: None,
+            docs: Vec::new(),
         }
     }
-}
-
-#[cfg(feature = "_access_functions")]
-impl Service {
-    pub fn dependencies(&mut self) -> Vec<Dependency> {
-        self.dependencies.clone()
-    }
 
-    pub fn functions(&mut self) -> OrderedMap<Function> {
-        self.functions.clone()
-    }
-
-    pub fn handlers(&mut self) -> OrderedMap<Function> {
-        const DEPRECATED: &str =  "Using the property 'handlers' is deprecated and will be removed in future versions. Use 'functions' instead.";
-        let mut stderr = StandardStream::stderr(ColorChoice::Always);
-        if stderr
-            .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
-            .is_ok()
-        {
-            writeln!(&mut stderr, "{}", DEPRECATED).unwrap();
-
-            let _ = stderr.set_color(&ColorSpec::default());
-        } else {
-            eprintln!("{}", DEPRECATED);
-        }
-        self.functions()
-    }
-
-    pub fn events(&mut self) -> OrderedMap<Event> {
-        self.events.clone()
+    #[must_use]
+    pub fn with_visibility(mut self, visibility: Option<Visibility>) -> Self {
+        self.visibility = visibility;
+        self
     }
 
-    pub fn attributes(&mut self) -> Vec<Attribute> {
-        self.attributes.clone()
+    #[must_use]
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
     }
 }
 
 Struct!(Function,
     arguments: OrderedMap<TypeName>,
+    /// The returned type, for `-> result of T, E` the ok type `T`.
     return_type: Option<TypeName>,
     attributes: Vec<Attribute>,
-    comments: Vec<String>
+    /// Free comments (`//` and `/* */`) before the element.
+    comments: Vec<String>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>,
+    /// The error type `E` of `-> result of T, E`.
+    #[serde(default)]
+    error_type: Option<TypeName>,
+    /// Declared with `oneway fn`: a notification without a response.
+    #[serde(default)]
+    is_oneway: bool
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Function {
+    #[new]
+    #[pyo3(signature = (arguments = OrderedMap::new(), return_type = None, attributes = Vec::new(), comments = Vec::new(), docs = Vec::new(), error_type = None, is_oneway = false))]
+    fn py_new(
+        arguments: OrderedMap<TypeName>,
+        return_type: Option<TypeName>,
+        attributes: Vec<Attribute>,
+        comments: Vec<String>,
+        docs: Vec<String>,
+        error_type: Option<TypeName>,
+        is_oneway: bool,
+    ) -> Self {
+        Self {
+            arguments,
+            return_type,
+            attributes,
+            comments,
+            docs,
+            error_type,
+            is_oneway,
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -395,6 +692,9 @@ impl Function {
             return_type,
             attributes,
             comments: Vec::new(),
+            docs: Vec::new(),
+            error_type: None,
+            is_oneway: false,
         }
     }
 
@@ -402,32 +702,55 @@ impl Function {
         self.comments.append(comments);
         self
     }
-}
 
-#[cfg(feature = "_access_functions")]
-impl Function {
-    pub fn arguments(&mut self) -> OrderedMap<TypeName> {
-        self.arguments.clone()
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
     }
 
-    pub fn return_type(&mut self) -> Option<TypeName> {
-        self.return_type.clone()
+    /// Sets the error type of a `-> result of T, E` function.
+    #[must_use]
+    pub fn with_error_type(mut self, error_type: Option<TypeName>) -> Self {
+        self.error_type = error_type;
+        self
     }
 
-    pub fn attributes(&mut self) -> Vec<Attribute> {
-        self.attributes.clone()
+    #[must_use]
+    pub fn with_oneway(mut self, is_oneway: bool) -> Self {
+        self.is_oneway = is_oneway;
+        self
     }
 }
 
 Struct!(Event,
     arguments: OrderedMap<TypeName>,
     attributes: Vec<Attribute>,
-    comments: Vec<String>
+    /// Free comments (`//` and `/* */`) before the element.
+    comments: Vec<String>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Event {
+    #[new]
+    #[pyo3(signature = (arguments = OrderedMap::new(), attributes = Vec::new(), comments = Vec::new(), docs = Vec::new()))]
+    fn py_new(
+        arguments: OrderedMap<TypeName>,
+        attributes: Vec<Attribute>,
+        comments: Vec<String>,
+        docs: Vec<String>,
+    ) -> Self {
+        Self {
+            arguments,
+            attributes,
+            comments,
+            docs,
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -440,6 +763,7 @@ impl Event {
             arguments,
             attributes,
             comments: Vec::new(),
+            docs: Vec::new(),
         }
     }
 
@@ -447,30 +771,104 @@ impl Event {
         self.comments.append(comments);
         self
     }
-}
-
-#[cfg(feature = "_access_functions")]
-impl Event {
-    pub fn arguments(&mut self) -> OrderedMap<TypeName> {
-        self.arguments.clone()
-    }
 
-    pub fn attributes(&mut self) -> Vec<Attribute> {
-        self.attributes.clone()
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
     }
 }
 
-Struct!(TypeName,
+/// The types which can be used without being declared, e.g. in properties or arguments.
+/// Generators are expected to know them, usually through their typemap.
+pub const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128", "f32", "f64",
+    "char", "string", "String", "bytes", "uuid", "datetime", "date", "time", "duration",
+];
+
+Struct!(
+    /// Serialized with `is_list` and the deprecated `count` of the outermost list as well.
+    #[serde(into = "TypeNameJson")]
+    TypeName,
+    typ: Namespace,
+    /// The lists around the type, outermost first: `None` for `list of` and the count for
+    /// `4 of`. `list of 4 of f32` has `[None, Some(4)]`, a plain type none.
+    lists: Vec<Option<usize>>,
+    attributes: Vec<Attribute>,
+    /// Free comments (`//` and `/* */`) before the element.
+    comments: Vec<String>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>,
+    /// The properties of an inline data type like `pos: { x: f32, y: f32, }`. Only the raw
+    /// elements have them, parsing lifts inline data types into named ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline: Option<OrderedMap<TypeName>>
+);
+
+/// The serialized form of [`TypeName`], which keeps the fields types had before nested lists, so
+/// templates and scripts written for them keep working.
+#[derive(Serialize)]
+struct TypeNameJson {
     typ: Namespace,
+    lists: Vec<Option<usize>>,
+    /// Whether `lists` isn't empty.
     is_list: bool,
+    /// Deprecated, the count of the outermost list.
     count: Option<usize>,
     attributes: Vec<Attribute>,
-    comments: Vec<String>
-);
+    comments: Vec<String>,
+    docs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inline: Option<OrderedMap<TypeName>>,
+}
+
+impl From<TypeName> for TypeNameJson {
+    fn from(type_name: TypeName) -> Self {
+        Self {
+            is_list: type_name.is_list(),
+            count: type_name.lists.first().copied().flatten(),
+            typ: type_name.typ,
+            lists: type_name.lists,
+            attributes: type_name.attributes,
+            comments: type_name.comments,
+            docs: type_name.docs,
+            inline: type_name.inline,
+        }
+    }
+}
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl TypeName {
+    #[new]
+    #[pyo3(signature = (typ, lists = Vec::new(), attributes = Vec::new(), comments = Vec::new(), docs = Vec::new()))]
+    fn py_new(
+        typ: Namespace,
+        lists: Vec<Option<usize>>,
+        attributes: Vec<Attribute>,
+        comments: Vec<String>,
+        docs: Vec<String>,
+    ) -> Self {
+        Self {
+            typ,
+            lists,
+            attributes,
+            comments,
+            docs,
+            inline: None,
+        }
+    }
+
+    #[pyo3(name = "is_primitive")]
+    fn py_is_primitive(&self) -> bool {
+        self.is_primitive()
+    }
+
+    #[pyo3(name = "is_list")]
+    fn py_is_list(&self) -> bool {
+        self.is_list()
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -478,18 +876,14 @@ impl TypeName {
 
 impl TypeName {
     #[must_use]
-    pub fn new(
-        typ: Namespace,
-        is_list: bool,
-        count: Option<usize>,
-        attributes: Vec<Attribute>,
-    ) -> Self {
+    pub fn new(typ: Namespace, lists: Vec<Option<usize>>, attributes: Vec<Attribute>) -> Self {
         Self {
             typ,
-            is_list,
-            count,
+            lists,
             attributes,
             comments: Vec::new(),
+            docs: Vec::new(),
+            inline: None,
         }
     }
 
@@ -497,6 +891,37 @@ impl TypeName {
         self.comments.append(comments);
         self
     }
+
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
+    }
+
+    #[must_use]
+    pub fn with_inline(mut self, properties: OrderedMap<TypeName>) -> Self {
+        self.inline = Some(properties);
+        self
+    }
+
+    /// Whether the type is one of the [`PRIMITIVE_TYPES`] (regardless of being a list).
+    #[must_use]
+    pub fn is_primitive(&self) -> bool {
+        matches!(self.typ.components.as_slice(), [name] if PRIMITIVE_TYPES.contains(&name.as_str()))
+    }
+
+    /// Whether the type is a list or an array (of any depth).
+    #[must_use]
+    pub fn is_list(&self) -> bool {
+        !self.lists.is_empty()
+    }
+
+    /// The type of the elements of the outermost list, e.g. `4 of f32` for `list of 4 of f32`,
+    /// or `None` if the type isn't a list.
+    #[must_use]
+    pub fn element(&self) -> Option<TypeName> {
+        let (_, inner) = self.lists.split_first()?;
+        Some(TypeName::new(self.typ.clone(), inner.to_vec(), Vec::new()))
+    }
 }
 
 #[cfg(feature = "_access_functions")]
@@ -505,28 +930,94 @@ impl TypeName {
         self.typ.clone()
     }
 
-    pub fn is_list(&mut self) -> bool {
-        self.is_list
+    pub fn lists(&mut self) -> Vec<Option<usize>> {
+        self.lists.clone()
     }
 
+    /// The count of the outermost list, only kept for generators written before nested lists.
     pub fn count(&mut self) -> Option<usize> {
-        self.count
+        shared::warn_deprecated("count", "lists");
+        self.lists.first().copied().flatten()
     }
 
     pub fn attributes(&mut self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    pub fn docs(&mut self) -> Vec<String> {
+        self.docs.clone()
+    }
+}
+
+/// How an enum value is written in the description, e.g. `255`, `0xFF` or `0b11111111`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "_python", pyclass)]
+#[serde(rename_all = "lowercase")]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hex,
+    Binary,
+}
+
+impl Radix {
+    /// Formats the value in this radix, e.g. `-0x1F` for -31 in [`Radix::Hex`].
+    #[must_use]
+    pub fn format(self, value: i64) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        match self {
+            Radix::Decimal => value.to_string(),
+            Radix::Hex => format!("{sign}0x{magnitude:X}"),
+            Radix::Binary => format!("{sign}0b{magnitude:b}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Radix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Radix::Decimal => write!(f, "decimal"),
+            Radix::Hex => write!(f, "hex"),
+            Radix::Binary => write!(f, "binary"),
+        }
+    }
 }
 
 Struct!(EnumValue,
     value: Option<i64>,
     attributes: Vec<Attribute>,
-    comments: Vec<String>
+    /// Free comments (`//` and `/* */`) before the element.
+    comments: Vec<String>,
+    /// Doc comments (`///`) of the element.
+    #[serde(default)]
+    docs: Vec<String>,
+    /// How the value is written, for generators which keep the representation.
+    #[serde(default)]
+    radix: Radix
 );
 
 #[cfg(feature = "_python")]
 #[pymethods]
 impl EnumValue {
+    #[new]
+    #[pyo3(signature = (value = None, attributes = Vec::new(), comments = Vec::new(), docs = Vec::new(), radix = Radix::Decimal))]
+    fn py_new(
+        value: Option<i64>,
+        attributes: Vec<Attribute>,
+        comments: Vec<String>,
+        docs: Vec<String>,
+        radix: Radix,
+    ) -> Self {
+        Self {
+            value,
+            attributes,
+            comments,
+            docs,
+            radix,
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -539,13 +1030,32 @@ impl EnumValue {
             value,
             attributes,
             comments: Vec::new(),
+            docs: Vec::new(),
+            radix: Radix::Decimal,
         }
     }
 
+    #[must_use]
+    pub fn with_radix(mut self, radix: Radix) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    /// The value as written in the description (in its [`Radix`]), if there is one.
+    #[must_use]
+    pub fn formatted_value(&self) -> Option<String> {
+        self.value.map(|value| self.radix.format(value))
+    }
+
     pub fn with_comments(mut self, comments: &mut Vec<String>) -> Self {
         self.comments.append(comments);
         self
     }
+
+    pub fn with_docs(mut self, docs: &mut Vec<String>) -> Self {
+        self.docs.append(docs);
+        self
+    }
 }
 
 #[cfg(feature = "_access_functions")]
@@ -557,6 +1067,18 @@ impl EnumValue {
     pub fn attributes(&mut self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    pub fn docs(&mut self) -> Vec<String> {
+        self.docs.clone()
+    }
+
+    pub fn radix(&mut self) -> String {
+        self.radix.to_string()
+    }
+
+    pub fn formatted(&mut self) -> Option<String> {
+        self.formatted_value()
+    }
 }
 
 Struct!(Namespace,
@@ -576,6 +1098,12 @@ impl IntoIterator for Namespace {
 #[cfg(feature = "_python")]
 #[pymethods]
 impl Namespace {
+    /// Creates the namespace from a path like `a::b::C`.
+    #[new]
+    fn py_new(path: &str) -> Self {
+        Self::new(path)
+    }
+
     fn __repr__(&self) -> String {
         format!("{self:?}")
     }
@@ -593,6 +1121,47 @@ impl Namespace {
     pub fn from_vec(components: Vec<String>) -> Self {
         Namespace { components }
     }
+
+    /// Appends the components of `other`, e.g. `a::b` joined with `c` is `a::b::c`.
+    ///
+    /// Empty components (like in `Namespace::new("")`) are skipped.
+    #[must_use]
+    pub fn join(&self, other: &Namespace) -> Self {
+        Namespace {
+            components: self
+                .components
+                .iter()
+                .chain(&other.components)
+                .filter(|c| !c.is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns the namespace without its last component, e.g. `a::b` for `a::b::C`.
+    #[must_use]
+    pub fn parent(&self) -> Option<Self> {
+        match self.components.split_last() {
+            Some((_, rest)) if !rest.is_empty() => Some(Namespace::from_vec(rest.to_vec())),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the first components are the ones of `prefix` (compared component-wise).
+    #[must_use]
+    pub fn starts_with(&self, prefix: &Namespace) -> bool {
+        self.components.starts_with(&prefix.components)
+    }
+
+    /// Returns the components after `base`, e.g. `c::D` for `a::b::c::D` relative to `a::b`.
+    ///
+    /// Returns `None` if the namespace doesn't start with `base`.
+    #[must_use]
+    pub fn relative_to(&self, base: &Namespace) -> Option<Self> {
+        self.components
+            .strip_prefix(base.components.as_slice())
+            .map(|rest| Namespace::from_vec(rest.to_vec()))
+    }
 }
 
 #[cfg(feature = "_access_functions")]
@@ -602,8 +1171,29 @@ impl Namespace {
     }
 }
 
-impl ToString for Namespace {
-    fn to_string(&self) -> String {
-        self.components.join("::")
+impl std::fmt::Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.components.join("::"))
     }
 }
+
+#[test]
+fn test_namespace_paths() {
+    let ns = Namespace::new("a::b::C");
+    assert_eq!(Namespace::new("a::b").join(&Namespace::new("C")), ns);
+    assert_eq!(
+        Namespace::new("").join(&Namespace::new("C")),
+        Namespace::new("C")
+    );
+    assert_eq!(ns.parent(), Some(Namespace::new("a::b")));
+    assert_eq!(Namespace::new("C").parent(), None);
+    assert!(ns.starts_with(&Namespace::new("a::b")));
+    // compared component-wise, not as text
+    assert!(!ns.starts_with(&Namespace::new("a::bb")));
+    assert!(!Namespace::new("a::bb::C").starts_with(&Namespace::new("a::b")));
+    assert_eq!(
+        ns.relative_to(&Namespace::new("a")),
+        Some(Namespace::new("b::C"))
+    );
+    assert_eq!(ns.relative_to(&Namespace::new("x")), None);
+}
@@ -0,0 +1,372 @@
+//! Lookup of types and of the elements which refer to them.
+//!
+//! ```
+//! use ssd_data::builder::{DataTypeBuilder, FunctionBuilder, ServiceBuilder, SsdModuleBuilder};
+//! use ssd_data::Namespace;
+//!
+//! let module = SsdModuleBuilder::new("shapes")
+//!     .data_type("Line", DataTypeBuilder::new().property("points", "list of Point"))
+//!     .data_type("Point", DataTypeBuilder::new().property("x", "i32"))
+//!     .service(
+//!         "Lines",
+//!         ServiceBuilder::new().function("length", FunctionBuilder::new().argument("line", "Line")),
+//!     )
+//!     .build();
+//!
+//! assert!(module.find_type(&Namespace::new("shapes::Point")).is_some());
+//! assert_eq!(module.referenced_types_of("Lines"), Some(vec![Namespace::new("Line")]));
+//! assert_eq!(module.dependents_of(&Namespace::new("Point")), vec!["Line"]);
+//!
+//! let sorted = module.data_types_topologically_sorted().unwrap();
+//! assert_eq!(sorted[0].0, "Point");
+//! assert_eq!(sorted[1].0, "Line");
+//! ```
+
+use std::fmt::{self, Display};
+
+use crate::visit::{walk_data_type, walk_service};
+use crate::{DataType, Enum, Import, Namespace, OrderedMap, SsdModule, TypeName, Visitor};
+
+/// A type defined in a module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeRef<'a> {
+    DataType(&'a DataType),
+    Enum(&'a Enum),
+}
+
+/// Collects the types used by the visited elements, without duplicates.
+#[derive(Default)]
+struct UsedTypes(Vec<Namespace>);
+
+impl Visitor for UsedTypes {
+    fn visit_type_name(&mut self, type_name: &TypeName) {
+        if !self.0.contains(&type_name.typ) {
+            self.0.push(type_name.typ.clone());
+        }
+    }
+}
+
+/// Returns the name of the type within a module with the given namespace, if `typ` refers to one
+/// of its types.
+///
+/// Both the plain name (`Point`) and the full path (`shapes::Point`) are accepted.
+fn local_name<'a>(namespace: &Namespace, typ: &'a Namespace) -> Option<&'a str> {
+    let components = typ
+        .components
+        .strip_prefix(namespace.components.as_slice())
+        .unwrap_or(&typ.components);
+    match components {
+        [name] => Some(name),
+        _ => None,
+    }
+}
+
+/// Data types which depend on each other through their properties, e.g. `["A", "B", "A"]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCycle(pub Vec<String>);
+
+impl Display for TypeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cyclic dependency between data types: {}",
+            self.0.join(" -> ")
+        )
+    }
+}
+
+impl std::error::Error for TypeCycle {}
+
+struct TopologicalSort<'a> {
+    namespace: &'a Namespace,
+    data_types: &'a OrderedMap<DataType>,
+    done: Vec<bool>,
+    path: Vec<usize>,
+    sorted: Vec<(&'a String, &'a DataType)>,
+}
+
+impl<'a> TopologicalSort<'a> {
+    fn visit(&mut self, index: usize) -> Result<(), TypeCycle> {
+        if self.done[index] {
+            return Ok(());
+        }
+        if let Some(start) = self.path.iter().position(|&i| i == index) {
+            let cycle = self.path[start..]
+                .iter()
+                .chain([&index])
+                .map(|&i| self.data_types.get_index(i).unwrap().0.clone())
+                .collect();
+            return Err(TypeCycle(cycle));
+        }
+
+        self.path.push(index);
+        let (name, data_type) = self.data_types.get_index(index).unwrap();
+        let mut used = UsedTypes::default();
+        walk_data_type(&mut used, name, data_type);
+        for typ in &used.0 {
+            let dependency =
+                local_name(self.namespace, typ).and_then(|name| self.data_types.get_index_of(name));
+            match dependency {
+                Some(dependency) if dependency != index => self.visit(dependency)?,
+                _ => (),
+            }
+        }
+        self.path.pop();
+
+        self.done[index] = true;
+        self.sorted.push(self.data_types.get_index(index).unwrap());
+        Ok(())
+    }
+}
+
+fn sort_data_types<'a>(
+    namespace: &'a Namespace,
+    data_types: &'a OrderedMap<DataType>,
+) -> Result<Vec<(&'a String, &'a DataType)>, TypeCycle> {
+    let mut sort = TopologicalSort {
+        namespace,
+        data_types,
+        done: vec![false; data_types.len()],
+        path: Vec::new(),
+        sorted: Vec::with_capacity(data_types.len()),
+    };
+    for index in 0..data_types.len() {
+        sort.visit(index)?;
+    }
+    Ok(sort.sorted)
+}
+
+/// Like [`SsdModule::data_types_topologically_sorted`] for data types without their module.
+///
+/// Only plain type names (e.g. `Point`) are treated as references to the given data types.
+pub fn sort_data_types_topologically(
+    data_types: &OrderedMap<DataType>,
+) -> Result<Vec<(&String, &DataType)>, TypeCycle> {
+    static NO_NAMESPACE: Namespace = Namespace {
+        components: Vec::new(),
+    };
+    sort_data_types(&NO_NAMESPACE, data_types)
+}
+
+impl Import {
+    /// Returns the full path of the type if it refers to something this import brings into
+    /// scope: the alias (`fb::User` for `import foo::bar as fb;`), one of the items (`User` for
+    /// `import foo::{User};`) or the last component of the path (`bar::User` for
+    /// `import foo::bar;`).
+    #[must_use]
+    pub fn resolve(&self, typ: &Namespace) -> Option<Namespace> {
+        let (first, rest) = typ.components.split_first()?;
+        let rest = Namespace::from_vec(rest.to_vec());
+        let name = match (&self.alias, &self.items) {
+            (Some(alias), _) => alias,
+            (None, Some(items)) => {
+                return (rest.components.is_empty() && items.contains(first))
+                    .then(|| self.path.join(typ));
+            }
+            (None, None) => self.path.components.last()?,
+        };
+        (first == name).then(|| self.path.join(&rest))
+    }
+}
+
+impl SsdModule {
+    fn local_name<'a>(&self, typ: &'a Namespace) -> Option<&'a str> {
+        local_name(&self.namespace, typ)
+    }
+
+    /// Returns the data types ordered so that every data type comes after the data types used by
+    /// its properties. Otherwise the order of the file is kept.
+    ///
+    /// A data type referring to itself (e.g. `children: list of Node`) is no cycle.
+    pub fn data_types_topologically_sorted(&self) -> Result<Vec<(&String, &DataType)>, TypeCycle> {
+        sort_data_types(&self.namespace, &self.data_types)
+    }
+
+    /// Finds the data type or enum with the given name (plain or with the module's namespace).
+    #[must_use]
+    pub fn find_type(&self, typ: &Namespace) -> Option<TypeRef<'_>> {
+        let name = self.local_name(typ)?;
+        self.data_types
+            .get(name)
+            .map(TypeRef::DataType)
+            .or_else(|| self.enums.get(name).map(TypeRef::Enum))
+    }
+
+    /// Returns the full path of the type: types of the module get its namespace, imported types
+    /// the path of their import (see [`Import::resolve`]). Other types, e.g. builtin ones, are
+    /// returned unchanged.
+    ///
+    /// ```
+    /// use ssd_data::builder::{DataTypeBuilder, SsdModuleBuilder};
+    /// use ssd_data::{Import, Namespace};
+    ///
+    /// let module = SsdModuleBuilder::new("shop")
+    ///     .import_with(Import::new(Namespace::new("auth::users"), Vec::new()).with_alias("u"))
+    ///     .import_with(Import::new(Namespace::new("geo"), Vec::new()).with_items(vec!["Point".into()]))
+    ///     .data_type("Order", DataTypeBuilder::new().property("id", "u64"))
+    ///     .build();
+    ///
+    /// let resolve = |typ| module.resolve_type(&Namespace::new(typ)).to_string();
+    /// assert_eq!(resolve("u::User"), "auth::users::User");
+    /// assert_eq!(resolve("Point"), "geo::Point");
+    /// assert_eq!(resolve("Order"), "shop::Order");
+    /// assert_eq!(resolve("u64"), "u64");
+    /// ```
+    #[must_use]
+    pub fn resolve_type(&self, typ: &Namespace) -> Namespace {
+        if let Some(name) = self.find_type(typ).and(self.local_name(typ)) {
+            return self.namespace.join(&Namespace::new(name));
+        }
+        self.imports
+            .iter()
+            .find_map(|import| import.resolve(typ))
+            .unwrap_or_else(|| typ.clone())
+    }
+
+    /// Returns the types used by the arguments and return types of a service, in order of their
+    /// first use. Returns `None` if there is no service with that name.
+    #[must_use]
+    pub fn referenced_types_of(&self, service: &str) -> Option<Vec<Namespace>> {
+        let service_type = self.services.get(service)?;
+        let mut used = UsedTypes::default();
+        walk_service(&mut used, service, service_type);
+        Some(used.0)
+    }
+
+    /// Returns the names of the data types and services which use the given type.
+    #[must_use]
+    pub fn dependents_of(&self, typ: &Namespace) -> Vec<&str> {
+        let Some(name) = self.local_name(typ) else {
+            return Vec::new();
+        };
+        let uses = |used: UsedTypes| {
+            used.0
+                .iter()
+                .any(|t| self.local_name(t).is_some_and(|n| n == name))
+        };
+
+        let data_types = self.data_types.iter().filter(|(n, dt)| {
+            let mut used = UsedTypes::default();
+            walk_data_type(&mut used, n, dt);
+            uses(used)
+        });
+        let services = self.services.iter().filter(|(n, service)| {
+            let mut used = UsedTypes::default();
+            walk_service(&mut used, n, service);
+            uses(used)
+        });
+        data_types
+            .map(|(n, _)| n.as_str())
+            .chain(services.map(|(n, _)| n.as_str()))
+            .collect()
+    }
+}
+
+#[test]
+fn test_type_lookup() {
+    use crate::builder::{
+        DataTypeBuilder, EnumBuilder, EventBuilder, FunctionBuilder, ServiceBuilder,
+        SsdModuleBuilder,
+    };
+
+    let module = SsdModuleBuilder::new("shop::orders")
+        .data_type("Order", DataTypeBuilder::new().property("state", "State"))
+        .data_type("Item", DataTypeBuilder::new().property("price", "f32"))
+        .enumeration("State", EnumBuilder::new().value("Open", 1))
+        .service(
+            "Orders",
+            ServiceBuilder::new()
+                .function(
+                    "add",
+                    FunctionBuilder::new()
+                        .argument("order", "shop::orders::Order")
+                        .argument("items", "list of Item")
+                        .returns("Order"),
+                )
+                .event("changed", EventBuilder::new().argument("state", "State")),
+        )
+        .build();
+
+    let find = |typ| module.find_type(&Namespace::new(typ));
+    assert!(matches!(find("Order"), Some(TypeRef::DataType(_))));
+    assert!(matches!(
+        find("shop::orders::Item"),
+        Some(TypeRef::DataType(_))
+    ));
+    assert!(matches!(find("State"), Some(TypeRef::Enum(_))));
+    assert_eq!(find("Orders"), None);
+    assert_eq!(find("other::Order"), None);
+    assert_eq!(find("shop::Order"), None);
+
+    // in order of their first use, without duplicates
+    assert_eq!(
+        module.referenced_types_of("Orders").unwrap(),
+        [
+            Namespace::new("shop::orders::Order"),
+            Namespace::new("Item"),
+            Namespace::new("Order"),
+            Namespace::new("State"),
+        ]
+    );
+    assert_eq!(module.referenced_types_of("Missing"), None);
+
+    let dependents = |typ| module.dependents_of(&Namespace::new(typ));
+    assert_eq!(dependents("State"), ["Order", "Orders"]);
+    assert_eq!(dependents("shop::orders::Order"), ["Orders"]);
+    assert_eq!(dependents("Item"), ["Orders"]);
+    assert!(dependents("f32").contains(&"Item"));
+    assert!(dependents("other::State").is_empty());
+}
+
+#[test]
+fn test_topological_sort() {
+    use crate::builder::{DataTypeBuilder, SsdModuleBuilder};
+
+    let module = SsdModuleBuilder::new("tree")
+        .data_type(
+            "Forest",
+            DataTypeBuilder::new().property("trees", "list of Tree"),
+        )
+        .data_type("Leaf", DataTypeBuilder::new().property("size", "f32"))
+        .data_type(
+            "Tree",
+            DataTypeBuilder::new()
+                .property("root", "tree::Node")
+                .property("leaves", "list of Leaf"),
+        )
+        // referring to itself is no cycle
+        .data_type(
+            "Node",
+            DataTypeBuilder::new().property("children", "list of Node"),
+        )
+        .data_type("Other", DataTypeBuilder::new())
+        .build();
+    let names = |sorted: Vec<(&String, &DataType)>| {
+        sorted
+            .into_iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        names(module.data_types_topologically_sorted().unwrap()),
+        "Node Leaf Tree Forest Other"
+    );
+    // without the module, `tree::Node` isn't known to be the data type
+    assert_eq!(
+        names(sort_data_types_topologically(&module.data_types).unwrap()),
+        "Leaf Tree Forest Node Other"
+    );
+
+    let module = SsdModuleBuilder::new("cycle")
+        .data_type("A", DataTypeBuilder::new().property("b", "B"))
+        .data_type("B", DataTypeBuilder::new().property("c", "list of C"))
+        .data_type("C", DataTypeBuilder::new().property("b", "B"))
+        .build();
+    let cycle = module.data_types_topologically_sorted().unwrap_err();
+    assert_eq!(cycle, TypeCycle(vec!["B".into(), "C".into(), "B".into()]));
+    assert_eq!(
+        cycle.to_string(),
+        "Cyclic dependency between data types: B -> C -> B"
+    );
+}
@@ -0,0 +1,207 @@
+//! Merging of modules.
+//!
+//! ```
+//! use ssd_data::builder::{DataTypeBuilder, SsdModuleBuilder};
+//!
+//! let mut module = SsdModuleBuilder::new("shapes")
+//!     .data_type("Rect", DataTypeBuilder::new().property("x", "i32"))
+//!     .build();
+//! let other = SsdModuleBuilder::new("other")
+//!     .data_type("Rect", DataTypeBuilder::new().property("x", "i64"))
+//!     .build();
+//!
+//! let err = module.merge(other).unwrap_err();
+//! assert_eq!(err.to_string(), "Could not merge modules: data type `Rect` is defined differently");
+//! ```
+
+use std::fmt::{self, Display};
+
+use crate::{OrderedMap, SsdModule};
+
+/// An element which is defined differently in both modules.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeConflict {
+    DataType(String),
+    Enum(String),
+    Service(String),
+    /// The name is used for different kinds of elements, e.g. a data type and an enum.
+    Name(String),
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeConflict::DataType(name) => write!(f, "data type `{name}` is defined differently"),
+            MergeConflict::Enum(name) => write!(f, "enum `{name}` is defined differently"),
+            MergeConflict::Service(name) => write!(f, "service `{name}` is defined differently"),
+            MergeConflict::Name(name) => {
+                write!(f, "`{name}` is used for different kinds of elements")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeError {
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Could not merge modules: ")?;
+        for (i, conflict) in self.conflicts.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{conflict}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Collects the conflicts of `other` with `own` and the elements of `other` which are new.
+fn merge_map<T: PartialEq>(
+    own: &OrderedMap<T>,
+    other: OrderedMap<T>,
+    conflict: fn(String) -> MergeConflict,
+    conflicts: &mut Vec<MergeConflict>,
+) -> OrderedMap<T> {
+    let mut new = OrderedMap::new();
+    for (name, element) in other {
+        match own.get(&name) {
+            Some(existing) if *existing == element => (),
+            Some(_) => conflicts.push(conflict(name)),
+            None => {
+                new.insert(name, element);
+            }
+        }
+    }
+    new
+}
+
+impl SsdModule {
+    fn kind_of(&self, name: &str) -> Option<&'static str> {
+        if self.data_types.contains_key(name) {
+            Some("data type")
+        } else if self.enums.contains_key(name) {
+            Some("enum")
+        } else if self.services.contains_key(name) {
+            Some("service")
+        } else {
+            None
+        }
+    }
+
+    /// Adds the imports, data types, enums and services of `other`, keeping the own namespace and
+    /// version.
+    ///
+    /// Elements which are defined identically in both modules (including comments and attributes)
+    /// are only kept once. If any element conflicts, all conflicts are returned and `self` is left
+    /// unchanged.
+    pub fn merge(&mut self, other: SsdModule) -> Result<(), MergeError> {
+        let mut conflicts = Vec::new();
+
+        let names = other
+            .data_types
+            .iter()
+            .map(|(name, _)| (name, "data type"))
+            .chain(other.enums.iter().map(|(name, _)| (name, "enum")))
+            .chain(other.services.iter().map(|(name, _)| (name, "service")));
+        for (name, kind) in names {
+            if matches!(self.kind_of(name), Some(own) if own != kind) {
+                conflicts.push(MergeConflict::Name(name.clone()));
+            }
+        }
+
+        let data_types = merge_map(
+            &self.data_types,
+            other.data_types,
+            MergeConflict::DataType,
+            &mut conflicts,
+        );
+        let enums = merge_map(
+            &self.enums,
+            other.enums,
+            MergeConflict::Enum,
+            &mut conflicts,
+        );
+        let services = merge_map(
+            &self.services,
+            other.services,
+            MergeConflict::Service,
+            &mut conflicts,
+        );
+
+        if !conflicts.is_empty() {
+            return Err(MergeError { conflicts });
+        }
+
+        for import in other.imports {
+            // the same path can be imported under different names
+            let imported = self.imports.iter().any(|i| {
+                i.path == import.path && i.alias == import.alias && i.items == import.items
+            });
+            if !imported {
+                self.imports.push(import);
+            }
+        }
+        self.data_types.extend(data_types);
+        self.enums.extend(enums);
+        self.services.extend(services);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_merge() {
+    use crate::builder::{DataTypeBuilder, EnumBuilder, ServiceBuilder, SsdModuleBuilder};
+
+    let mut module = SsdModuleBuilder::new("shapes")
+        .import("std::fmt")
+        .data_type("Rect", DataTypeBuilder::new().property("x", "i32"))
+        .enumeration("Kind", EnumBuilder::new().value("Square", 1))
+        .build();
+    let other = SsdModuleBuilder::new("other")
+        .version("2.0.0")
+        .import("std::fmt")
+        .import("std::io")
+        .data_type("Rect", DataTypeBuilder::new().property("x", "i32"))
+        .data_type("Circle", DataTypeBuilder::new().property("r", "f32"))
+        .service("Shapes", ServiceBuilder::new())
+        .build();
+    module.merge(other).unwrap();
+    assert_eq!(module.namespace.to_string(), "shapes");
+    assert!(module.version.is_none());
+    let imports = module
+        .imports
+        .iter()
+        .map(|i| i.path.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(imports, ["std::fmt", "std::io"]);
+    assert_eq!(
+        module.data_types.keys().collect::<Vec<_>>(),
+        ["Rect", "Circle"]
+    );
+    assert!(module.services.contains_key("Shapes"));
+
+    // all conflicts are reported and nothing gets merged
+    let before = module.clone();
+    let other = SsdModuleBuilder::new("other")
+        .data_type("Rect", DataTypeBuilder::new().property("x", "i64"))
+        .data_type("Kind", DataTypeBuilder::new())
+        .data_type("Square", DataTypeBuilder::new())
+        .enumeration("Kind", EnumBuilder::new().value("Square", 2))
+        .build();
+    let error = module.merge(other).unwrap_err();
+    assert_eq!(
+        error.conflicts,
+        [
+            MergeConflict::Name("Kind".to_owned()),
+            MergeConflict::DataType("Rect".to_owned()),
+            MergeConflict::Enum("Kind".to_owned()),
+        ]
+    );
+    assert_eq!(module, before);
+}
@@ -0,0 +1,140 @@
+//! The map type used throughout the model.
+//!
+//! ```
+//! use ssd_data::OrderedMap;
+//!
+//! let mut map = OrderedMap::new();
+//! map.insert("b".to_string(), 1);
+//! map.insert("a".to_string(), 2);
+//! assert_eq!(map.insert("b".to_string(), 3), Some(1));
+//! assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a"]);
+//! assert_eq!(map.get("b"), Some(&3));
+//! ```
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "_python")]
+use pyo3::prelude::*;
+
+/// Named elements in the order of the description file.
+///
+/// Derefs to an [`IndexMap`] for lookups by name or index. It serializes as a list of
+/// `[name, value]` pairs in every format (and shows up as a list of tuples in Python), so
+/// generators see the same shape everywhere, whichever format they read.
+#[derive(Clone)]
+pub struct OrderedMap<T>(IndexMap<String, T>);
+
+impl<T> OrderedMap<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(IndexMap::new())
+    }
+}
+
+impl<T> Default for OrderedMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for OrderedMap<T> {
+    type Target = IndexMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for OrderedMap<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Like a list of pairs, the order of the elements matters.
+impl<T: PartialEq> PartialEq for OrderedMap<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.iter().eq(other.0.iter())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OrderedMap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.0).finish()
+    }
+}
+
+impl<T> From<IndexMap<String, T>> for OrderedMap<T> {
+    fn from(map: IndexMap<String, T>) -> Self {
+        Self(map)
+    }
+}
+
+impl<T> From<Vec<(String, T)>> for OrderedMap<T> {
+    fn from(entries: Vec<(String, T)>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<T> FromIterator<(String, T)> for OrderedMap<T> {
+    fn from_iter<I: IntoIterator<Item = (String, T)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for OrderedMap<T> {
+    type Item = (String, T);
+    type IntoIter = indexmap::map::IntoIter<String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OrderedMap<T> {
+    type Item = (&'a String, &'a T);
+    type IntoIter = indexmap::map::Iter<'a, String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut OrderedMap<T> {
+    type Item = (&'a String, &'a mut T);
+    type IntoIter = indexmap::map::IterMut<'a, String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+impl<T: Serialize> Serialize for OrderedMap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        indexmap::map::serde_seq::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OrderedMap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        indexmap::map::serde_seq::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(feature = "_python")]
+impl<'source, T: FromPyObject<'source>> FromPyObject<'source> for OrderedMap<T> {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<Vec<(String, T)>>()?.into())
+    }
+}
+
+#[cfg(feature = "_python")]
+impl<T: IntoPy<PyObject>> IntoPy<PyObject> for OrderedMap<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        self.into_iter().collect::<Vec<_>>().into_py(py)
+    }
+}
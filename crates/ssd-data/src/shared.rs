@@ -0,0 +1,399 @@
+//! Cheaply clonable handles to the elements of a model, which are used by the script engines.
+//!
+//! Accessing e.g. the data types of a `Shared<SsdModule>` returns handles to the data types,
+//! instead of copies of them including all their properties. The same goes for properties,
+//! arguments, enum values, dependencies, attributes and doc comments.
+//!
+//! ```
+//! use ssd_data::builder::{DataTypeBuilder, SsdModuleBuilder};
+//! use ssd_data::Shared;
+//!
+//! let mut module = Shared::new(
+//!     SsdModuleBuilder::new("shapes")
+//!         .data_type("Point", DataTypeBuilder::new().property("x", "i32"))
+//!         .build(),
+//! );
+//! let mut point = module.data_types()["Point"].clone();
+//! let mut x = point.properties()["x"].clone();
+//! assert_eq!(x.typ().to_string(), "i32");
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
+    OrderedMap, Parameter, Service, SsdModule, TypeCycle, TypeName,
+};
+
+/// Warns on stderr that a generator uses a deprecated property, once per property.
+pub(crate) fn warn_deprecated(property: &str, replacement: &str) {
+    static WARNED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    if !WARNED
+        .lock()
+        .is_ok_and(|mut warned| warned.insert(property.to_string()))
+    {
+        return;
+    }
+    let message = format!(
+        "Using the property '{property}' is deprecated and will be removed in future versions. Use '{replacement}' instead."
+    );
+    let mut stderr = StandardStream::stderr(ColorChoice::Always);
+    if stderr
+        .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
+        .is_ok()
+    {
+        let _ = writeln!(&mut stderr, "{message}");
+        let _ = stderr.set_color(&ColorSpec::default());
+    } else {
+        eprintln!("{message}");
+    }
+}
+
+/// Finds an element in the module it belongs to.
+type Lens<T> = Arc<dyn for<'a> Fn(&'a SsdModule) -> &'a T + Send + Sync>;
+
+/// Lets the compiler infer that the element lives as long as the module.
+fn lens<T, F: for<'a> Fn(&'a SsdModule) -> &'a T>(f: F) -> F {
+    f
+}
+
+/// A handle to an element of a module, which shares the module instead of copying the element.
+pub struct Shared<T: 'static> {
+    module: Arc<SsdModule>,
+    lens: Lens<T>,
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            module: self.module.clone(),
+            lens: self.lens.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        (self.lens)(&self.module)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> Shared<T> {
+    /// A handle to a part of the element, e.g. to one of its properties.
+    pub fn project<U>(
+        &self,
+        part: impl for<'a> Fn(&'a T) -> &'a U + Send + Sync + 'static,
+    ) -> Shared<U> {
+        let element = self.lens.clone();
+        Shared {
+            module: self.module.clone(),
+            lens: Arc::new(lens(move |module| part(element(module)))),
+        }
+    }
+
+    fn entries<U>(
+        &self,
+        map: impl for<'a> Fn(&'a T) -> &'a OrderedMap<U> + Copy + Send + Sync + 'static,
+    ) -> OrderedMap<Shared<U>> {
+        map(self)
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                let element = self.project(move |element| &map(element)[i]);
+                (name.clone(), element)
+            })
+            .collect()
+    }
+
+    fn items<U>(
+        &self,
+        list: impl for<'a> Fn(&'a T) -> &'a Vec<U> + Copy + Send + Sync + 'static,
+    ) -> Vec<Shared<U>> {
+        (0..list(self).len())
+            .map(|i| self.project(move |element| &list(element)[i]))
+            .collect()
+    }
+}
+
+/// Iterates the lines of shared doc comments.
+pub struct Lines {
+    docs: Shared<Vec<String>>,
+    next: usize,
+}
+
+impl Iterator for Lines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let line = self.docs.get(self.next)?.clone();
+        self.next += 1;
+        Some(line)
+    }
+}
+
+impl IntoIterator for Shared<Vec<String>> {
+    type Item = String;
+    type IntoIter = Lines;
+
+    fn into_iter(self) -> Lines {
+        Lines {
+            docs: self,
+            next: 0,
+        }
+    }
+}
+
+impl Shared<SsdModule> {
+    #[must_use]
+    pub fn new(module: SsdModule) -> Self {
+        Self {
+            module: Arc::new(module),
+            lens: Arc::new(lens(|module| module)),
+        }
+    }
+
+    pub fn namespace(&mut self) -> Namespace {
+        self.namespace.clone()
+    }
+
+    pub fn imports(&mut self) -> Vec<Import> {
+        self.imports.clone()
+    }
+
+    pub fn version(&mut self) -> Option<String> {
+        self.version.clone()
+    }
+
+    pub fn data_types(&mut self) -> OrderedMap<Shared<DataType>> {
+        self.entries(|module| &module.data_types)
+    }
+
+    pub fn enums(&mut self) -> OrderedMap<Shared<Enum>> {
+        self.entries(|module| &module.enums)
+    }
+
+    pub fn services(&mut self) -> OrderedMap<Shared<Service>> {
+        self.entries(|module| &module.services)
+    }
+
+    /// Like [`SsdModule::data_types_topologically_sorted`], but returns handles.
+    pub fn data_types_topologically_sorted(
+        &mut self,
+    ) -> Result<OrderedMap<Shared<DataType>>, TypeCycle> {
+        let sorted = self.module.data_types_topologically_sorted()?;
+        Ok(sorted
+            .into_iter()
+            .filter_map(|(name, _)| {
+                let index = self.data_types.get_index_of(name)?;
+                let data_type = self.project(move |module| &module.data_types[index]);
+                Some((name.clone(), data_type))
+            })
+            .collect())
+    }
+}
+
+impl From<SsdModule> for Shared<SsdModule> {
+    fn from(module: SsdModule) -> Self {
+        Self::new(module)
+    }
+}
+
+impl Shared<DataType> {
+    pub fn properties(&mut self) -> OrderedMap<Shared<TypeName>> {
+        self.entries(|data_type| &data_type.properties)
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|data_type| &data_type.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|data_type| &data_type.docs)
+    }
+
+    /// `pub`, `internal` or empty if the declaration has no marker.
+    pub fn visibility(&mut self) -> String {
+        crate::visibility_marker(self.visibility)
+    }
+}
+
+impl Shared<Enum> {
+    pub fn values(&mut self) -> OrderedMap<Shared<EnumValue>> {
+        self.entries(|en| &en.values)
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|en| &en.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|en| &en.docs)
+    }
+
+    /// `pub`, `internal` or empty if the declaration has no marker.
+    pub fn visibility(&mut self) -> String {
+        crate::visibility_marker(self.visibility)
+    }
+
+    pub fn is_flags(&mut self) -> bool {
+        self.is_flags
+    }
+}
+
+impl Shared<Service> {
+    pub fn dependencies(&mut self) -> Vec<Shared<Dependency>> {
+        self.items(|service| &service.dependencies)
+    }
+
+    pub fn functions(&mut self) -> OrderedMap<Shared<Function>> {
+        self.entries(|service| &service.functions)
+    }
+
+    pub fn handlers(&mut self) -> OrderedMap<Shared<Function>> {
+        warn_deprecated("handlers", "functions");
+        self.functions()
+    }
+
+    pub fn events(&mut self) -> OrderedMap<Shared<Event>> {
+        self.entries(|service| &service.events)
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|service| &service.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|service| &service.docs)
+    }
+
+    /// `pub`, `internal` or empty if the declaration has no marker.
+    pub fn visibility(&mut self) -> String {
+        crate::visibility_marker(self.visibility)
+    }
+}
+
+impl Shared<Function> {
+    pub fn arguments(&mut self) -> OrderedMap<Shared<TypeName>> {
+        self.entries(|function| &function.arguments)
+    }
+
+    pub fn return_type(&mut self) -> Option<Shared<TypeName>> {
+        self.return_type.as_ref()?;
+        Some(self.project(|function| function.return_type.as_ref().unwrap()))
+    }
+
+    pub fn error_type(&mut self) -> Option<Shared<TypeName>> {
+        self.error_type.as_ref()?;
+        Some(self.project(|function| function.error_type.as_ref().unwrap()))
+    }
+
+    pub fn is_oneway(&mut self) -> bool {
+        self.is_oneway
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|function| &function.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|function| &function.docs)
+    }
+}
+
+impl Shared<Event> {
+    pub fn arguments(&mut self) -> OrderedMap<Shared<TypeName>> {
+        self.entries(|event| &event.arguments)
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|event| &event.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|event| &event.docs)
+    }
+}
+
+impl Shared<TypeName> {
+    pub fn typ(&mut self) -> Namespace {
+        self.typ.clone()
+    }
+
+    pub fn lists(&mut self) -> Vec<Option<usize>> {
+        self.lists.clone()
+    }
+
+    /// The count of the outermost list, only kept for generators written before nested lists.
+    pub fn count(&mut self) -> Option<usize> {
+        warn_deprecated("count", "lists");
+        self.lists.first().copied().flatten()
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|typ| &typ.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|typ| &typ.docs)
+    }
+}
+
+impl Shared<EnumValue> {
+    pub fn value(&mut self) -> Option<i64> {
+        self.value
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|value| &value.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|value| &value.docs)
+    }
+
+    pub fn radix(&mut self) -> String {
+        self.radix.to_string()
+    }
+
+    pub fn formatted(&mut self) -> Option<String> {
+        self.formatted_value()
+    }
+}
+
+impl Shared<Dependency> {
+    pub fn name(&mut self) -> Namespace {
+        self.name.clone()
+    }
+
+    pub fn attributes(&mut self) -> Vec<Shared<Attribute>> {
+        self.items(|dependency| &dependency.attributes)
+    }
+
+    pub fn docs(&mut self) -> Shared<Vec<String>> {
+        self.project(|dependency| &dependency.docs)
+    }
+}
+
+impl Shared<Attribute> {
+    pub fn name(&mut self) -> Namespace {
+        self.name.clone()
+    }
+
+    pub fn parameters(&mut self) -> Vec<Parameter> {
+        self.parameters.clone()
+    }
+}
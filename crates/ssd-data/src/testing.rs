@@ -0,0 +1,335 @@
+//! [proptest](https://docs.rs/proptest) strategies for the model types, enabled with the
+//! `testing` feature.
+//!
+//! Every type implements [`Arbitrary`], so `any::<SsdModule>()` generates models which can be
+//! written as `.svc` source and parsed back unchanged: names are identifiers (no keywords) and
+//! unique where the parser requires it, enums have at least one value, comments and docs are
+//! single trimmed lines, return types have neither attributes nor comments and `oneway` functions
+//! return nothing. Values shrink towards fewer and shorter elements.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use ssd_data::SsdModule;
+//!
+//! proptest!(|(module in any::<SsdModule>())| {
+//!     prop_assert!(module.enums.iter().all(|(_, en)| !en.values.is_empty()));
+//! });
+//! ```
+
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+
+use crate::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
+    OrderedMap, Parameter, Radix, Service, SsdModule, TypeName, Visibility,
+};
+
+/// Words of the format which can't be used as names.
+const KEYWORDS: &[&str] = &[
+    "import", "data", "enum", "service", "fn", "handles", "event", "depends", "on", "list", "of",
+    "pub", "internal",
+];
+
+/// An identifier which can be used as a name, e.g. `Rect` or `_x1`.
+pub fn ident() -> impl Strategy<Value = String> {
+    "[a-zA-Z_][a-zA-Z0-9_]{0,8}".prop_filter("keywords can't be used as names", |name| {
+        !KEYWORDS.contains(&name.as_str())
+    })
+}
+
+/// A single line of text as it is stored for comments and docs (trimmed).
+fn comment() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 ,.]{0,24}".prop_map(|comment| comment.trim().to_string())
+}
+
+fn comments() -> impl Strategy<Value = Vec<String>> {
+    vec(comment(), 0..3)
+}
+
+fn attributes() -> impl Strategy<Value = Vec<Attribute>> {
+    vec(any::<Attribute>(), 0..3)
+}
+
+fn visibility() -> impl Strategy<Value = Option<Visibility>> {
+    option::of(prop_oneof![
+        Just(Visibility::Public),
+        Just(Visibility::Internal)
+    ])
+}
+
+/// Named elements with unique names, in the generated order (later duplicates are dropped).
+pub fn ordered_map<T: std::fmt::Debug>(
+    element: impl Strategy<Value = T>,
+    size: impl Into<proptest::collection::SizeRange>,
+) -> impl Strategy<Value = OrderedMap<T>> {
+    vec((ident(), element), size).prop_map(|elements| {
+        let mut map = OrderedMap::new();
+        for (name, element) in elements {
+            map.entry(name).or_insert(element);
+        }
+        map
+    })
+}
+
+impl Arbitrary for Namespace {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        vec(ident(), 1..4).prop_map(Namespace::from_vec).boxed()
+    }
+}
+
+impl Arbitrary for Parameter {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (ident(), option::of("[a-zA-Z0-9 _.:-]{0,12}"))
+            .prop_map(|(name, value)| Parameter { name, value })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Attribute {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (any::<Namespace>(), vec(any::<Parameter>(), 0..3))
+            .prop_map(|(name, parameters)| Attribute { name, parameters })
+            .boxed()
+    }
+}
+
+impl Arbitrary for TypeName {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let list = prop_oneof![Just(None), (1..64usize).prop_map(Some)];
+        (
+            any::<Namespace>(),
+            vec(list, 0..3),
+            attributes(),
+            comments(),
+            comments(),
+        )
+            .prop_map(|(typ, lists, attributes, mut comments, mut docs)| {
+                TypeName::new(typ, lists, attributes)
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Import {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let scope = prop_oneof![
+            Just((None, None)),
+            ident().prop_map(|alias| (Some(alias), None)),
+            vec(ident(), 1..4).prop_map(|items| (None, Some(items))),
+        ];
+        (any::<Namespace>(), attributes(), scope)
+            .prop_map(|(path, attributes, (alias, items))| Import {
+                path,
+                attributes,
+                alias,
+                items,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Dependency {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (any::<Namespace>(), attributes(), comments(), comments())
+            .prop_map(|(name, attributes, mut comments, mut docs)| {
+                Dependency::new(name, attributes)
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for DataType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            ordered_map(any::<TypeName>(), 0..6),
+            attributes(),
+            visibility(),
+            comments(),
+        )
+            .prop_map(|(properties, attributes, visibility, mut docs)| {
+                DataType::new(properties, attributes)
+                    .with_visibility(visibility)
+                    .with_docs(&mut docs)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for EnumValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let radix = prop_oneof![Just(Radix::Decimal), Just(Radix::Hex), Just(Radix::Binary)];
+        // values without a number are always decimal
+        let value = option::of((-1000..1000i64, radix))
+            .prop_map(|value| value.map_or((None, Radix::Decimal), |(v, r)| (Some(v), r)));
+        (value, attributes(), comments(), comments())
+            .prop_map(|((value, radix), attributes, mut comments, mut docs)| {
+                EnumValue::new(value, attributes)
+                    .with_radix(radix)
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Enum {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            ordered_map(any::<EnumValue>(), 1..6),
+            attributes(),
+            visibility(),
+            comments(),
+            any::<bool>(),
+        )
+            .prop_map(|(values, attributes, visibility, mut docs, is_flags)| {
+                let mut en = Enum::new(values, attributes)
+                    .with_visibility(visibility)
+                    .with_docs(&mut docs)
+                    .with_flags(is_flags);
+                // parsing assigns the values of flags
+                if is_flags {
+                    en.assign_flag_values();
+                }
+                en
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Function {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let return_type =
+            || any::<TypeName>().prop_map(|typ| TypeName::new(typ.typ, typ.lists, Vec::new()));
+        // `-> T` or `-> result of T, E`
+        let returns = option::of((return_type(), option::of(return_type())));
+        (
+            ordered_map(any::<TypeName>(), 0..4),
+            returns,
+            any::<bool>(),
+            attributes(),
+            comments(),
+            comments(),
+        )
+            .prop_map(
+                |(arguments, returns, is_oneway, attributes, mut comments, mut docs)| {
+                    let (return_type, error_type) =
+                        returns.map_or((None, None), |(ok, error)| (Some(ok), error));
+                    // only functions without a return type can be `oneway`
+                    let is_oneway = is_oneway && return_type.is_none();
+                    Function::new(arguments, return_type, attributes)
+                        .with_error_type(error_type)
+                        .with_oneway(is_oneway)
+                        .with_comments(&mut comments)
+                        .with_docs(&mut docs)
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Event {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            ordered_map(any::<TypeName>(), 0..4),
+            attributes(),
+            comments(),
+            comments(),
+        )
+            .prop_map(|(arguments, attributes, mut comments, mut docs)| {
+                Event::new(arguments, attributes)
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Service {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            vec(any::<Dependency>(), 0..3),
+            ordered_map(any::<Function>(), 0..4),
+            ordered_map(any::<Event>(), 0..3),
+            attributes(),
+            visibility(),
+            comments(),
+        )
+            .prop_map(
+                |(dependencies, functions, events, attributes, visibility, mut docs)| {
+                    Service::new(dependencies, functions, events, attributes)
+                        .with_visibility(visibility)
+                        .with_docs(&mut docs)
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for SsdModule {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            any::<Namespace>(),
+            vec(any::<Import>(), 0..3),
+            ordered_map(any::<DataType>(), 0..4),
+            ordered_map(any::<Enum>(), 0..3),
+            ordered_map(any::<Service>(), 0..3),
+            option::of(r"[0-9]{1,2}\.[0-9]{1,2}\.[0-9]{1,2}"),
+        )
+            .prop_map(
+                |(namespace, imports, data_types, mut enums, mut services, version)| {
+                    // types and services share one namespace
+                    enums.retain(|name, _| !data_types.contains_key(name));
+                    services.retain(|name, _| {
+                        !data_types.contains_key(name) && !enums.contains_key(name)
+                    });
+                    SsdModule::new(namespace, imports, data_types, enums, services)
+                        .with_version(version)
+                },
+            )
+            .boxed()
+    }
+}
@@ -0,0 +1,390 @@
+//! Traversal of the model.
+//!
+//! Implement [`Visitor`] (or [`VisitorMut`]) and override the methods for the elements you are
+//! interested in. The default implementations call the matching `walk_*` function, which visits
+//! the children of the element. When overriding a method, call the `walk_*` function yourself,
+//! if the children should still be visited.
+
+use crate::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Service, SsdModule,
+    TypeName,
+};
+
+pub trait Visitor {
+    fn visit_module(&mut self, module: &SsdModule) {
+        walk_module(self, module);
+    }
+
+    fn visit_import(&mut self, import: &Import) {
+        walk_attributes(self, &import.attributes);
+    }
+
+    fn visit_data_type(&mut self, name: &str, data_type: &DataType) {
+        walk_data_type(self, name, data_type);
+    }
+
+    fn visit_property(&mut self, _name: &str, property: &TypeName) {
+        self.visit_type_name(property);
+    }
+
+    fn visit_enum(&mut self, name: &str, en: &Enum) {
+        walk_enum(self, name, en);
+    }
+
+    fn visit_enum_value(&mut self, _name: &str, value: &EnumValue) {
+        walk_attributes(self, &value.attributes);
+    }
+
+    fn visit_service(&mut self, name: &str, service: &Service) {
+        walk_service(self, name, service);
+    }
+
+    fn visit_dependency(&mut self, dependency: &Dependency) {
+        walk_attributes(self, &dependency.attributes);
+    }
+
+    fn visit_function(&mut self, name: &str, function: &Function) {
+        walk_function(self, name, function);
+    }
+
+    fn visit_event(&mut self, name: &str, event: &Event) {
+        walk_event(self, name, event);
+    }
+
+    fn visit_argument(&mut self, _name: &str, argument: &TypeName) {
+        self.visit_type_name(argument);
+    }
+
+    /// Called for properties, arguments and return types.
+    fn visit_type_name(&mut self, type_name: &TypeName) {
+        walk_type_name(self, type_name);
+    }
+
+    fn visit_attribute(&mut self, _attribute: &Attribute) {}
+}
+
+pub fn walk_module<V: Visitor + ?Sized>(visitor: &mut V, module: &SsdModule) {
+    for import in &module.imports {
+        visitor.visit_import(import);
+    }
+    for (name, data_type) in &module.data_types {
+        visitor.visit_data_type(name, data_type);
+    }
+    for (name, en) in &module.enums {
+        visitor.visit_enum(name, en);
+    }
+    for (name, service) in &module.services {
+        visitor.visit_service(name, service);
+    }
+}
+
+pub fn walk_attributes<V: Visitor + ?Sized>(visitor: &mut V, attributes: &[Attribute]) {
+    for attribute in attributes {
+        visitor.visit_attribute(attribute);
+    }
+}
+
+pub fn walk_data_type<V: Visitor + ?Sized>(visitor: &mut V, _name: &str, data_type: &DataType) {
+    walk_attributes(visitor, &data_type.attributes);
+    for (name, property) in &data_type.properties {
+        visitor.visit_property(name, property);
+    }
+}
+
+/// Walks the attributes and the properties of an inline data type (only found in the raw
+/// elements).
+pub fn walk_type_name<V: Visitor + ?Sized>(visitor: &mut V, type_name: &TypeName) {
+    walk_attributes(visitor, &type_name.attributes);
+    for (name, property) in type_name.inline.iter().flatten() {
+        visitor.visit_property(name, property);
+    }
+}
+
+pub fn walk_enum<V: Visitor + ?Sized>(visitor: &mut V, _name: &str, en: &Enum) {
+    walk_attributes(visitor, &en.attributes);
+    for (name, value) in &en.values {
+        visitor.visit_enum_value(name, value);
+    }
+}
+
+pub fn walk_service<V: Visitor + ?Sized>(visitor: &mut V, _name: &str, service: &Service) {
+    walk_attributes(visitor, &service.attributes);
+    for dependency in &service.dependencies {
+        visitor.visit_dependency(dependency);
+    }
+    for (name, function) in &service.functions {
+        visitor.visit_function(name, function);
+    }
+    for (name, event) in &service.events {
+        visitor.visit_event(name, event);
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, _name: &str, function: &Function) {
+    walk_attributes(visitor, &function.attributes);
+    for (name, argument) in &function.arguments {
+        visitor.visit_argument(name, argument);
+    }
+    if let Some(return_type) = &function.return_type {
+        visitor.visit_type_name(return_type);
+    }
+    if let Some(error_type) = &function.error_type {
+        visitor.visit_type_name(error_type);
+    }
+}
+
+pub fn walk_event<V: Visitor + ?Sized>(visitor: &mut V, _name: &str, event: &Event) {
+    walk_attributes(visitor, &event.attributes);
+    for (name, argument) in &event.arguments {
+        visitor.visit_argument(name, argument);
+    }
+}
+
+/// Like [`Visitor`], but with mutable access to the elements.
+pub trait VisitorMut {
+    fn visit_module_mut(&mut self, module: &mut SsdModule) {
+        walk_module_mut(self, module);
+    }
+
+    fn visit_import_mut(&mut self, import: &mut Import) {
+        walk_attributes_mut(self, &mut import.attributes);
+    }
+
+    fn visit_data_type_mut(&mut self, name: &str, data_type: &mut DataType) {
+        walk_data_type_mut(self, name, data_type);
+    }
+
+    fn visit_property_mut(&mut self, _name: &str, property: &mut TypeName) {
+        self.visit_type_name_mut(property);
+    }
+
+    fn visit_enum_mut(&mut self, name: &str, en: &mut Enum) {
+        walk_enum_mut(self, name, en);
+    }
+
+    fn visit_enum_value_mut(&mut self, _name: &str, value: &mut EnumValue) {
+        walk_attributes_mut(self, &mut value.attributes);
+    }
+
+    fn visit_service_mut(&mut self, name: &str, service: &mut Service) {
+        walk_service_mut(self, name, service);
+    }
+
+    fn visit_dependency_mut(&mut self, dependency: &mut Dependency) {
+        walk_attributes_mut(self, &mut dependency.attributes);
+    }
+
+    fn visit_function_mut(&mut self, name: &str, function: &mut Function) {
+        walk_function_mut(self, name, function);
+    }
+
+    fn visit_event_mut(&mut self, name: &str, event: &mut Event) {
+        walk_event_mut(self, name, event);
+    }
+
+    fn visit_argument_mut(&mut self, _name: &str, argument: &mut TypeName) {
+        self.visit_type_name_mut(argument);
+    }
+
+    /// Called for properties, arguments and return types.
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        walk_type_name_mut(self, type_name);
+    }
+
+    fn visit_attribute_mut(&mut self, _attribute: &mut Attribute) {}
+}
+
+pub fn walk_module_mut<V: VisitorMut + ?Sized>(visitor: &mut V, module: &mut SsdModule) {
+    for import in &mut module.imports {
+        visitor.visit_import_mut(import);
+    }
+    for (name, data_type) in &mut module.data_types {
+        visitor.visit_data_type_mut(name, data_type);
+    }
+    for (name, en) in &mut module.enums {
+        visitor.visit_enum_mut(name, en);
+    }
+    for (name, service) in &mut module.services {
+        visitor.visit_service_mut(name, service);
+    }
+}
+
+pub fn walk_attributes_mut<V: VisitorMut + ?Sized>(visitor: &mut V, attributes: &mut [Attribute]) {
+    for attribute in attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+}
+
+pub fn walk_data_type_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _name: &str,
+    data_type: &mut DataType,
+) {
+    walk_attributes_mut(visitor, &mut data_type.attributes);
+    for (name, property) in &mut data_type.properties {
+        visitor.visit_property_mut(name, property);
+    }
+}
+
+pub fn walk_type_name_mut<V: VisitorMut + ?Sized>(visitor: &mut V, type_name: &mut TypeName) {
+    walk_attributes_mut(visitor, &mut type_name.attributes);
+    for (name, property) in type_name.inline.iter_mut().flatten() {
+        visitor.visit_property_mut(name, property);
+    }
+}
+
+pub fn walk_enum_mut<V: VisitorMut + ?Sized>(visitor: &mut V, _name: &str, en: &mut Enum) {
+    walk_attributes_mut(visitor, &mut en.attributes);
+    for (name, value) in &mut en.values {
+        visitor.visit_enum_value_mut(name, value);
+    }
+}
+
+pub fn walk_service_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _name: &str,
+    service: &mut Service,
+) {
+    walk_attributes_mut(visitor, &mut service.attributes);
+    for dependency in &mut service.dependencies {
+        visitor.visit_dependency_mut(dependency);
+    }
+    for (name, function) in &mut service.functions {
+        visitor.visit_function_mut(name, function);
+    }
+    for (name, event) in &mut service.events {
+        visitor.visit_event_mut(name, event);
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _name: &str,
+    function: &mut Function,
+) {
+    walk_attributes_mut(visitor, &mut function.attributes);
+    for (name, argument) in &mut function.arguments {
+        visitor.visit_argument_mut(name, argument);
+    }
+    if let Some(return_type) = &mut function.return_type {
+        visitor.visit_type_name_mut(return_type);
+    }
+    if let Some(error_type) = &mut function.error_type {
+        visitor.visit_type_name_mut(error_type);
+    }
+}
+
+pub fn walk_event_mut<V: VisitorMut + ?Sized>(visitor: &mut V, _name: &str, event: &mut Event) {
+    walk_attributes_mut(visitor, &mut event.attributes);
+    for (name, argument) in &mut event.arguments {
+        visitor.visit_argument_mut(name, argument);
+    }
+}
+
+#[test]
+fn test_visit_order() {
+    use crate::builder::{
+        DataTypeBuilder, EnumBuilder, EventBuilder, FunctionBuilder, ServiceBuilder,
+        SsdModuleBuilder,
+    };
+
+    #[derive(Default)]
+    struct Collect(Vec<String>);
+
+    impl Visitor for Collect {
+        fn visit_data_type(&mut self, name: &str, data_type: &DataType) {
+            self.0.push(format!("data {name}"));
+            walk_data_type(self, name, data_type);
+        }
+
+        fn visit_property(&mut self, name: &str, property: &TypeName) {
+            self.0.push(format!("property {name}"));
+            self.visit_type_name(property);
+        }
+
+        fn visit_enum_value(&mut self, name: &str, _value: &EnumValue) {
+            self.0.push(format!("value {name}"));
+        }
+
+        fn visit_dependency(&mut self, dependency: &Dependency) {
+            self.0.push(format!("depends on {}", dependency.name));
+        }
+
+        fn visit_argument(&mut self, name: &str, argument: &TypeName) {
+            self.0.push(format!("argument {name}"));
+            self.visit_type_name(argument);
+        }
+
+        // not walking the children of functions skips their arguments and return types
+        fn visit_function(&mut self, name: &str, _function: &Function) {
+            self.0.push(format!("fn {name}"));
+        }
+
+        fn visit_type_name(&mut self, type_name: &TypeName) {
+            self.0.push(format!("type {}", type_name.typ));
+            walk_type_name(self, type_name);
+        }
+
+        fn visit_attribute(&mut self, attribute: &Attribute) {
+            self.0.push(format!("#{}", attribute.name));
+        }
+    }
+
+    let module = SsdModuleBuilder::new("test")
+        .data_type(
+            "A",
+            DataTypeBuilder::new()
+                .attribute("a")
+                .property("x", "i32")
+                .property("b", "list of B"),
+        )
+        .enumeration("E", EnumBuilder::new().value("One", 1).value("Two", 2))
+        .service(
+            "S",
+            ServiceBuilder::new()
+                .depends_on("Logger")
+                .function("f", FunctionBuilder::new().argument("a", "A").returns("B"))
+                .event("changed", EventBuilder::new().argument("id", "u64")),
+        )
+        .build();
+
+    let mut collect = Collect::default();
+    collect.visit_module(&module);
+    assert_eq!(
+        collect.0,
+        [
+            "data A",
+            "#a",
+            "property x",
+            "type i32",
+            "property b",
+            "type B",
+            "value One",
+            "value Two",
+            "depends on Logger",
+            "fn f",
+            "argument id",
+            "type u64",
+        ]
+    );
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+            if type_name.typ.to_string() == "B" {
+                type_name.typ = crate::Namespace::new("other::B");
+            }
+        }
+    }
+
+    let mut module = module;
+    Rename.visit_module_mut(&mut module);
+    assert_eq!(
+        module.data_types["A"].properties["b"].typ.to_string(),
+        "other::B"
+    );
+    let f = &module.services["S"].functions["f"];
+    assert_eq!(f.return_type.as_ref().unwrap().typ.to_string(), "other::B");
+    assert_eq!(f.arguments["a"].typ.to_string(), "A");
+}
@@ -0,0 +1,16 @@
+//! Parses arbitrary input with the limits used for untrusted input, which must never panic.
+//!
+//! Run with `cargo +nightly fuzz run parse` in `crates/ssd`, add `--features rd_parser` for the
+//! hand-written parser.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ssd::{parse_limited, parse_raw_limited, ParseLimits};
+use ssd_data::Namespace;
+
+fuzz_target!(|content: &str| {
+    let limits = ParseLimits::default();
+    let _ = parse_raw_limited(content, &limits);
+    let _ = parse_limited(content, Namespace::new("fuzz"), &limits);
+});
@@ -0,0 +1,51 @@
+/*
+ * C API of ssd, available when the library is built with the `capi` feature:
+ *
+ *     cargo build --release -p ssd --features capi
+ *
+ * and linked against the resulting cdylib (libssd.so, libssd.dylib or ssd.dll).
+ *
+ * Every function returns an SsdResult, which holds either the output or an error message.
+ * Strings returned from a result belong to it and stay valid until it is released with
+ * ssd_free_result.
+ */
+#ifndef SSD_H
+#define SSD_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct SsdResult SsdResult;
+
+/* Parses the description and returns the module serialized as json. */
+SsdResult *ssd_parse(const char *content, const char *ns);
+
+/*
+ * Runs the rhai script for the description file and returns its output.
+ *
+ * typemap and out_dir can be NULL. Files emitted by the script are written relative to out_dir,
+ * they are discarded without it.
+ */
+SsdResult *ssd_generate_rhai(const char *file, const char *script, const char *typemap,
+                             const char *out_dir);
+
+/* Returns whether the call succeeded. */
+bool ssd_result_is_ok(const SsdResult *result);
+
+/* Returns the output of a successful call, NULL otherwise. */
+const char *ssd_result_output(const SsdResult *result);
+
+/* Returns the error message of a failed call, NULL otherwise. */
+const char *ssd_result_error(const SsdResult *result);
+
+/* Releases the result. Passing NULL does nothing. */
+void ssd_free_result(SsdResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SSD_H */
@@ -0,0 +1,289 @@
+//! Generating code from other Rust programs (e.g. build scripts), without running the command.
+//!
+//! ```no_run
+//! use ssd::{generate, GenerateRequest, Generator};
+//!
+//! let mut request = GenerateRequest::new(
+//!     "api/service.svc",
+//!     Generator::rhai("generators/rust.rhai"),
+//! );
+//! request.typemaps.push("generators/rust.tym".into());
+//!
+//! let output = generate(request)?;
+//! println!("{}", output.output);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use ssd_data::{Defines, Namespace};
+
+use crate::options::BaseInputData;
+#[cfg(any(feature = "handlebars", feature = "tera", feature = "wasm"))]
+use crate::options::BaseOutputData;
+use crate::postprocess::{postprocess_files, PostProcess};
+use crate::transform::Transform;
+
+/// The generator to run and the script, template or plugin it uses.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Generator {
+    #[cfg(feature = "rhai")]
+    Rhai { script: PathBuf },
+    #[cfg(feature = "handlebars")]
+    Handlebars { template: PathBuf },
+    #[cfg(feature = "tera")]
+    Tera { template: PathBuf },
+    #[cfg(feature = "wasm")]
+    Wasm { plugin: PathBuf },
+}
+
+impl Generator {
+    #[cfg(feature = "rhai")]
+    pub fn rhai(script: impl Into<PathBuf>) -> Self {
+        Self::Rhai {
+            script: script.into(),
+        }
+    }
+
+    #[cfg(feature = "handlebars")]
+    pub fn handlebars(template: impl Into<PathBuf>) -> Self {
+        Self::Handlebars {
+            template: template.into(),
+        }
+    }
+
+    #[cfg(feature = "tera")]
+    pub fn tera(template: impl Into<PathBuf>) -> Self {
+        Self::Tera {
+            template: template.into(),
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn wasm(plugin: impl Into<PathBuf>) -> Self {
+        Self::Wasm {
+            plugin: plugin.into(),
+        }
+    }
+
+    /// Looks the generator up by its name (`rhai`, `handlebars`, `tera` or `wasm`), e.g. from
+    /// a request or a file. Fails if the feature of the generator isn't enabled.
+    pub fn from_name(name: &str, source: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        match name {
+            #[cfg(feature = "rhai")]
+            "rhai" => Ok(Self::rhai(source)),
+            #[cfg(feature = "handlebars")]
+            "handlebars" => Ok(Self::handlebars(source)),
+            #[cfg(feature = "tera")]
+            "tera" => Ok(Self::tera(source)),
+            #[cfg(feature = "wasm")]
+            "wasm" => Ok(Self::wasm(source)),
+            _ => anyhow::bail!("Unknown generator \"{name}\"."),
+        }
+    }
+
+    /// The script, template or plugin of the generator.
+    pub fn source(&self) -> &PathBuf {
+        match self {
+            #[cfg(feature = "rhai")]
+            Self::Rhai { script } => script,
+            #[cfg(feature = "handlebars")]
+            Self::Handlebars { template } => template,
+            #[cfg(feature = "tera")]
+            Self::Tera { template } => template,
+            #[cfg(feature = "wasm")]
+            Self::Wasm { plugin } => plugin,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenerateRequest {
+    /// The description file to generate from.
+    pub file: PathBuf,
+    pub generator: Generator,
+    /// The directory the namespace of the file is relative to (like the current directory for
+    /// the command). By default the namespace is taken from `file` as it is.
+    pub base: PathBuf,
+    /// The namespace of the module instead of the one derived from `file`, like `--namespace`.
+    pub namespace: Option<Namespace>,
+    pub defines: Defines,
+    /// Typemaps, later ones override the mappings of earlier ones.
+    pub typemaps: Vec<PathBuf>,
+    pub no_map: bool,
+    pub transforms: Vec<Transform>,
+    /// Data used as raw model instead of the description `file`, like with `--raw`.
+    pub raw: Option<serde_value::Value>,
+    /// Commands run on the output and the files, like `--postprocess`.
+    pub postprocess: Vec<PostProcess>,
+}
+
+impl GenerateRequest {
+    pub fn new(file: impl Into<PathBuf>, generator: Generator) -> Self {
+        Self {
+            file: file.into(),
+            generator,
+            base: PathBuf::new(),
+            namespace: None,
+            defines: Defines::new(),
+            typemaps: Vec::new(),
+            no_map: false,
+            transforms: Vec::new(),
+            raw: None,
+            postprocess: Vec::new(),
+        }
+    }
+
+    fn input(&self) -> BaseInputData {
+        BaseInputData {
+            no_map: self.no_map,
+            // build scripts and other tools shouldn't leave a cache in the current directory
+            no_cache: true,
+            namespace: self.namespace.as_ref().map(ToString::to_string),
+            typemap: self.typemaps.clone(),
+            typemap_report: false,
+            transforms: self.transforms.clone(),
+            raw: self.raw.is_some(),
+            raw_format: crate::helper::RawFormat::Auto,
+            raw_files: Vec::new(),
+            raw_merge: crate::helper::RawMerge::Key,
+            raw_data: self.raw.clone(),
+            timestamp: false,
+            reproducible: false,
+            with_ast: false,
+            file: self.file.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerateOutput {
+    /// The output of the generator, which the command prints or writes to `--out`.
+    pub output: String,
+    /// Files emitted by rhai scripts with `emit(path, content)`, keyed by their relative path.
+    pub files: BTreeMap<String, String>,
+}
+
+impl From<String> for GenerateOutput {
+    fn from(output: String) -> Self {
+        Self {
+            output,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+/// The output options of the generators which take them, the output is returned instead.
+#[cfg(any(feature = "handlebars", feature = "tera", feature = "wasm"))]
+fn no_output() -> BaseOutputData {
+    BaseOutputData {
+        out: None,
+        postprocess: Vec::new(),
+        emit_manifest: None,
+        written: Default::default(),
+        dry_run: None,
+    }
+}
+
+/// Runs the generator of the request and returns its output, without writing any files.
+pub fn generate(request: GenerateRequest) -> anyhow::Result<GenerateOutput> {
+    let input = request.input();
+    let GenerateRequest {
+        generator,
+        base,
+        defines,
+        postprocess,
+        ..
+    } = request;
+
+    let mut output: GenerateOutput = match generator {
+        #[cfg(feature = "rhai")]
+        Generator::Rhai { script } => {
+            let limits = crate::generators::rhai::Limits {
+                max_operations: None,
+                timeout: None,
+                max_string_size: None,
+            };
+            let (output, files) = crate::generators::rhai::run_script(
+                &base,
+                defines,
+                &script,
+                &[],
+                input,
+                false,
+                &limits,
+            )
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+            GenerateOutput { output, files }
+        }
+        #[cfg(feature = "handlebars")]
+        Generator::Handlebars { template } => crate::generators::handlebars::generate_to_string(
+            &base,
+            defines,
+            crate::generators::handlebars::Parameters {
+                template,
+                partials: Vec::new(),
+                strict: false,
+                input,
+                out: no_output(),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .into(),
+        #[cfg(feature = "tera")]
+        Generator::Tera { template } => crate::generators::tera::generate_to_string(
+            &base,
+            defines,
+            crate::generators::tera::Parameters {
+                template,
+                templates: None,
+                input,
+                out: no_output(),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .into(),
+        #[cfg(feature = "wasm")]
+        Generator::Wasm { plugin } => crate::generators::wasm::generate_to_string(
+            &base,
+            defines,
+            crate::generators::wasm::Parameters {
+                wasm: plugin,
+                function: "generate".to_owned(),
+                input,
+                out: no_output(),
+            },
+        )?
+        .into(),
+    };
+    if !output.output.is_empty() {
+        output.output = crate::postprocess::postprocess(&postprocess, None, &output.output)?;
+    }
+    postprocess_files(&postprocess, &mut output.files)?;
+    Ok(output)
+}
+
+#[cfg(feature = "rhai")]
+#[test]
+fn test_generate_raw() {
+    let dir = std::env::temp_dir().join(format!("ssd-api-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("raw.rhai");
+    std::fs::write(&script, "for name in module.names { emit(name + NL); }").unwrap();
+
+    let mut request = GenerateRequest::new("", Generator::rhai(&script));
+    request.raw = Some(serde_value::Value::Map(
+        [(
+            serde_value::Value::String("names".into()),
+            serde_value::Value::Seq(vec![
+                serde_value::Value::String("a".into()),
+                serde_value::Value::String("b".into()),
+            ]),
+        )]
+        .into(),
+    ));
+    assert_eq!(generate(request).unwrap().output, "a\nb\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -7,24 +7,44 @@ pub use ssd_data::*;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(clippy::module_name_repetitions)]
 pub enum AstElement {
+    /// A line of a free comment (`//` or `/* */`).
     Comment(String),
+    /// A line of a doc comment (`///`) of the following data type, enum or service.
+    DocComment(String),
+    /// `version "2.1.0";`, which can only come before all other elements.
+    Version(String),
     Import(Import),
     DataType((String, DataType)),
     Enum((String, Enum)),
-    Service((String, Vec<ServiceAstElement>, Vec<Attribute>)),
+    Service(
+        (
+            String,
+            Vec<ServiceAstElement>,
+            Vec<Attribute>,
+            Option<Visibility>,
+        ),
+    ),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum ServiceAstElement {
+    /// A line of a free comment (`//` or `/* */`).
     Comment(String),
+    /// A line of a doc comment (`///`) of the following dependency, function or event.
+    DocComment(String),
     Dependency(Dependency),
     Function((String, Function)),
     Event((String, Event)),
+    /// A data type declared in the service, which parsing lifts into the module.
+    DataType((String, DataType)),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ComparableAstElement {
     Comment(String),
+    DocComment(String),
+    Version(String),
     Import(Import),
     DataType((String, DataType)),
     Enum((String, Enum)),
@@ -35,13 +55,17 @@ impl From<&AstElement> for ComparableAstElement {
     fn from(value: &AstElement) -> Self {
         match value {
             AstElement::Comment(c) => ComparableAstElement::Comment(c.clone()),
+            AstElement::DocComment(c) => ComparableAstElement::DocComment(c.clone()),
+            AstElement::Version(v) => ComparableAstElement::Version(v.clone()),
             AstElement::Import(i) => ComparableAstElement::Import(i.clone()),
             AstElement::DataType(dt) => ComparableAstElement::DataType(dt.clone()),
             AstElement::Enum(en) => ComparableAstElement::Enum(en.clone()),
-            AstElement::Service((name, svc, attributes)) => ComparableAstElement::Service((
-                name.clone(),
-                raw_service_to_service(svc, attributes),
-            )),
+            AstElement::Service((name, svc, attributes, visibility)) => {
+                ComparableAstElement::Service((
+                    name.clone(),
+                    raw_service_to_service(svc, attributes).with_visibility(*visibility),
+                ))
+            }
         }
     }
 }
@@ -0,0 +1,378 @@
+//! Running generators from Cargo build scripts.
+//!
+//! ```no_run
+//! // build.rs
+//! use ssd::Generator;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     ssd::build()
+//!         .input("api/*.svc")
+//!         .generator(Generator::rhai("generators/rust.rhai"))
+//!         .typemap("generators/rust.tym")
+//!         .run()
+//! }
+//! ```
+//!
+//! Every input file `api/service.svc` is written to `$OUT_DIR/api/service.rs` and can be included
+//! with `include!(concat!(env!("OUT_DIR"), "/api/service.rs"));`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::{generate, GenerateRequest, Generator};
+use crate::helper::{collect_files, map_ordered, typemap_paths, write_atomic, write_files};
+use crate::transform::Transform;
+use ssd_data::Defines;
+
+/// The file in the output directory which records the files generated by the last run.
+const MANIFEST: &str = "ssd-manifest.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+/// The files one generator wrote for one input file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    input: PathBuf,
+    generator: PathBuf,
+    /// The hash of everything the output depends on, see [`Build::hash_inputs`].
+    hash: String,
+    /// Relative to the output directory.
+    outputs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Build {
+    inputs: Vec<PathBuf>,
+    generators: Vec<Generator>,
+    typemaps: Vec<PathBuf>,
+    no_map: bool,
+    transforms: Vec<Transform>,
+    defines: Defines,
+    base: PathBuf,
+    out_dir: Option<PathBuf>,
+    extension: String,
+    rerun_if_changed: bool,
+}
+
+/// Starts configuring the generators to run from a build script.
+pub fn build() -> Build {
+    Build {
+        inputs: Vec::new(),
+        generators: Vec::new(),
+        typemaps: Vec::new(),
+        no_map: false,
+        transforms: Vec::new(),
+        defines: Defines::new(),
+        base: PathBuf::new(),
+        out_dir: None,
+        extension: "rs".to_owned(),
+        rerun_if_changed: true,
+    }
+}
+
+impl Build {
+    /// A description file, a directory (searched recursively for svc files) or a glob pattern.
+    pub fn input(mut self, input: impl Into<PathBuf>) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    pub fn generator(mut self, generator: Generator) -> Self {
+        self.generators.push(generator);
+        self
+    }
+
+    /// Later typemaps override the mappings of earlier ones.
+    pub fn typemap(mut self, typemap: impl Into<PathBuf>) -> Self {
+        self.typemaps.push(typemap.into());
+        self
+    }
+
+    pub fn no_map(mut self) -> Self {
+        self.no_map = true;
+        self
+    }
+
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    pub fn define(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.defines
+            .insert(name.to_owned(), serde_value::Value::String(value.into()));
+        self
+    }
+
+    /// Adds the defines, e.g. the ones loaded from define files.
+    pub fn defines(mut self, defines: Defines) -> Self {
+        self.defines.extend(defines);
+        self
+    }
+
+    /// The directory the namespaces of the input files are relative to.
+    pub fn base(mut self, base: impl Into<PathBuf>) -> Self {
+        self.base = base.into();
+        self
+    }
+
+    /// Where the generated files get written to, `$OUT_DIR` by default.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// The extension of the generated files, `rs` by default.
+    pub fn extension(mut self, extension: &str) -> Self {
+        extension.clone_into(&mut self.extension);
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-changed` for the inputs, which only build scripts need.
+    /// On by default.
+    pub fn rerun_if_changed(mut self, rerun_if_changed: bool) -> Self {
+        self.rerun_if_changed = rerun_if_changed;
+        self
+    }
+
+    /// The path of the output for `file`, relative to the output directory.
+    fn output_name(&self, file: &Path, generator: &Generator) -> PathBuf {
+        // only keep the normal components, so absolute paths stay in the output directory
+        let mut name = file
+            .strip_prefix(&self.base)
+            .unwrap_or(file)
+            .with_extension("")
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect::<PathBuf>();
+        if self.generators.len() > 1 {
+            let stem = generator.source().file_stem().unwrap_or_default();
+            name.as_mut_os_string().push(".");
+            name.as_mut_os_string().push(stem);
+        }
+        name.as_mut_os_string().push(".");
+        name.as_mut_os_string().push(&self.extension);
+        name
+    }
+
+    /// Hashes the input file, the script (or template), the typemaps, the defines and the
+    /// transforms, together with the version of ssd and where the output goes.
+    fn hash_inputs(&self, file: &Path, generator: &Generator) -> anyhow::Result<String> {
+        let mut hasher = Sha256::new();
+        let mut update = |data: &[u8]| {
+            hasher.update(data);
+            hasher.update([0]);
+        };
+        update(env!("CARGO_PKG_VERSION").as_bytes());
+        update(format!("{generator:?}").as_bytes());
+        update(
+            self.output_name(file, generator)
+                .to_string_lossy()
+                .as_bytes(),
+        );
+        update(&std::fs::read(file)?);
+        update(&std::fs::read(generator.source())?);
+        for typemap in typemap_paths(self.no_map, self.typemaps.clone(), Some(generator.source())) {
+            update(typemap.to_string_lossy().as_bytes());
+            if typemap.is_file() {
+                update(&std::fs::read(&typemap)?);
+            }
+        }
+        // the defines are a hash map, so they get sorted to get a stable hash
+        let defines = self.defines.iter().collect::<BTreeMap<_, _>>();
+        update(serde_json::to_string(&defines)?.as_bytes());
+        update(format!("{:?}", self.transforms).as_bytes());
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Runs every generator for every input file and prints `cargo:rerun-if-changed` for all
+    /// inputs, scripts, templates and typemaps (unless turned off with
+    /// [`Build::rerun_if_changed`]).
+    ///
+    /// The output for `dir/file.svc` is written to `<out_dir>/dir/file.<extension>`. With multiple
+    /// generators, the name of the script (or template) is added, e.g. `dir/file.types.rs`.
+    /// Files emitted by rhai scripts are written relative to the output directory.
+    ///
+    /// With the `rayon` feature the generators run in parallel.
+    ///
+    /// The generated files are recorded in `ssd-manifest.json` in the output directory. The next
+    /// run skips generators whose input file, script (or template), typemaps, defines and
+    /// transforms haven't changed, and removes files which aren't generated anymore. Files
+    /// included by scripts or templates aren't tracked.
+    pub fn run(self) -> anyhow::Result<()> {
+        let out_dir = match self.out_dir.clone() {
+            Some(out_dir) => out_dir,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No output directory given and OUT_DIR isn't set.")
+                })?,
+        };
+        if self.generators.is_empty() {
+            anyhow::bail!("No generator given.");
+        }
+
+        let files = collect_files(&self.inputs, "svc")?;
+        let mut watched = files.clone();
+        // new files in the input directories have to trigger a rerun as well
+        for input in &self.inputs {
+            let dir = input
+                .components()
+                .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+                .collect::<PathBuf>();
+            if dir.is_dir() {
+                watched.push(dir);
+            }
+        }
+        for generator in &self.generators {
+            let source = generator.source();
+            watched.push(source.clone());
+            watched.extend(typemap_paths(
+                self.no_map,
+                self.typemaps.clone(),
+                Some(source),
+            ));
+        }
+        if self.rerun_if_changed {
+            for path in watched.iter().filter(|path| path.exists()) {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+
+        let manifest_path = out_dir.join(MANIFEST);
+        let previous = std::fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|manifest| serde_json::from_str::<Manifest>(&manifest).ok())
+            .unwrap_or_default();
+
+        let mut manifest = Manifest::default();
+        let mut jobs = Vec::new();
+        for file in &files {
+            for generator in &self.generators {
+                let hash = self.hash_inputs(file, generator)?;
+                // skip generators whose inputs didn't change since the last run
+                let unchanged = previous.entries.iter().find(|entry| {
+                    entry.input == *file
+                        && entry.generator == *generator.source()
+                        && entry.hash == hash
+                        && entry
+                            .outputs
+                            .iter()
+                            .all(|path| out_dir.join(path).is_file())
+                });
+                if let Some(entry) = unchanged {
+                    manifest.entries.push(entry.clone());
+                } else {
+                    jobs.push((file, generator, hash));
+                }
+            }
+        }
+
+        // generating can run in parallel, the files are written in order afterwards
+        let outputs = map_ordered(&jobs, |(file, generator, _)| {
+            let mut request = GenerateRequest::new(*file, (*generator).clone());
+            request.base.clone_from(&self.base);
+            request.defines.clone_from(&self.defines);
+            request.typemaps.clone_from(&self.typemaps);
+            request.no_map = self.no_map;
+            request.transforms.clone_from(&self.transforms);
+            generate(request)
+        });
+
+        for ((file, generator, hash), output) in jobs.into_iter().zip(outputs) {
+            let output = output?;
+
+            let name = self.output_name(file, generator);
+            write_atomic(&out_dir.join(&name), output.output.as_bytes())?;
+            write_files(&out_dir, &output.files)?;
+
+            manifest.entries.push(ManifestEntry {
+                input: file.clone(),
+                generator: generator.source().clone(),
+                hash,
+                outputs: std::iter::once(name)
+                    .chain(output.files.keys().map(PathBuf::from))
+                    .collect(),
+            });
+        }
+
+        // remove the files of earlier runs which aren't generated anymore
+        let outputs = manifest
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.outputs)
+            .collect::<HashSet<_>>();
+        for stale in previous
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.outputs)
+            .filter(|path| !outputs.contains(path))
+        {
+            let path = out_dir.join(stale);
+            if path.is_file() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        manifest
+            .entries
+            .sort_by(|a, b| (&a.input, &a.generator).cmp(&(&b.input, &b.generator)));
+        std::fs::write(manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "handlebars")]
+#[test]
+fn test_incremental_build() {
+    let dir = std::env::temp_dir().join(format!("ssd-build-test-{}", std::process::id()));
+    let out_dir = dir.join("out");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.svc"), "data A { a: i32, };").unwrap();
+    std::fs::write(dir.join("b.svc"), "data B { b: i32, };").unwrap();
+    let template = dir.join("gen.hbs");
+    std::fs::write(
+        &template,
+        "{{#each module.data_types}}{{this.[0]}}{{/each}}",
+    )
+    .unwrap();
+
+    let run = || {
+        build()
+            .base(&dir)
+            .input(&dir)
+            .generator(Generator::handlebars(&template))
+            .no_map()
+            .out_dir(&out_dir)
+            .run()
+            .unwrap();
+    };
+    run();
+    assert_eq!(std::fs::read_to_string(out_dir.join("a.rs")).unwrap(), "A");
+    assert_eq!(std::fs::read_to_string(out_dir.join("b.rs")).unwrap(), "B");
+
+    // unchanged inputs aren't generated again
+    std::fs::write(out_dir.join("a.rs"), "kept").unwrap();
+    std::fs::remove_file(dir.join("b.svc")).unwrap();
+    run();
+    assert_eq!(
+        std::fs::read_to_string(out_dir.join("a.rs")).unwrap(),
+        "kept"
+    );
+    assert!(!out_dir.join("b.rs").exists());
+
+    std::fs::write(&template, "changed").unwrap();
+    run();
+    assert_eq!(
+        std::fs::read_to_string(out_dir.join("a.rs")).unwrap(),
+        "changed"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,234 @@
+//! Bundling of a module and the modules it imports into one self-contained model, for consumers
+//! which can't resolve imports themselves (e.g. wasm plugins or generators in other languages).
+//!
+//! The bundle has the namespace, imports and services of the root module together with its data
+//! types and enums. The data types and enums of imported modules are added if they are used,
+//! directly or through other types. They are named by their full path (`users::User`), unless
+//! the bundle is flattened, which gives them their plain name (`User`).
+//!
+//! All references to types are rewritten to these names, references to types of imports which
+//! aren't part of the bundle to their full path (`sio::Error` to `std::io::Error` for
+//! `import std::io as sio;`).
+
+use ssd_data::visit::{walk_data_type, walk_service};
+use ssd_data::{MergeError, Namespace, OrderedMap, SsdModule, TypeName, Visitor, VisitorMut};
+
+/// Collects the full paths of the types used by the visited elements.
+struct References<'a> {
+    module: &'a SsdModule,
+    types: Vec<Namespace>,
+}
+
+impl Visitor for References<'_> {
+    fn visit_type_name(&mut self, type_name: &TypeName) {
+        self.types.push(self.module.resolve_type(&type_name.typ));
+    }
+}
+
+/// Rewrites the types to their names in the bundle.
+struct Rename<'a> {
+    module: &'a SsdModule,
+    names: &'a [(Namespace, String)],
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        let path = self.module.resolve_type(&type_name.typ);
+        type_name.typ = match self.names.iter().find(|(full, _)| *full == path) {
+            Some((_, name)) => Namespace::new(name),
+            None => path,
+        };
+    }
+}
+
+/// Returns the index of the module and the name of the type the full path refers to.
+fn find_type(modules: &[SsdModule], path: &Namespace) -> Option<(usize, String)> {
+    let (name, namespace) = path.components.split_last()?;
+    modules
+        .iter()
+        .position(|module| {
+            module.namespace.components == namespace
+                && module.find_type(&Namespace::new(name)).is_some()
+        })
+        .map(|index| (index, name.clone()))
+}
+
+/// The name of a type of an imported module in the bundle.
+fn bundled_name(namespace: &Namespace, name: &str, flatten: bool) -> String {
+    if flatten {
+        name.to_owned()
+    } else {
+        namespace.join(&Namespace::new(name)).to_string()
+    }
+}
+
+/// Bundles the modules as returned by [`load_with_imports`](crate::load_with_imports): the last
+/// one is the root module, see the [module documentation](self).
+///
+/// # Errors
+///
+/// Returns an error if the bundle is flattened and types with the same name are defined
+/// differently.
+///
+/// # Panics
+///
+/// Panics if `modules` is empty.
+pub fn bundle(mut modules: Vec<SsdModule>, flatten: bool) -> Result<SsdModule, MergeError> {
+    let root = modules.len() - 1;
+
+    // the used types of imported modules, starting with the ones used by the root module
+    let mut used: Vec<(usize, String)> = Vec::new();
+    let mut pending: Vec<(usize, Option<String>)> = vec![(root, None)];
+    while let Some((index, name)) = pending.pop() {
+        let module = &modules[index];
+        let mut references = References {
+            module,
+            types: Vec::new(),
+        };
+        match &name {
+            Some(name) => {
+                if let Some(data_type) = module.data_types.get(name) {
+                    walk_data_type(&mut references, name, data_type);
+                }
+            }
+            None => {
+                for (name, data_type) in &module.data_types {
+                    walk_data_type(&mut references, name, data_type);
+                }
+                for (name, service) in &module.services {
+                    walk_service(&mut references, name, service);
+                }
+            }
+        }
+        for path in references.types {
+            match find_type(&modules, &path) {
+                Some(typ) if typ.0 != root && !used.contains(&typ) => {
+                    used.push(typ.clone());
+                    pending.push((typ.0, Some(typ.1)));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let names = modules
+        .iter()
+        .enumerate()
+        .flat_map(|(index, module)| {
+            let types = module.data_types.iter().map(|(name, _)| name);
+            let enums = module.enums.iter().map(|(name, _)| name);
+            types.chain(enums).map(move |name| {
+                let path = module.namespace.join(&Namespace::new(name));
+                (
+                    path,
+                    bundled_name(&module.namespace, name, flatten || index == root),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    for module in &mut modules {
+        let scope = module.clone();
+        Rename {
+            module: &scope,
+            names: &names,
+        }
+        .visit_module_mut(module);
+    }
+
+    // imports of bundled modules aren't needed anymore
+    let namespaces = modules
+        .iter()
+        .map(|module| module.namespace.clone())
+        .collect::<Vec<_>>();
+    let is_bundled = |path: &Namespace| {
+        namespaces
+            .iter()
+            .any(|namespace| path.components.starts_with(&namespace.components))
+    };
+    let mut bundle = modules.pop().expect("the root module");
+    bundle.imports.retain(|import| !is_bundled(&import.path));
+
+    for (index, module) in modules.into_iter().enumerate() {
+        let is_used = |name: &String| used.contains(&(index, name.clone()));
+        let mut types = SsdModule::new(
+            bundle.namespace.clone(),
+            module.imports,
+            OrderedMap::new(),
+            OrderedMap::new(),
+            OrderedMap::new(),
+        );
+        types.imports.retain(|import| !is_bundled(&import.path));
+        for (name, data_type) in module
+            .data_types
+            .into_iter()
+            .filter(|(name, _)| is_used(name))
+        {
+            let name = bundled_name(&module.namespace, &name, flatten);
+            types.data_types.insert(name, data_type);
+        }
+        for (name, en) in module.enums.into_iter().filter(|(name, _)| is_used(name)) {
+            let name = bundled_name(&module.namespace, &name, flatten);
+            types.enums.insert(name, en);
+        }
+        bundle.merge(types)?;
+    }
+    Ok(bundle)
+}
+
+#[test]
+fn test_bundle() {
+    let parse = |content: &str, namespace: &str| {
+        crate::parser::parse(content, Namespace::new(namespace)).unwrap()
+    };
+    let modules = || {
+        vec![
+            parse(
+                "import std::time as t; data User { id: u64, created: t::Instant, role: Role, }; \
+                 enum Role { Admin, }; data Unused {};",
+                "users",
+            ),
+            parse("import users::User; data Item { owner: User, };", "shop::items"),
+            parse(
+                "import users; import shop::items::{Item}; data Order { user: users::User, item: Item, }; \
+                 service Orders { fn get(id: u64) -> Order; };",
+                "shop::orders",
+            ),
+        ]
+    };
+
+    let bundled = bundle(modules(), false).unwrap();
+    assert_eq!(bundled.namespace.to_string(), "shop::orders");
+    // only imports of modules which aren't bundled are kept
+    assert_eq!(bundled.imports.len(), 1);
+    assert_eq!(bundled.imports[0].path.to_string(), "std::time");
+    let names = |module: &SsdModule| {
+        module
+            .data_types
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(module.enums.iter().map(|(name, _)| name.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    assert_eq!(
+        names(&bundled),
+        "Order, users::User, shop::items::Item, users::Role"
+    );
+    let order = &bundled.data_types[0].properties;
+    assert_eq!(order[0].typ.to_string(), "users::User");
+    assert_eq!(order[1].typ.to_string(), "shop::items::Item");
+    let user = &bundled.data_types.get("users::User").unwrap().properties;
+    assert_eq!(user[1].typ.to_string(), "std::time::Instant");
+    assert_eq!(user[2].typ.to_string(), "users::Role");
+
+    let flat = bundle(modules(), true).unwrap();
+    assert_eq!(names(&flat), "Order, User, Item, Role");
+    assert_eq!(flat.data_types[0].properties[0].typ.to_string(), "User");
+
+    let mut conflicting = modules();
+    conflicting[2] = parse(
+        "import users; data User { name: string, }; data Order { user: users::User, };",
+        "shop::orders",
+    );
+    assert!(bundle(conflicting, true).is_err());
+}
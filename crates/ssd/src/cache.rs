@@ -0,0 +1,158 @@
+//! On-disk cache of parsed and transformed modules, so unchanged files don't get parsed again.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use ssd_data::{Defines, Namespace, SsdModule};
+
+use crate::cfg::apply_cfg;
+use crate::exit::{Classify, ErrorCode, WithPath};
+use crate::helper::typemap_paths;
+use crate::options::BaseInputData;
+use crate::parser::{parse_file, parse_file_with_namespace};
+use crate::timings::{self, Phase};
+use crate::transform::transform_module;
+
+/// The directory (relative to the base directory) the cache is stored in.
+pub const CACHE_DIR: &str = ".ssd-cache";
+
+/// Computes the key of a cache entry from everything the resulting module depends on:
+/// the version of ssd, the file and its content, the defines, the namespace, the typemaps and the
+/// transforms.
+fn cache_key(
+    base: &Path,
+    content: &str,
+    defines: &Defines,
+    input: &BaseInputData,
+    script: Option<&PathBuf>,
+) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut update = |data: &[u8]| {
+        hasher.update(data);
+        hasher.update([0]);
+    };
+    update(env!("CARGO_PKG_VERSION").as_bytes());
+    update(base.to_string_lossy().as_bytes());
+    update(input.file.to_string_lossy().as_bytes());
+    update(content.as_bytes());
+    // the defines are a hash map, so they get sorted to get a stable key
+    let defines = defines.iter().collect::<BTreeMap<_, _>>();
+    update(serde_json::to_string(&defines)?.as_bytes());
+    for typemap in typemap_paths(input.no_map, input.typemap.clone(), script) {
+        update(typemap.to_string_lossy().as_bytes());
+        if typemap.is_file() {
+            update(&std::fs::read(&typemap).with_path(&typemap)?);
+        }
+    }
+    update(format!("{:?}", input.namespace).as_bytes());
+    update(format!("{:?}", input.transforms).as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Parses the file, removes the elements whose `#[cfg(...)]` doesn't hold and runs the
+/// transforms (including the typemap).
+///
+/// Unless `--no-cache` is given, the result is stored in [`CACHE_DIR`] and reused as long as
+/// nothing it depends on changes. The cache isn't used with `--typemap-report`, because the
+/// report is created while applying the typemap.
+pub fn load_module(
+    base: &PathBuf,
+    defines: &Defines,
+    input: &BaseInputData,
+    script: Option<&PathBuf>,
+) -> anyhow::Result<SsdModule> {
+    let load = || -> anyhow::Result<SsdModule> {
+        let module = timings::measure(Phase::Parse, Some(&input.file), || match &input.namespace {
+            Some(namespace) => parse_file_with_namespace(&input.file, Namespace::new(namespace)),
+            None => parse_file(base, &input.file),
+        })
+        .map_err(anyhow::Error::from)
+        .classify(ErrorCode::Parse)?;
+        let module = apply_cfg(module, defines);
+        transform_module(module, input, script)
+    };
+    // read before parsing, so a missing file isn't reported as parse error
+    let content = std::fs::read_to_string(&input.file).with_path(&input.file)?;
+    if input.no_cache || input.typemap_report {
+        return load();
+    }
+
+    let timing = timings::span(Phase::Cache, Some(&input.file));
+    let key = cache_key(base, &content, defines, input, script)?;
+    let path = base.join(CACHE_DIR).join(format!("{key}.json"));
+    // entries that can't be read (e.g. from an older format) are simply replaced
+    if let Some(module) = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|cached| serde_json::from_str(&cached).ok())
+    {
+        return Ok(module);
+    }
+    drop(timing);
+
+    let module = load()?;
+    let _timing = timings::span(Phase::Cache, Some(&input.file));
+    // the cache is only an optimization, so failing to write it isn't an error
+    if std::fs::create_dir_all(base.join(CACHE_DIR)).is_ok() {
+        if let Ok(serialized) = serde_json::to_string(&module) {
+            let _ = std::fs::write(path, serialized);
+        }
+    }
+    Ok(module)
+}
+
+/// Removes the cache below `base`.
+pub fn clear(base: &Path) -> anyhow::Result<()> {
+    let dir = base.join(CACHE_DIR);
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cache_key() {
+    let dir = std::env::temp_dir().join(format!("ssd-cache-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("test.svc");
+    std::fs::write(&file, "data Config { name: string, };").unwrap();
+
+    let mut input = BaseInputData {
+        no_map: true,
+        no_cache: false,
+        namespace: None,
+        typemap: Vec::new(),
+        typemap_report: false,
+        transforms: Vec::new(),
+        raw: false,
+        raw_format: crate::helper::RawFormat::Auto,
+        raw_files: Vec::new(),
+        raw_merge: crate::helper::RawMerge::Key,
+        raw_data: None,
+        timestamp: false,
+        reproducible: false,
+        with_ast: false,
+        file: file.clone(),
+    };
+    let defines = Defines::new();
+    let parsed = load_module(&dir, &defines, &input, None).unwrap();
+    assert!(dir.join(CACHE_DIR).is_dir());
+    let cached = load_module(&dir, &defines, &input, None).unwrap();
+    assert_eq!(parsed, cached);
+
+    let key = cache_key(&dir, "", &defines, &input, None).unwrap();
+    assert_ne!(key, cache_key(&dir, " ", &defines, &input, None).unwrap());
+    input
+        .transforms
+        .push(crate::transform::Transform::FlattenImports);
+    assert_ne!(key, cache_key(&dir, "", &defines, &input, None).unwrap());
+
+    input.namespace = Some("my::api".to_owned());
+    let renamed = load_module(&dir, &defines, &input, None).unwrap();
+    assert_eq!(renamed.namespace, Namespace::new("my::api"));
+    assert_eq!(load_module(&dir, &defines, &input, None).unwrap(), renamed);
+
+    clear(&dir).unwrap();
+    assert!(!dir.join(CACHE_DIR).exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,139 @@
+use sha2::{Digest, Sha256};
+
+use crate::ast::{AstElement, Namespace, Radix, ServiceAstElement, SsdModule};
+use crate::parser::raw_to_ssd_file;
+use crate::pretty::pretty;
+
+/// Removes all comments (including docs) and sorts everything whose order carries no meaning.
+///
+/// Imports, data types, enums, services, dependencies, functions and events get sorted by name.
+/// Properties, enum values, arguments and attributes keep their order.
+fn canonicalize(mut module: SsdModule) -> SsdModule {
+    module.imports.sort_by_key(|import| import.path.to_string());
+    module.data_types.sort_keys();
+    module.enums.sort_keys();
+    module.services.sort_keys();
+
+    for (_, dt) in &mut module.data_types {
+        dt.docs.clear();
+        for (_, prop) in &mut dt.properties {
+            prop.comments.clear();
+            prop.docs.clear();
+        }
+    }
+    for (_, en) in &mut module.enums {
+        en.docs.clear();
+        for (_, value) in &mut en.values {
+            value.comments.clear();
+            value.docs.clear();
+            value.radix = Radix::Decimal;
+        }
+    }
+    for (_, service) in &mut module.services {
+        service
+            .dependencies
+            .sort_by_key(|dependency| dependency.name.to_string());
+        service.functions.sort_keys();
+        service.events.sort_keys();
+        service.docs.clear();
+        for dependency in &mut service.dependencies {
+            dependency.comments.clear();
+            dependency.docs.clear();
+        }
+        for (_, function) in &mut service.functions {
+            function.comments.clear();
+            function.docs.clear();
+            for (_, arg) in &mut function.arguments {
+                arg.comments.clear();
+                arg.docs.clear();
+            }
+        }
+        for (_, event) in &mut service.events {
+            event.comments.clear();
+            event.docs.clear();
+            for (_, arg) in &mut event.arguments {
+                arg.comments.clear();
+                arg.docs.clear();
+            }
+        }
+    }
+
+    module
+}
+
+fn module_to_raw(module: SsdModule) -> Vec<AstElement> {
+    let version = module.version.map(AstElement::Version);
+    let imports = module.imports.into_iter().map(AstElement::Import);
+    let data_types = module.data_types.into_iter().map(AstElement::DataType);
+    let enums = module.enums.into_iter().map(AstElement::Enum);
+    let services = module.services.into_iter().map(|(name, service)| {
+        let elements = service
+            .dependencies
+            .into_iter()
+            .map(ServiceAstElement::Dependency)
+            .chain(
+                service
+                    .functions
+                    .into_iter()
+                    .map(ServiceAstElement::Function),
+            )
+            .chain(service.events.into_iter().map(ServiceAstElement::Event))
+            .collect();
+        AstElement::Service((name, elements, service.attributes, service.visibility))
+    });
+    version
+        .into_iter()
+        .chain(imports)
+        .chain(data_types)
+        .chain(enums)
+        .chain(services)
+        .collect()
+}
+
+/// Returns the canonical form of the file, which only changes if the described model changes.
+pub fn canonical_form(raw: &[AstElement]) -> String {
+    let module = raw_to_ssd_file(Namespace::new(""), raw);
+    pretty(&module_to_raw(canonicalize(module)))
+}
+
+/// Returns the sha256 digest (hex encoded) of the canonical form.
+pub fn fingerprint(raw: &[AstElement]) -> String {
+    format!("{:x}", Sha256::digest(canonical_form(raw)))
+}
+
+#[test]
+fn test_fingerprint() {
+    let a = crate::parser::parse_raw(
+        r"
+        import b; import a;
+        /// doc
+        data Rect { x: i32, y: i32, };
+        service S { fn b(); /// doc
+            fn a(x: i32); depends on Log; };
+        ",
+    )
+    .unwrap();
+    let b = crate::parser::parse_raw(
+        r"
+        import a;
+        import b;
+        service S {
+            depends on Log;
+            fn a(x: i32);
+            fn b();
+        };
+        data Rect {
+            x: i32,
+            y: i32,
+        };
+        ",
+    )
+    .unwrap();
+    // the order of properties matters
+    let c = crate::parser::parse_raw(
+        "import a; import b; service S { depends on Log; fn a(x: i32); fn b(); }; data Rect { y: i32, x: i32, };",
+    )
+    .unwrap();
+    assert_eq!(fingerprint(&a), fingerprint(&b));
+    assert_ne!(fingerprint(&a), fingerprint(&c));
+}
@@ -0,0 +1,184 @@
+//! A C API to embed the parser and the rhai generator into other programs, see `include/ssd.h`.
+//!
+//! Every function returns an [`SsdResult`], which holds either the output or an error message
+//! and has to be released with [`ssd_free_result`].
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, UnwindSafe};
+use std::path::Path;
+
+use ssd_data::Namespace;
+
+use crate::api::{generate, GenerateRequest, Generator};
+use crate::helper::write_files;
+
+/// The outcome of a call, either the output or an error message.
+pub struct SsdResult(Result<CString, CString>);
+
+/// Converts the string, cutting it off at the first nul byte.
+fn to_c_string(s: String) -> CString {
+    CString::new(s).unwrap_or_else(|e| {
+        let position = e.nul_position();
+        let mut bytes = e.into_vec();
+        bytes.truncate(position);
+        CString::new(bytes).unwrap_or_default()
+    })
+}
+
+impl SsdResult {
+    /// Runs `f`, panics are turned into errors, so they don't unwind into the caller.
+    fn run(f: impl FnOnce() -> anyhow::Result<String> + UnwindSafe) -> *mut Self {
+        let result = match catch_unwind(f) {
+            Ok(result) => result.map_err(|e| format!("{e:#}")),
+            Err(panic) => Err(panic
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "ssd panicked.".to_string())),
+        };
+        Box::into_raw(Box::new(SsdResult(
+            result.map(to_c_string).map_err(to_c_string),
+        )))
+    }
+}
+
+/// # Safety
+///
+/// `s` has to be null or a valid, nul-terminated string.
+unsafe fn to_str<'a>(s: *const c_char, name: &str) -> anyhow::Result<&'a str> {
+    if s.is_null() {
+        anyhow::bail!("{name} must not be null.");
+    }
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+/// Parses the description and returns the module serialized as json.
+///
+/// # Safety
+///
+/// `content` and `namespace` have to be valid, nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_parse(
+    content: *const c_char,
+    namespace: *const c_char,
+) -> *mut SsdResult {
+    SsdResult::run(|| {
+        let content = to_str(content, "content")?;
+        let namespace = to_str(namespace, "namespace")?;
+        let module = crate::parse(content, Namespace::new(namespace))?;
+        Ok(serde_json::to_string(&module)?)
+    })
+}
+
+/// Runs the rhai script for the description file and returns its output.
+///
+/// `typemap` and `out_dir` can be null. Files emitted by the script are written relative to
+/// `out_dir`, they are discarded without it.
+///
+/// # Safety
+///
+/// All arguments have to be null or valid, nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_generate_rhai(
+    file: *const c_char,
+    script: *const c_char,
+    typemap: *const c_char,
+    out_dir: *const c_char,
+) -> *mut SsdResult {
+    SsdResult::run(|| {
+        let mut request = GenerateRequest::new(
+            to_str(file, "file")?,
+            Generator::rhai(to_str(script, "script")?),
+        );
+        if !typemap.is_null() {
+            request.typemaps.push(to_str(typemap, "typemap")?.into());
+        }
+        let output = generate(request)?;
+        if !out_dir.is_null() {
+            write_files(Path::new(to_str(out_dir, "out_dir")?), &output.files)?;
+        }
+        Ok(output.output)
+    })
+}
+
+/// Returns whether the call succeeded.
+///
+/// # Safety
+///
+/// `result` has to be a result returned by one of the functions, which wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_result_is_ok(result: *const SsdResult) -> bool {
+    result.as_ref().is_some_and(|result| result.0.is_ok())
+}
+
+/// Returns the output of a successful call, null otherwise.
+///
+/// The string belongs to the result and is valid until the result is freed.
+///
+/// # Safety
+///
+/// `result` has to be a result returned by one of the functions, which wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_result_output(result: *const SsdResult) -> *const c_char {
+    match result.as_ref() {
+        Some(SsdResult(Ok(output))) => output.as_ptr(),
+        _ => std::ptr::null(),
+    }
+}
+
+/// Returns the error message of a failed call, null otherwise.
+///
+/// The string belongs to the result and is valid until the result is freed.
+///
+/// # Safety
+///
+/// `result` has to be a result returned by one of the functions, which wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_result_error(result: *const SsdResult) -> *const c_char {
+    match result.as_ref() {
+        Some(SsdResult(Err(error))) => error.as_ptr(),
+        _ => std::ptr::null(),
+    }
+}
+
+/// Releases the result. Passing null does nothing.
+///
+/// # Safety
+///
+/// `result` has to be null or a result returned by one of the functions, which wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ssd_free_result(result: *mut SsdResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+#[test]
+fn test_capi() {
+    unsafe {
+        let result = ssd_parse(c"data Rect { x: i32, };".as_ptr(), c"shapes".as_ptr());
+        assert!(ssd_result_is_ok(result));
+        assert!(ssd_result_error(result).is_null());
+        let output = CStr::from_ptr(ssd_result_output(result)).to_str().unwrap();
+        assert!(output.contains("\"Rect\""));
+        ssd_free_result(result);
+
+        let result = ssd_parse(c"data Rect {".as_ptr(), std::ptr::null());
+        assert!(!ssd_result_is_ok(result));
+        assert!(ssd_result_output(result).is_null());
+        let error = CStr::from_ptr(ssd_result_error(result)).to_str().unwrap();
+        assert_eq!(error, "namespace must not be null.");
+        ssd_free_result(result);
+        ssd_free_result(std::ptr::null_mut());
+    }
+
+    // every exported function has to be declared in the header
+    let header = include_str!("../include/ssd.h");
+    for function in include_str!("capi.rs")
+        .lines()
+        .filter_map(|line| line.strip_prefix("pub unsafe extern \"C\" fn "))
+        .filter_map(|line| line.split('(').next())
+    {
+        assert!(header.contains(&format!("{function}(")), "{function}");
+    }
+}
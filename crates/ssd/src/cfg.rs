@@ -0,0 +1,156 @@
+use serde_value::Value;
+use ssd_data::{Attribute, Defines, SsdModule, VisitorMut};
+
+use crate::inline::CFG_ATTRIBUTE;
+use crate::transform::Filter;
+
+/// Looks up a define. Nested values can be accessed with `::`, e.g. `features::pro`.
+fn lookup<'a>(defines: &'a Defines, name: &str) -> Option<&'a Value> {
+    let mut parts = name.split("::");
+    let mut value = defines.get(parts.next()?)?;
+    for part in parts {
+        let Value::Map(map) = value else {
+            return None;
+        };
+        value = map.get(&Value::String(part.to_owned()))?;
+    }
+    Some(value)
+}
+
+/// Compares a define with the expected value. Numbers and booleans are compared by their text.
+fn has_value(define: &Value, expected: &str) -> bool {
+    match serde_json::to_value(define) {
+        Ok(serde_json::Value::String(value)) => value == expected,
+        Ok(serde_json::Value::Bool(value)) => value.to_string() == expected,
+        Ok(serde_json::Value::Number(value)) => value.to_string() == expected,
+        _ => false,
+    }
+}
+
+/// Whether a define is switched off. `-D NAME=false` is a string, so `"false"` and `"0"` count as
+/// well, while `-D NAME` (an empty string) switches it on.
+fn is_off(define: &Value) -> bool {
+    match define {
+        Value::Bool(value) => !value,
+        Value::String(value) => value == "false" || value == "0",
+        _ => false,
+    }
+}
+
+/// Checks the `#[cfg(...)]` attributes of an element against the defines.
+///
+/// `#[cfg(NAME)]` holds if `NAME` is defined and not `false` (or `"false"`/`"0"`, see
+/// [`is_off`]), `#[cfg(NAME = "value")]` holds
+/// if `NAME` is defined with exactly that value. All conditions of all cfg attributes have to
+/// hold.
+pub fn is_enabled(attributes: &[Attribute], defines: &Defines) -> bool {
+    attributes
+        .iter()
+        .filter(|a| a.name.to_string() == CFG_ATTRIBUTE)
+        .flat_map(|a| &a.parameters)
+        .all(|p| match (lookup(defines, &p.name), &p.value) {
+            (Some(define), Some(value)) => has_value(define, value),
+            (Some(define), None) => !is_off(define),
+            (None, _) => false,
+        })
+}
+
+/// Removes all elements of the module whose cfg conditions don't hold.
+pub fn apply_cfg(mut module: SsdModule, defines: &Defines) -> SsdModule {
+    Filter(|attributes: &[Attribute]| is_enabled(attributes, defines))
+        .visit_module_mut(&mut module);
+    module
+}
+
+#[test]
+fn test_apply_cfg() {
+    let module = crate::parser::parse(
+        r#"
+        #[cfg(PRO)]
+        data License { key: string, };
+        data Config {
+            name: string,
+            #[cfg(VARIANT = "cloud")]
+            region: string,
+        };
+        "#,
+        ssd_data::Namespace::new("test"),
+    )
+    .unwrap();
+
+    let defines = Defines::from([("VARIANT".to_owned(), Value::String("onprem".to_owned()))]);
+    let filtered = apply_cfg(module.clone(), &defines);
+    assert_eq!(filtered.data_types.len(), 1);
+    assert_eq!(filtered.data_types[0].properties.len(), 1);
+
+    let defines = Defines::from([
+        ("PRO".to_owned(), Value::Bool(true)),
+        ("VARIANT".to_owned(), Value::String("cloud".to_owned())),
+    ]);
+    let filtered = apply_cfg(module, &defines);
+    assert_eq!(filtered.data_types.len(), 2);
+    assert_eq!(filtered.data_types[1].properties.len(), 2);
+}
+
+#[test]
+fn test_cfg_with_command_line_defines() {
+    let module = crate::parser::parse(
+        "#[cfg(PRO)] data License { key: string, };",
+        ssd_data::Namespace::new("test"),
+    )
+    .unwrap();
+    let enabled = |value: &str| {
+        let defines =
+            crate::helper::load_defines(&[], vec![("PRO".to_owned(), value.to_owned())]).unwrap();
+        !apply_cfg(module.clone(), &defines).data_types.is_empty()
+    };
+    assert!(enabled(""));
+    assert!(enabled("true"));
+    assert!(enabled("1"));
+    assert!(!enabled("false"));
+    assert!(!enabled("0"));
+}
+
+#[test]
+fn test_cfg_of_lifted_types() {
+    let module = crate::parser::parse(
+        r#"
+        #[cfg(PRO)]
+        pub data A { pos: { x: f32, y: f32, }, };
+        data B {
+            #[cfg(PRO)]
+            size: { w: f32, inner: { h: f32, }, },
+        };
+        #[cfg(PRO)]
+        service S {
+            data Request { page: { index: u32, }, };
+            fn list(request: Request);
+        };
+        "#,
+        ssd_data::Namespace::new("test"),
+    )
+    .unwrap();
+    let names = |module: &SsdModule| {
+        module
+            .data_types
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let enabled = apply_cfg(
+        module.clone(),
+        &Defines::from([("PRO".to_owned(), Value::Bool(true))]),
+    );
+    assert_eq!(
+        names(&enabled),
+        "APos A BSizeInner BSize B SRequestPage SRequest"
+    );
+    assert_eq!(enabled.services.len(), 1);
+
+    let disabled = apply_cfg(module, &Defines::new());
+    assert_eq!(names(&disabled), "B");
+    assert!(disabled.data_types["B"].properties.is_empty());
+    assert!(disabled.services.is_empty());
+}
@@ -0,0 +1,251 @@
+//! `ssd compat`: finds the changes between two versions of a module which break its users.
+//!
+//! Removing a data type, enum, service, property, enum value, function or event is breaking,
+//! as is changing the type of a property, the value of an enum value, or the arguments or the
+//! return type of a function. Adding elements isn't.
+//!
+//! Breaking changes have to come with a new major version (`version "2.0.0";`), or a new minor
+//! version before 1.0.0 (`0.3.0` after `0.2.4`).
+
+use std::fmt::{self, Display};
+
+use ssd_data::{OrderedMap, SsdModule, TypeName};
+
+use crate::pretty::type_to_string;
+
+/// A change which breaks users of the old version of the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakingChange {
+    /// The changed element, e.g. `User.id`.
+    pub element: String,
+    pub description: String,
+}
+
+impl Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.element, self.description)
+    }
+}
+
+fn arguments_to_string(arguments: &OrderedMap<TypeName>) -> String {
+    let arguments = arguments
+        .iter()
+        .map(|(name, typ)| format!("{name}: {}", type_to_string(typ)))
+        .collect::<Vec<_>>();
+    format!("({})", arguments.join(", "))
+}
+
+fn return_type_to_string(return_type: Option<&TypeName>) -> String {
+    return_type.map_or_else(|| "nothing".to_owned(), type_to_string)
+}
+
+#[derive(Default)]
+struct Changes(Vec<BreakingChange>);
+
+impl Changes {
+    fn push(&mut self, element: impl Into<String>, description: impl Into<String>) {
+        self.0.push(BreakingChange {
+            element: element.into(),
+            description: description.into(),
+        });
+    }
+
+    fn changed(&mut self, element: String, what: &str, old: &str, new: &str) {
+        if old != new {
+            self.push(element, format!("changed its {what} from {old} to {new}"));
+        }
+    }
+}
+
+/// Returns the changes from `old` to `new` which break users of `old`, in the order of the
+/// elements in `old`.
+#[must_use]
+pub fn breaking_changes(old: &SsdModule, new: &SsdModule) -> Vec<BreakingChange> {
+    let mut changes = Changes::default();
+
+    for (name, data_type) in &old.data_types {
+        let Some(new_data_type) = new.data_types.get(name) else {
+            changes.push(format!("data {name}"), "was removed");
+            continue;
+        };
+        for (property, typ) in &data_type.properties {
+            let element = format!("{name}.{property}");
+            match new_data_type.properties.get(property) {
+                Some(new_typ) => changes.changed(
+                    element,
+                    "type",
+                    &type_to_string(typ),
+                    &type_to_string(new_typ),
+                ),
+                None => changes.push(element, "was removed"),
+            }
+        }
+    }
+
+    for (name, en) in &old.enums {
+        let Some(new_enum) = new.enums.get(name) else {
+            changes.push(format!("enum {name}"), "was removed");
+            continue;
+        };
+        for (value_name, value) in &en.values {
+            let element = format!("{name}::{value_name}");
+            match new_enum.values.get(value_name) {
+                Some(new_value) if new_value.value != value.value => {
+                    let to_string = |value: Option<i64>| {
+                        value.map_or_else(|| "none".to_owned(), |value| value.to_string())
+                    };
+                    changes.changed(
+                        element,
+                        "value",
+                        &to_string(value.value),
+                        &to_string(new_value.value),
+                    );
+                }
+                Some(_) => {}
+                None => changes.push(element, "was removed"),
+            }
+        }
+    }
+
+    for (name, service) in &old.services {
+        let Some(new_service) = new.services.get(name) else {
+            changes.push(format!("service {name}"), "was removed");
+            continue;
+        };
+        for (function_name, function) in &service.functions {
+            let element = format!("{name}.{function_name}");
+            let Some(new_function) = new_service.functions.get(function_name) else {
+                changes.push(element, "was removed");
+                continue;
+            };
+            changes.changed(
+                element.clone(),
+                "arguments",
+                &arguments_to_string(&function.arguments),
+                &arguments_to_string(&new_function.arguments),
+            );
+            changes.changed(
+                element,
+                "return type",
+                &return_type_to_string(function.return_type.as_ref()),
+                &return_type_to_string(new_function.return_type.as_ref()),
+            );
+        }
+        for (event_name, event) in &service.events {
+            let element = format!("{name}.{event_name}");
+            match new_service.events.get(event_name) {
+                Some(new_event) => changes.changed(
+                    element,
+                    "arguments",
+                    &arguments_to_string(&event.arguments),
+                    &arguments_to_string(&new_event.arguments),
+                ),
+                None => changes.push(element, "was removed"),
+            }
+        }
+    }
+
+    changes.0
+}
+
+/// The part of a version which has to change for breaking changes: the major version, or the
+/// minor version before 1.0.0.
+fn breaking_part(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.split(['.', '-', '+']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+    Some(if major == 0 { (0, minor) } else { (major, 0) })
+}
+
+/// Whether `new` is allowed to contain breaking changes after `old`, see the
+/// [module documentation](self).
+///
+/// # Errors
+///
+/// Fails if one of the versions is missing or doesn't start with numbers.
+pub fn allows_breaking_changes(old: Option<&str>, new: Option<&str>) -> anyhow::Result<bool> {
+    let parse = |version: Option<&str>, which: &str| {
+        let version = version.ok_or_else(|| {
+            anyhow::anyhow!(
+                "The {which} module has no version, declare one with `version \"1.0.0\";`."
+            )
+        })?;
+        breaking_part(version).ok_or_else(|| {
+            anyhow::anyhow!(
+                "The version \"{version}\" of the {which} module isn't a semantic version."
+            )
+        })
+    };
+    Ok(parse(new, "new")? > parse(old, "old")?)
+}
+
+#[test]
+fn test_breaking_changes() {
+    let parse =
+        |content: &str| crate::parser::parse(content, ssd_data::Namespace::new("api")).unwrap();
+    let old = parse(
+        r"
+        data User { id: u64, name: string, tags: list of string, };
+        data Group { id: u64, };
+        enum Role { Admin = 1, User = 2, Guest = 3, };
+        service Users {
+            fn get(id: u64) -> User;
+            fn delete(id: u64);
+            fn list() -> list of User;
+            event created(user: User);
+        };
+        ",
+    );
+    let new = parse(
+        r"
+        data User { id: string, tags: list of string, email: string, };
+        enum Role { Admin = 1, User = 4, Owner = 5, };
+        service Users {
+            fn get(id: u64, full: bool) -> User;
+            fn delete(id: u64) -> bool;
+            fn list() -> list of User;
+            fn count() -> u64;
+        };
+        ",
+    );
+
+    let changes = breaking_changes(&old, &new);
+    let changes = changes.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            "User.id changed its type from u64 to string",
+            "User.name was removed",
+            "data Group was removed",
+            "Role::User changed its value from 2 to 4",
+            "Role::Guest was removed",
+            "Users.get changed its arguments from (id: u64) to (id: u64, full: bool)",
+            "Users.delete changed its return type from nothing to bool",
+            "Users.created was removed",
+        ]
+    );
+    assert!(breaking_changes(&new, &new).is_empty());
+}
+
+#[test]
+fn test_allows_breaking_changes() {
+    let allows = |old, new| allows_breaking_changes(Some(old), Some(new)).unwrap();
+    assert!(allows("1.2.3", "2.0.0"));
+    assert!(!allows("1.2.3", "1.3.0"));
+    assert!(!allows("2.0.0", "1.9.0"));
+    assert!(allows("0.2.4", "0.3.0"));
+    assert!(!allows("0.2.4", "0.2.5"));
+    assert!(allows("0.9.1", "1.0.0-rc.1"));
+    assert!(allows("1", "2"));
+
+    let error = allows_breaking_changes(None, Some("2.0.0")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The old module has no version, declare one with `version \"1.0.0\";`."
+    );
+    let error = allows_breaking_changes(Some("1.0.0"), Some("next")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The version \"next\" of the new module isn't a semantic version."
+    );
+}
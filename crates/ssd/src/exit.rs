@@ -0,0 +1,205 @@
+//! The codes of the errors of the CLI, so scripts wrapping it can branch on the kind of failure.
+//!
+//! Every error is printed with its code (e.g. `Error[E0002]: ...`) and ends the run with the exit
+//! code of its class. Both are stable, new codes only get added:
+//!
+//! | Code | Exit code | Error |
+//! | --- | --- | --- |
+//! | E0001 | 1 | Other errors, e.g. invalid combinations of arguments |
+//! | E0002 | 3 | A description file can't be parsed |
+//! | E0003 | 3 | Modules import each other |
+//! | E0004 | 3 | Raw data, a define file or a typemap can't be parsed |
+//! | E0005 | 4 | The data doesn't conform to the model (`ssd validate`) |
+//! | E0006 | 4 | `ssd check` found errors or denied lints |
+//! | E0007 | 5 | A generator (script, template, plugin, program or post-processing) failed |
+//! | E0008 | 6 | Files aren't formatted (`ssd pretty --check`) |
+//! | E0009 | 6 | Results differ from the expected ones (`ssd test`, `ssd test-script`, `ssd verify`) |
+//! | E0010 | 7 | A file can't be read or written |
+//!
+//! Invalid arguments are reported by clap with the exit code 2.
+//!
+//! The code of an error can't be found by its type once it was converted to a boxed error, so
+//! the places which know what failed [`Classify`] the error before passing it on. The first
+//! classified error of a run wins, because it's the closest to the cause.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::imports::ImportCycle;
+use crate::parser::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Other,
+    Parse,
+    ImportCycle,
+    InvalidData,
+    Violations,
+    CheckFailed,
+    Generator,
+    Unformatted,
+    Mismatch,
+    Io,
+}
+
+impl ErrorCode {
+    /// The stable identifier of the error, e.g. `E0002`.
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            ErrorCode::Other => "E0001",
+            ErrorCode::Parse => "E0002",
+            ErrorCode::ImportCycle => "E0003",
+            ErrorCode::InvalidData => "E0004",
+            ErrorCode::Violations => "E0005",
+            ErrorCode::CheckFailed => "E0006",
+            ErrorCode::Generator => "E0007",
+            ErrorCode::Unformatted => "E0008",
+            ErrorCode::Mismatch => "E0009",
+            ErrorCode::Io => "E0010",
+        }
+    }
+
+    #[must_use]
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorCode::Other => 1,
+            ErrorCode::Parse | ErrorCode::ImportCycle | ErrorCode::InvalidData => 3,
+            ErrorCode::Violations | ErrorCode::CheckFailed => 4,
+            ErrorCode::Generator => 5,
+            ErrorCode::Unformatted | ErrorCode::Mismatch => 6,
+            ErrorCode::Io => 7,
+        }
+    }
+
+    /// The code of the error or one of its sources, if its type tells what failed.
+    fn of(error: &(dyn Error + 'static)) -> Option<Self> {
+        std::iter::successors(Some(error), |&error| error.source()).find_map(|error| {
+            if error.is::<ParseError>() {
+                Some(ErrorCode::Parse)
+            } else if error.is::<ImportCycle>() {
+                Some(ErrorCode::ImportCycle)
+            } else if error.is::<std::io::Error>() || error.is::<IoError>() {
+                Some(ErrorCode::Io)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+thread_local! {
+    static CLASSIFIED: Cell<Option<ErrorCode>> = const { Cell::new(None) };
+}
+
+fn record(code: ErrorCode) {
+    if CLASSIFIED.get().is_none() {
+        CLASSIFIED.set(Some(code));
+    }
+}
+
+/// Returns the code of the error which ended the run.
+#[must_use]
+pub fn code_of(error: &(dyn Error + 'static)) -> ErrorCode {
+    CLASSIFIED
+        .get()
+        .or_else(|| ErrorCode::of(error))
+        .unwrap_or(ErrorCode::Other)
+}
+
+/// Ends the run with the exit code of a failure which was already reported, e.g. the violations
+/// printed by `ssd validate`.
+pub fn fail(code: ErrorCode) -> ! {
+    std::process::exit(code.exit_code().into())
+}
+
+/// Records the code of an error, see the [module documentation](self).
+pub trait Classify {
+    /// Records `code` for the error, unless its type tells what failed (e.g. io errors) or an
+    /// error was classified before.
+    #[must_use]
+    fn classify(self, code: ErrorCode) -> Self;
+}
+
+impl<T> Classify for anyhow::Result<T> {
+    fn classify(self, code: ErrorCode) -> Self {
+        if let Err(error) = &self {
+            record(ErrorCode::of(&**error).unwrap_or(code));
+        }
+        self
+    }
+}
+
+impl<T> Classify for Result<T, Box<dyn Error>> {
+    fn classify(self, code: ErrorCode) -> Self {
+        if let Err(error) = &self {
+            record(ErrorCode::of(error.as_ref()).unwrap_or(code));
+        }
+        self
+    }
+}
+
+/// An io error together with the file it happened for.
+#[derive(Debug)]
+pub struct IoError {
+    path: PathBuf,
+    error: std::io::Error,
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+// the io error is part of the message, so it isn't the source
+impl Error for IoError {}
+
+/// Adds the path to the io error and classifies it as [`ErrorCode::Io`], for the places where
+/// the error would otherwise only show up as e.g. a failing generator.
+pub fn io_error(path: &Path, error: std::io::Error) -> anyhow::Error {
+    record(ErrorCode::Io);
+    anyhow::Error::new(IoError {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Adds the path to io errors and classifies them, see [`io_error`].
+pub trait WithPath<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> anyhow::Result<T>;
+}
+
+impl<T> WithPath<T> for std::io::Result<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> anyhow::Result<T> {
+        self.map_err(|error| io_error(path.as_ref(), error))
+    }
+}
+
+#[test]
+fn test_classify() {
+    let parse = || -> Result<(), Box<dyn Error>> {
+        crate::parser::parse("data A {", ssd_data::Namespace::new("a"))?;
+        Ok(())
+    };
+    let error = parse().unwrap_err();
+    assert_eq!(ErrorCode::of(error.as_ref()), Some(ErrorCode::Parse));
+
+    let read = || -> anyhow::Result<String> {
+        let content = std::fs::read_to_string("/ssd/missing.svc")?;
+        Ok(content)
+    };
+    let error = read().unwrap_err();
+    assert_eq!(ErrorCode::of(&*error), Some(ErrorCode::Io));
+    let boxed: Box<dyn Error> = error.into();
+    // the type of the error is lost, so it has to be classified before
+    assert_eq!(ErrorCode::of(boxed.as_ref()), None);
+    assert_eq!(code_of(boxed.as_ref()), ErrorCode::Other);
+
+    let _ = read().classify(ErrorCode::InvalidData);
+    let _ = Err::<(), _>(anyhow::anyhow!("script failed")).classify(ErrorCode::Generator);
+    assert_eq!(code_of(boxed.as_ref()), ErrorCode::Io);
+    assert_eq!(ErrorCode::Io.exit_code(), 7);
+}
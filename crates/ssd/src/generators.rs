@@ -1,11 +1,50 @@
+#[cfg(feature = "_bin")]
+pub mod exec;
+
 #[cfg(feature = "handlebars")]
-pub(crate) mod handlebars;
+pub mod handlebars;
+
+#[cfg(feature = "js")]
+pub mod js;
+
+#[cfg(feature = "lua")]
+pub mod lua;
+
+#[cfg(feature = "python")]
+pub mod python;
 
 #[cfg(feature = "rhai")]
-pub(crate) mod rhai;
+pub mod rhai;
 
 #[cfg(feature = "tera")]
-pub(crate) mod tera;
+pub mod tera;
 
 #[cfg(feature = "wasm")]
-pub(crate) mod wasm;
+pub mod wasm;
+
+#[cfg(feature = "web")]
+pub mod wasm_web;
+
+/// The function of wasm plugins which gets called by default.
+#[cfg(any(feature = "wasm", feature = "web"))]
+pub(crate) const DEFAULT_FUNCTION: &str = "generate";
+
+/// Returns the role of an entry point following the `generate_<role>` convention.
+#[cfg(any(feature = "wasm", feature = "web"))]
+pub(crate) fn role_of(function: &str) -> Option<&str> {
+    function
+        .strip_prefix(DEFAULT_FUNCTION)
+        .and_then(|role| role.strip_prefix('_'))
+        .filter(|role| !role.is_empty())
+}
+
+#[cfg(any(feature = "wasm", feature = "web"))]
+#[test]
+fn test_role_of() {
+    assert_eq!(role_of("generate_header"), Some("header"));
+    assert_eq!(role_of("generate_source_file"), Some("source_file"));
+    assert_eq!(role_of("generate"), None);
+    assert_eq!(role_of("generate_"), None);
+    assert_eq!(role_of("generateheader"), None);
+    assert_eq!(role_of("render_header"), None);
+}
@@ -0,0 +1,150 @@
+//! Generators running as external processes, which can be written in any language.
+//!
+//! The process gets the model as json on stdin, in the same format as wasm plugins
+//! (`{"module": ..., "defines": ...}`, or `{"raw": ..., "defines": ...}` with `--raw`), and
+//! answers with a json object on stdout:
+//! ```json
+//! {"output": "...", "files": {"path/file.rs": "..."}, "error": null}
+//! ```
+//! All fields are optional. Messages for the user can be written to stderr.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use clap::Parser;
+use serde::Deserialize;
+use ssd_data::{Defines, RawModel, SsdModel};
+
+use crate::cache::load_module;
+use crate::options::{BaseInputData, BaseOutputData};
+
+#[derive(Debug, Parser)]
+pub struct Parameters {
+    /// The program to run, e.g. `./my-generator`.
+    pub program: PathBuf,
+    #[clap(long = "arg", allow_hyphen_values = true)]
+    /// Arguments passed to the program. Can be given multiple times.
+    pub args: Vec<String>,
+    #[clap(flatten)]
+    pub input: BaseInputData,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+    #[clap(long)]
+    /// The directory where the files of the response get written to.
+    pub out_dir: Option<PathBuf>,
+}
+
+/// The answer of the program.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Response {
+    output: String,
+    files: BTreeMap<String, String>,
+    error: Option<String>,
+}
+
+fn run(program: &PathBuf, args: &[String], request: &[u8]) -> anyhow::Result<Response> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Could not start {}: {e}", program.display()))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // written from another thread, so programs answering while reading can't block on a full pipe
+    let result = std::thread::scope(|scope| {
+        scope.spawn(move || {
+            // the program may exit without reading everything, e.g. on errors
+            let _ = stdin.write_all(request);
+        });
+        child.wait_with_output()
+    })?;
+    if !result.status.success() {
+        anyhow::bail!("{} failed ({}).", program.display(), result.status);
+    }
+    let response: Response = serde_json::from_slice(&result.stdout)
+        .map_err(|e| anyhow::anyhow!("{} answered with invalid json: {e}", program.display()))?;
+    if let Some(error) = response.error {
+        anyhow::bail!("{}: {error}", program.display());
+    }
+    Ok(response)
+}
+
+pub fn generate(base: &PathBuf, defines: Defines, params: Parameters) -> anyhow::Result<()> {
+    let out = params.out.clone();
+    let out_dir = params.out_dir.clone();
+    let (output, files) = generate_to_string(base, defines, params)?;
+    if !output.is_empty() {
+        out.write(&output)?;
+    }
+    if !files.is_empty() {
+        let Some(out_dir) = out_dir else {
+            anyhow::bail!("The generator returned files, but no --out-dir was given.");
+        };
+        out.write_files(&out_dir, files)?;
+    }
+    Ok(())
+}
+
+/// Runs the program and returns its output and files instead of writing them.
+pub fn generate_to_string(
+    base: &PathBuf,
+    defines: Defines,
+    Parameters {
+        program,
+        args,
+        input,
+        out: _,
+        out_dir: _,
+    }: Parameters,
+) -> anyhow::Result<(String, BTreeMap<String, String>)> {
+    let request = if input.raw {
+        let meta = input.meta("");
+        let raw = input.load_raw()?;
+        serde_json::to_vec(&RawModel { raw, defines, meta })?
+    } else {
+        let module = load_module(base, &defines, &input, None)?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
+        serde_json::to_vec(&SsdModel {
+            module,
+            defines,
+            meta,
+            ast,
+        })?
+    };
+    let Response { output, files, .. } = run(&program, &args, &request)?;
+    Ok((output, files))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exec() {
+    let run_sh = |script: &str| {
+        let args = ["-c".to_string(), script.to_string()];
+        run(&PathBuf::from("sh"), &args, br#"{"module": {}}"#)
+    };
+
+    let response =
+        run_sh(r#"cat > /dev/null; echo '{"output": "out", "files": {"a.txt": "a"}}'"#).unwrap();
+    assert_eq!(response.output, "out");
+    assert_eq!(response.files["a.txt"], "a");
+
+    let error = run_sh(r#"echo '{"error": "no types"}'"#).unwrap_err();
+    assert_eq!(error.to_string(), "sh: no types");
+    assert!(run_sh("exit 1").is_err());
+    assert!(run_sh("echo no json").is_err());
+
+    // more than a pipe buffer in both directions, answered before reading the request
+    let request = format!(
+        r#"{{"module": {{}}, "padding": "{}"}}"#,
+        " ".repeat(1 << 20)
+    );
+    let script = r#"printf '{"output": "'; head -c 1048576 /dev/zero | tr '\0' a; printf '"}'; cat > /dev/null"#;
+    let args = ["-c".to_string(), script.to_string()];
+    let response = run(&PathBuf::from("sh"), &args, request.as_bytes()).unwrap();
+    assert_eq!(response.output.len(), 1 << 20);
+}
@@ -1,53 +1,386 @@
+#[cfg(feature = "_bin")]
 use crate::options::{BaseInputData, BaseOutputData};
+#[cfg(feature = "_bin")]
 use clap::Parser;
+#[cfg(feature = "_bin")]
+use serde::Serialize;
+use serde_json::Value as Json;
+#[cfg(feature = "web")]
 use std::collections::HashMap;
 use std::error::Error;
+use std::path::Path;
+#[cfg(feature = "_bin")]
 use std::path::PathBuf;
 
-use crate::parser::parse_file;
-use ssd_data::{RawModel, SsdModel};
+#[cfg(feature = "_bin")]
+use crate::cache::load_module;
+#[cfg(feature = "_bin")]
+use crate::exit::{io_error, WithPath};
+use ssd_data::find_attribute;
+#[cfg(feature = "_bin")]
+use ssd_data::{Defines, RawModel, SsdModel};
 
-use crate::helper::parse_raw_data;
-use crate::helper::{print_or_write, update_types_from_file};
+#[cfg(feature = "_bin")]
+use crate::helper::load_typemap;
+use crate::helper::{
+    json_attributes as attributes_of, json_sort_data_types, json_to_string, Diagnostic, TypeMap,
+};
+use crate::idents::{
+    to_camel_case, to_kebab_case, to_pascal_case, to_screaming_snake_case, to_snake_case,
+};
 
-use handlebars::Handlebars;
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError,
+    ScopedJson,
+};
 
+#[cfg(feature = "_bin")]
 #[derive(Debug, Parser)]
 pub struct Parameters {
     /// The template to use to generate the file.
+    ///
+    /// If the path does not exist, it's used as the name of a template from the partials.
     pub template: PathBuf,
+    #[clap(long)]
+    /// Directories containing additional templates (*.hbs), which can be used as partials.
+    ///
+    /// Templates get registered with their path relative to the directory, without extension.
+    /// e.g.: `partials/types/decl.hbs` can be used with `{{> types/decl}}`.
+    pub partials: Vec<PathBuf>,
+    #[clap(long)]
+    /// Fail with a diagnostic when the template accesses a field that does not exist,
+    /// instead of rendering an empty string.
+    pub strict: bool,
     #[clap(flatten)]
     pub input: BaseInputData,
     #[clap(flatten)]
     pub out: BaseOutputData,
 }
 
+fn value_to_string(value: &Json) -> String {
+    json_to_string(value, "::")
+}
+
+handlebars_helper!(snake_case: |v: Json| to_snake_case(&value_to_string(v)));
+handlebars_helper!(camel_case: |v: Json| to_camel_case(&value_to_string(v)));
+handlebars_helper!(pascal_case: |v: Json| to_pascal_case(&value_to_string(v)));
+handlebars_helper!(kebab_case: |v: Json| to_kebab_case(&value_to_string(v)));
+handlebars_helper!(screaming_snake_case: |v: Json| to_screaming_snake_case(&value_to_string(v)));
+
+handlebars_helper!(join: |list: array, sep: str| {
+    list.iter().map(value_to_string).collect::<Vec<_>>().join(sep)
+});
+
+handlebars_helper!(indent: |text: str, count: u64| {
+    #[allow(clippy::cast_possible_truncation)]
+    let prefix = " ".repeat(count as usize);
+    text.lines()
+        .map(|l| if l.is_empty() { String::new() } else { format!("{prefix}{l}") })
+        .collect::<Vec<_>>()
+        .join("\n")
+});
+
+handlebars_helper!(has_attribute: |element: Json, name: str| {
+    find_attribute(&attributes_of(element), name).is_some()
+});
+
+handlebars_helper!(attribute_param: |element: Json, name: str, param: str| {
+    find_attribute(&attributes_of(element), name)
+        .and_then(|a| a.find_parameter(param))
+        .and_then(|p| p.value.clone())
+        .map_or(Json::Null, Json::String)
+});
+
+/// Looks up a type in the typemap, returning the type unchanged if there is no mapping.
+struct MapType(TypeMap);
+
+impl HelperDef for MapType {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let typ = h
+            .param(0)
+            .map(|p| value_to_string(p.value()))
+            .ok_or_else(|| RenderError::new("map_type requires the type as parameter"))?;
+        let mapped = self.0.get(&typ).unwrap_or(typ);
+        Ok(ScopedJson::Derived(Json::String(mapped)))
+    }
+}
+
+/// Sorts the data types given as parameter topologically.
+struct TopologicallySorted;
+
+impl HelperDef for TopologicallySorted {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let data_types = h.param(0).ok_or_else(|| {
+            RenderError::new("topologically_sorted requires the data types as parameter")
+        })?;
+        let sorted = json_sort_data_types(data_types.value())
+            .map_err(|e| RenderError::new(e.to_string()))?;
+        Ok(ScopedJson::Derived(sorted))
+    }
+}
+
+fn build_registry(typemap: TypeMap) -> Handlebars<'static> {
+    let mut reg = Handlebars::new();
+    reg.register_helper("snake_case", Box::new(snake_case));
+    reg.register_helper("camel_case", Box::new(camel_case));
+    reg.register_helper("pascal_case", Box::new(pascal_case));
+    reg.register_helper("kebab_case", Box::new(kebab_case));
+    reg.register_helper("screaming_snake_case", Box::new(screaming_snake_case));
+    reg.register_helper("join", Box::new(join));
+    reg.register_helper("indent", Box::new(indent));
+    reg.register_helper("has_attribute", Box::new(has_attribute));
+    reg.register_helper("attribute_param", Box::new(attribute_param));
+    reg.register_helper("map_type", Box::new(MapType(typemap)));
+    reg.register_helper("topologically_sorted", Box::new(TopologicallySorted));
+    reg
+}
+
+#[cfg(feature = "_bin")]
+fn register_partials(reg: &mut Handlebars, dir: &Path) -> Result<(), Box<dyn Error>> {
+    for path in glob::glob(&dir.join("**/*.hbs").to_string_lossy())? {
+        let path = path?;
+        let name = path
+            .strip_prefix(dir)?
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let source = std::fs::read_to_string(&path).with_path(&path)?;
+        reg.register_template_string(&name, source)?;
+    }
+    Ok(())
+}
+
+/// Formats a render error as a diagnostic pointing at the offending template line.
+fn diagnostic(err: &RenderError, template: &Path, source: Option<&str>) -> Diagnostic {
+    let name = err
+        .template_name
+        .clone()
+        .unwrap_or_else(|| template.display().to_string());
+    let mut result = err.desc.clone();
+    if let (Some(line), Some(column)) = (err.line_no, err.column_no) {
+        result.push_str(&format!("\n  --> {name}:{line}:{column}"));
+        let source_line = source
+            .filter(|_| err.template_name.is_none())
+            .and_then(|source| source.lines().nth(line.saturating_sub(1)));
+        if let Some(source_line) = source_line {
+            let width = line.to_string().len();
+            let padding = " ".repeat(width);
+            let marker = " ".repeat(column.saturating_sub(1));
+            result.push_str(&format!(
+                "\n{padding} |\n{line} | {source_line}\n{padding} | {marker}^"
+            ));
+        }
+    } else {
+        result.push_str(&format!("\n  --> {name}"));
+    }
+    Diagnostic(result)
+}
+
+#[cfg(feature = "_bin")]
+fn render<T: Serialize>(
+    reg: &Handlebars,
+    template: &Path,
+    data: &T,
+) -> Result<String, Box<dyn Error>> {
+    if template.is_file() {
+        let source = std::fs::read_to_string(template).with_path(template)?;
+        reg.render_template(&source, data)
+            .map_err(|e| diagnostic(&e, template, Some(&source)).into())
+    } else {
+        let name = template.to_string_lossy();
+        if !reg.has_template(&name) {
+            let error = std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "neither a template file nor a partial",
+            );
+            return Err(io_error(template, error).into());
+        }
+        reg.render(&name, data)
+            .map_err(|e| diagnostic(&e, template, None).into())
+    }
+}
+
+#[cfg(feature = "_bin")]
 pub fn generate(
     base: &PathBuf,
-    defines: HashMap<String, String>,
+    defines: Defines,
+    params: Parameters,
+) -> Result<(), Box<dyn Error>> {
+    let out = params.out.clone();
+    let result = generate_to_string(base, defines, params)?;
+    out.write(&result)?;
+
+    Ok(())
+}
+
+/// Renders the template and returns the result instead of writing it.
+#[cfg(feature = "_bin")]
+pub fn generate_to_string(
+    base: &PathBuf,
+    defines: Defines,
     Parameters {
         input,
-        out,
+        out: _,
         template,
+        partials,
+        strict,
     }: Parameters,
-) -> Result<(), Box<dyn Error>> {
-    let reg = Handlebars::new();
+) -> Result<String, Box<dyn Error>> {
+    let typemap = load_typemap(input.no_map, input.typemap.clone(), Some(&template))?;
+    let mut reg = build_registry(typemap);
+    reg.set_strict_mode(strict);
+    for dir in &partials {
+        register_partials(&mut reg, dir)?;
+    }
     let result = if input.raw {
-        let raw = parse_raw_data(input.file)?;
+        let meta = input.meta("");
+        let raw = input.load_raw()?;
 
-        reg.render_template(
-            &std::fs::read_to_string(template)?,
-            &RawModel { raw, defines },
-        )?
+        render(&reg, &template, &RawModel { raw, defines, meta })?
     } else {
-        let module = parse_file(base, &input.file)?;
-        let module = update_types_from_file(module, input.no_map, input.typemap, Some(&template))?;
-        reg.render_template(
-            &std::fs::read_to_string(template)?,
-            &SsdModel { module, defines },
+        let module = load_module(base, &defines, &input, Some(&template))?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
+        render(
+            &reg,
+            &template,
+            &SsdModel {
+                module,
+                defines,
+                meta,
+                ast,
+            },
         )?
     };
-    print_or_write(out.out, &result)?;
 
-    Ok(())
+    Ok(result)
+}
+
+/// Renders the template for the description in the playground, the typemap is applied to the
+/// model and used by `map_type`.
+#[cfg(feature = "web")]
+// only exported by the library, the binary gets the feature through the workspace
+pub fn generate_web(
+    defines: HashMap<String, String>,
+    namespace: &str,
+    template: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<String, Box<dyn Error>> {
+    let reg = build_registry(crate::helper::parse_typemap(typemap)?);
+    let model = crate::helper::load_web_model(defines, namespace, typemap, data)?;
+    reg.render_template(template, &model)
+        .map_err(|e| diagnostic(&e, Path::new("template"), Some(template)).into())
+}
+
+#[test]
+fn test_helpers() {
+    let module = crate::parser::parse(
+        r#"
+        data Group { owner: UserAccount, };
+        #[table(name = "users", temporary)]
+        data UserAccount { id: u64, };
+        "#,
+        ssd_data::Namespace::new("accounts::users"),
+    )
+    .unwrap();
+    let reg = build_registry(crate::helper::parse_typemap(r#"u64 = "number""#).unwrap());
+    let data = serde_json::json!({ "module": module, "text": "a\n\nb" });
+    let render = |template: &str| reg.render_template(template, &data).unwrap();
+
+    assert_eq!(
+        render(
+            "{{#each module.data_types}}{{snake_case this.[0]}} {{camel_case this.[0]}} \
+             {{pascal_case this.[0]}} {{kebab_case this.[0]}} \
+             {{screaming_snake_case this.[0]}}|{{/each}}"
+        ),
+        "group group Group group GROUP|user_account userAccount UserAccount user-account \
+         USER_ACCOUNT|"
+    );
+    assert_eq!(
+        render(r#"{{join module.namespace.components "::"}}"#),
+        "accounts::users"
+    );
+    assert_eq!(render("{{indent text 2}}"), "  a\n\n  b");
+
+    assert_eq!(
+        render(
+            "{{#with (lookup (lookup module.data_types 1) 1)}}{{has_attribute this \"table\"}} \
+             {{has_attribute this \"view\"}} {{attribute_param this \"table\" \"name\"}}|\
+             {{attribute_param this \"table\" \"temporary\"}}{{/with}}"
+        ),
+        "true false users|"
+    );
+    assert_eq!(
+        render(
+            "{{#each module.data_types}}{{#each this.[1].properties}}{{map_type this.[1].typ}} \
+             {{/each}}{{/each}}{{map_type \"string\"}}"
+        ),
+        "UserAccount number string"
+    );
+    assert_eq!(
+        render("{{#each (topologically_sorted module.data_types)}}{{this.[0]}} {{/each}}"),
+        "UserAccount Group "
+    );
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_partials() {
+    let dir = std::env::temp_dir().join(format!("ssd-partials-{}", std::process::id()));
+    let partials = dir.join("partials");
+    std::fs::create_dir_all(partials.join("types")).unwrap();
+    std::fs::write(partials.join("types/decl.hbs"), "struct {{name}};").unwrap();
+    std::fs::write(partials.join("entry.hbs"), "// entry\n{{> types/decl}}").unwrap();
+    let main = dir.join("main.hbs");
+    std::fs::write(&main, "{{> types/decl}}").unwrap();
+
+    let mut reg = build_registry(TypeMap::default());
+    register_partials(&mut reg, &partials).unwrap();
+    let data = serde_json::json!({ "name": "User" });
+    assert_eq!(render(&reg, &main, &data).unwrap(), "struct User;");
+    // a template which isn't a file is looked up in the partials
+    assert_eq!(
+        render(&reg, Path::new("entry"), &data).unwrap(),
+        "// entry\nstruct User;"
+    );
+    let error = render(&reg, Path::new("missing"), &data).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("neither a template file nor a partial"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_strict_diagnostic() {
+    let template = "struct {{name}} {\n    {{missing}}\n}";
+    let data = serde_json::json!({ "name": "User" });
+    let mut reg = build_registry(TypeMap::default());
+    assert_eq!(
+        reg.render_template(template, &data).unwrap(),
+        "struct User {\n    \n}"
+    );
+
+    reg.set_strict_mode(true);
+    let error = reg.render_template(template, &data).unwrap_err();
+    let diagnostic = diagnostic(&error, Path::new("user.hbs"), Some(template)).to_string();
+    assert_eq!(
+        diagnostic,
+        "Variable \"missing\" not found in strict mode.\n  --> user.hbs:2:5\n  |\n\
+         2 |     {{missing}}\n  |     ^"
+    );
 }
@@ -0,0 +1,236 @@
+use crate::options::{BaseInputData, BaseOutputData};
+use clap::Parser;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rquickjs::context::EvalOptions;
+use rquickjs::function::{Opt, Rest};
+use rquickjs::{CatchResultExt, Coerced, Context, Ctx, Function, Object, Runtime, Value};
+use ssd_data::{Defines, RawModel, SsdModel};
+
+use crate::cache::load_module;
+use crate::helper::{not_reproducible, Diagnostic};
+
+/// Files emitted by a script through `emit(path, content)`, keyed by their relative path.
+type EmittedFiles = BTreeMap<String, String>;
+
+#[derive(Debug, Parser)]
+pub struct Parameters {
+    /// The script to use to generate the file.
+    ///
+    /// TypeScript has to be compiled to JavaScript (e.g. with `tsc`) first.
+    pub script: PathBuf,
+    #[clap(long, short)]
+    /// Enables debug mode (print function in the script).
+    pub debug: bool,
+    #[clap(flatten)]
+    pub input: BaseInputData,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+    #[clap(long)]
+    /// The directory where files emitted with `emit(path, content)` get written to.
+    pub out_dir: Option<PathBuf>,
+}
+
+fn register_functions<'js>(
+    ctx: &Ctx<'js>,
+    output: Rc<RefCell<Vec<String>>>,
+    files: Rc<RefCell<EmittedFiles>>,
+    debug: bool,
+) -> rquickjs::Result<()> {
+    let globals = ctx.globals();
+    globals.set(
+        "emit",
+        Function::new(
+            ctx.clone(),
+            move |first: String, second: Opt<String>| match second.0 {
+                Some(content) => files
+                    .borrow_mut()
+                    .entry(first)
+                    .or_default()
+                    .push_str(&content),
+                None => output.borrow_mut().push(first),
+            },
+        )?,
+    )?;
+    globals.set(
+        "print",
+        Function::new(ctx.clone(), move |args: Rest<Coerced<String>>| {
+            if debug {
+                let args = args.0.into_iter().map(|a| a.0).collect::<Vec<_>>();
+                eprintln!("INFO => {}", args.join(" "));
+            }
+        })?,
+    )?;
+    globals.set("NL", "\n")?;
+    Ok(())
+}
+
+/// Replaces `Math.random`, `Date.now` and `Date()` without arguments (the current time) by
+/// functions which throw (`--reproducible`). `MESSAGE` is replaced by the error message.
+const DISABLE_NONDETERMINISTIC: &str = r"
+const fail = (name) => () => { throw new Error(`MESSAGE`); };
+Math.random = fail('Math.random()');
+Date.now = fail('Date.now()');
+globalThis.Date = new Proxy(Date, {
+    apply: fail('Date()'),
+    construct: (target, args) =>
+        args.length === 0 ? fail('new Date()')() : Reflect.construct(target, args),
+});
+";
+
+/// Evaluates the script with `model` (a serialized `SsdModel` or `RawModel`) split into the
+/// globals `module`, `defines`, `meta` and `ast` (undefined without `--with-ast`).
+fn run_script(
+    script: &PathBuf,
+    model: &str,
+    output: Rc<RefCell<Vec<String>>>,
+    files: Rc<RefCell<EmittedFiles>>,
+    debug: bool,
+    reproducible: bool,
+) -> Result<(), Box<dyn Error>> {
+    let runtime = Runtime::new()?;
+    let context = Context::full(&runtime)?;
+    context.with(|ctx| {
+        let run = || -> rquickjs::Result<()> {
+            register_functions(&ctx, output.clone(), files, debug)?;
+            if reproducible {
+                let message = not_reproducible("${name}");
+                ctx.eval::<(), _>(DISABLE_NONDETERMINISTIC.replace("MESSAGE", &message))?;
+            }
+            let model: Object = ctx.json_parse(model)?.get()?;
+            let globals = ctx.globals();
+            let module: Value = model.get("module")?;
+            let module = if module.is_undefined() {
+                model.get("raw")?
+            } else {
+                module
+            };
+            globals.set("module", module)?;
+            globals.set("defines", model.get::<_, Value>("defines")?)?;
+            globals.set("meta", model.get::<_, Value>("meta")?)?;
+            globals.set("ast", model.get::<_, Value>("ast")?)?;
+
+            let mut options = EvalOptions::default();
+            options.strict = false;
+            let result: Value = ctx.eval_file_with_options(script, options)?;
+            if let Some(result) = result.as_string() {
+                output.borrow_mut().push(result.to_string()?);
+            }
+            Ok(())
+        };
+        run()
+            .catch(&ctx)
+            .map_err(|e| Diagnostic(format!("{}: {e}", script.display())).into())
+    })
+}
+
+pub fn generate(
+    base: &PathBuf,
+    defines: Defines,
+    Parameters {
+        script,
+        debug,
+        input,
+        out,
+        out_dir,
+    }: Parameters,
+) -> Result<(), Box<dyn Error>> {
+    let reproducible = input.reproducible;
+    let model = if input.raw {
+        let meta = input.meta("");
+        let raw = input.load_raw()?;
+        serde_json::to_string(&RawModel { raw, defines, meta })?
+    } else {
+        let module = load_module(base, &defines, &input, Some(&script))?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
+        serde_json::to_string(&SsdModel {
+            module,
+            defines,
+            meta,
+            ast,
+        })?
+    };
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    run_script(
+        &script,
+        &model,
+        output.clone(),
+        files.clone(),
+        debug,
+        reproducible,
+    )?;
+
+    let result = output.borrow().join("");
+    if !result.is_empty() {
+        out.write(&result)?;
+    }
+    let files = files.take();
+    if !files.is_empty() {
+        let out_dir = out_dir.ok_or("The script emitted files, but no --out-dir was given.")?;
+        out.write_files(&out_dir, files)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_run_script() {
+    let dir = std::env::temp_dir().join(format!("ssd-js-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("gen.js");
+    let module = crate::parser::parse(
+        "data User { id: u64, }; data Group {};",
+        ssd_data::Namespace::new("users"),
+    )
+    .unwrap();
+    let model = serde_json::json!({ "module": module, "defines": { "PRO": "true" } }).to_string();
+
+    let run = |source: &str, reproducible: bool| {
+        std::fs::write(&script, source).unwrap();
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let files = Rc::new(RefCell::new(EmittedFiles::new()));
+        run_script(
+            &script,
+            &model,
+            output.clone(),
+            files.clone(),
+            false,
+            reproducible,
+        )
+        .map(|()| (output.take().join(""), files.take()))
+        .map_err(|e| e.to_string())
+    };
+
+    let (output, files) = run(
+        r#"
+        for (const [name, dt] of module.data_types) {
+            emit(`${name}:${dt.properties.length} `);
+            emit(`${name.toLowerCase()}.rs`, `struct ${name};`);
+        }
+        `${module.namespace.components.join("::")} ${defines.PRO} ${ast}`
+        "#,
+        false,
+    )
+    .unwrap();
+    assert_eq!(output, "User:1 Group:0 users true undefined");
+    assert_eq!(files["user.rs"], "struct User;");
+    assert_eq!(files.len(), 2);
+
+    assert!(run("Math.random()", false).is_ok());
+    let error = run("Math.random()", true).unwrap_err();
+    assert!(error.contains("Math.random() can't be used with --reproducible"));
+    assert!(run("new Date(0).getTime()", true).is_ok());
+    let error = run("new Date()", true).unwrap_err();
+    assert!(error.contains("new Date() can't be used with --reproducible"));
+
+    let error = run("throw new Error('broken')", false).unwrap_err();
+    assert!(error.starts_with(&script.display().to_string()), "{error}");
+    assert!(error.contains("broken"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
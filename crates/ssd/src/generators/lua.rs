@@ -0,0 +1,458 @@
+use crate::options::{BaseInputData, BaseOutputData};
+use clap::Parser;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use mlua::{
+    AnyUserData, IntoLua, Lua, LuaSerdeExt, MetaMethod, Table, UserDataFields, UserDataMethods,
+    Value, Variadic,
+};
+use ssd_data::{find_attribute, Defines, OrderedMap};
+
+use crate::ast::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
+    Parameter, Service, SsdModule, TypeName,
+};
+use crate::cache::load_module;
+use crate::exit::WithPath;
+use crate::helper::not_reproducible;
+use crate::idents::{
+    sanitize_ident, to_camel_case, to_kebab_case, to_pascal_case, to_screaming_snake_case,
+    to_snake_case,
+};
+
+/// Files emitted by a script through `emit(path, content)`, keyed by their relative path.
+type EmittedFiles = BTreeMap<String, String>;
+
+#[derive(Debug, Parser)]
+pub struct Parameters {
+    /// The script to use to generate the file.
+    pub script: PathBuf,
+    #[clap(long, short)]
+    /// Enables debug mode (print function in the script).
+    pub debug: bool,
+    #[clap(flatten)]
+    pub input: BaseInputData,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+    #[clap(long)]
+    /// The directory where files emitted with `emit(path, content)` get written to.
+    pub out_dir: Option<PathBuf>,
+}
+
+fn list<T: 'static>(lua: &Lua, items: Vec<T>) -> mlua::Result<Table<'_>> {
+    let items = items
+        .into_iter()
+        .map(|item| lua.create_any_userdata(item))
+        .collect::<mlua::Result<Vec<_>>>()?;
+    lua.create_sequence_from(items)
+}
+
+/// Ordered maps become a list of `{ first = name, second = value }` tables, like in rhai.
+fn pairs<T: 'static>(lua: &Lua, items: OrderedMap<T>) -> mlua::Result<Table<'_>> {
+    let items = items
+        .into_iter()
+        .map(|(name, value)| {
+            let pair = lua.create_table()?;
+            pair.set("first", name)?;
+            pair.set("second", lua.create_any_userdata(value)?)?;
+            Ok(pair)
+        })
+        .collect::<mlua::Result<Vec<_>>>()?;
+    lua.create_sequence_from(items)
+}
+
+fn optional<T: 'static>(lua: &Lua, item: Option<T>) -> mlua::Result<Value<'_>> {
+    item.map_or(Ok(Value::Nil), |item| {
+        lua.create_any_userdata(item).map(Value::UserData)
+    })
+}
+
+fn attribute<'lua>(
+    lua: &'lua Lua,
+    attributes: &[Attribute],
+    name: &str,
+) -> mlua::Result<Value<'lua>> {
+    optional(lua, find_attribute(attributes, name).cloned())
+}
+
+macro_rules! register_attribute_lookup {
+    ($reg: ident) => {
+        $reg.add_field_method_get("attributes", |lua, this| list(lua, this.attributes.clone()));
+        $reg.add_method("get_attribute", |lua, this, name: String| {
+            attribute(lua, &this.attributes, &name)
+        });
+        $reg.add_method("has_attribute", |_, this, name: String| {
+            Ok(find_attribute(&this.attributes, &name).is_some())
+        });
+    };
+}
+
+#[allow(clippy::too_many_lines)]
+fn register_types(lua: &Lua) -> mlua::Result<()> {
+    lua.register_userdata_type::<SsdModule>(|reg| {
+        reg.add_field_method_get("name", |lua, this| {
+            lua.create_any_userdata(this.namespace.clone())
+        });
+        reg.add_field_method_get("imports", |lua, this| list(lua, this.imports.clone()));
+        reg.add_field_method_get("version", |_, this| Ok(this.version.clone()));
+        reg.add_field_method_get("data_types", |lua, this| {
+            pairs(lua, this.data_types.clone())
+        });
+        reg.add_field_method_get("types", |lua, this| pairs(lua, this.data_types.clone()));
+        reg.add_field_method_get("enums", |lua, this| pairs(lua, this.enums.clone()));
+        reg.add_field_method_get("services", |lua, this| pairs(lua, this.services.clone()));
+    })?;
+
+    lua.register_userdata_type::<Import>(|reg| {
+        reg.add_field_method_get("path", |lua, this| {
+            lua.create_any_userdata(this.path.clone())
+        });
+        reg.add_field_method_get("alias", |_, this| Ok(this.alias.clone()));
+        reg.add_field_method_get("items", |_, this| Ok(this.items.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<DataType>(|reg| {
+        reg.add_field_method_get("properties", |lua, this| {
+            pairs(lua, this.properties.clone())
+        });
+        reg.add_field_method_get("visibility", |_, this| {
+            Ok(this.visibility.map(|v| v.to_string()))
+        });
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Enum>(|reg| {
+        reg.add_field_method_get("values", |lua, this| pairs(lua, this.values.clone()));
+        reg.add_field_method_get("visibility", |_, this| {
+            Ok(this.visibility.map(|v| v.to_string()))
+        });
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        reg.add_field_method_get("is_flags", |_, this| Ok(this.is_flags));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Service>(|reg| {
+        reg.add_field_method_get("dependencies", |lua, this| {
+            list(lua, this.dependencies.clone())
+        });
+        reg.add_field_method_get("functions", |lua, this| pairs(lua, this.functions.clone()));
+        reg.add_field_method_get("events", |lua, this| pairs(lua, this.events.clone()));
+        reg.add_field_method_get("visibility", |_, this| {
+            Ok(this.visibility.map(|v| v.to_string()))
+        });
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Dependency>(|reg| {
+        reg.add_field_method_get("name", |lua, this| {
+            lua.create_any_userdata(this.name.clone())
+        });
+        reg.add_field_method_get("comments", |_, this| Ok(this.comments.clone()));
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Function>(|reg| {
+        reg.add_field_method_get("arguments", |lua, this| pairs(lua, this.arguments.clone()));
+        reg.add_field_method_get("return_type", |lua, this| {
+            optional(lua, this.return_type.clone())
+        });
+        reg.add_field_method_get("error_type", |lua, this| {
+            optional(lua, this.error_type.clone())
+        });
+        reg.add_field_method_get("is_oneway", |_, this| Ok(this.is_oneway));
+        reg.add_field_method_get("comments", |_, this| Ok(this.comments.clone()));
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Event>(|reg| {
+        reg.add_field_method_get("arguments", |lua, this| pairs(lua, this.arguments.clone()));
+        reg.add_field_method_get("comments", |_, this| Ok(this.comments.clone()));
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<TypeName>(|reg| {
+        reg.add_field_method_get("type", |lua, this| {
+            lua.create_any_userdata(this.typ.clone())
+        });
+        reg.add_field_method_get("is_list", |_, this| Ok(this.is_list()));
+        // `false` for `list of`, so `ipairs` doesn't stop at it
+        reg.add_field_method_get("lists", |lua, this| {
+            let lists = this
+                .lists
+                .iter()
+                .map(|list| list.map_or(Ok(Value::Boolean(false)), |count| count.into_lua(lua)))
+                .collect::<mlua::Result<Vec<_>>>()?;
+            lua.create_sequence_from(lists)
+        });
+        reg.add_field_method_get("element", |lua, this| optional(lua, this.element()));
+        reg.add_field_method_get("is_primitive", |_, this| Ok(this.is_primitive()));
+        reg.add_field_method_get("comments", |_, this| Ok(this.comments.clone()));
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<EnumValue>(|reg| {
+        reg.add_field_method_get("value", |_, this| Ok(this.value));
+        reg.add_field_method_get("radix", |_, this| Ok(this.radix.to_string()));
+        reg.add_field_method_get("formatted", |_, this| Ok(this.formatted_value()));
+        reg.add_field_method_get("comments", |_, this| Ok(this.comments.clone()));
+        reg.add_field_method_get("docs", |_, this| Ok(this.docs.clone()));
+        register_attribute_lookup!(reg);
+    })?;
+
+    lua.register_userdata_type::<Attribute>(|reg| {
+        reg.add_field_method_get("name", |lua, this| {
+            lua.create_any_userdata(this.name.clone())
+        });
+        reg.add_field_method_get("parameters", |lua, this| list(lua, this.parameters.clone()));
+        reg.add_method("get_param", |_, this, name: String| {
+            Ok(this.find_parameter(&name).and_then(|p| p.value.clone()))
+        });
+        reg.add_method("has_param", |_, this, name: String| {
+            Ok(this.find_parameter(&name).is_some())
+        });
+    })?;
+
+    lua.register_userdata_type::<Parameter>(|reg| {
+        reg.add_field_method_get("name", |_, this| Ok(this.name.clone()));
+        reg.add_field_method_get("value", |_, this| Ok(this.value.clone()));
+    })?;
+
+    lua.register_userdata_type::<Namespace>(|reg| {
+        reg.add_field_method_get("components", |_, this| Ok(this.components.clone()));
+        reg.add_method("join", |_, this, sep: String| {
+            Ok(this.components.join(&sep))
+        });
+        reg.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+    })?;
+
+    Ok(())
+}
+
+/// Replaces the functions of the standard library whose results change between runs by ones
+/// which fail (`--reproducible`).
+fn disable_nondeterministic(lua: &Lua) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let functions = [
+        ("os", "time"),
+        ("os", "date"),
+        ("os", "clock"),
+        ("os", "getenv"),
+        ("math", "random"),
+        ("math", "randomseed"),
+    ];
+    for (table, function) in functions {
+        let message = not_reproducible(&format!("{table}.{function}()"));
+        let table: Table = globals.get(table)?;
+        table.set(
+            function,
+            lua.create_function(move |_, _: Variadic<Value>| -> mlua::Result<()> {
+                Err(mlua::Error::RuntimeError(message.clone()))
+            })?,
+        )?;
+    }
+    Ok(())
+}
+
+fn register_functions(
+    lua: &Lua,
+    output: Rc<RefCell<Vec<String>>>,
+    files: Rc<RefCell<EmittedFiles>>,
+    debug: bool,
+) -> mlua::Result<()> {
+    let globals = lua.globals();
+
+    globals.set(
+        "emit",
+        lua.create_function(move |_, args: Variadic<String>| {
+            match args.as_slice() {
+                [text] => output.borrow_mut().push(text.clone()),
+                [path, content] => {
+                    files
+                        .borrow_mut()
+                        .entry(path.clone())
+                        .or_default()
+                        .push_str(content);
+                }
+                _ => Err(mlua::Error::runtime(
+                    "emit expects either (text) or (path, content)",
+                ))?,
+            }
+            Ok(())
+        })?,
+    )?;
+
+    globals.set(
+        "print",
+        lua.create_function(move |_, args: Variadic<String>| {
+            if debug {
+                eprintln!("INFO => {}", args.join("\t"));
+            }
+            Ok(())
+        })?,
+    )?;
+
+    globals.set(
+        "to_snake_case",
+        lua.create_function(|_, s: String| Ok(to_snake_case(&s)))?,
+    )?;
+    globals.set(
+        "to_camel_case",
+        lua.create_function(|_, s: String| Ok(to_camel_case(&s)))?,
+    )?;
+    globals.set(
+        "to_pascal_case",
+        lua.create_function(|_, s: String| Ok(to_pascal_case(&s)))?,
+    )?;
+    globals.set(
+        "to_kebab_case",
+        lua.create_function(|_, s: String| Ok(to_kebab_case(&s)))?,
+    )?;
+    globals.set(
+        "to_screaming_snake_case",
+        lua.create_function(|_, s: String| Ok(to_screaming_snake_case(&s)))?,
+    )?;
+    globals.set(
+        "sanitize_ident",
+        lua.create_function(|_, (ident, lang): (String, String)| {
+            sanitize_ident(&ident, &lang).map_err(mlua::Error::external)
+        })?,
+    )?;
+
+    Ok(())
+}
+
+pub fn generate(
+    base: &PathBuf,
+    defines: Defines,
+    Parameters {
+        script,
+        debug,
+        input,
+        out,
+        out_dir,
+    }: Parameters,
+) -> Result<(), Box<dyn Error>> {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+
+    let lua = Lua::new();
+    register_types(&lua)?;
+    register_functions(&lua, output.clone(), files.clone(), debug)?;
+    if input.reproducible {
+        disable_nondeterministic(&lua)?;
+    }
+
+    if let Some(dir) = script.parent() {
+        let package: Table = lua.globals().get("package")?;
+        let path: String = package.get("path")?;
+        let dir = dir.to_string_lossy();
+        package.set("path", format!("{dir}/?.lua;{path}"))?;
+    }
+
+    let ast = lua.to_value(&input.load_ast()?)?;
+    let (module, meta) = if input.raw {
+        let meta = input.meta("");
+        (lua.to_value(&input.load_raw()?)?, meta)
+    } else {
+        let module = load_module(base, &defines, &input, Some(&script))?;
+        let meta = input.meta(&module.namespace.to_string());
+        let module = AnyUserData::into_lua(lua.create_any_userdata(module)?, &lua)?;
+        (module, meta)
+    };
+
+    let globals = lua.globals();
+    globals.set("module", module)?;
+    globals.set("defines", lua.to_value(&defines)?)?;
+    globals.set("meta", lua.to_value(&meta)?)?;
+    globals.set("ast", ast)?;
+    globals.set("NL", "\n")?;
+
+    let source = std::fs::read_to_string(&script).with_path(&script)?;
+    let result: Option<String> = lua
+        .load(&source)
+        .set_name(script.to_string_lossy())
+        .eval()?;
+    if let Some(result) = result {
+        output.borrow_mut().push(result);
+    }
+
+    let result = output.borrow().join("");
+    if !result.is_empty() {
+        out.write(&result)?;
+    }
+    let files = files.take();
+    if !files.is_empty() {
+        let out_dir = out_dir.ok_or("The script emitted files, but no --out-dir was given.")?;
+        out.write_files(&out_dir, files)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_model_access() {
+    let module = crate::parser::parse(
+        r#"
+        #[table(name = "users")]
+        data User {
+            /// The tags
+            tags: 2 of list of string,
+        };
+        enum Role { Admin = 1, };
+        service Users {
+            fn get(id: u64) -> User;
+        };
+        "#,
+        Namespace::new("users"),
+    )
+    .unwrap();
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let lua = Lua::new();
+    register_types(&lua).unwrap();
+    register_functions(&lua, output.clone(), files.clone(), false).unwrap();
+    lua.globals()
+        .set("module", lua.create_any_userdata(module).unwrap())
+        .unwrap();
+    lua.load(
+        r#"
+        for _, dt in ipairs(module.data_types) do
+            emit(to_snake_case(dt.first) .. ":" .. dt.second:get_attribute("table"):get_param("name"))
+            for _, p in ipairs(dt.second.properties) do
+                local typ = p.second
+                emit(" " .. p.first .. "=" .. typ.type:join("::") .. tostring(typ.is_list))
+                for _, list in ipairs(typ.lists) do emit(" " .. tostring(list)) end
+                emit(" " .. typ.docs[1])
+            end
+        end
+        local value = module.enums[1].second.values[1]
+        emit(" " .. value.first .. "=" .. value.second.value)
+        local get = module.services[1].second.functions[1]
+        emit(" " .. get.first .. "->" .. tostring(get.second.return_type.type))
+        emit(get.first .. ".rs", "fn get()")
+        "#,
+    )
+    .exec()
+    .unwrap();
+    assert_eq!(
+        output.borrow().join(""),
+        "user:users tags=stringtrue 2 false The tags Admin=1 get->User"
+    );
+    assert_eq!(files.borrow()["get.rs"], "fn get()");
+
+    disable_nondeterministic(&lua).unwrap();
+    let error = lua.load("return os.time()").exec().unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("os.time() can't be used with --reproducible"));
+}
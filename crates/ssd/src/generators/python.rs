@@ -0,0 +1,154 @@
+use crate::options::{BaseInputData, BaseOutputData};
+use clap::Parser;
+use std::error::Error;
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use ssd_data::{Defines, Meta};
+
+use crate::cache::load_module;
+use crate::exit::WithPath;
+use crate::helper::Diagnostic;
+
+const ENTRY_POINT: &str = "generate";
+
+#[derive(Debug, Parser)]
+pub struct Parameters {
+    /// The python script to use to generate the file.
+    ///
+    /// The script has to define a function `generate(model, defines)` which returns the
+    /// generated code as string. Information about the run is available as the global `meta`.
+    pub script: PathBuf,
+    #[clap(flatten)]
+    pub input: BaseInputData,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+}
+
+/// Formats a python exception including its traceback.
+fn diagnostic(py: Python<'_>, err: &PyErr) -> Diagnostic {
+    let traceback = err
+        .traceback(py)
+        .and_then(|tb| tb.format().ok())
+        .unwrap_or_default();
+    Diagnostic(format!("{traceback}{err}"))
+}
+
+/// Converts serialized json into python objects.
+fn json_loads(py: Python<'_>, json: &str) -> PyResult<PyObject> {
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+/// Loads the script, sets its globals `meta` and `ast` and calls its `generate` function with
+/// the model created by `model`.
+fn run_script<F>(
+    script: &PathBuf,
+    defines: &Defines,
+    meta: &Meta,
+    ast: Option<&serde_value::Value>,
+    model: F,
+) -> Result<String, Box<dyn Error>>
+where
+    F: FnOnce(Python<'_>) -> PyResult<PyObject>,
+{
+    let code = std::fs::read_to_string(script).with_path(script)?;
+    let defines = serde_json::to_string(defines)?;
+    let meta = serde_json::to_string(meta)?;
+    let ast = serde_json::to_string(&ast)?;
+    let result = Python::with_gil(|py| {
+        let run = || -> PyResult<String> {
+            if let Some(dir) = script.parent() {
+                let path: &PyList = py.import("sys")?.getattr("path")?.downcast()?;
+                path.insert(0, dir.to_string_lossy())?;
+            }
+            // the module would be reused by the next run in this process (e.g. ssd verify),
+            // keeping the definitions of the previous script
+            let modules = py.import("sys")?.getattr("modules")?;
+            if modules.contains("generator")? {
+                modules.del_item("generator")?;
+            }
+            let generator = PyModule::from_code(py, &code, &script.to_string_lossy(), "generator")?;
+            generator.setattr("meta", json_loads(py, &meta)?)?;
+            generator.setattr("ast", json_loads(py, &ast)?)?;
+            generator
+                .getattr(ENTRY_POINT)?
+                .call1((model(py)?, json_loads(py, &defines)?))?
+                .extract()
+        };
+        run().map_err(|e| diagnostic(py, &e))
+    })?;
+    Ok(result)
+}
+
+pub fn generate(
+    base: &PathBuf,
+    defines: Defines,
+    Parameters { script, input, out }: Parameters,
+) -> Result<(), Box<dyn Error>> {
+    let result = if input.raw {
+        let meta = input.meta("");
+        let raw = serde_json::to_string(&input.load_raw()?)?;
+        run_script(&script, &defines, &meta, None, |py| json_loads(py, &raw))?
+    } else {
+        let module = load_module(base, &defines, &input, Some(&script))?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
+        run_script(&script, &defines, &meta, ast.as_ref(), |py| {
+            Ok(module.into_py(py))
+        })?
+    };
+
+    out.write(&result)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_run_script() {
+    let dir = std::env::temp_dir().join(format!("ssd-python-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("gen.py");
+    std::fs::write(
+        dir.join("common.py"),
+        "def header(ns): return f'// {ns}\\n'\n",
+    )
+    .unwrap();
+    let module = crate::parser::parse(
+        "data User { id: u64, }; data Group {};",
+        ssd_data::Namespace::new("users"),
+    )
+    .unwrap();
+    let defines = Defines::from([(
+        "PRO".to_owned(),
+        serde_value::Value::String("true".to_owned()),
+    )]);
+    let meta = Meta {
+        namespace: "users".to_owned(),
+        ..Meta::default()
+    };
+
+    let run = |source: &str| {
+        std::fs::write(&script, source).unwrap();
+        let module = module.clone();
+        run_script(&script, &defines, &meta, None, |py| Ok(module.into_py(py)))
+            .map_err(|e| e.to_string())
+    };
+
+    // scripts next to the generator can be imported
+    let result = run(r"from common import header
+
+def generate(model, defines):
+    names = ' '.join(name for name, _ in model.data_types)
+    return header(meta['namespace']) + names + ' ' + defines['PRO'] + str(ast)
+")
+    .unwrap();
+    assert_eq!(result, "// users\nUser Group trueNone");
+
+    let error = run("def other(model, defines): pass\n").unwrap_err();
+    assert!(error.contains("generate"), "{error}");
+    let error = run("def generate(model, defines):\n    raise ValueError('broken')\n").unwrap_err();
+    assert!(error.contains("Traceback"), "{error}");
+    assert!(error.contains("ValueError: broken"), "{error}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,15 +1,30 @@
-use crate::helper::parse_raw_data;
 #[cfg(feature = "_bin")]
 use crate::options::{BaseInputData, BaseOutputData};
 #[cfg(feature = "_bin")]
 use clap::Parser;
-use ssd_data::{Namespace, SsdModule};
+use ssd_data::{find_attribute, Namespace, OrderedMap, Shared, SsdModule};
+#[cfg(feature = "_bin")]
+use ssd_data::{Defines, LintDiagnostic};
+use std::collections::BTreeMap;
+#[cfg(feature = "web")]
 use std::collections::HashMap;
 use std::error::Error;
+use std::path::Path;
+#[cfg(feature = "_bin")]
 use std::path::PathBuf;
 
-use crate::helper::{print_or_write, update_types_from_file};
-use crate::parser::parse_file;
+#[cfg(feature = "_bin")]
+use crate::cache::load_module;
+#[cfg(feature = "_bin")]
+use crate::exit::io_error;
+#[cfg(feature = "_bin")]
+use crate::helper::{
+    diff_files, not_reproducible, read_files, report_case, text_diff, write_files,
+};
+use crate::idents::{
+    sanitize_ident, to_camel_case, to_kebab_case, to_pascal_case, to_screaming_snake_case,
+    to_snake_case,
+};
 
 use crate::ast::{
     Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Parameter, Service,
@@ -17,13 +32,24 @@ use crate::ast::{
 };
 use glob::glob;
 use rhai::packages::{CorePackage, Package};
-use rhai::{Array, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope, FLOAT, INT};
-use std::{any::TypeId, cell::RefCell, rc::Rc, time::Instant};
+use rhai::{
+    Array, Dynamic, Engine, EvalAltResult, FnAccess, FnNamespace, ImmutableString, Map, Module,
+    RegisterNativeFunction, Scope, Variant, FLOAT, INT,
+};
+use std::{
+    any::{type_name, TypeId},
+    cell::RefCell,
+    rc::Rc,
+    time::Instant,
+};
 
 const INDENT: &str = "    ";
 
 type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
 
+/// Files emitted by a script through `emit(path, content)`, keyed by their relative path.
+pub type EmittedFiles = BTreeMap<String, String>;
+
 #[cfg(feature = "_bin")]
 #[derive(Debug, Parser)]
 pub struct Parameters {
@@ -36,6 +62,96 @@ pub struct Parameters {
     pub input: BaseInputData,
     #[clap(flatten)]
     pub out: BaseOutputData,
+    #[clap(long)]
+    /// The directory where files emitted with `emit(path, content)` get written to.
+    pub out_dir: Option<PathBuf>,
+    #[clap(long)]
+    /// Scripts which get evaluated before the generator script.
+    ///
+    /// Functions and constants defined in a prelude are available in the generator script.
+    pub prelude: Vec<PathBuf>,
+    #[clap(flatten)]
+    pub limits: Limits,
+}
+
+#[cfg(feature = "_bin")]
+#[derive(Debug, Parser)]
+pub struct Limits {
+    #[clap(long)]
+    /// The maximum number of operations the script is allowed to execute.
+    pub max_operations: Option<u64>,
+    #[clap(long)]
+    /// The maximum number of seconds the script is allowed to run.
+    pub timeout: Option<u64>,
+    #[clap(long)]
+    /// The maximum length (in bytes) of strings created by the script.
+    pub max_string_size: Option<usize>,
+}
+
+#[cfg(feature = "_bin")]
+fn apply_limits(
+    engine: &mut Engine,
+    &Limits {
+        max_operations,
+        timeout,
+        max_string_size,
+    }: &Limits,
+) {
+    if let Some(max_operations) = max_operations {
+        engine.set_max_operations(max_operations);
+    }
+    if let Some(max_string_size) = max_string_size {
+        engine.set_max_string_size(max_string_size);
+    }
+    if let Some(timeout) = timeout {
+        let timeout = std::time::Duration::from_secs(timeout);
+        let start = Instant::now();
+        engine.on_progress(move |_| {
+            (start.elapsed() > timeout)
+                .then(|| format!("Script timed out after {}s.", timeout.as_secs()).into())
+        });
+    }
+}
+
+/// Rhai only prints "Script terminated" when a limit stops the script, so the reason given by
+/// the progress callback is shown instead.
+#[cfg(feature = "_bin")]
+fn termination_reason(e: Box<EvalAltResult>) -> Box<dyn Error> {
+    match &*e {
+        EvalAltResult::ErrorTerminated(reason, pos) if reason.is_string() => {
+            format!("{reason} ({pos})").into()
+        }
+        _ => e,
+    }
+}
+
+/// Returns the scripts imported by the scripts (`import "common" as c;`, recursively) which
+/// aren't among them. Like when they run, imports are relative to `script_dir`. Only imports of
+/// string literals can be found without running the scripts.
+#[cfg(feature = "_bin")]
+pub fn imported_scripts(script_dir: &Path, scripts: &[PathBuf]) -> Vec<PathBuf> {
+    let mut imported = Vec::new();
+    let mut pending = scripts.to_vec();
+    while let Some(script) = pending.pop() {
+        let Ok(source) = std::fs::read_to_string(&script) else {
+            continue;
+        };
+        for rest in source.split("import").skip(1) {
+            let Some((path, _)) = rest
+                .trim_start()
+                .strip_prefix('"')
+                .and_then(|rest| rest.split_once('"'))
+            else {
+                continue;
+            };
+            let path = script_dir.join(path).with_extension("rhai");
+            if path.is_file() && !scripts.contains(&path) && !imported.contains(&path) {
+                imported.push(path.clone());
+                pending.push(path);
+            }
+        }
+    }
+    imported
 }
 
 #[allow(clippy::unnecessary_box_returns)]
@@ -45,16 +161,19 @@ fn error_to_runtime_error<E: std::error::Error>(e: E) -> Box<EvalAltResult> {
 
 use rhai::Token;
 
+#[cfg(not(feature = "web"))]
 fn script_exists(path: &str) -> bool {
-    PathBuf::from(path).exists()
+    Path::new(path).exists()
 }
 
+#[cfg(not(feature = "web"))]
 fn script_is_file(path: &str) -> bool {
-    PathBuf::from(path).is_file()
+    Path::new(path).is_file()
 }
 
+#[cfg(not(feature = "web"))]
 fn script_is_dir(path: &str) -> bool {
-    PathBuf::from(path).is_dir()
+    Path::new(path).is_dir()
 }
 
 #[allow(clippy::needless_pass_by_value)]
@@ -82,6 +201,10 @@ fn script_join_typepath(v: Namespace, sep: &str) -> String {
     v.into_iter().collect::<Vec<_>>().join(sep)
 }
 
+fn script_sanitize_ident(ident: &str, target_lang: &str) -> ScriptResult<String> {
+    sanitize_ident(ident, target_lang).map_err(|e| e.to_string().into())
+}
+
 fn script_find_paths(pattern: &str) -> ScriptResult<Vec<Dynamic>> {
     glob(pattern)
         .map_err(error_to_runtime_error)?
@@ -122,6 +245,7 @@ fn script_rsplitn(s: &str, n: INT, pattern: &str) -> Vec<Dynamic> {
         .collect()
 }
 
+#[cfg(not(feature = "web"))]
 fn script_read_file(path: &str) -> ScriptResult<String> {
     std::fs::read_to_string(path).map_err(error_to_runtime_error)
 }
@@ -258,10 +382,109 @@ fn script_array_contains(arr: Array, v: &Dynamic) -> bool {
         .any(|ele| script_value_equals(ele, v.clone()).unwrap_or_default())
 }
 
+/// The type name without the paths of the types (e.g. `Vec<String>`), rhai only strips the path
+/// in front of the last component.
+fn short_type_name(name: &str) -> String {
+    let name = name
+        .replace("&str", "String")
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .replace(type_name::<Array>(), "Array")
+        .replace(type_name::<Map>(), "Map");
+    let mut parts = name.split("::").collect::<Vec<_>>();
+    let last = parts.pop().unwrap_or_default();
+    parts
+        .into_iter()
+        .map(|part| part.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_'))
+        .chain([last])
+        .collect()
+}
+
+/// The functions and getters of the engine, registered with the names of their parameters and a
+/// doc comment, which the rhai language server shows on hover.
+#[derive(Default)]
+struct Functions(Module);
+
+impl Functions {
+    /// Like `Engine::register_fn`, with a markdown description and an example.
+    fn register<
+        A: 'static,
+        const N: usize,
+        const C: bool,
+        R: Variant + Clone,
+        const L: bool,
+        F: RegisterNativeFunction<A, N, C, R, L> + 'static,
+    >(
+        &mut self,
+        name: &str,
+        params: [&str; N],
+        doc: &str,
+        example: &str,
+        func: F,
+    ) -> &mut Self {
+        let hash = self.0.set_fn(
+            name,
+            FnNamespace::Global,
+            FnAccess::Public,
+            None,
+            F::param_types(),
+            func.into_callable_function(name.into(), true),
+        );
+        let mut params = params
+            .iter()
+            .zip(F::param_names())
+            .map(|(name, typ)| format!("{name}: {}", short_type_name(typ)))
+            .collect::<Vec<_>>();
+        params.push(short_type_name(type_name::<R>()));
+        let mut comment = doc
+            .lines()
+            .map(|line| format!("/// {line}").trim_end().to_owned())
+            .collect::<Vec<_>>();
+        comment.extend(["///".to_owned(), "/// ```rhai".to_owned()]);
+        comment.extend(example.lines().map(|line| format!("/// {line}")));
+        comment.push("/// ```".to_owned());
+        self.0
+            .update_fn_metadata_with_comments(hash, params, [comment.join("\n")]);
+        self
+    }
+
+    /// Like `Engine::register_get`, with a markdown description and an example.
+    fn register_get<
+        A: 'static,
+        const C: bool,
+        R: Variant + Clone,
+        const L: bool,
+        F: RegisterNativeFunction<A, 1, C, R, L> + 'static,
+    >(
+        &mut self,
+        name: &str,
+        doc: &str,
+        example: &str,
+        func: F,
+    ) -> &mut Self {
+        self.register(&format!("get${name}"), ["this"], doc, example, func)
+    }
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
+pub fn build_engine(
+    messages: Rc<RefCell<Vec<String>>>,
+    files: Rc<RefCell<EmittedFiles>>,
+    script_dir: Option<&Path>,
+    debug: bool,
+) -> Engine {
     let mut engine = Engine::new();
     engine.set_max_expr_depths(128, 64);
+
+    // Allow scripts to `import "common" as c;` other scripts next to them
+    #[cfg(not(feature = "web"))]
+    if let Some(script_dir) = script_dir {
+        engine.set_module_resolver(rhai::module_resolvers::FileModuleResolver::new_with_path(
+            script_dir,
+        ));
+    }
+    #[cfg(feature = "web")]
+    let _ = script_dir;
     // Register a token mapper function to allow module as identifier name
     #[allow(deprecated)]
     engine.on_parse_token(|token, _pos, _state| {
@@ -285,32 +508,120 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     engine.register_iterator::<Namespace>();
     engine.register_type::<Namespace>();
 
+    // Functions and getters are collected with their docs, which show up in the definitions
+    let mut functions = Functions::default();
+
     macro_rules! register_vec {
         ($T: ty) => {
             engine
                 .register_type::<Vec<$T>>()
-                .register_fn("len", |v: Vec<$T>| v.len())
                 .register_iterator::<Vec<$T>>()
                 .register_iterator::<&Vec<&$T>>()
                 .register_iterator::<Vec<$T>>()
-                .register_iterator::<&Vec<&$T>>()
-                .register_indexer_get(|v: &mut Vec<$T>, i: i64| v[i as usize].clone());
+                .register_iterator::<&Vec<&$T>>();
+            functions
+                .register(
+                    "len",
+                    ["list"],
+                    "The number of elements in the list.",
+                    "module.data_types.len()",
+                    |v: Vec<$T>| v.len(),
+                )
+                .register(
+                    "index$get$",
+                    ["list", "index"],
+                    "The element at the index.",
+                    "module.data_types[0]",
+                    |v: &mut Vec<$T>, i: i64| v[i as usize].clone(),
+                );
         };
     }
 
-    register_vec!(SsdModule);
     register_vec!(Import);
     register_vec!(Attribute);
-    register_vec!(Dependency);
+    register_vec!(Shared<Attribute>);
+    register_vec!(Shared<Dependency>);
     register_vec!(Parameter);
     register_vec!((String, Namespace));
-    register_vec!((String, Event));
-    register_vec!((String, Enum));
-    register_vec!((String, EnumValue));
-    register_vec!((String, DataType));
-    register_vec!((String, Service));
-    register_vec!((String, TypeName));
-    register_vec!((String, Function));
+    register_vec!(Option<usize>);
+
+    // Named elements are lists of pairs in scripts, which can also be looked up by name
+    macro_rules! register_ordered_map {
+        ($($T: ty),*) => {
+            $(
+            engine
+                .register_type::<OrderedMap<$T>>()
+                .register_iterator::<OrderedMap<$T>>();
+            functions
+                .register(
+                    "len",
+                    ["map"],
+                    "The number of elements.",
+                    "module.data_types.len()",
+                    |v: &mut OrderedMap<$T>| v.len(),
+                )
+                .register(
+                    "index$get$",
+                    ["map", "index"],
+                    "The pair of name and element at the index.",
+                    "module.data_types[0]",
+                    |v: &mut OrderedMap<$T>, i: i64| -> ScriptResult<(String, $T)> {
+                        usize::try_from(i)
+                            .ok()
+                            .and_then(|i| v.get_index(i))
+                            .map(|(name, element)| (name.clone(), element.clone()))
+                            .ok_or_else(|| format!("Index {i} is out of bounds.").into())
+                    },
+                )
+                .register(
+                    "index$get$",
+                    ["map", "name"],
+                    "The element with the name, or `()` if there is none.",
+                    "module.data_types[\"User\"]",
+                    |v: &mut OrderedMap<$T>, name: &str| {
+                        v.get(name).map_or(Dynamic::UNIT, |element| Dynamic::from(element.clone()))
+                    },
+                );
+            )*
+        };
+    }
+
+    register_ordered_map!(
+        Shared<DataType>,
+        Shared<Enum>,
+        Shared<Service>,
+        Shared<Function>,
+        Shared<Event>,
+        Shared<TypeName>,
+        Shared<EnumValue>
+    );
+
+    // Doc comments are shared with the module, their lines are only copied when they are used
+    engine
+        .register_type::<Shared<Vec<String>>>()
+        .register_iterator::<Shared<Vec<String>>>();
+    functions
+        .register(
+            "len",
+            ["docs"],
+            "The number of lines of the doc comments.",
+            "data_type.docs.len()",
+            |v: &mut Shared<Vec<String>>| v.len(),
+        )
+        .register(
+            "index$get$",
+            ["docs", "index"],
+            "The line at the index.",
+            "data_type.docs[0]",
+            |v: &mut Shared<Vec<String>>, i: i64| v[i as usize].clone(),
+        )
+        .register(
+            "join",
+            ["docs", "sep"],
+            "Joins the lines with the separator.",
+            "data_type.docs.join(NL)",
+            |v: &mut Shared<Vec<String>>, sep: &str| v.join(sep),
+        );
 
     // The globally mutable shared value
     let indent = Rc::new(RefCell::new(INDENT.to_owned()));
@@ -327,23 +638,57 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     // Register an API to access the globally mutable shared value
     let v = indent.clone();
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-    engine.register_fn("IND", move |count: i64| v.borrow().repeat(count as usize));
+    functions.register(
+        "IND",
+        ["count"],
+        "The indentation (`IND`, four spaces unless set with `SET_INDENT`) repeated `count` times.",
+        "IND(2) ++ \"return x;\" ++ NL",
+        move |count: i64| v.borrow().repeat(count as usize),
+    );
 
     let v = indent.clone();
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-    engine.register_fn("SET_INDENT", move |count: i64| {
-        *v.borrow_mut() = " ".repeat(count as usize)
-    });
+    functions.register(
+        "SET_INDENT",
+        ["count"],
+        "Sets the indentation (`IND`) to `count` spaces.",
+        "SET_INDENT(2);",
+        move |count: i64| *v.borrow_mut() = " ".repeat(count as usize),
+    );
 
     let v = indent.clone();
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-    engine.register_fn("SET_INDENT", move |value: &str, count: i64| {
-        *v.borrow_mut() = value.repeat(count as usize)
-    });
+    functions.register(
+        "SET_INDENT",
+        ["value", "count"],
+        "Sets the indentation (`IND`) to `value` repeated `count` times.",
+        "SET_INDENT(\"\\t\", 1);",
+        move |value: &str, count: i64| *v.borrow_mut() = value.repeat(count as usize),
+    );
 
-    engine.register_fn("to_string", |this: &mut Import| this.path.clone());
+    functions.register(
+        "to_string",
+        ["import"],
+        "The path of the import.",
+        "import.to_string()",
+        |this: &mut Import| this.path.clone(),
+    );
+    // e.g. for the counts of lists
+    functions.register(
+        "to_string",
+        ["value"],
+        "The number as string, e.g. for the sizes of arrays.",
+        "typ.lists[0].unwrap().to_string()",
+        |this: &mut usize| this.to_string(),
+    );
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-    engine.register_fn("NL", |count: i64| "\n".repeat(count as usize));
+    functions.register(
+        "NL",
+        ["count"],
+        "`count` line breaks.",
+        "\"}\" ++ NL(2)",
+        |count: i64| "\n".repeat(count as usize),
+    );
 
     #[allow(clippy::items_after_statements)]
     fn script_first<A: Clone, B>(tuple: &mut (A, B)) -> A {
@@ -358,10 +703,20 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     macro_rules! register_pairs {
         ($(($A: ty, $B: ty)),*) => {
             $(
-            engine
-                .register_type::<($A, $B)>()
-                .register_get("first", script_first::<$A, $B>)
-                .register_get("second", script_second::<$A, $B>);
+            engine.register_type::<($A, $B)>();
+            functions
+                .register_get(
+                    "first",
+                    "The name of the entry.",
+                    "for entry in module.data_types { entry.first }",
+                    script_first::<$A, $B>,
+                )
+                .register_get(
+                    "second",
+                    "The element of the entry.",
+                    "for entry in module.data_types { entry.second.properties }",
+                    script_second::<$A, $B>,
+                );
             )*
         };
     }
@@ -375,13 +730,13 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     }
 
     register_string_pairs!(
-        Enum,
-        DataType,
-        Service,
-        Event,
-        Function,
-        TypeName,
-        EnumValue,
+        Shared<Enum>,
+        Shared<DataType>,
+        Shared<Service>,
+        Shared<Event>,
+        Shared<Function>,
+        Shared<TypeName>,
+        Shared<EnumValue>,
         Option<EnumValue>
     );
 
@@ -392,154 +747,846 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     //         _ => panic!("Cannot index into non-object"),
     //     });
 
-    engine
-        .register_type::<SsdModule>()
-        .register_get("name", SsdModule::namespace)
-        .register_get("imports", SsdModule::imports)
-        .register_get("data_types", SsdModule::data_types)
-        .register_get("types", SsdModule::data_types)
-        .register_get("enums", SsdModule::enums)
-        .register_get("services", SsdModule::services);
-
-    engine
-        .register_type::<Import>()
-        .register_get("path", Import::path)
-        .register_get("attributes", Import::attributes);
-
-    engine
-        .register_type::<DataType>()
-        .register_get("properties", DataType::properties)
-        .register_get("attributes", DataType::attributes);
-
-    engine
-        .register_type::<Enum>()
-        .register_get("values", Enum::values)
-        .register_get("attributes", Enum::attributes);
-
-    engine
-        .register_type::<Service>()
-        .register_get("dependencies", Service::dependencies)
-        .register_get("functions", Service::functions)
-        .register_get("handlers", Service::handlers)
-        .register_get("events", Service::events)
-        .register_get("attributes", Service::attributes);
-
-    engine
-        .register_type::<Dependency>()
-        .register_get("name", Dependency::name)
-        .register_get("attributes", Dependency::attributes);
+    engine.register_type::<Shared<SsdModule>>();
+    functions
+        .register_get(
+            "name",
+            "The namespace of the module.",
+            "module.name.join(\"::\")",
+            Shared::<SsdModule>::namespace,
+        )
+        .register_get(
+            "imports",
+            "The imports of the module.",
+            "for import in module.imports { import.path }",
+            Shared::<SsdModule>::imports,
+        )
+        .register_get(
+            "version",
+            "The version of the module (`version 1;`), if it has one.",
+            "module.version.unwrap_or(\"0\")",
+            Shared::<SsdModule>::version,
+        )
+        .register_get(
+            "data_types",
+            "The data types of the module as pairs of name and data type.",
+            "for data_type in module.data_types { data_type.first }",
+            Shared::<SsdModule>::data_types,
+        )
+        .register_get(
+            "types",
+            "Alias of `data_types`.",
+            "module.types.len()",
+            Shared::<SsdModule>::data_types,
+        )
+        .register_get(
+            "enums",
+            "The enums of the module as pairs of name and enum.",
+            "for en in module.enums { en.second.values }",
+            Shared::<SsdModule>::enums,
+        )
+        .register_get(
+            "services",
+            "The services of the module as pairs of name and service.",
+            "for service in module.services { service.second.functions }",
+            Shared::<SsdModule>::services,
+        )
+        .register(
+            "referenced_types_of",
+            ["module", "service"],
+            "The names of the types used by the functions, handlers and events of the service.",
+            "module.referenced_types_of(\"Users\")",
+            |this: &mut Shared<SsdModule>, service: &str| {
+                this.referenced_types_of(service)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Dynamic::from)
+                    .collect::<Array>()
+            },
+        )
+        .register(
+            "data_types_topologically_sorted",
+            ["module"],
+            "The data types ordered after the data types they use.\n\nFails on reference cycles.",
+            "for data_type in module.data_types_topologically_sorted() { data_type.first }",
+            |this: &mut Shared<SsdModule>| {
+                this.data_types_topologically_sorted()
+                    .map_err(error_to_runtime_error)
+            },
+        )
+        .register(
+            "resolve_type",
+            ["module", "typ"],
+            "The full path of the type, following import aliases and selective imports.",
+            "module.resolve_type(\"fb::User\")",
+            |this: &mut Shared<SsdModule>, typ: &str| {
+                this.resolve_type(&Namespace::new(typ)).to_string()
+            },
+        )
+        .register(
+            "dependents_of",
+            ["module", "typ"],
+            "The names of the data types, functions, handlers and events which use the type.",
+            "module.dependents_of(\"User\")",
+            |this: &mut Shared<SsdModule>, typ: &str| {
+                this.dependents_of(&Namespace::new(typ))
+                    .into_iter()
+                    .map(|name| Dynamic::from(name.to_owned()))
+                    .collect::<Array>()
+            },
+        );
+
+    engine.register_type::<Import>();
+    functions
+        .register_get(
+            "path",
+            "The imported path.",
+            "import.path.join(\"::\")",
+            Import::path,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the import.",
+            "import.attributes.len()",
+            Import::attributes,
+        )
+        .register_get(
+            "alias",
+            "The alias of the import (`import a::b as c;`), if it has one.",
+            "import.alias.unwrap_or(\"\")",
+            Import::alias,
+        )
+        .register_get(
+            "items",
+            "The names of a selective import (`import a::{B, C};`), or `()` for other imports.",
+            "if import.items != () { import.items.len() }",
+            |this: &mut Import| {
+                this.items().map_or(Dynamic::UNIT, |items| {
+                    items
+                        .into_iter()
+                        .map(Dynamic::from)
+                        .collect::<Array>()
+                        .into()
+                })
+            },
+        );
+
+    engine.register_type::<Shared<DataType>>();
+    functions
+        .register_get(
+            "properties",
+            "The properties of the data type as pairs of name and type.",
+            "for property in data_type.properties { property.second.type }",
+            Shared::<DataType>::properties,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the data type.",
+            "data_type.attributes.len()",
+            Shared::<DataType>::attributes,
+        )
+        .register_get(
+            "visibility",
+            "The visibility marker of the data type (`\"pub\"`, `\"internal\"` or empty).",
+            "data_type.visibility == \"pub\"",
+            Shared::<DataType>::visibility,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the data type.",
+            "data_type.docs.join(NL)",
+            Shared::<DataType>::docs,
+        );
+
+    engine.register_type::<Shared<Enum>>();
+    functions
+        .register_get(
+            "values",
+            "The values of the enum as pairs of name and value.",
+            "for value in en.values { value.first }",
+            Shared::<Enum>::values,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the enum.",
+            "en.attributes.len()",
+            Shared::<Enum>::attributes,
+        )
+        .register_get(
+            "visibility",
+            "The visibility marker of the enum (`\"pub\"`, `\"internal\"` or empty).",
+            "en.visibility == \"pub\"",
+            Shared::<Enum>::visibility,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the enum.",
+            "en.docs.join(NL)",
+            Shared::<Enum>::docs,
+        )
+        .register_get(
+            "is_flags",
+            "Whether the values of the enum are bit flags.",
+            "if en.is_flags { \"bitflags\" }",
+            Shared::<Enum>::is_flags,
+        );
+
+    engine.register_type::<Shared<Service>>();
+    functions
+        .register_get(
+            "dependencies",
+            "The dependencies (`depends on`) of the service.",
+            "for dependency in service.dependencies { dependency.name }",
+            Shared::<Service>::dependencies,
+        )
+        .register_get(
+            "functions",
+            "The functions of the service as pairs of name and function.",
+            "for function in service.functions { function.first }",
+            Shared::<Service>::functions,
+        )
+        .register_get(
+            "handlers",
+            "The handlers of the service as pairs of name and handler.",
+            "for handler in service.handlers { handler.first }",
+            Shared::<Service>::handlers,
+        )
+        .register_get(
+            "events",
+            "The events of the service as pairs of name and event.",
+            "for event in service.events { event.first }",
+            Shared::<Service>::events,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the service.",
+            "service.attributes.len()",
+            Shared::<Service>::attributes,
+        )
+        .register_get(
+            "visibility",
+            "The visibility marker of the service (`\"pub\"`, `\"internal\"` or empty).",
+            "service.visibility == \"pub\"",
+            Shared::<Service>::visibility,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the service.",
+            "service.docs.join(NL)",
+            Shared::<Service>::docs,
+        );
+
+    engine.register_type::<Shared<Dependency>>();
+    functions
+        .register_get(
+            "name",
+            "The path of the service the service depends on.",
+            "dependency.name.join(\"::\")",
+            Shared::<Dependency>::name,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the dependency.",
+            "dependency.attributes.len()",
+            Shared::<Dependency>::attributes,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the dependency.",
+            "dependency.docs.join(NL)",
+            Shared::<Dependency>::docs,
+        );
+
+    engine.register_type::<Shared<Function>>();
+    functions
+        .register_get(
+            "arguments",
+            "The arguments of the function as pairs of name and type.",
+            "for argument in function.arguments { argument.first }",
+            Shared::<Function>::arguments,
+        )
+        .register_get(
+            "return_type",
+            "The return type of the function, if it has one.",
+            "if function.return_type.is_some() { function.return_type.unwrap().type }",
+            Shared::<Function>::return_type,
+        )
+        .register_get(
+            "error_type",
+            "The error type (`throws`) of the function, if it has one.",
+            "function.error_type.is_some()",
+            Shared::<Function>::error_type,
+        )
+        .register_get(
+            "is_oneway",
+            "Whether the function is `oneway`, i.e. the caller doesn't wait for a result.",
+            "if function.is_oneway { \"void\" }",
+            Shared::<Function>::is_oneway,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the function.",
+            "function.attributes.len()",
+            Shared::<Function>::attributes,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the function.",
+            "function.docs.join(NL)",
+            Shared::<Function>::docs,
+        );
+
+    engine.register_type::<Shared<Event>>();
+    functions
+        .register_get(
+            "arguments",
+            "The arguments of the event as pairs of name and type.",
+            "for argument in event.arguments { argument.first }",
+            Shared::<Event>::arguments,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the event.",
+            "event.attributes.len()",
+            Shared::<Event>::attributes,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the event.",
+            "event.docs.join(NL)",
+            Shared::<Event>::docs,
+        );
+
+    // The types of properties and arguments are shared, the element types of lists are not
+    macro_rules! register_type_name {
+        ($($T: ty),*) => {
+            $(
+            engine.register_type::<$T>();
+            functions
+                .register_get(
+                    "type",
+                    "The path of the type, without the lists around it.",
+                    "typ.type.join(\"::\")",
+                    |this: &mut $T| this.typ(),
+                )
+                .register_get(
+                    "is_list",
+                    "Whether the type is a list or an array (of any depth).",
+                    "if typ.is_list { \"Vec\" }",
+                    |this: &mut $T| this.is_list(),
+                )
+                .register_get(
+                    "lists",
+                    "The lists around the type, outermost first: the size of arrays or `()` for lists.",
+                    "typ.lists.len()",
+                    |this: &mut $T| this.lists(),
+                )
+                .register_get(
+                    "count",
+                    "Deprecated, use `lists` instead. The count of the outermost list, if it is an array.",
+                    "if typ.count.is_some() { typ.count.unwrap() }",
+                    |this: &mut $T| this.count(),
+                )
+                .register_get(
+                    "element",
+                    "The type of the elements of the outermost list, or `()` if the type isn't a list.",
+                    "typ.element.unwrap().type",
+                    |this: &mut $T| this.element(),
+                )
+                .register_get(
+                    "is_primitive",
+                    "Whether the type is one of the builtin primitive types (e.g. `i32` or `string`).",
+                    "if typ.is_primitive { typ.type.join(\"\") }",
+                    |this: &mut $T| this.is_primitive(),
+                )
+                .register_get(
+                    "attributes",
+                    "The attributes of the property or argument.",
+                    "typ.attributes.len()",
+                    |this: &mut $T| this.attributes(),
+                )
+                .register_get(
+                    "docs",
+                    "The doc comments of the property or argument.",
+                    "typ.docs.join(NL)",
+                    |this: &mut $T| this.docs(),
+                );
+            )*
+        };
+    }
 
-    engine
-        .register_type::<Function>()
-        .register_get("arguments", Function::arguments)
-        .register_get("return_type", Function::return_type)
-        .register_get("attributes", Function::attributes);
+    register_type_name!(TypeName, Shared<TypeName>);
+
+    engine.register_type::<Shared<EnumValue>>();
+    functions
+        .register_get(
+            "value",
+            "The assigned number of the enum value, if it has one.",
+            "value.value.unwrap_or(0)",
+            Shared::<EnumValue>::value,
+        )
+        .register_get(
+            "attributes",
+            "The attributes of the enum value.",
+            "value.attributes.len()",
+            Shared::<EnumValue>::attributes,
+        )
+        .register_get(
+            "docs",
+            "The doc comments of the enum value.",
+            "value.docs.join(NL)",
+            Shared::<EnumValue>::docs,
+        )
+        .register_get(
+            "radix",
+            "The radix the number was written in.",
+            "value.radix",
+            Shared::<EnumValue>::radix,
+        )
+        .register_get(
+            "formatted",
+            "The assigned number in its radix (e.g. `0x1`), if the value has one.",
+            "value.formatted.unwrap_or(\"\")",
+            Shared::<EnumValue>::formatted,
+        );
+
+    macro_rules! register_attribute {
+        ($($T: ty),*) => {
+            $(
+            engine.register_type::<$T>();
+            functions
+                .register_get(
+                    "name",
+                    "The path of the attribute.",
+                    "attribute.name.join(\"::\")",
+                    |this: &mut $T| this.name(),
+                )
+                .register_get(
+                    "parameters",
+                    "The parameters of the attribute.",
+                    "for parameter in attribute.parameters { parameter.name }",
+                    |this: &mut $T| this.parameters(),
+                );
+            )*
+        };
+    }
 
-    engine
-        .register_type::<Event>()
-        .register_get("arguments", Event::arguments)
-        .register_get("attributes", Event::attributes);
+    register_attribute!(Attribute, Shared<Attribute>);
+
+    engine.register_type::<Parameter>();
+    functions
+        .register_get(
+            "name",
+            "The name of the parameter.",
+            "parameter.name",
+            Parameter::name,
+        )
+        .register_get(
+            "value",
+            "The value of the parameter, if it has one (`#[attr(name = \"value\")]`).",
+            "parameter.value.unwrap_or(\"\")",
+            Parameter::value,
+        );
 
-    engine
-        .register_type::<TypeName>()
-        .register_get("type", TypeName::typ)
-        .register_get("is_list", TypeName::is_list)
-        .register_get("count", TypeName::count)
-        .register_get("attributes", TypeName::attributes);
+    engine.register_type::<Namespace>();
+    functions.register_get(
+        "components",
+        "The components of the path.",
+        "module.name.components.len()",
+        Namespace::components,
+    );
 
-    engine
-        .register_type::<EnumValue>()
-        .register_get("value", EnumValue::value)
-        .register_get("attributes", EnumValue::attributes);
+    macro_rules! register_attribute_lookup {
+        ($($T: ty),*) => {
+            $(
+            functions
+                .register(
+                    "get_attribute",
+                    ["element", "name"],
+                    "The attribute with the name, if the element has one.",
+                    "element.get_attribute(\"serde\")",
+                    |this: &mut $T, name: &str| find_attribute(&this.attributes, name).cloned(),
+                )
+                .register(
+                    "has_attribute",
+                    ["element", "name"],
+                    "Whether the element has an attribute with the name.",
+                    "if element.has_attribute(\"deprecated\") { \"#[deprecated]\" }",
+                    |this: &mut $T, name: &str| find_attribute(&this.attributes, name).is_some(),
+                );
+            )*
+        };
+    }
 
-    engine
-        .register_type::<Attribute>()
-        .register_get("name", Attribute::name)
-        .register_get("parameters", Attribute::parameters);
+    register_attribute_lookup!(
+        Import,
+        Shared<DataType>,
+        Shared<Enum>,
+        Shared<Service>,
+        Shared<Dependency>,
+        Shared<Function>,
+        Shared<Event>,
+        TypeName,
+        Shared<TypeName>,
+        Shared<EnumValue>
+    );
 
-    engine
-        .register_type::<Parameter>()
-        .register_get("name", Parameter::name)
-        .register_get("value", Parameter::value);
+    macro_rules! register_parameter_lookup {
+        ($($T: ty),*) => {
+            $(
+            functions
+                .register(
+                    "get_param",
+                    ["attribute", "name"],
+                    "The value of the parameter with the name, if the attribute has it with a value.",
+                    "attribute.get_param(\"rename\").unwrap_or(name)",
+                    |this: &mut $T, name: &str| {
+                        this.find_parameter(name).and_then(|p| p.value.clone())
+                    },
+                )
+                .register(
+                    "has_param",
+                    ["attribute", "name"],
+                    "Whether the attribute has a parameter with the name.",
+                    "attribute.has_param(\"skip\")",
+                    |this: &mut $T, name: &str| this.find_parameter(name).is_some(),
+                );
+            )*
+        };
+    }
 
-    engine
-        .register_type::<Namespace>()
-        .register_get("components", Namespace::components);
+    register_parameter_lookup!(Attribute, Shared<Attribute>);
 
     macro_rules! register_options {
         ($($T: ty),*) => {
             $(
-            engine
-                .register_fn("is_some", script_is_some::<$T>)
-                .register_fn("unwrap", script_unwrap::<$T>)
-                .register_fn("unwrap_or", script_unwrap_or::<$T>);
+            functions
+                .register(
+                    "is_some",
+                    ["option"],
+                    "Whether the optional value is set.",
+                    "parameter.value.is_some()",
+                    script_is_some::<$T>,
+                )
+                .register(
+                    "unwrap",
+                    ["option"],
+                    "The optional value, fails if it isn't set.",
+                    "parameter.value.unwrap()",
+                    script_unwrap::<$T>,
+                )
+                .register(
+                    "unwrap_or",
+                    ["option", "default"],
+                    "The optional value, or `default` if it isn't set.",
+                    "parameter.value.unwrap_or(\"\")",
+                    script_unwrap_or::<$T>,
+                );
             )*
         };
     }
 
     register_options!(
-        String, i64, u64, i32, u32, i16, u16, i8, u8, usize, isize, i128, u128, TypeName
+        String, i64, u64, i32, u32, i16, u16, i8, u8, usize, isize, i128, u128, TypeName, Attribute
     );
-
-    engine
-        .register_fn("unwrap_or", script_unwrap_string_or)
-        .register_fn("join", script_join)
-        .register_fn("join", script_join_typepath)
-        .register_fn("split", script_split)
-        .register_fn("splitn", script_splitn)
-        .register_fn("rsplitn", script_rsplitn)
-        .register_fn("is_empty", script_string_is_empty)
-        .register_fn("is_empty", script_array_is_empty)
-        .register_fn("starts_with", script_starts_with)
-        .register_fn("ends_with", script_ends_with)
-        .register_fn("trim", script_trim)
-        .register_fn("is_string", script_is_no_string)
-        .register_fn("is_string", script_is_string)
-        .register_fn("find_paths", script_find_paths);
-
-    #[cfg(not(feature = "_web"))]
-    engine
-        .register_fn("read_file", script_read_file)
-        .register_fn("is_dir", script_is_dir)
-        .register_fn("is_file", script_is_file)
-        .register_fn("exists", script_exists);
+    register_options!(Shared<TypeName>, Shared<Attribute>);
+
+    functions
+        .register(
+            "unwrap_or",
+            ["path", "default"],
+            "The optional path as string, or `default` if it isn't set.",
+            "path.unwrap_or(\"\")",
+            script_unwrap_string_or,
+        )
+        .register(
+            "join",
+            ["list", "sep"],
+            "Joins the strings with the separator.",
+            "data_type.docs.join(NL)",
+            script_join,
+        )
+        .register(
+            "join",
+            ["path", "sep"],
+            "Joins the components of the path with the separator.",
+            "module.name.join(\"::\")",
+            script_join_typepath,
+        )
+        .register(
+            "split",
+            ["text", "pattern"],
+            "Splits the text at every occurrence of the pattern.",
+            "\"a::b\".split(\"::\")",
+            script_split,
+        )
+        .register(
+            "splitn",
+            ["text", "n", "pattern"],
+            "Splits the text at the first `n - 1` occurrences of the pattern.",
+            "\"a::b::c\".splitn(2, \"::\")",
+            script_splitn,
+        )
+        .register(
+            "rsplitn",
+            ["text", "n", "pattern"],
+            "Splits the text at the last `n - 1` occurrences of the pattern, from the end.",
+            "\"a::b::c\".rsplitn(2, \"::\")",
+            script_rsplitn,
+        )
+        .register(
+            "is_empty",
+            ["text"],
+            "Whether the string is empty.",
+            "data_type.visibility.is_empty()",
+            script_string_is_empty,
+        )
+        .register(
+            "is_empty",
+            ["array"],
+            "Whether the array is empty.",
+            "module.referenced_types_of(\"Users\").is_empty()",
+            script_array_is_empty,
+        )
+        .register(
+            "starts_with",
+            ["text", "pattern"],
+            "Whether the text starts with the pattern.",
+            "name.starts_with(\"get_\")",
+            script_starts_with,
+        )
+        .register(
+            "ends_with",
+            ["text", "pattern"],
+            "Whether the text ends with the pattern.",
+            "name.ends_with(\"Id\")",
+            script_ends_with,
+        )
+        .register(
+            "trim",
+            ["text"],
+            "The text without leading and trailing whitespace.",
+            "doc.trim()",
+            script_trim,
+        )
+        .register(
+            "is_string",
+            ["value"],
+            "Whether the value is a string.",
+            "value.is_string()",
+            script_is_no_string,
+        )
+        .register(
+            "is_string",
+            ["value"],
+            "Whether the value is a string.",
+            "value.is_string()",
+            script_is_string,
+        )
+        .register(
+            "find_paths",
+            ["pattern"],
+            "The paths of the files matching the glob pattern.",
+            "find_paths(\"templates/*.txt\")",
+            script_find_paths,
+        );
+
+    functions
+        .register(
+            "to_snake_case",
+            ["name"],
+            "Converts the name to `snake_case`.",
+            "to_snake_case(\"UserId\")",
+            to_snake_case,
+        )
+        .register(
+            "to_camel_case",
+            ["name"],
+            "Converts the name to `camelCase`.",
+            "to_camel_case(\"user_id\")",
+            to_camel_case,
+        )
+        .register(
+            "to_pascal_case",
+            ["name"],
+            "Converts the name to `PascalCase`.",
+            "to_pascal_case(\"user_id\")",
+            to_pascal_case,
+        )
+        .register(
+            "to_kebab_case",
+            ["name"],
+            "Converts the name to `kebab-case`.",
+            "to_kebab_case(\"UserId\")",
+            to_kebab_case,
+        )
+        .register(
+            "to_screaming_snake_case",
+            ["name"],
+            "Converts the name to `SCREAMING_SNAKE_CASE`.",
+            "to_screaming_snake_case(\"UserId\")",
+            to_screaming_snake_case,
+        )
+        .register(
+            "sanitize_ident",
+            ["ident", "target_lang"],
+            "Escapes the identifier if it's a keyword of the target language (e.g. `rust`).",
+            "sanitize_ident(\"type\", \"rust\")",
+            script_sanitize_ident,
+        );
+
+    #[cfg(not(feature = "web"))]
+    functions
+        .register(
+            "read_file",
+            ["path"],
+            "The content of the file.",
+            "read_file(\"header.txt\")",
+            script_read_file,
+        )
+        .register(
+            "is_dir",
+            ["path"],
+            "Whether the path is an existing directory.",
+            "is_dir(\"templates\")",
+            script_is_dir,
+        )
+        .register(
+            "is_file",
+            ["path"],
+            "Whether the path is an existing file.",
+            "is_file(\"header.txt\")",
+            script_is_file,
+        )
+        .register(
+            "exists",
+            ["path"],
+            "Whether the path exists.",
+            "exists(\"header.txt\")",
+            script_exists,
+        );
 
     // DSL
     engine
         .register_custom_operator("and", 60)
         .unwrap()
-        .register_fn("and", |a: bool, b: bool| a && b)
         .register_custom_operator("or", 30)
         .unwrap()
-        .register_fn("or", |a: bool, b: bool| a || b)
         .register_custom_operator("xor", 30)
         .unwrap()
-        .register_fn("xor", |a: bool, b: bool| a ^ b)
         .register_custom_operator("contains", 15)
         .unwrap()
         .register_custom_operator("equals", 15)
         .unwrap()
         .register_custom_operator("require", 15)
-        .unwrap()
-        .register_fn("contains", script_map_contains)
-        .register_fn("contains", script_string_contains)
-        .register_fn("equals", script_map_equals)
-        .register_fn("equals", script_value_equals)
-        .register_fn("equals", script_array_equals)
-        .register_fn("contains", script_array_contains)
-        .register_fn("require", script_require)
-        .register_fn("any", script_any)
-        .register_fn("all", script_all)
-        .register_fn("none", script_none);
+        .unwrap();
+    functions
+        .register(
+            "and",
+            ["a", "b"],
+            "Whether both are true.",
+            "typ.is_list and typ.is_primitive",
+            |a: bool, b: bool| a && b,
+        )
+        .register(
+            "or",
+            ["a", "b"],
+            "Whether at least one of them is true.",
+            "typ.is_list or typ.is_primitive",
+            |a: bool, b: bool| a || b,
+        )
+        .register(
+            "xor",
+            ["a", "b"],
+            "Whether exactly one of them is true.",
+            "typ.is_list xor typ.is_primitive",
+            |a: bool, b: bool| a ^ b,
+        )
+        .register(
+            "contains",
+            ["map", "name"],
+            "Whether the map has the key.",
+            "defines contains \"prefix\"",
+            script_map_contains,
+        )
+        .register(
+            "contains",
+            ["text", "pattern"],
+            "Whether the text contains the pattern.",
+            "name contains \"::\"",
+            script_string_contains,
+        )
+        .register(
+            "equals",
+            ["a", "b"],
+            "Whether both maps have the same keys with equal values.",
+            "#{a: 1} equals #{a: 1}",
+            script_map_equals,
+        )
+        .register(
+            "equals",
+            ["a", "b"],
+            "Whether both values have the same type and are equal (deeply for arrays and maps).",
+            "value equals \"x\"",
+            script_value_equals,
+        )
+        .register(
+            "equals",
+            ["a", "b"],
+            "Whether both arrays have the same length and equal elements.",
+            "[1, 2] equals [1, 2]",
+            script_array_equals,
+        )
+        .register(
+            "contains",
+            ["array", "value"],
+            "Whether the array has an element equal to the value.",
+            "[\"i32\", \"i64\"] contains name",
+            script_array_contains,
+        )
+        .register(
+            "require",
+            ["conditions", "n"],
+            "Whether exactly `n` of the conditions are true.",
+            "[typ.is_list, typ.is_primitive] require 1",
+            script_require,
+        )
+        .register(
+            "any",
+            ["conditions"],
+            "Whether at least one of the conditions is true.",
+            "any([typ.is_list, typ.is_primitive])",
+            script_any,
+        )
+        .register(
+            "all",
+            ["conditions"],
+            "Whether all of the conditions are true.",
+            "all([typ.is_list, typ.is_primitive])",
+            script_all,
+        )
+        .register(
+            "none",
+            ["conditions"],
+            "Whether none of the conditions is true.",
+            "none([typ.is_list, typ.is_primitive])",
+            script_none,
+        );
 
     macro_rules! register_msg_single {
         ($($T: ty),*) => {
             $(
             {
                 let messages = messages.clone();
-                engine.register_fn("-", move |msg: $T| {
-                    messages.borrow_mut().push(format!("{msg}"));
-                });
+                functions.register(
+                    "-",
+                    ["value"],
+                    "Emits the value to the output.",
+                    "- \"}\\n\";",
+                    move |msg: $T| {
+                        messages.borrow_mut().push(format!("{msg}"));
+                    },
+                );
             }
             )*
         };
@@ -552,10 +1599,16 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
             $(
             {
                 let messages = messages.clone();
-                engine.register_fn("++", move |a: $A, b: $B| {
-                    messages.borrow_mut().push(format!("{a}"));
-                    messages.borrow_mut().push(format!("{b}"));
-                });
+                functions.register(
+                    "++",
+                    ["a", "b"],
+                    "Emits both values to the output.",
+                    "\"struct \" ++ name ++ \" {\\n\";",
+                    move |a: $A, b: $B| {
+                        messages.borrow_mut().push(format!("{a}"));
+                        messages.borrow_mut().push(format!("{b}"));
+                    },
+                );
             }
             )*
         };
@@ -572,22 +1625,37 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
         (bool, bool)
     );
 
+    const COMPARISON_DOC: &str =
+        "Compares integers of different types, e.g. with the `usize` lengths of lists.";
+    const COMPARISON_EXAMPLE: &str = "if function.arguments.len() > 0 { \"(args)\" }";
+
+    macro_rules! register_comparison_ops {
+        ($A: ty, $B: ty, $C: ty) => {
+            let operators: [(&str, fn($C, $C) -> bool); 6] = [
+                (">", |left, right| left > right),
+                (">=", |left, right| left >= right),
+                ("<", |left, right| left < right),
+                ("<=", |left, right| left <= right),
+                ("!=", |left, right| left != right),
+                ("==", |left, right| left == right),
+            ];
+            for (op, compare) in operators {
+                functions.register(
+                    op,
+                    ["left", "right"],
+                    COMPARISON_DOC,
+                    COMPARISON_EXAMPLE,
+                    move |left: $A, right: $B| compare(left as $C, right as $C),
+                );
+            }
+        };
+    }
+
     macro_rules! register_comparison {
         ($(($A: ty, $B: ty, $C: ty)),*) => {
             $(
-            engine.register_fn(">",  |left: $A, right: $B| left as $C >  right as $C);
-            engine.register_fn(">=", |left: $A, right: $B| left as $C >= right as $C);
-            engine.register_fn("<",  |left: $A, right: $B| left as $C <  right as $C);
-            engine.register_fn("<=", |left: $A, right: $B| left as $C <= right as $C);
-            engine.register_fn("!=", |left: $A, right: $B| left as $C != right as $C);
-            engine.register_fn("==", |left: $A, right: $B| left as $C == right as $C);
-
-            engine.register_fn(">",  |left: $B, right: $A| left as $C >  right as $C);
-            engine.register_fn(">=", |left: $B, right: $A| left as $C >= right as $C);
-            engine.register_fn("<",  |left: $B, right: $A| left as $C <  right as $C);
-            engine.register_fn("<=", |left: $B, right: $A| left as $C <= right as $C);
-            engine.register_fn("!=", |left: $B, right: $A| left as $C != right as $C);
-            engine.register_fn("==", |left: $B, right: $A| left as $C == right as $C);
+            register_comparison_ops!($A, $B, $C);
+            register_comparison_ops!($B, $A, $C);
             )*
         };
     }
@@ -603,18 +1671,33 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
         (u8, usize, usize)
     );
 
+    const CONCAT_DOC: &str = "Emits both values to the output.";
+    const CONCAT_EXAMPLE: &str = "\"[\" ++ size ++ \"]\";";
+
     macro_rules! register_string_concat_void {
         ($($T: ty),*) => {$({
             let messages = messages.clone();
-            engine.register_fn("++", move |a: $T, _b: ()| {
-                messages.borrow_mut().push(a.to_string());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: $T, _b: ()| {
+                    messages.borrow_mut().push(a.to_string());
+                },
+            );
         }
         {
             let messages = messages.clone();
-            engine.register_fn("++", move |_a: (), b: $T| {
-                messages.borrow_mut().push(b.to_string());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |_a: (), b: $T| {
+                    messages.borrow_mut().push(b.to_string());
+                },
+            );
         }
         )*};
     }
@@ -622,48 +1705,84 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     macro_rules! register_string_concat {
         ($($T: ty),*) => {$({
             let messages = messages.clone();
-            engine.register_fn("++", move |a: $T, b: &str| {
-                messages.borrow_mut().push(a.to_string());
-                messages.borrow_mut().push(b.to_owned());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: $T, b: &str| {
+                    messages.borrow_mut().push(a.to_string());
+                    messages.borrow_mut().push(b.to_owned());
+                },
+            );
         }
         {
             let messages = messages.clone();
-            engine.register_fn("++", move |a: &str, b: $T| {
-                messages.borrow_mut().push(a.to_owned());
-                messages.borrow_mut().push(b.to_string());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: &str, b: $T| {
+                    messages.borrow_mut().push(a.to_owned());
+                    messages.borrow_mut().push(b.to_string());
+                },
+            );
         }
         {
             let messages = messages.clone();
-            engine.register_fn("++", move |a: $T, b: $T| {
-                messages.borrow_mut().push(a.to_string());
-                messages.borrow_mut().push(b.to_string());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: $T, b: $T| {
+                    messages.borrow_mut().push(a.to_string());
+                    messages.borrow_mut().push(b.to_string());
+                },
+            );
         })*};
     }
 
     macro_rules! register_string_concat_vec {
         ($($T: ty),*) => {$({
             let messages = messages.clone();
-            engine.register_fn("++", move |a: &Vec<$T>, b: &str| {
-                messages.borrow_mut().push(format!("{:?}", a));
-                messages.borrow_mut().push(b.to_owned());
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: &Vec<$T>, b: &str| {
+                    messages.borrow_mut().push(format!("{:?}", a));
+                    messages.borrow_mut().push(b.to_owned());
+                },
+            );
         }
         {
             let messages = messages.clone();
-            engine.register_fn("++", move |a: &str, b: &Vec<$T>| {
-                messages.borrow_mut().push(a.to_owned());
-                messages.borrow_mut().push(format!("{:?}", b));
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: &str, b: &Vec<$T>| {
+                    messages.borrow_mut().push(a.to_owned());
+                    messages.borrow_mut().push(format!("{:?}", b));
+                },
+            );
         }
         {
             let messages = messages.clone();
-            engine.register_fn("++", move |a: &Vec<$T>, b: &Vec<$T>| {
-                messages.borrow_mut().push(format!("{:?}", a));
-                messages.borrow_mut().push(format!("{:?}", b));
-            });
+            functions.register(
+                "++",
+                ["a", "b"],
+                CONCAT_DOC,
+                CONCAT_EXAMPLE,
+                move |a: &Vec<$T>, b: &Vec<$T>| {
+                    messages.borrow_mut().push(format!("{:?}", a));
+                    messages.borrow_mut().push(format!("{:?}", b));
+                },
+            );
         })*};
     }
 
@@ -679,70 +1798,127 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
 
     {
         let messages = messages.clone();
-        engine.register_fn("++", move |(): (), b: &str| {
-            messages.borrow_mut().push(b.to_owned());
-        });
+        functions.register(
+            "++",
+            ["a", "b"],
+            CONCAT_DOC,
+            CONCAT_EXAMPLE,
+            move |(): (), b: &str| {
+                messages.borrow_mut().push(b.to_owned());
+            },
+        );
     }
     {
         let messages = messages.clone();
-        engine.register_fn("++", move |(): (), b: usize| {
-            messages.borrow_mut().push(b.to_string());
-        });
+        functions.register(
+            "++",
+            ["a", "b"],
+            CONCAT_DOC,
+            CONCAT_EXAMPLE,
+            move |(): (), b: usize| {
+                messages.borrow_mut().push(b.to_string());
+            },
+        );
     }
     engine.register_custom_operator("++", 15).unwrap();
     {
         let messages = messages.clone();
-        engine.register_fn("emit", move |msg: &str| {
-            messages.borrow_mut().push(msg.to_owned());
-        });
+        functions.register(
+            "emit",
+            ["text"],
+            "Emits the text to the output.",
+            "emit(\"}\" + NL);",
+            move |msg: &str| {
+                messages.borrow_mut().push(msg.to_owned());
+            },
+        );
     }
+    functions.register(
+        "emit",
+        ["path", "content"],
+        "Appends the content to the file with the path (relative to `--out-dir`).",
+        "emit(to_snake_case(name) + \".rs\", content);",
+        move |path: &str, content: &str| {
+            files
+                .borrow_mut()
+                .entry(path.to_owned())
+                .or_default()
+                .push_str(content);
+        },
+    );
     engine.register_custom_operator("then_emit", 15).unwrap();
     {
         let messages = messages.clone();
-        engine.register_fn("then_emit", move |a: bool, msg: &str| {
-            if a {
-                messages.borrow_mut().push(msg.to_owned());
-            }
-            a
-        });
+        functions.register(
+            "then_emit",
+            ["condition", "text"],
+            "Emits the text if the condition is true, and returns the condition.",
+            "typ.is_list then_emit \"Vec<\";",
+            move |a: bool, msg: &str| {
+                if a {
+                    messages.borrow_mut().push(msg.to_owned());
+                }
+                a
+            },
+        );
     }
     {
         let messages = messages.clone();
-        engine.register_fn("then_emit", move |a: bool, m: Map| {
-            if a {
-                let msg = m
-                    .get("msg")
-                    .map(|e| e.clone().into_string().unwrap())
-                    .unwrap();
-                messages.borrow_mut().push(msg);
-            }
-            a
-        });
+        functions.register(
+            "then_emit",
+            ["condition", "options"],
+            "Emits `msg` of the map if the condition is true, and returns the condition.",
+            "typ.is_list then_emit #{msg: \"Vec<\"};",
+            move |a: bool, m: Map| {
+                if a {
+                    let msg = m
+                        .get("msg")
+                        .map(|e| e.clone().into_string().unwrap())
+                        .unwrap();
+                    messages.borrow_mut().push(msg);
+                }
+                a
+            },
+        );
     }
     engine.register_custom_operator("or_emit", 15).unwrap();
     {
         let messages = messages.clone();
-        engine.register_fn("or_emit", move |a: bool, msg: &str| {
-            if !a {
-                messages.borrow_mut().push(msg.to_owned());
-            }
-            a
-        });
+        functions.register(
+            "or_emit",
+            ["condition", "text"],
+            "Emits the text if the condition is false, and returns the condition.",
+            "typ.is_primitive or_emit \"crate::\";",
+            move |a: bool, msg: &str| {
+                if !a {
+                    messages.borrow_mut().push(msg.to_owned());
+                }
+                a
+            },
+        );
     }
     {
-        engine.register_fn("or_emit", move |a: bool, m: Map| {
-            if !a {
-                let msg = m
-                    .get("msg")
-                    .map(|e| e.clone().into_string().unwrap())
-                    .unwrap();
-                messages.borrow_mut().push(msg);
-            }
-            a
-        });
+        functions.register(
+            "or_emit",
+            ["condition", "options"],
+            "Emits `msg` of the map if the condition is false, and returns the condition.",
+            "typ.is_primitive or_emit #{msg: \"crate::\"};",
+            move |a: bool, m: Map| {
+                if !a {
+                    let msg = m
+                        .get("msg")
+                        .map(|e| e.clone().into_string().unwrap())
+                        .unwrap();
+                    messages.borrow_mut().push(msg);
+                }
+                a
+            },
+        );
     }
     // END DSL
 
+    engine.register_global_module(functions.0.into());
+
     if debug {
         engine.on_print(move |x| eprintln!("INFO => {x}"));
         engine.on_debug(move |x, _, pos| eprintln!("DEBUG({pos:?}) => {x}"));
@@ -756,7 +1932,24 @@ pub fn build_engine(messages: Rc<RefCell<Vec<String>>>, debug: bool) -> Engine {
     engine
 }
 
-#[cfg(feature = "_web")]
+/// The definitions of the builtin functions and the ones registered by `build_engine`, for the
+/// rhai language server.
+#[cfg(feature = "_bin")]
+pub fn definitions(engine: &Engine) -> String {
+    let builtin = engine.definitions();
+    // rhai skips all global modules when the standard packages are included, so the registered
+    // functions are taken from definitions without them
+    let registered = engine.definitions().include_standard_packages(false);
+    format!(
+        "module static;\n\n{}\n{}\n{}\n",
+        builtin.builtin_functions_operators(),
+        builtin.builtin_functions(),
+        registered.static_module()
+    )
+}
+
+#[cfg(feature = "web")]
+// only exported by the library, the binary gets the feature through the workspace
 pub fn generate_web(
     defines: HashMap<String, String>,
     namespace: &str,
@@ -764,56 +1957,755 @@ pub fn generate_web(
     typemap: &str,
     data: &str,
     debug: bool,
-) -> Result<String, Box<dyn Error>> {
+) -> Result<(String, EmittedFiles), Box<dyn Error>> {
     let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
 
-    let engine = build_engine(messages.clone(), debug);
+    let engine = build_engine(messages.clone(), files.clone(), None, debug);
 
     let mut scope = Scope::new();
-    let module = crate::parse(data, Namespace::new(namespace))?;
-    let module = crate::update_types(module, typemap)?;
-
-    scope.push("module", module);
-    scope.push_constant("defines", defines);
+    let ssd_data::SsdModel {
+        module,
+        defines,
+        meta,
+        ..
+    } = crate::helper::load_web_model(defines, namespace, typemap, data)?;
+
+    scope.push("module", Shared::new(module));
+    scope.push_constant("defines", rhai::serde::to_dynamic(&defines)?);
+    scope.push_constant("meta", rhai::serde::to_dynamic(&meta)?);
     scope.push_constant("NL", "\n");
     engine.run_with_scope(&mut scope, script)?;
     let messages = messages.borrow();
-    Ok(messages.join(""))
+    Ok((messages.join(""), files.take()))
+}
+
+/// Compiles the script file. Rhai reports files which can't be read as system errors, so the io
+/// error is taken out of them to classify it.
+#[cfg(feature = "_bin")]
+fn compile_file(engine: &Engine, scope: &Scope, path: &Path) -> Result<rhai::AST, Box<dyn Error>> {
+    engine
+        .compile_file_with_scope(scope, path.to_path_buf())
+        .map_err(|error| match *error {
+            EvalAltResult::ErrorSystem(_, source) => match source.downcast::<std::io::Error>() {
+                Ok(source) => io_error(path, *source).into(),
+                Err(source) => source,
+            },
+            error => error.into(),
+        })
+}
+
+#[cfg(feature = "_bin")]
+pub fn run_script(
+    base: &PathBuf,
+    defines: Defines,
+    script: &PathBuf,
+    prelude: &[PathBuf],
+    input: BaseInputData,
+    debug: bool,
+    limits: &Limits,
+) -> Result<(String, EmittedFiles), Box<dyn Error>> {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+
+    let mut engine = build_engine(messages.clone(), files.clone(), script.parent(), debug);
+    apply_limits(&mut engine, limits);
+    if input.reproducible {
+        engine.register_fn("timestamp", || -> Result<Dynamic, Box<EvalAltResult>> {
+            Err(not_reproducible("timestamp()").into())
+        });
+    }
+
+    let mut scope = Scope::new();
+    scope.push_constant("ast", rhai::serde::to_dynamic(input.load_ast()?)?);
+    let meta = if input.raw {
+        let meta = input.meta("");
+        let module = input.load_raw()?;
+
+        scope.push("module", rhai::serde::to_dynamic(&module)?);
+        meta
+    } else {
+        let module = load_module(base, &defines, &input, Some(script))?;
+        let meta = input.meta(&module.namespace.to_string());
+
+        scope.push("module", Shared::new(module));
+        meta
+    };
+    scope.push_constant("defines", rhai::serde::to_dynamic(&defines)?);
+    scope.push_constant("meta", rhai::serde::to_dynamic(&meta)?);
+    scope.push_constant("NL", "\n");
+    let mut ast = rhai::AST::empty();
+    for prelude in prelude {
+        ast += compile_file(&engine, &scope, prelude)?;
+    }
+    ast += compile_file(&engine, &scope, script)?;
+    engine
+        .run_ast_with_scope(&mut scope, &ast)
+        .map_err(termination_reason)?;
+    let result = messages.borrow().join("");
+    let files = files.take();
+    Ok((result, files))
+}
+
+/// Runs a lint script (`ssd check --lint`) against the module and returns what it reported with
+/// `report(level, span, message)`.
+#[cfg(feature = "_bin")]
+pub fn run_lint(
+    defines: &Defines,
+    script: &Path,
+    module: SsdModule,
+) -> Result<Vec<LintDiagnostic>, Box<dyn Error>> {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let reports = Rc::new(RefCell::new(Vec::new()));
+
+    let mut engine = build_engine(messages, files, script.parent(), false);
+    {
+        let reports = reports.clone();
+        engine.register_fn(
+            "report",
+            move |level: &str, span: &str, message: &str| -> Result<(), Box<EvalAltResult>> {
+                if level != "warn" && level != "deny" {
+                    return Err(format!("Unknown level {level}, expected warn or deny").into());
+                }
+                reports.borrow_mut().push(LintDiagnostic {
+                    level: level.to_owned(),
+                    span: span.to_owned(),
+                    message: message.to_owned(),
+                });
+                Ok(())
+            },
+        );
+    }
+
+    let mut scope = Scope::new();
+    scope.push("module", Shared::new(module));
+    scope.push_constant("defines", rhai::serde::to_dynamic(defines)?);
+    scope.push_constant("NL", "\n");
+    let ast = compile_file(&engine, &scope, script)?;
+    engine.run_ast_with_scope(&mut scope, &ast)?;
+    drop(engine);
+    Ok(reports.take())
 }
 
 #[cfg(feature = "_bin")]
 pub fn generate(
     base: &PathBuf,
-    defines: HashMap<String, String>,
+    defines: Defines,
     Parameters {
         input,
         debug,
         script,
         out,
+        out_dir,
+        prelude,
+        limits,
     }: Parameters,
 ) -> Result<(), Box<dyn Error>> {
+    let (result, files) = run_script(base, defines, &script, &prelude, input, debug, &limits)?;
+    if !result.is_empty() {
+        out.write(&result)?;
+    }
+    if !files.is_empty() {
+        let out_dir = out_dir.ok_or("The script emitted files, but no --out-dir was given.")?;
+        out.write_files(&out_dir, files)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "_bin")]
+#[derive(Debug, Parser)]
+pub struct TestParameters {
+    /// The script to test.
+    pub script: PathBuf,
+    #[clap(long)]
+    /// The directory containing the test cases.
+    ///
+    /// Every `<case>.svc` file is used as input. The output of the script gets compared to
+    /// `<case>.out` and the files emitted by the script get compared to the contents of
+    /// `<case>.files/`.
+    pub cases: PathBuf,
+    #[clap(long)]
+    /// Update the golden files with the current output instead of comparing them.
+    pub bless: bool,
+    #[clap(long = "typemap", visible_alias = "tm")]
+    /// Files containing type mappings, later typemaps override earlier ones.
+    pub typemap: Vec<PathBuf>,
+    #[clap(long)]
+    /// Scripts which get evaluated before the script under test.
+    pub prelude: Vec<PathBuf>,
+    #[clap(flatten)]
+    pub limits: Limits,
+}
+
+/// Runs the script against every test case and compares the results with the golden files.
+///
+/// Returns `false` if any test case failed, which includes script errors and missing golden
+/// files.
+#[cfg(feature = "_bin")]
+pub fn test_script(
+    defines: &Defines,
+    TestParameters {
+        script,
+        cases,
+        bless,
+        typemap,
+        prelude,
+        limits,
+    }: TestParameters,
+) -> Result<bool, Box<dyn Error>> {
+    let mut inputs =
+        glob(&cases.join("*.svc").to_string_lossy())?.collect::<Result<Vec<_>, _>>()?;
+    inputs.sort();
+
+    let mut success = true;
+    for file in inputs {
+        let name = file.with_extension("");
+        let name = name.file_name().unwrap_or_default().to_string_lossy();
+        let golden_out = cases.join(format!("{name}.out"));
+        let golden_files = cases.join(format!("{name}.files"));
+        let input = BaseInputData {
+            no_map: false,
+            no_cache: true,
+            namespace: None,
+            typemap: typemap.clone(),
+            typemap_report: false,
+            transforms: Vec::new(),
+            raw: false,
+            raw_format: crate::helper::RawFormat::Auto,
+            raw_files: Vec::new(),
+            raw_merge: crate::helper::RawMerge::Key,
+            raw_data: None,
+            timestamp: false,
+            reproducible: false,
+            with_ast: false,
+            file,
+        };
+        let (result, files) = match run_script(
+            &cases,
+            defines.clone(),
+            &script,
+            &prelude,
+            input,
+            false,
+            &limits,
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                success &= report_case(&name, &[e.to_string()]);
+                continue;
+            }
+        };
+
+        if bless {
+            std::fs::write(&golden_out, &result)?;
+            if golden_files.exists() {
+                std::fs::remove_dir_all(&golden_files)?;
+            }
+            if !files.is_empty() {
+                write_files(&golden_files, &files)?;
+            }
+            println!("blessed {name}");
+            continue;
+        }
+
+        let expected = match std::fs::read_to_string(&golden_out) {
+            Ok(expected) => expected,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let golden_out = golden_out.display();
+                let missing = format!("{golden_out} is missing, run with --bless to create it.");
+                success &= report_case(&name, &[missing]);
+                continue;
+            }
+            Err(e) => return Err(io_error(&golden_out, e).into()),
+        };
+        let mut diffs = Vec::new();
+        if expected != result {
+            diffs.push(text_diff(&golden_out.to_string_lossy(), &expected, &result));
+        }
+        diffs.extend(diff_files(&read_files(&golden_files)?, &files));
+        success &= report_case(&name, &diffs);
+    }
+    Ok(success)
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_run_lint() {
+    let module = crate::parser::parse(
+        "service Orders { #[auth] fn get(id: u64); fn delete(id: u64); };",
+        Namespace::new("orders"),
+    )
+    .unwrap();
+    let script = std::env::temp_dir().join(format!("ssd-lint-{}.rhai", std::process::id()));
+    std::fs::write(
+        &script,
+        r#"
+        for service in module.services {
+            for function in service.second.functions {
+                if !function.second.has_attribute("auth") {
+                    report("deny", `${service.first}.${function.first}`, "missing #[auth] attribute");
+                }
+            }
+        }
+        "#,
+    )
+    .unwrap();
+    let reports = run_lint(&Defines::default(), &script, module.clone()).unwrap();
+    assert_eq!(
+        reports,
+        [LintDiagnostic {
+            level: "deny".to_owned(),
+            span: "Orders.delete".to_owned(),
+            message: "missing #[auth] attribute".to_owned(),
+        }]
+    );
+
+    std::fs::write(&script, r#"report("fatal", "", "x");"#).unwrap();
+    assert!(run_lint(&Defines::default(), &script, module).is_err());
+    std::fs::remove_file(&script).unwrap();
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_run_script_meta() {
+    let dir = std::env::temp_dir().join(format!("ssd-meta-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("orders.svc");
+    let script = dir.join("header.rhai");
+    std::fs::write(&file, "data Order { id: u64, };").unwrap();
+    std::fs::write(
+        &script,
+        r#"emit(`${meta.namespace} ${meta.tool_version == ""} ${meta.timestamp == ()}`);"#,
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let input = BaseInputData::parse_from(
+            ["input", "--no-cache"]
+                .iter()
+                .chain(args)
+                .copied()
+                .chain([file.to_str().unwrap()]),
+        );
+        let limits = Limits::parse_from(["limits"]);
+        run_script(
+            &dir,
+            Defines::default(),
+            &script,
+            &[],
+            input,
+            false,
+            &limits,
+        )
+        .map(|(result, _)| result)
+        .map_err(|e| e.to_string())
+    };
+    assert_eq!(run(&[]).unwrap(), "orders false true");
+    assert_eq!(run(&["--timestamp"]).unwrap(), "orders false false");
+    assert_eq!(run(&["--reproducible"]).unwrap(), "orders false true");
+
+    std::fs::write(&script, "let start = timestamp();").unwrap();
+    assert!(run(&[]).is_ok());
+    let error = run(&["--reproducible"]).unwrap_err();
+    assert!(error.contains("timestamp() can't be used with --reproducible"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_run_script_prelude() {
+    let dir = std::env::temp_dir().join(format!("ssd-prelude-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("orders.svc");
+    let script = dir.join("names.rhai");
+    let common = dir.join("common.rhai");
+    let extra = dir.join("extra.rhai");
+    std::fs::write(&file, "data Order { id: u64, }; data Item { id: u64, };").unwrap();
+    std::fs::write(
+        &common,
+        r#"const SEP = ";"; fn type_name(name) { "I" + name }"#,
+    )
+    .unwrap();
+    // later preludes see what the earlier ones defined
+    std::fs::write(&extra, "fn line(name) { type_name(name) }").unwrap();
+    std::fs::write(
+        &script,
+        "for dt in module.data_types { emit(line(dt.first) + SEP); }",
+    )
+    .unwrap();
+
+    let run = |prelude: &[PathBuf]| {
+        let input = BaseInputData::parse_from(["input", "--no-cache", file.to_str().unwrap()]);
+        let limits = Limits::parse_from(["limits"]);
+        run_script(
+            &dir,
+            Defines::default(),
+            &script,
+            prelude,
+            input,
+            false,
+            &limits,
+        )
+        .map(|(result, _)| result)
+        .map_err(|e| e.to_string())
+    };
+    assert_eq!(
+        run(&[common.clone(), extra.clone()]).unwrap(),
+        "IOrder;IItem;"
+    );
+    assert!(run(std::slice::from_ref(&extra))
+        .unwrap_err()
+        .contains("type_name"));
+    assert!(run(&[]).unwrap_err().contains("line"));
+    let missing = dir.join("missing.rhai");
+    let error = run(std::slice::from_ref(&missing)).unwrap_err();
+    assert!(error.contains(&missing.display().to_string()), "{error}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_run_script_ast() {
+    let dir = std::env::temp_dir().join(format!("ssd-ast-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("orders.svc");
+    let script = dir.join("docs.rhai");
+    std::fs::write(
+        &file,
+        "// Orders\ndata Order { id: u64, };\n// States\nenum State { Open, };",
+    )
+    .unwrap();
+    std::fs::write(
+        &script,
+        r#"
+if ast == () { emit("no ast"); return; }
+for element in ast {
+    if "Comment" in element { emit(`#${element.Comment}|`); }
+    if "DataType" in element { emit(`data ${element.DataType[0]}|`); }
+    if "Enum" in element { emit(`enum ${element.Enum[0]}|`); }
+}"#,
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let input = BaseInputData::parse_from(
+            ["input", "--no-cache"]
+                .iter()
+                .chain(args)
+                .copied()
+                .chain([file.to_str().unwrap()]),
+        );
+        let limits = Limits::parse_from(["limits"]);
+        run_script(
+            &dir,
+            Defines::default(),
+            &script,
+            &[],
+            input,
+            false,
+            &limits,
+        )
+        .map(|(result, _)| result)
+        .unwrap()
+    };
+    assert_eq!(run(&[]), "no ast");
+    assert_eq!(
+        run(&["--with-ast"]),
+        "#Orders|data Order|#States|enum State|"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_deprecated_type_getters() {
+    let module = crate::parser::parse(
+        "data Scene { transforms: 4 of list of f32, rows: list of string, id: u64, };",
+        Namespace::new("scene"),
+    )
+    .unwrap();
     let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages, files, None, false);
+    let mut scope = Scope::new();
+    scope.push("module", Shared::new(module));
+    let result = engine
+        .eval_with_scope::<String>(
+            &mut scope,
+            r#"
+            let result = "";
+            for property in module.data_types[0].second.properties {
+                let typ = property.second;
+                result += `${typ.is_list}:${if typ.count.is_some() { typ.count.unwrap() } else { "-" }} `;
+            }
+            result
+            "#,
+        )
+        .unwrap();
+    assert_eq!(result, "true:4 true:- false:- ");
+}
 
-    let engine = build_engine(messages.clone(), debug);
+#[test]
+fn test_shared_elements() {
+    let module = crate::parser::parse(
+        r#"
+        /// A point
+        /// in space
+        data Point {
+            /// The x coordinate
+            #[serde(rename = "X")]
+            x: i32,
+        };
+        enum Axis {
+            /// Horizontal
+            X = 1,
+        };
+        service Points {
+            depends on Storage;
+            fn get(id: u64) -> Point;
+        };
+        "#,
+        Namespace::new("points"),
+    )
+    .unwrap();
+    let mut module = Shared::new(module);
+
+    // the handles point into the module instead of copying the elements
+    let mut point = module.data_types()["Point"].clone();
+    let x = &point.properties()["x"];
+    assert!(std::ptr::eq(&**x, &module.data_types[0].properties[0]));
+    let docs = point.docs();
+    assert!(std::ptr::eq(&*docs, &module.data_types[0].docs));
+    let lines = docs.into_iter().collect::<Vec<_>>();
+    assert_eq!(lines, ["A point", "in space"]);
 
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages, files, None, false);
     let mut scope = Scope::new();
-    if input.raw {
-        let module = parse_raw_data(input.file)?;
+    scope.push("module", module);
+    let result = engine
+        .eval_with_scope::<String>(
+            &mut scope,
+            r#"
+            let point = module.data_types[0].second;
+            let result = `${point.docs.len()}:${point.docs[1]}:`;
+            for line in point.docs { result += line; }
+            let x = point.properties[0].second;
+            result += `:${x.type.join("")}:${x.docs.join("")}`;
+            result += `:${x.get_attribute("serde").unwrap().get_param("rename").unwrap()}`;
+            for attribute in x.attributes { result += `:${attribute.has_param("rename")}`; }
+            let value = module.enums[0].second.values[0];
+            result += `:${value.first}=${value.second.value.unwrap()}${value.second.docs.join("")}`;
+            let service = module.services[0].second;
+            result += `:${service.dependencies[0].name.join("")}`;
+            result += `:${service.functions[0].second.return_type.unwrap().type.join("")}`;
+            result
+            "#,
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "2:in space:A pointin space:i32:The x coordinate:X:true:X=1Horizontal:Storage:Point"
+    );
+}
 
-        scope.push("module", module);
-    } else {
-        let module = parse_file(base, &input.file)?;
-        let module = update_types_from_file(module, input.no_map, input.typemap, Some(&script))?;
+#[test]
+fn test_emit_files() {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages.clone(), files.clone(), None, false);
+    engine
+        .run(
+            r#"
+            emit("a.rs", "struct A;");
+            emit("stdout");
+            emit("nested/b.rs", "struct B;");
+            emit("a.rs", "\n");
+            "#,
+        )
+        .unwrap();
+    assert_eq!(messages.borrow().join(""), "stdout");
+    assert_eq!(
+        files.take(),
+        EmittedFiles::from([
+            ("a.rs".to_owned(), "struct A;\n".to_owned()),
+            ("nested/b.rs".to_owned(), "struct B;".to_owned()),
+        ])
+    );
+}
+
+#[cfg(not(feature = "web"))]
+#[test]
+fn test_script_imports() {
+    let dir = std::env::temp_dir().join(format!("ssd-script-imports-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("common.rhai"),
+        r#"fn greet(name) { `Hello ${name}` }"#,
+    )
+    .unwrap();
+    let script = r#"import "common" as c; c::greet("World")"#;
+
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages.clone(), files.clone(), Some(&dir), false);
+    assert_eq!(engine.eval::<String>(script).unwrap(), "Hello World");
+
+    // without a script directory, imports are resolved from the working directory
+    let engine = build_engine(messages, files, None, false);
+    assert!(engine.eval::<String>(script).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
 
-        scope.push("module", module);
+#[cfg(feature = "_bin")]
+#[test]
+fn test_limits() {
+    let eval = |args: &[&str], script: &str| {
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let files = Rc::new(RefCell::new(EmittedFiles::new()));
+        let mut engine = build_engine(messages, files, None, false);
+        let limits = Limits::parse_from(["limits"].iter().chain(args));
+        apply_limits(&mut engine, &limits);
+        engine
+            .run(script)
+            .map_err(|e| termination_reason(e).to_string())
     };
-    scope.push_constant("defines", defines);
-    scope.push_constant("NL", "\n");
-    engine.run_file_with_scope(&mut scope, script)?;
-    let messages = messages.borrow();
-    if !messages.is_empty() {
-        let result = messages.join("");
-        print_or_write(out.out, &result)?;
+    let busy = "let x = 0; for i in 0..1000 { x += i; }";
+    assert!(eval(&[], busy).is_ok());
+    assert!(eval(&["--max-operations", "100"], busy)
+        .unwrap_err()
+        .contains("Too many operations"));
+    let long = r#"let s = ""; for i in 0..100 { s += "abcdefgh"; }"#;
+    assert!(eval(&["--max-string-size", "1000"], long).is_ok());
+    assert!(eval(&["--max-string-size", "100"], long)
+        .unwrap_err()
+        .contains("Length of string"));
+    assert!(eval(&["--timeout", "0"], busy)
+        .unwrap_err()
+        .contains("Script timed out after 0s."));
+    assert!(eval(&["--timeout", "1"], "loop {}")
+        .unwrap_err()
+        .contains("Script timed out after 1s."));
+}
+
+#[test]
+fn test_attribute_lookups() {
+    let module = crate::parser::parse(
+        r#"
+        #[table(name = "users", temporary)]
+        data User {
+            #[serde::rename(value = "ID")]
+            id: u64,
+        };
+        service Users {
+            #[http(get)]
+            fn get(id: u64) -> User;
+        };
+        "#,
+        Namespace::new("users"),
+    )
+    .unwrap();
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages, files, None, false);
+    let mut scope = Scope::new();
+    scope.push("module", Shared::new(module));
+    let result = engine
+        .eval_with_scope::<String>(
+            &mut scope,
+            r#"
+            let user = module.data_types["User"];
+            let table = user.get_attribute("table").unwrap();
+            let result = `${user.has_attribute("table")}:${user.has_attribute("view")}`;
+            result += `:${user.get_attribute("view").is_some()}`;
+            result += `:${table.get_param("name").unwrap()}:${table.has_param("temporary")}`;
+            result += `:${table.get_param("temporary").is_some()}:${table.has_param("schema")}`;
+            let id = user.properties["id"];
+            result += `:${id.get_attribute("serde::rename").unwrap().get_param("value").unwrap()}`;
+            result += `:${id.has_attribute("rename")}`;
+            let get = module.services["Users"].functions["get"];
+            result += `:${get.get_attribute("http").unwrap().has_param("get")}`;
+            result
+            "#,
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "true:false:false:users:true:false:false:ID:false:true"
+    );
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_definitions() {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let files = Rc::new(RefCell::new(EmittedFiles::new()));
+    let engine = build_engine(messages, files, None, false);
+    let definitions = definitions(&engine);
+
+    // the builtin functions are still there
+    assert!(definitions.contains("fn is_def_fn(fn_name: String, num_params: int) -> bool;"));
+    assert!(definitions.contains(
+        "/// Joins the components of the path with the separator.\n///\n/// ```rhai\n/// module.name.join(\"::\")\n/// ```\nfn join(path: Namespace, sep: String) -> String;"
+    ));
+    assert!(definitions
+        .contains("fn get data_types(this: Shared<SsdModule>) -> OrderedMap<Shared<DataType>>;"));
+    assert!(definitions.contains("op then_emit(bool, String) -> bool;"));
+
+    let registered = engine
+        .definitions()
+        .include_standard_packages(false)
+        .static_module();
+    let lines = registered.lines().collect::<Vec<_>>();
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("fn ") || line.starts_with("op ") {
+            assert!(
+                i > 0 && lines[i - 1] == "/// ```",
+                "{line} has no documentation"
+            );
+        }
     }
-    Ok(())
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_test_script() {
+    let dir = std::env::temp_dir().join(format!("ssd-test-script-{}", std::process::id()));
+    let cases = dir.join("cases");
+    std::fs::create_dir_all(&cases).unwrap();
+    let script = dir.join("gen.rhai");
+    std::fs::write(
+        &script,
+        r#"let count = 0;
+        for dt in module.data_types { emit(dt.first + NL); count += 1; }
+        if count == 0 { throw "no data types"; }"#,
+    )
+    .unwrap();
+    // the failing case comes first, the other one still runs
+    std::fs::write(cases.join("a.svc"), "").unwrap();
+    std::fs::write(cases.join("b.svc"), "data B {};").unwrap();
+    let test = |args: &[&str]| {
+        let params = TestParameters::parse_from(
+            ["test-script", script.to_str().unwrap(), "--cases"]
+                .iter()
+                .chain([&cases.to_str().unwrap()])
+                .chain(args)
+                .copied(),
+        );
+        test_script(&Defines::default(), params).unwrap()
+    };
+
+    // a missing golden file fails instead of comparing to nothing
+    assert!(!test(&[]));
+    assert!(!cases.join("b.out").exists());
+    // a still fails with --bless, but b gets blessed
+    assert!(!test(&["--bless"]));
+    assert_eq!(std::fs::read_to_string(cases.join("b.out")).unwrap(), "B\n");
+
+    std::fs::remove_file(cases.join("a.svc")).unwrap();
+    assert!(test(&[]));
+    std::fs::write(cases.join("b.out"), "C\n").unwrap();
+    assert!(!test(&[]));
+    std::fs::remove_dir_all(&dir).unwrap();
 }
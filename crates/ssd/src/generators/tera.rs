@@ -1,53 +1,298 @@
+#[cfg(feature = "_bin")]
 use clap::Parser;
 use std::collections::HashMap;
 use std::error::Error;
+#[cfg(feature = "_bin")]
 use std::path::PathBuf;
 
-use ssd_data::{RawModel, SsdModel};
+use ssd_data::find_attribute;
+#[cfg(feature = "_bin")]
+use ssd_data::{Defines, RawModel, SsdModel};
 
-use crate::helper::parse_raw_data;
-use crate::helper::{print_or_write, update_types_from_file};
+#[cfg(feature = "_bin")]
+use crate::cache::load_module;
+#[cfg(feature = "_bin")]
+use crate::exit::WithPath;
+use crate::helper::{json_attributes, json_sort_data_types, json_to_string, TypeMap};
+#[cfg(feature = "_bin")]
+use crate::helper::{load_typemap, not_reproducible};
+use crate::idents::{
+    to_camel_case, to_kebab_case, to_pascal_case, to_screaming_snake_case, to_snake_case,
+};
+#[cfg(feature = "_bin")]
 use crate::options::{BaseInputData, BaseOutputData};
-use crate::parser::parse_file;
 
-use tera::{Context, Tera};
+use tera::{Context, Tera, Value};
 
+#[cfg(feature = "_bin")]
 #[derive(Debug, Parser)]
 pub struct Parameters {
     /// The template to use to generate the file.
+    ///
+    /// If `--templates` is used, this is the name of the root template instead.
     pub template: PathBuf,
+    #[clap(long)]
+    /// Glob path for where to search for templates (e.g. "templates/**/*").
+    ///
+    /// All matching templates get loaded, so `extends`, `include` and `import` work.
+    /// Templates are named by their path relative to the directory the glob starts in.
+    pub templates: Option<String>,
     #[clap(flatten)]
     pub input: BaseInputData,
     #[clap(flatten)]
     pub out: BaseOutputData,
 }
 
+type Args = HashMap<String, Value>;
+
+fn string_arg(args: &Args, name: &str) -> Option<String> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned)
+}
+
+fn register_case_filter(tera: &mut Tera, name: &str, convert: fn(&str) -> String) {
+    tera.register_filter(name, move |value: &Value, _: &Args| {
+        Ok(Value::String(convert(&json_to_string(value, "::"))))
+    });
+}
+
+fn register_filters(tera: &mut Tera, typemap: TypeMap) {
+    register_case_filter(tera, "snake_case", to_snake_case);
+    register_case_filter(tera, "camel_case", to_camel_case);
+    register_case_filter(tera, "pascal_case", to_pascal_case);
+    register_case_filter(tera, "kebab_case", to_kebab_case);
+    register_case_filter(tera, "screaming_snake_case", to_screaming_snake_case);
+
+    tera.register_filter("qualified_name", |value: &Value, args: &Args| {
+        let sep = string_arg(args, "sep").unwrap_or_else(|| "::".to_owned());
+        let name = match value {
+            Value::String(s) => s.split("::").collect::<Vec<_>>().join(&sep),
+            _ => json_to_string(value, &sep),
+        };
+        Ok(Value::String(name))
+    });
+
+    tera.register_filter("map_type", move |value: &Value, _: &Args| {
+        let typ = json_to_string(value, "::");
+        Ok(Value::String(typemap.get(&typ).unwrap_or(typ)))
+    });
+
+    tera.register_filter("topologically_sorted", |value: &Value, _: &Args| {
+        json_sort_data_types(value).map_err(|e| tera::Error::msg(e.to_string()))
+    });
+
+    tera.register_tester("has_attribute", |value: Option<&Value>, args: &[Value]| {
+        let name = args
+            .first()
+            .and_then(Value::as_str)
+            .ok_or("The has_attribute test requires the attribute name as argument.")?;
+        Ok(value.is_some_and(|v| find_attribute(&json_attributes(v), name).is_some()))
+    });
+}
+
+#[cfg(feature = "_bin")]
 pub fn generate(
     base: &PathBuf,
-    defines: HashMap<String, String>,
+    defines: Defines,
+    params: Parameters,
+) -> Result<(), Box<dyn Error>> {
+    let out = params.out.clone();
+    let result = generate_to_string(base, defines, params)?;
+    out.write(&result)?;
+
+    Ok(())
+}
+
+/// Renders the template and returns the result instead of writing it.
+#[cfg(feature = "_bin")]
+pub fn generate_to_string(
+    base: &PathBuf,
+    defines: Defines,
     Parameters {
         template,
+        templates,
         input,
-        out,
+        out: _,
     }: Parameters,
-) -> Result<(), Box<dyn Error>> {
-    let mut tera = Tera::default();
-    tera.add_template_file(&template, None)?;
+) -> Result<String, Box<dyn Error>> {
+    let mut tera = if let Some(templates) = templates {
+        let tera = Tera::new(&templates)?;
+        let name = template.to_string_lossy();
+        if !tera.get_template_names().any(|n| n == name) {
+            Err(format!("Template \"{name}\" not found in \"{templates}\"."))?;
+        }
+        tera
+    } else {
+        let mut tera = Tera::default();
+        let source = std::fs::read_to_string(&template).with_path(&template)?;
+        tera.add_raw_template(&template.to_string_lossy(), &source)?;
+        tera
+    };
+    register_filters(
+        &mut tera,
+        load_typemap(input.no_map, input.typemap.clone(), None)?,
+    );
+    if input.reproducible {
+        for function in ["now", "get_random", "get_env"] {
+            tera.register_function(function, move |_: &Args| {
+                Err(not_reproducible(&format!("{function}()")).into())
+            });
+        }
+    }
     let result = if input.raw {
-        let raw = parse_raw_data(input.file)?;
+        let meta = input.meta("");
+        let raw = input.load_raw()?;
         tera.render(
             &template.to_string_lossy(),
-            &Context::from_serialize(RawModel { raw, defines })?,
+            &Context::from_serialize(RawModel { raw, defines, meta })?,
         )?
     } else {
-        let module = parse_file(base, &input.file)?;
-        let module = update_types_from_file(module, input.no_map, input.typemap, None)?;
+        let module = load_module(base, &defines, &input, None)?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
         tera.render(
             &template.to_string_lossy(),
-            &Context::from_serialize(SsdModel { module, defines })?,
+            &Context::from_serialize(SsdModel {
+                module,
+                defines,
+                meta,
+                ast,
+            })?,
         )?
     };
-    print_or_write(out.out, &result)?;
 
-    Ok(())
+    Ok(result)
+}
+
+/// Renders the template for the description in the playground, the typemap is applied to the
+/// model and used by `map_type`.
+#[cfg(feature = "web")]
+// only exported by the library, the binary gets the feature through the workspace
+pub fn generate_web(
+    defines: HashMap<String, String>,
+    namespace: &str,
+    template: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("template", template)
+        .map_err(|e| error_chain(&e))?;
+    register_filters(&mut tera, crate::helper::parse_typemap(typemap)?);
+    let model = crate::helper::load_web_model(defines, namespace, typemap, data)?;
+    let result = tera
+        .render("template", &Context::from_serialize(model)?)
+        .map_err(|e| error_chain(&e))?;
+    Ok(result)
+}
+
+/// The message of the error with its causes, tera only names the template in the outermost one.
+#[cfg(feature = "web")]
+fn error_chain(error: &tera::Error) -> String {
+    std::iter::successors(Some(error as &dyn Error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n  caused by: ")
+}
+
+#[cfg(feature = "_bin")]
+#[test]
+fn test_templates_glob() {
+    use clap::Parser as _;
+
+    let dir = std::env::temp_dir().join(format!("ssd-tera-templates-{}", std::process::id()));
+    let templates = dir.join("templates");
+    std::fs::create_dir_all(templates.join("types")).unwrap();
+    std::fs::write(
+        templates.join("base.tera"),
+        "// {{ module.namespace.components | join(sep=\"::\") }}\n{% block types %}{% endblock %}",
+    )
+    .unwrap();
+    std::fs::write(
+        templates.join("types/macros.tera"),
+        "{% macro decl(name) %}struct {{ name }};{% endmacro %}",
+    )
+    .unwrap();
+    std::fs::write(
+        templates.join("main.tera"),
+        "{% extends \"base.tera\" %}{% import \"types/macros.tera\" as m %}\
+         {% block types %}{% for dt in module.data_types %}{{ m::decl(name=dt.0) }}\n\
+         {% endfor %}{% endblock %}",
+    )
+    .unwrap();
+    let file = dir.join("orders.svc");
+    std::fs::write(&file, "data Order {}; data Item {};").unwrap();
+
+    let generate = |template: &str| {
+        let glob = format!("{}/**/*.tera", templates.display());
+        let params = Parameters::parse_from([
+            "tera",
+            "--no-cache",
+            "--templates",
+            &glob,
+            template,
+            file.to_str().unwrap(),
+        ]);
+        generate_to_string(&dir, Defines::default(), params).map_err(|e| e.to_string())
+    };
+    assert_eq!(
+        generate("main.tera").unwrap(),
+        "// orders\nstruct Order;\nstruct Item;\n"
+    );
+    assert!(generate("missing.tera")
+        .unwrap_err()
+        .contains("Template \"missing.tera\" not found"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_filters() {
+    let module = crate::parser::parse(
+        r#"
+        data Group { owner: UserAccount, };
+        #[table]
+        data UserAccount { id: u64, };
+        "#,
+        ssd_data::Namespace::new("accounts::users"),
+    )
+    .unwrap();
+    let mut tera = Tera::default();
+    register_filters(
+        &mut tera,
+        crate::helper::parse_typemap(r#"u64 = "number""#).unwrap(),
+    );
+    let context = Context::from_serialize(serde_json::json!({ "module": module })).unwrap();
+    let mut render = |template: &str| tera.render_str(template, &context).unwrap();
+
+    assert_eq!(
+        render(
+            "{% for dt in module.data_types %}{{ dt.0 | snake_case }} {{ dt.0 | camel_case }} \
+             {{ dt.0 | pascal_case }} {{ dt.0 | kebab_case }} \
+             {{ dt.0 | screaming_snake_case }}|{% endfor %}"
+        ),
+        "group group Group group GROUP|user_account userAccount UserAccount user-account \
+         USER_ACCOUNT|"
+    );
+    assert_eq!(
+        render(
+            "{{ module.namespace | qualified_name }} {{ module.namespace | qualified_name(sep=\".\") }} \
+             {{ \"a::b\" | qualified_name(sep=\"/\") }}"
+        ),
+        "accounts::users accounts.users a/b"
+    );
+    assert_eq!(
+        render(
+            "{% for dt in module.data_types %}{% for p in dt.1.properties %}\
+             {{ p.1.typ | map_type }} {% endfor %}{% endfor %}{{ \"string\" | map_type }}"
+        ),
+        "UserAccount number string"
+    );
+    assert_eq!(
+        render(
+            "{% for dt in module.data_types | topologically_sorted %}{{ dt.0 }}\
+             {% if dt.1 is has_attribute(\"table\") %}(table){% endif %} {% endfor %}"
+        ),
+        "UserAccount(table) Group "
+    );
 }
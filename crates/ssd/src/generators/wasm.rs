@@ -1,44 +1,123 @@
 use clap::Parser;
 use extism::{convert::Json, Manifest, PluginBuilder, Wasm};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use ssd_data::{RawModel, SsdModel};
+use ssd_data::{Defines, LintDiagnostic, Meta, RawModel, SsdModel, SsdModule};
 
-use crate::helper::parse_raw_data;
-use crate::helper::{print_or_write, update_types_from_file};
+use super::{role_of, DEFAULT_FUNCTION};
+use crate::cache::load_module;
+use crate::exit::WithPath;
 use crate::options::{BaseInputData, BaseOutputData};
-use crate::parser::parse_file;
+
+const LINT_FUNCTION: &str = "lint";
 
 #[derive(Debug, Parser)]
 pub struct Parameters {
     /// The wasm plugin to use to generate the file.
     pub wasm: PathBuf,
+    #[clap(long, short, default_value = DEFAULT_FUNCTION)]
+    /// The function exported by the plugin that should be called.
+    ///
+    /// Plugins which produce multiple outputs export one function per output,
+    /// named `generate_<role>` (e.g. `generate_header` and `generate_source`).
+    /// The role gets passed to the plugin through the config key `role`.
+    pub function: String,
     #[clap(flatten)]
     pub input: BaseInputData,
     #[clap(flatten)]
     pub out: BaseOutputData,
 }
 
-pub fn generate(
+pub fn generate(base: &PathBuf, defines: Defines, params: Parameters) -> anyhow::Result<()> {
+    let out = params.out.clone();
+    let result = generate_to_string(base, defines, params)?;
+    out.write(&result)?;
+
+    Ok(())
+}
+
+/// Calls the plugin and returns the result instead of writing it.
+pub fn generate_to_string(
     base: &PathBuf,
-    defines: HashMap<String, String>,
-    Parameters { wasm, input, out }: Parameters,
-) -> anyhow::Result<()> {
-    let file = Wasm::file(&wasm);
-    let manifest = Manifest::new([file]);
+    defines: Defines,
+    Parameters {
+        wasm,
+        function,
+        input,
+        out: _,
+    }: Parameters,
+) -> anyhow::Result<String> {
+    let file = Wasm::data(std::fs::read(&wasm).with_path(&wasm)?);
+    let mut manifest = Manifest::new([file]);
+    if let Some(role) = role_of(&function) {
+        manifest = manifest.with_config_key("role", role);
+    }
     let mut plugin = PluginBuilder::new(&manifest).with_wasi(false).build()?;
 
+    if !plugin.function_exists(&function) {
+        anyhow::bail!(
+            "The plugin {} does not export a function named \"{function}\".",
+            wasm.display()
+        );
+    }
+
     let result = if input.raw {
-        let raw = parse_raw_data(input.file)?;
-        plugin.call::<Json<RawModel>, &str>("generate", Json(RawModel { raw, defines }))?
+        let meta = input.meta("");
+        let raw = input.load_raw()?;
+        plugin.call::<Json<RawModel>, &str>(&function, Json(RawModel { raw, defines, meta }))?
     } else {
-        let module = parse_file(base, &input.file)?;
-        let module = update_types_from_file(module, input.no_map, input.typemap, Some(&wasm))?;
-        plugin.call::<Json<SsdModel>, &str>("generate", Json(SsdModel { module, defines }))?
+        let module = load_module(base, &defines, &input, Some(&wasm))?;
+        let meta = input.meta(&module.namespace.to_string());
+        let ast = input.load_ast()?;
+        plugin.call::<Json<SsdModel>, &str>(
+            &function,
+            Json(SsdModel {
+                module,
+                defines,
+                meta,
+                ast,
+            }),
+        )?
     };
 
-    print_or_write(out.out, result)?;
+    Ok(result.to_owned())
+}
 
-    Ok(())
+/// Calls the `lint` export of the plugin (`ssd check --lint`) with the module and returns the
+/// diagnostics it found.
+pub fn run_lint(
+    defines: &Defines,
+    wasm: &Path,
+    module: SsdModule,
+) -> anyhow::Result<Vec<LintDiagnostic>> {
+    let manifest = Manifest::new([Wasm::data(std::fs::read(wasm).with_path(wasm)?)]);
+    let mut plugin = PluginBuilder::new(&manifest).with_wasi(false).build()?;
+
+    if !plugin.function_exists(LINT_FUNCTION) {
+        anyhow::bail!(
+            "The plugin {} does not export a function named \"{LINT_FUNCTION}\".",
+            wasm.display()
+        );
+    }
+
+    let Json(diagnostics) = plugin.call::<Json<SsdModel>, Json<Vec<LintDiagnostic>>>(
+        LINT_FUNCTION,
+        Json(SsdModel {
+            module,
+            defines: defines.clone(),
+            meta: Meta::default(),
+            ast: None,
+        }),
+    )?;
+    if let Some(diagnostic) = diagnostics
+        .iter()
+        .find(|d| d.level != "warn" && d.level != "deny")
+    {
+        anyhow::bail!(
+            "The plugin {} reported the unknown level {}, expected warn or deny.",
+            wasm.display(),
+            diagnostic.level
+        );
+    }
+    Ok(diagnostics)
 }
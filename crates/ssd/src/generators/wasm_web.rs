@@ -0,0 +1,445 @@
+//! Runs extism plugins in the browser, where the extism runtime isn't available.
+//!
+//! Plugins built with the extism pdk import their memory management from `extism:host/env`.
+//! [`Kernel`] implements these functions in rust and [`generate_web`] instantiates the plugin
+//! through the `WebAssembly` api of the browser with them.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+
+/// The memory shared between the host and the plugin, like the kernel of the extism runtime.
+///
+/// Memory is addressed by offsets, which start at 1 so 0 can mean "no value". Every plugin call
+/// uses a new kernel, so freed memory isn't reused.
+pub(crate) struct Kernel {
+    memory: Vec<u8>,
+    blocks: HashMap<u64, u64>,
+    input: (u64, u64),
+    output: (u64, u64),
+    error: u64,
+    config: HashMap<String, String>,
+    vars: HashMap<String, Vec<u8>>,
+}
+
+impl Kernel {
+    pub fn new(input: &[u8], config: HashMap<String, String>) -> Self {
+        let mut kernel = Self {
+            memory: vec![0],
+            blocks: HashMap::new(),
+            input: (0, 0),
+            output: (0, 0),
+            error: 0,
+            config,
+            vars: HashMap::new(),
+        };
+        let offset = kernel.alloc_bytes(input);
+        kernel.input = (offset, input.len() as u64);
+        kernel
+    }
+
+    fn range(&self, offset: u64, len: u64) -> anyhow::Result<std::ops::Range<usize>> {
+        let start = offset as usize;
+        let end = start.saturating_add(len as usize);
+        if offset == 0 || end > self.memory.len() {
+            bail!("The plugin accessed memory outside of the allocated blocks (offset {offset}, length {len}).");
+        }
+        Ok(start..end)
+    }
+
+    fn bytes(&self, offset: u64) -> anyhow::Result<&[u8]> {
+        let len = self
+            .blocks
+            .get(&offset)
+            .with_context(|| format!("The plugin used the unknown memory block {offset}."))?;
+        Ok(&self.memory[self.range(offset, *len)?])
+    }
+
+    fn string(&self, offset: u64) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.bytes(offset)?.to_vec())?)
+    }
+
+    fn alloc_bytes(&mut self, bytes: &[u8]) -> u64 {
+        let offset = self.alloc(bytes.len() as u64);
+        let start = offset as usize;
+        self.memory[start..start + bytes.len()].copy_from_slice(bytes);
+        offset
+    }
+
+    pub fn alloc(&mut self, len: u64) -> u64 {
+        let offset = self.memory.len() as u64;
+        // empty blocks still need their own offset
+        self.memory
+            .resize(self.memory.len() + len.max(1) as usize, 0);
+        self.blocks.insert(offset, len);
+        offset
+    }
+
+    pub fn free(&mut self, offset: u64) {
+        self.blocks.remove(&offset);
+    }
+
+    pub fn length(&self, offset: u64) -> u64 {
+        self.blocks.get(&offset).copied().unwrap_or_default()
+    }
+
+    pub fn load_u8(&self, offset: u64) -> anyhow::Result<u8> {
+        Ok(self.memory[self.range(offset, 1)?][0])
+    }
+
+    pub fn load_u64(&self, offset: u64) -> anyhow::Result<u64> {
+        let bytes = &self.memory[self.range(offset, 8)?];
+        Ok(u64::from_le_bytes(bytes.try_into()?))
+    }
+
+    pub fn store_u8(&mut self, offset: u64, value: u8) -> anyhow::Result<()> {
+        let range = self.range(offset, 1)?;
+        self.memory[range][0] = value;
+        Ok(())
+    }
+
+    pub fn store_u64(&mut self, offset: u64, value: u64) -> anyhow::Result<()> {
+        let range = self.range(offset, 8)?;
+        self.memory[range].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    pub fn input_length(&self) -> u64 {
+        self.input.1
+    }
+
+    pub fn input_offset(&self) -> u64 {
+        self.input.0
+    }
+
+    pub fn input_load_u8(&self, index: u64) -> anyhow::Result<u8> {
+        self.load_u8(self.input.0 + index)
+    }
+
+    pub fn input_load_u64(&self, index: u64) -> anyhow::Result<u64> {
+        self.load_u64(self.input.0 + index)
+    }
+
+    pub fn input_set(&mut self, offset: u64, len: u64) {
+        self.input = (offset, len);
+    }
+
+    pub fn output_set(&mut self, offset: u64, len: u64) {
+        self.output = (offset, len);
+    }
+
+    pub fn error_set(&mut self, offset: u64) {
+        self.error = offset;
+    }
+
+    pub fn error_get(&self) -> u64 {
+        self.error
+    }
+
+    pub fn config_get(&mut self, offset: u64) -> anyhow::Result<u64> {
+        let key = self.string(offset)?;
+        Ok(match self.config.get(&key).cloned() {
+            Some(value) => self.alloc_bytes(value.as_bytes()),
+            None => 0,
+        })
+    }
+
+    pub fn var_get(&mut self, offset: u64) -> anyhow::Result<u64> {
+        let key = self.string(offset)?;
+        Ok(match self.vars.get(&key).cloned() {
+            Some(value) => self.alloc_bytes(&value),
+            None => 0,
+        })
+    }
+
+    /// Sets the variable, or removes it if `value` is 0.
+    pub fn var_set(&mut self, offset: u64, value: u64) -> anyhow::Result<()> {
+        let key = self.string(offset)?;
+        if value == 0 {
+            self.vars.remove(&key);
+        } else {
+            let value = self.bytes(value)?.to_vec();
+            self.vars.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// The output the plugin set, or the error it reported.
+    pub fn output(&self) -> anyhow::Result<String> {
+        if self.error != 0 {
+            bail!("{}", self.string(self.error)?);
+        }
+        if self.output.0 == 0 {
+            return Ok(String::new());
+        }
+        let output = &self.memory[self.range(self.output.0, self.output.1)?];
+        Ok(String::from_utf8(output.to_vec())?)
+    }
+}
+
+/// The kernel for a call of the plugin function, with the model as input and the role of the
+/// function in the config, like `ssd generate wasm` passes them.
+fn plugin_kernel(
+    function: &str,
+    defines: HashMap<String, String>,
+    namespace: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<Kernel, Box<dyn std::error::Error>> {
+    let model = crate::helper::load_web_model(defines, namespace, typemap, data)?;
+    let mut config = HashMap::new();
+    if let Some(role) = super::role_of(function) {
+        config.insert("role".to_owned(), role.to_owned());
+    }
+    Ok(Kernel::new(&serde_json::to_vec(&model)?, config))
+}
+
+/// Runs the function of the plugin for the description and returns its output.
+///
+/// Plugins only get the functions of the extism kernel, the config and variables. Http requests
+/// fail and log messages are dropped.
+pub async fn generate_web(
+    plugin: &[u8],
+    function: &str,
+    defines: HashMap<String, String>,
+    namespace: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use js_sys::{Function, Object, Reflect, WebAssembly};
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::{JsCast, JsError, JsValue};
+
+    fn js_error(e: impl std::fmt::Display) -> JsValue {
+        JsError::new(&e.to_string()).into()
+    }
+
+    fn error_message(e: JsValue) -> String {
+        e.dyn_ref::<js_sys::Error>()
+            .map(|e| String::from(e.message()))
+            .or_else(|| e.as_string())
+            .unwrap_or_else(|| format!("{e:?}"))
+    }
+
+    let kernel = Rc::new(RefCell::new(plugin_kernel(
+        function, defines, namespace, typemap, data,
+    )?));
+
+    let env = Object::new();
+    // the closures have to live until the plugin returned
+    let mut closures: Vec<Box<dyn std::any::Any>> = Vec::new();
+    macro_rules! import {
+        ($name:expr, |$kernel:ident $(, $arg:ident: $ty:ty)*| -> $ret:ty $body:block) => {{
+            let kernel = kernel.clone();
+            let closure = Closure::wrap(Box::new(move |$($arg: $ty),*| -> Result<$ret, JsValue> {
+                #[allow(unused_mut)]
+                let mut $kernel = kernel.borrow_mut();
+                $body
+            }) as Box<dyn FnMut($($ty),*) -> Result<$ret, JsValue>>);
+            Reflect::set(&env, &JsValue::from($name), closure.as_ref())
+                .map_err(error_message)?;
+            closures.push(Box::new(closure));
+        }};
+    }
+
+    import!("alloc", |k, n: i64| -> i64 { Ok(k.alloc(n as u64) as i64) });
+    import!("free", |k, offset: i64| -> () {
+        k.free(offset as u64);
+        Ok(())
+    });
+    import!("length", |k, offset: i64| -> i64 {
+        Ok(k.length(offset as u64) as i64)
+    });
+    import!("length_unsafe", |k, offset: i64| -> i64 {
+        Ok(k.length(offset as u64) as i64)
+    });
+    import!("load_u8", |k, offset: i64| -> i32 {
+        k.load_u8(offset as u64).map(i32::from).map_err(js_error)
+    });
+    import!("load_u64", |k, offset: i64| -> i64 {
+        k.load_u64(offset as u64)
+            .map(|v| v as i64)
+            .map_err(js_error)
+    });
+    import!("store_u8", |k, offset: i64, value: i32| -> () {
+        k.store_u8(offset as u64, value as u8).map_err(js_error)
+    });
+    import!("store_u64", |k, offset: i64, value: i64| -> () {
+        k.store_u64(offset as u64, value as u64).map_err(js_error)
+    });
+    import!("input_length", |k| -> i64 { Ok(k.input_length() as i64) });
+    import!("input_offset", |k| -> i64 { Ok(k.input_offset() as i64) });
+    import!("input_load_u8", |k, index: i64| -> i32 {
+        k.input_load_u8(index as u64)
+            .map(i32::from)
+            .map_err(js_error)
+    });
+    import!("input_load_u64", |k, index: i64| -> i64 {
+        k.input_load_u64(index as u64)
+            .map(|v| v as i64)
+            .map_err(js_error)
+    });
+    import!("input_set", |k, offset: i64, len: i64| -> () {
+        k.input_set(offset as u64, len as u64);
+        Ok(())
+    });
+    import!("output_set", |k, offset: i64, len: i64| -> () {
+        k.output_set(offset as u64, len as u64);
+        Ok(())
+    });
+    import!("error_set", |k, offset: i64| -> () {
+        k.error_set(offset as u64);
+        Ok(())
+    });
+    import!("error_get", |k| -> i64 { Ok(k.error_get() as i64) });
+    import!("config_get", |k, offset: i64| -> i64 {
+        k.config_get(offset as u64)
+            .map(|v| v as i64)
+            .map_err(js_error)
+    });
+    import!("var_get", |k, offset: i64| -> i64 {
+        k.var_get(offset as u64).map(|v| v as i64).map_err(js_error)
+    });
+    import!("var_set", |k, offset: i64, value: i64| -> () {
+        k.var_set(offset as u64, value as u64).map_err(js_error)
+    });
+    import!("http_request", |_k, _request: i64, _body: i64| -> i64 {
+        Err(js_error("Plugins can't make http requests in the browser."))
+    });
+    import!("http_status_code", |_k| -> i32 { Ok(0) });
+    // higher than every level, so the pdk doesn't even try to log
+    import!("get_log_level", |_k| -> i32 { Ok(i32::MAX) });
+    for level in ["trace", "debug", "info", "warn", "error"] {
+        import!(format!("log_{level}"), |_k, _message: i64| -> () { Ok(()) });
+    }
+
+    let imports = Object::new();
+    Reflect::set(&imports, &"extism:host/env".into(), &env).map_err(error_message)?;
+    let instantiated =
+        wasm_bindgen_futures::JsFuture::from(WebAssembly::instantiate_buffer(plugin, &imports))
+            .await
+            .map_err(error_message)?;
+    let instance: WebAssembly::Instance = Reflect::get(&instantiated, &"instance".into())
+        .map_err(error_message)?
+        .dyn_into()
+        .map_err(error_message)?;
+    let exports = instance.exports();
+
+    let export = |name: &str| {
+        Reflect::get(&exports, &name.into())
+            .ok()
+            .and_then(|f| f.dyn_into::<Function>().ok())
+    };
+    if let Some(initialize) = export("_initialize") {
+        initialize
+            .call0(&JsValue::UNDEFINED)
+            .map_err(error_message)?;
+    }
+    let Some(function) = export(function) else {
+        return Err(format!("The plugin does not export a function named \"{function}\".").into());
+    };
+    let rc = function
+        .call0(&JsValue::UNDEFINED)
+        .map_err(error_message)?
+        .as_f64()
+        .unwrap_or_default();
+    drop(closures);
+
+    let output = kernel.borrow().output();
+    match output {
+        Ok(_) if rc != 0.0 => Err(format!("The plugin returned the error code {rc}.").into()),
+        Ok(output) => Ok(output),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[test]
+fn test_kernel() {
+    let config = HashMap::from([("role".to_owned(), "header".to_owned())]);
+    let mut kernel = Kernel::new(b"0123456789", config);
+
+    // read the input like the pdk does
+    let input = (0..kernel.input_length())
+        .map(|i| kernel.input_load_u8(i).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(input, b"0123456789");
+    assert_eq!(
+        kernel.input_load_u64(0).unwrap(),
+        u64::from_le_bytes(*b"01234567")
+    );
+    assert!(kernel.input_load_u64(4).is_err());
+
+    let write = |kernel: &mut Kernel, bytes: &[u8]| {
+        let offset = kernel.alloc(bytes.len() as u64);
+        for (i, byte) in bytes.iter().enumerate() {
+            kernel.store_u8(offset + i as u64, *byte).unwrap();
+        }
+        offset
+    };
+    let key = write(&mut kernel, b"role");
+    let value = kernel.config_get(key).unwrap();
+    assert_eq!(kernel.bytes(value).unwrap(), b"header");
+    let missing = write(&mut kernel, b"missing");
+    assert_eq!(kernel.config_get(missing).unwrap(), 0);
+
+    let value = write(&mut kernel, b"42");
+    kernel.var_set(key, value).unwrap();
+    let var = kernel.var_get(key).unwrap();
+    assert_eq!(kernel.length(var), 2);
+    kernel.var_set(key, 0).unwrap();
+    assert_eq!(kernel.var_get(key).unwrap(), 0);
+
+    let number = kernel.alloc(8);
+    kernel.store_u64(number, 1234).unwrap();
+    assert_eq!(kernel.load_u64(number).unwrap(), 1234);
+    kernel.free(number);
+    assert_eq!(kernel.length(number), 0);
+    assert!(kernel.load_u8(0).is_err());
+
+    let output = write(&mut kernel, "generated".as_bytes());
+    kernel.output_set(output, 9);
+    assert_eq!(kernel.output().unwrap(), "generated");
+    let error = write(&mut kernel, b"failed");
+    kernel.error_set(error);
+    assert_eq!(kernel.output().unwrap_err().to_string(), "failed");
+}
+
+#[test]
+fn test_plugin_kernel() {
+    let defines = HashMap::from([("PRO".to_owned(), "true".to_owned())]);
+    let mut kernel = plugin_kernel(
+        "generate_header",
+        defines,
+        "shapes",
+        "i32 = \"int\"",
+        "data Rect { x: i32, };",
+    )
+    .unwrap();
+
+    let input = (0..kernel.input_length())
+        .map(|i| kernel.input_load_u8(i).unwrap())
+        .collect::<Vec<_>>();
+    let model: ssd_data::SsdModel = serde_json::from_slice(&input).unwrap();
+    assert_eq!(model.meta.namespace, "shapes");
+    assert_eq!(
+        model.defines.get("PRO"),
+        Some(&serde_value::Value::String("true".to_owned()))
+    );
+    let (name, rect) = model.module.data_types.get_index(0).unwrap();
+    assert_eq!(name, "Rect");
+    assert_eq!(rect.properties["x"].typ.to_string(), "int");
+
+    let key = kernel.alloc_bytes(b"role");
+    let role = kernel.config_get(key).unwrap();
+    assert_eq!(kernel.string(role).unwrap(), "header");
+    let kernel = plugin_kernel("generate", HashMap::new(), "shapes", "", "data Rect {};").unwrap();
+    assert!(kernel.config.is_empty());
+
+    let error = plugin_kernel("generate", HashMap::new(), "shapes", "", "data Rect {")
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("--> 1:12"), "{error}");
+}
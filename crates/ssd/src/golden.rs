@@ -0,0 +1,211 @@
+//! `ssd test`: golden-file tests for generators of any kind.
+//!
+//! The test cases are listed in a manifest (`ssd-test.toml` by default), paths are relative to
+//! the manifest:
+//! ```toml
+//! [[case]]
+//! name = "rust"
+//! generator = "rhai"
+//! source = "generators/rust.rhai"
+//! input = "tests/api.svc"
+//! typemaps = ["generators/rust.tym"]
+//! postprocess = ["rustfmt --edition 2021"]
+//! expected = "tests/expected/rust"
+//! ```
+//! The files emitted by the generator are compared to the files in `expected`, its output to the
+//! file `stdout` in there.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use ssd_data::{Defines, Namespace};
+
+use crate::api::{generate, GenerateRequest, Generator};
+use crate::helper::{
+    diff_files, parse_raw_data_as, read_files, report_case, write_files, RawFormat,
+};
+use crate::postprocess::PostProcess;
+use crate::transform::Transform;
+
+/// The file in the expected directory holding the output of the generator.
+const STDOUT: &str = "stdout";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default, rename = "case")]
+    cases: Vec<Case>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Case {
+    /// Defaults to the name of the expected directory.
+    #[serde(default)]
+    name: Option<String>,
+    /// `rhai`, `handlebars`, `tera` or `wasm`, depending on the enabled features.
+    generator: String,
+    /// The script, template or plugin of the generator.
+    source: PathBuf,
+    /// The description file.
+    #[serde(default)]
+    input: Option<PathBuf>,
+    /// A data file used as raw model instead of `input`, like with `--raw`.
+    #[serde(default)]
+    raw: Option<PathBuf>,
+    /// Used instead of the namespace derived from `input`, like `--namespace`.
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    typemaps: Vec<PathBuf>,
+    #[serde(default)]
+    no_map: bool,
+    #[serde(default)]
+    transforms: Vec<String>,
+    /// Commands run on the output and the emitted files, like `--postprocess`.
+    #[serde(default)]
+    postprocess: Vec<String>,
+    /// Merged into the defines given on the command line.
+    #[serde(default)]
+    defines: Defines,
+    /// The directory with the expected output and files.
+    expected: PathBuf,
+}
+
+impl Case {
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.expected
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+    }
+
+    /// Runs the generator and returns the output (as `stdout`) together with the emitted files.
+    fn run(&self, dir: &Path, defines: &Defines) -> anyhow::Result<BTreeMap<String, String>> {
+        let (file, raw) = match (&self.input, &self.raw) {
+            (Some(input), None) => (dir.join(input), None),
+            (None, Some(raw)) => (
+                PathBuf::new(),
+                Some(parse_raw_data_as(dir.join(raw), RawFormat::Auto)?),
+            ),
+            _ => anyhow::bail!("Either input or raw has to be given."),
+        };
+        let mut request = GenerateRequest::new(
+            file,
+            Generator::from_name(&self.generator, dir.join(&self.source))?,
+        );
+        request.base = dir.to_path_buf();
+        request.namespace = self.namespace.as_deref().map(Namespace::new);
+        request.defines = defines.clone();
+        request.defines.extend(self.defines.clone());
+        request.typemaps = self.typemaps.iter().map(|path| dir.join(path)).collect();
+        request.no_map = self.no_map;
+        request.transforms = self
+            .transforms
+            .iter()
+            .map(|transform| transform.parse::<Transform>())
+            .collect::<Result<_, _>>()?;
+        request.postprocess = self
+            .postprocess
+            .iter()
+            .map(|command| command.parse::<PostProcess>())
+            .collect::<Result<_, _>>()?;
+        request.raw = raw;
+
+        let output = generate(request)?;
+        let mut files = output.files;
+        if !output.output.is_empty() {
+            files.insert(STDOUT.to_owned(), output.output);
+        }
+        Ok(files)
+    }
+}
+
+/// Runs the test cases of the manifest (all, or the ones named in `only`) and compares the
+/// results with the expected directories, or replaces them if `bless` is set.
+///
+/// Returns `false` if any test case failed.
+pub fn run_tests(
+    manifest: &Path,
+    defines: &Defines,
+    only: &[String],
+    bless: bool,
+) -> anyhow::Result<bool> {
+    let content = std::fs::read_to_string(manifest)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", manifest.display()))?;
+    let Manifest { cases } = toml::from_str(&content)?;
+    let dir = manifest.parent().unwrap_or(Path::new(""));
+
+    let mut success = true;
+    for case in cases {
+        let name = case.name();
+        if !only.is_empty() && !only.contains(&name) {
+            continue;
+        }
+        let files = match case.run(dir, defines) {
+            Ok(files) => files,
+            Err(e) => {
+                success &= report_case(&name, &[format!("{e:#}")]);
+                continue;
+            }
+        };
+
+        let expected = dir.join(&case.expected);
+        if bless {
+            if expected.exists() {
+                std::fs::remove_dir_all(&expected)?;
+            }
+            std::fs::create_dir_all(&expected)?;
+            write_files(&expected, &files)?;
+            println!("blessed {name}");
+            continue;
+        }
+
+        success &= report_case(&name, &diff_files(&read_files(&expected)?, &files));
+    }
+    Ok(success)
+}
+
+#[cfg(feature = "rhai")]
+#[test]
+fn test_run_tests() {
+    let dir = std::env::temp_dir().join(format!("ssd-golden-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("api.svc"), "data Rect { x: i32, };").unwrap();
+    std::fs::write(
+        dir.join("gen.rhai"),
+        r#"for dt in module.data_types { emit(dt.first + defines.suffix + NL); emit("types.txt", dt.first); }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("ssd-test.toml"),
+        r#"
+        [[case]]
+        generator = "rhai"
+        source = "gen.rhai"
+        input = "api.svc"
+        defines = { suffix = "!" }
+        expected = "expected/rect"
+        "#,
+    )
+    .unwrap();
+    let manifest = dir.join("ssd-test.toml");
+
+    // without expected files the case fails, after blessing it passes
+    assert!(!run_tests(&manifest, &Defines::new(), &[], false).unwrap());
+    assert!(run_tests(&manifest, &Defines::new(), &[], true).unwrap());
+    let expected = std::fs::read_to_string(dir.join("expected/rect/stdout")).unwrap();
+    assert_eq!(expected, "Rect!\n");
+    assert!(run_tests(&manifest, &Defines::new(), &[], false).unwrap());
+
+    std::fs::write(dir.join("expected/rect/types.txt"), "Circle").unwrap();
+    assert!(!run_tests(&manifest, &Defines::new(), &[], false).unwrap());
+    // cases which aren't selected don't run
+    assert!(run_tests(&manifest, &Defines::new(), &["other".to_owned()], false).unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
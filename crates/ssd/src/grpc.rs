@@ -0,0 +1,1479 @@
+//! gRPC stubs for the services of a model (`ssd grpc`), so the model doesn't have to go through
+//! a `.proto` file and protoc first.
+//!
+//! The model is mapped like a `.proto` file would map it: data types become messages, enums
+//! enums (flags `uint64`) and lists repeated fields. Every function becomes a unary method taking
+//! a `{Name}Request` with its arguments and returning a `{Name}Response` with the return type as
+//! `value` and the error type of `result of` as `error`. Every event becomes a server-streaming
+//! method taking an empty `{Name}Request` and sending a `{Name}Event` with its arguments. Fields
+//! are numbered in order, starting with 1.
+//!
+//! The tonic target writes what tonic-build 0.10 (with prost 0.12) would write for such a
+//! `.proto` file, the grpc-web target TypeScript messages and clients together with the small
+//! runtime they need.
+
+use clap::ValueEnum;
+use ssd_data::{find_attribute, Attribute, Enum, SsdModule, TypeName, TypeRef};
+
+use crate::idents::{sanitize_ident, to_camel_case, to_pascal_case, to_snake_case};
+
+/// The highest field number protobuf allows.
+const MAX_TAG: u32 = (1 << 29) - 1;
+/// Field numbers protobuf reserves for its own use.
+const RESERVED_TAGS: std::ops::RangeInclusive<u32> = 19000..=19999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GrpcTarget {
+    /// Rust messages (prost) with a client and a server for tonic
+    #[default]
+    Tonic,
+    /// TypeScript messages and fetch-based clients for grpc-web, without dependencies
+    GrpcWeb,
+}
+
+/// The protobuf scalar types the builtin types are mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    Bool,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Float,
+    Double,
+    String,
+    Bytes,
+}
+
+impl Scalar {
+    fn from_builtin(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Self::Bool,
+            "i8" | "i16" | "i32" => Self::Int32,
+            "i64" => Self::Int64,
+            "u8" | "u16" | "u32" => Self::Uint32,
+            "u64" => Self::Uint64,
+            "f32" => Self::Float,
+            "f64" => Self::Double,
+            "char" | "string" | "String" | "uuid" | "datetime" | "date" | "time" | "duration" => {
+                Self::String
+            }
+            "bytes" => Self::Bytes,
+            _ => return None,
+        })
+    }
+
+    /// The name in `.proto` files, `#[prost(...)]` attributes and the TypeScript runtime.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::Int32 => "int32",
+            Self::Int64 => "int64",
+            Self::Uint32 => "uint32",
+            Self::Uint64 => "uint64",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::String => "string",
+            Self::Bytes => "bytes",
+        }
+    }
+
+    fn rust_type(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::Int32 => "i32",
+            Self::Int64 => "i64",
+            Self::Uint32 => "u32",
+            Self::Uint64 => "u64",
+            Self::Float => "f32",
+            Self::Double => "f64",
+            Self::String => "::prost::alloc::string::String",
+            Self::Bytes => "::prost::alloc::vec::Vec<u8>",
+        }
+    }
+
+    fn ts_type(self) -> &'static str {
+        match self {
+            Self::Bool => "boolean",
+            Self::Int64 | Self::Uint64 => "bigint",
+            Self::Int32 | Self::Uint32 | Self::Float | Self::Double => "number",
+            Self::String => "string",
+            Self::Bytes => "Uint8Array",
+        }
+    }
+
+    fn ts_default(self) -> &'static str {
+        match self {
+            Self::Bool => "false",
+            Self::Int64 | Self::Uint64 => "0n",
+            Self::Int32 | Self::Uint32 | Self::Float | Self::Double => "0",
+            Self::String => "\"\"",
+            Self::Bytes => "new Uint8Array(0)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Scalar(Scalar),
+    Enum(String),
+    Message(String),
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    tag: u32,
+    kind: Kind,
+    repeated: bool,
+    /// Scalars and enums which are only sent when set, like `optional` fields of proto3.
+    optional: bool,
+    docs: Vec<String>,
+}
+
+impl Field {
+    /// Whether the field can be missing, which singular message fields always can.
+    fn has_presence(&self) -> bool {
+        !self.repeated && (self.optional || matches!(self.kind, Kind::Message(_)))
+    }
+}
+
+#[derive(Debug)]
+struct Message {
+    name: String,
+    docs: Vec<String>,
+    fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+struct ProtoEnum {
+    name: String,
+    docs: Vec<String>,
+    /// The values with their numbers, the one with 0 (the default) first.
+    values: Vec<(String, i32, Vec<String>)>,
+}
+
+#[derive(Debug)]
+struct Method {
+    /// The name on the wire, e.g. `PlaceOrder`.
+    name: String,
+    input: String,
+    output: String,
+    /// Events send a stream of messages.
+    streaming: bool,
+    docs: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Service {
+    name: String,
+    package: String,
+    docs: Vec<String>,
+    methods: Vec<Method>,
+}
+
+impl Service {
+    /// The full name of the service, e.g. `shop.Orders`.
+    fn full_name(&self) -> String {
+        if self.package.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.package, self.name)
+        }
+    }
+
+    fn path(&self, method: &Method) -> String {
+        format!("/{}/{}", self.full_name(), method.name)
+    }
+}
+
+/// The model as it would be described in a `.proto` file.
+#[derive(Debug, Default)]
+struct Package {
+    enums: Vec<ProtoEnum>,
+    messages: Vec<Message>,
+    services: Vec<Service>,
+}
+
+/// Returns the value of the parameter of the `#[grpc(...)]` attribute.
+fn grpc_param<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    find_attribute(attributes, "grpc")?
+        .find_parameter(name)?
+        .value
+        .as_deref()
+}
+
+/// The name of a service, function or event on the wire: PascalCase or as given with
+/// `#[grpc(name = "...")]`.
+fn grpc_name(attributes: &[Attribute], name: &str) -> String {
+    grpc_param(attributes, "name").map_or_else(|| to_pascal_case(name), str::to_owned)
+}
+
+/// Maps the type of a property, argument or return type, `element` names it in errors.
+fn field(
+    module: &SsdModule,
+    element: &str,
+    name: &str,
+    typ: &TypeName,
+    tag: u32,
+) -> anyhow::Result<Field> {
+    let repeated = match typ.lists.len() {
+        0 => false,
+        1 => true,
+        _ => anyhow::bail!(
+            "{element} is a nested list, which protobuf can't express. Wrap the inner list in a data type."
+        ),
+    };
+    let local_name = typ.typ.components.last().cloned().unwrap_or_default();
+    let kind = match module.find_type(&typ.typ) {
+        Some(TypeRef::DataType(_)) => Kind::Message(local_name),
+        Some(TypeRef::Enum(en)) if en.is_flags => Kind::Scalar(Scalar::Uint64),
+        Some(TypeRef::Enum(_)) => Kind::Enum(local_name),
+        None if typ.is_primitive() => Kind::Scalar(
+            Scalar::from_builtin(&local_name).ok_or_else(|| {
+                anyhow::anyhow!("{element} is a {local_name}, which protobuf has no type for.")
+            })?,
+        ),
+        None => anyhow::bail!(
+            "{element} is a {}, which isn't declared in the model. Imported types aren't supported.",
+            typ.typ.to_string()
+        ),
+    };
+    Ok(Field {
+        name: name.to_owned(),
+        tag,
+        kind,
+        repeated,
+        optional: false,
+        docs: typ.docs.clone(),
+    })
+}
+
+/// Maps properties or arguments. They are numbered in order starting with 1, or as given with
+/// `#[grpc(tag = "N")]`, after which the numbering continues.
+fn fields<'a>(
+    module: &SsdModule,
+    parent: &str,
+    items: impl IntoIterator<Item = (&'a String, &'a TypeName)>,
+) -> anyhow::Result<Vec<Field>> {
+    let mut fields: Vec<Field> = Vec::new();
+    let mut next = 1;
+    for (name, typ) in items {
+        let element = format!("{parent}.{name}");
+        let tag = match grpc_param(&typ.attributes, "tag") {
+            Some(tag) => tag.parse().map_err(|_| {
+                anyhow::anyhow!("The tag of {element} has to be a number, not \"{tag}\".")
+            })?,
+            None => next,
+        };
+        if tag == 0 || tag > MAX_TAG || RESERVED_TAGS.contains(&tag) {
+            anyhow::bail!(
+                "The tag {tag} of {element} isn't allowed, tags go from 1 to {MAX_TAG} without {} to {}.",
+                RESERVED_TAGS.start(),
+                RESERVED_TAGS.end()
+            );
+        }
+        if let Some(other) = fields.iter().find(|field| field.tag == tag) {
+            anyhow::bail!(
+                "{element} has the same tag ({tag}) as {parent}.{}.",
+                other.name
+            );
+        }
+        next = tag.saturating_add(1);
+        fields.push(field(module, &element, name, typ, tag)?);
+    }
+    Ok(fields)
+}
+
+impl Package {
+    fn from_module(module: &SsdModule) -> anyhow::Result<Self> {
+        let mut package = Self::default();
+        for (name, en) in &module.enums {
+            if !en.is_flags {
+                package.add_enum(name, en)?;
+            }
+        }
+        for (name, data_type) in &module.data_types {
+            let fields = fields(module, name, &data_type.properties)?;
+            package.add_message(
+                name,
+                Message {
+                    name: name.clone(),
+                    docs: data_type.docs.clone(),
+                    fields,
+                },
+            )?;
+        }
+        for (service_name, service) in &module.services {
+            package.add_service(module, service_name, service)?;
+        }
+        Ok(package)
+    }
+
+    fn add_enum(&mut self, name: &str, en: &Enum) -> anyhow::Result<()> {
+        let mut next = 0i64;
+        let mut values = Vec::new();
+        for (value_name, value) in &en.values {
+            let number = value.value.unwrap_or(next);
+            next = number.saturating_add(1);
+            let number = i32::try_from(number).map_err(|_| {
+                anyhow::anyhow!(
+                    "The value {number} of {name}::{value_name} doesn't fit into the int32 of protobuf enums."
+                )
+            })?;
+            if let Some((other, _, _)) = values.iter().find(|(_, n, _)| *n == number) {
+                anyhow::bail!(
+                    "{name}::{value_name} has the same value ({number}) as {name}::{other}."
+                );
+            }
+            values.push((value_name.clone(), number, value.docs.clone()));
+        }
+        if !values.iter().any(|(_, number, _)| *number == 0) {
+            anyhow::bail!("{name} has no value 0, which protobuf enums need as their default.");
+        }
+        values.sort_by_key(|(_, number, _)| *number != 0);
+        self.enums.push(ProtoEnum {
+            name: name.to_owned(),
+            docs: en.docs.clone(),
+            values,
+        });
+        Ok(())
+    }
+
+    /// Adds a message, `element` names what it was created for in errors.
+    fn add_message(&mut self, element: &str, message: Message) -> anyhow::Result<()> {
+        if self.messages.iter().any(|m| m.name == message.name)
+            || self.enums.iter().any(|e| e.name == message.name)
+        {
+            anyhow::bail!(
+                "{element} needs the message {}, but the name is already taken. Rename it with #[grpc(name = \"...\")].",
+                message.name
+            );
+        }
+        self.messages.push(message);
+        Ok(())
+    }
+
+    fn add_service(
+        &mut self,
+        module: &SsdModule,
+        service_name: &str,
+        service: &ssd_data::Service,
+    ) -> anyhow::Result<()> {
+        let mut methods: Vec<Method> = Vec::new();
+        for (function_name, function) in &service.functions {
+            let element = format!("{service_name}.{function_name}");
+            let name = grpc_name(&function.attributes, function_name);
+            let input = format!("{name}Request");
+            let output = format!("{name}Response");
+            let fields = fields(module, &element, &function.arguments)?;
+            self.add_message(
+                &element,
+                Message {
+                    name: input.clone(),
+                    docs: vec![format!("The arguments of {name}.")],
+                    fields,
+                },
+            )?;
+            let mut fields = Vec::new();
+            if let Some(typ) = &function.return_type {
+                let element = format!("The return type of {element}");
+                fields.push(field(module, &element, "value", typ, 1)?);
+            }
+            if let Some(typ) = &function.error_type {
+                let element = format!("The error type of {element}");
+                let mut error = field(module, &element, "error", typ, 2)?;
+                error.optional = !error.repeated;
+                fields.push(error);
+            }
+            self.add_message(
+                &element,
+                Message {
+                    name: output.clone(),
+                    docs: vec![format!("The result of {name}.")],
+                    fields,
+                },
+            )?;
+            methods.push(Method {
+                name,
+                input,
+                output,
+                streaming: false,
+                docs: function.docs.clone(),
+            });
+        }
+        for (event_name, event) in &service.events {
+            let element = format!("{service_name}.{event_name}");
+            let name = grpc_name(&event.attributes, event_name);
+            let input = format!("{name}Request");
+            let output = format!("{name}Event");
+            self.add_message(
+                &element,
+                Message {
+                    name: input.clone(),
+                    docs: vec![format!("Subscribes to {name}.")],
+                    fields: Vec::new(),
+                },
+            )?;
+            let fields = fields(module, &element, &event.arguments)?;
+            self.add_message(
+                &element,
+                Message {
+                    name: output.clone(),
+                    docs: event.docs.clone(),
+                    fields,
+                },
+            )?;
+            methods.push(Method {
+                name,
+                input,
+                output,
+                streaming: true,
+                docs: event.docs.clone(),
+            });
+        }
+        for (i, method) in methods.iter().enumerate() {
+            if methods[..i].iter().any(|m| m.name == method.name) {
+                anyhow::bail!(
+                    "{service_name} has more than one method {}. Rename one with #[grpc(name = \"...\")].",
+                    method.name
+                );
+            }
+        }
+        self.services.push(Service {
+            name: grpc_name(&service.attributes, service_name),
+            package: grpc_param(&service.attributes, "package")
+                .map_or_else(|| module.namespace.components.join("."), str::to_owned),
+            docs: service.docs.clone(),
+            methods,
+        });
+        Ok(())
+    }
+
+    fn message(&self, name: &str) -> Option<&Message> {
+        self.messages.iter().find(|message| message.name == name)
+    }
+
+    /// Whether the field leads back to the message through singular message fields, which Rust
+    /// can only express with a `Box`.
+    fn is_recursive(&self, message: &str, field: &Field) -> bool {
+        let Kind::Message(typ) = &field.kind else {
+            return false;
+        };
+        if field.repeated {
+            return false;
+        }
+        let mut visited = Vec::new();
+        let mut pending = vec![typ.as_str()];
+        while let Some(name) = pending.pop() {
+            if name == message {
+                return true;
+            }
+            if visited.contains(&name) {
+                continue;
+            }
+            visited.push(name);
+            if let Some(message) = self.message(name) {
+                pending.extend(message.fields.iter().filter_map(|field| match &field.kind {
+                    Kind::Message(typ) if !field.repeated => Some(typ.as_str()),
+                    _ => None,
+                }));
+            }
+        }
+        false
+    }
+}
+
+/// Replaces the `{name}` placeholders of the template in one pass, so values are never
+/// replaced again.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let placeholder = values.iter().find_map(|(name, value)| {
+            let tail = rest.strip_prefix(name)?.strip_prefix('}')?;
+            Some((value, tail))
+        });
+        match placeholder {
+            Some((value, tail)) => {
+                out.push_str(value);
+                rest = tail;
+            }
+            None => out.push('{'),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn rust_ident(name: &str) -> anyhow::Result<String> {
+    sanitize_ident(name, "rust")
+}
+
+fn rust_docs(docs: &[String], indent: &str) -> String {
+    docs.iter()
+        .map(|doc| format!("{indent}/// {doc}").trim_end().to_owned() + "\n")
+        .collect()
+}
+
+const RUST_CLIENT: &str = r#"
+/// Generated client implementations.
+pub mod {module}_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+{docs}    #[derive(Debug, Clone)]
+    pub struct {service}Client<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl {service}Client<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> std::result::Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> {service}Client<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+{methods}    }
+}
+"#;
+
+const RUST_CLIENT_METHOD: &str = r#"{docs}        pub async fn {method}(
+            &mut self,
+            request: impl tonic::IntoRequest<super::{input}>,
+        ) -> std::result::Result<tonic::Response<{response}>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("{path}");
+            self.inner.{call}(request.into_request(), path, codec).await
+        }
+"#;
+
+const RUST_SERVER: &str = r#"
+/// Generated server implementations.
+pub mod {module}_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with {service}Server.
+    #[async_trait]
+    pub trait {service}: Send + Sync + 'static {
+{methods}    }
+{docs}    pub struct {service}Server<T: {service}> {
+        inner: Arc<T>,
+    }
+    impl<T: {service}> {service}Server<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for {service}Server<T>
+    where
+        T: {service},
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+{routes}                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: {service}> Clone for {service}Server<T> {
+        fn clone(&self) -> Self {
+            Self {
+                inner: Arc::clone(&self.inner),
+            }
+        }
+    }
+    impl<T: {service}> tonic::server::NamedService for {service}Server<T> {
+        const NAME: &'static str = "{name}";
+    }
+}
+"#;
+
+const RUST_SERVER_METHOD: &str = r#"{stream}{docs}        async fn {method}(
+            &self,
+            request: tonic::Request<super::{input}>,
+        ) -> std::result::Result<tonic::Response<{response}>, tonic::Status>;
+"#;
+
+const RUST_SERVER_STREAM: &str = r#"        /// Server streaming response type for the {method} method.
+        type {stream}: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::{output}, tonic::Status>,
+            >
+            + Send
+            + 'static;
+"#;
+
+const RUST_SERVER_ROUTE: &str = r#"                "{path}" => {
+                    #[allow(non_camel_case_types)]
+                    struct {svc}<T: {service}>(pub Arc<T>);
+                    impl<T: {service}> tonic::server::{kind}<super::{input}> for {svc}<T> {
+                        type Response = super::{output};
+{types}                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::{input}>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as {service}>::{method}(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = Arc::clone(&self.inner);
+                    let fut = async move {
+                        let method = {svc}(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec);
+                        let res = grpc.{call}(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+"#;
+
+fn rust_field(package: &Package, message: &Message, field: &Field) -> anyhow::Result<String> {
+    let boxed = package.is_recursive(&message.name, field);
+    let (kind, typ) = match &field.kind {
+        Kind::Scalar(Scalar::Bytes) => (
+            "bytes = \"vec\"".to_owned(),
+            Scalar::Bytes.rust_type().to_owned(),
+        ),
+        Kind::Scalar(scalar) => (scalar.name().to_owned(), scalar.rust_type().to_owned()),
+        Kind::Enum(name) => (
+            format!("enumeration = \"{}\"", rust_ident(name)?),
+            "i32".to_owned(),
+        ),
+        Kind::Message(name) if boxed => (
+            "message".to_owned(),
+            format!("::prost::alloc::boxed::Box<{}>", rust_ident(name)?),
+        ),
+        Kind::Message(name) => ("message".to_owned(), rust_ident(name)?),
+    };
+    let mut attribute = vec![kind];
+    let typ = if field.repeated {
+        attribute.push("repeated".to_owned());
+        format!("::prost::alloc::vec::Vec<{typ}>")
+    } else if field.has_presence() {
+        attribute.push("optional".to_owned());
+        format!("::core::option::Option<{typ}>")
+    } else {
+        typ
+    };
+    if boxed {
+        attribute.push("boxed".to_owned());
+    }
+    attribute.push(format!("tag = \"{}\"", field.tag));
+    Ok(format!(
+        "{}    #[prost({})]\n    pub {}: {typ},\n",
+        rust_docs(&field.docs, "    "),
+        attribute.join(", "),
+        rust_ident(&to_snake_case(&field.name))?
+    ))
+}
+
+fn tonic(package: &Package) -> anyhow::Result<String> {
+    let mut out = String::from("// @generated by ssd grpc for tonic 0.10 and prost 0.12.\n");
+    for en in &package.enums {
+        out.push('\n');
+        out.push_str(&rust_docs(&en.docs, ""));
+        out.push_str("#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]\n");
+        out.push_str("#[repr(i32)]\n");
+        out.push_str(&format!("pub enum {} {{\n", rust_ident(&en.name)?));
+        for (name, number, docs) in &en.values {
+            out.push_str(&rust_docs(docs, "    "));
+            out.push_str(&format!(
+                "    {} = {number},\n",
+                rust_ident(&to_pascal_case(name))?
+            ));
+        }
+        out.push_str("}\n");
+    }
+    for message in &package.messages {
+        out.push('\n');
+        out.push_str(&rust_docs(&message.docs, ""));
+        out.push_str("#[allow(clippy::derive_partial_eq_without_eq)]\n");
+        out.push_str("#[derive(Clone, PartialEq, ::prost::Message)]\n");
+        let name = rust_ident(&message.name)?;
+        if message.fields.is_empty() {
+            out.push_str(&format!("pub struct {name} {{}}\n"));
+            continue;
+        }
+        out.push_str(&format!("pub struct {name} {{\n"));
+        for field in &message.fields {
+            out.push_str(&rust_field(package, message, field)?);
+        }
+        out.push_str("}\n");
+    }
+
+    for service in &package.services {
+        let name = rust_ident(&service.name)?;
+        let module = to_snake_case(&service.name);
+        let full_name = service.full_name();
+        let mut client_methods = String::new();
+        let mut server_methods = String::new();
+        let mut routes = String::new();
+        for method in &service.methods {
+            let ident = rust_ident(&to_snake_case(&method.name))?;
+            let path = service.path(method);
+            let input = rust_ident(&method.input)?;
+            let output = rust_ident(&method.output)?;
+            let docs = rust_docs(&method.docs, "        ");
+            let stream = format!("{}Stream", method.name);
+            let (call, kind, client_response, server_response, types, stream_type) = if method
+                .streaming
+            {
+                (
+                        "server_streaming",
+                        "ServerStreamingService",
+                        format!("tonic::codec::Streaming<super::{output}>"),
+                        format!("Self::{stream}"),
+                        format!(
+                            "                        type ResponseStream = T::{stream};\n                        type Future = BoxFuture<\n                            tonic::Response<Self::ResponseStream>,\n                            tonic::Status,\n                        >;\n"
+                        ),
+                        fill(
+                            RUST_SERVER_STREAM,
+                            &[("method", &ident), ("stream", &stream), ("output", &output)],
+                        ),
+                    )
+            } else {
+                (
+                        "unary",
+                        "UnaryService",
+                        format!("super::{output}"),
+                        format!("super::{output}"),
+                        "                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;\n".to_owned(),
+                        String::new(),
+                    )
+            };
+            client_methods.push_str(&fill(
+                RUST_CLIENT_METHOD,
+                &[
+                    ("docs", &docs),
+                    ("method", &ident),
+                    ("input", &input),
+                    ("response", &client_response),
+                    ("path", &path),
+                    ("call", call),
+                ],
+            ));
+            server_methods.push_str(&fill(
+                RUST_SERVER_METHOD,
+                &[
+                    ("stream", &stream_type),
+                    ("docs", &docs),
+                    ("method", &ident),
+                    ("input", &input),
+                    ("response", &server_response),
+                ],
+            ));
+            routes.push_str(&fill(
+                RUST_SERVER_ROUTE,
+                &[
+                    ("path", &path),
+                    ("svc", &format!("{}Svc", method.name)),
+                    ("service", &name),
+                    ("kind", kind),
+                    ("input", &input),
+                    ("output", &output),
+                    ("types", &types),
+                    ("method", &ident),
+                    ("call", call),
+                ],
+            ));
+        }
+        let docs = rust_docs(&service.docs, "    ");
+        out.push_str(&fill(
+            RUST_CLIENT,
+            &[
+                ("module", &module),
+                ("docs", &docs),
+                ("service", &name),
+                ("methods", &client_methods),
+            ],
+        ));
+        out.push_str(&fill(
+            RUST_SERVER,
+            &[
+                ("module", &module),
+                ("docs", &docs),
+                ("service", &name),
+                ("methods", &server_methods),
+                ("routes", &routes),
+                ("name", &full_name),
+            ],
+        ));
+    }
+    Ok(out)
+}
+
+/// The encoding and the transport the messages and clients of the grpc-web target use.
+const TS_RUNTIME: &str = r#"
+export interface Codec<T> {
+  /** The protobuf wire type: 0 varint, 1 64 bit, 2 length-delimited or 5 32 bit. */
+  wireType: number;
+  write(writer: Writer, value: T): void;
+  read(reader: Reader): T;
+}
+
+export interface MessageCodec<T> extends Codec<T> {
+  encode(value: T): Uint8Array;
+  decode(bytes: Uint8Array): T;
+}
+
+export class GrpcError extends Error {
+  constructor(
+    readonly code: number,
+    message: string,
+  ) {
+    super(message);
+    this.name = "GrpcError";
+  }
+}
+
+export class Writer {
+  private buffer: number[] = [];
+
+  varint(value: bigint): void {
+    let rest = BigInt.asUintN(64, value);
+    while (rest > 0x7fn) {
+      this.buffer.push(Number(rest & 0x7fn) | 0x80);
+      rest >>= 7n;
+    }
+    this.buffer.push(Number(rest));
+  }
+
+  raw(bytes: Uint8Array): void {
+    for (let i = 0; i < bytes.length; i++) {
+      this.buffer.push(bytes[i]);
+    }
+  }
+
+  bytes(bytes: Uint8Array): void {
+    this.varint(BigInt(bytes.length));
+    this.raw(bytes);
+  }
+
+  tag(field: number, wireType: number): void {
+    this.varint((BigInt(field) << 3n) | BigInt(wireType));
+  }
+
+  field<T>(field: number, codec: Codec<T>, value: T): void {
+    this.tag(field, codec.wireType);
+    codec.write(this, value);
+  }
+
+  /** Writes the values of a repeated field, numbers packed like proto3 does by default. */
+  repeated<T>(field: number, codec: Codec<T>, values: T[]): void {
+    if (codec.wireType === 2) {
+      values.forEach((value) => this.field(field, codec, value));
+    } else if (values.length > 0) {
+      const packed = new Writer();
+      values.forEach((value) => codec.write(packed, value));
+      this.tag(field, 2);
+      this.bytes(packed.finish());
+    }
+  }
+
+  finish(): Uint8Array {
+    return Uint8Array.from(this.buffer);
+  }
+}
+
+export class Reader {
+  private position = 0;
+
+  constructor(private readonly buffer: Uint8Array) {}
+
+  done(): boolean {
+    return this.position >= this.buffer.length;
+  }
+
+  take(length: number): Uint8Array {
+    if (this.position + length > this.buffer.length) {
+      throw new GrpcError(13, "The message is truncated.");
+    }
+    const bytes = this.buffer.subarray(this.position, this.position + length);
+    this.position += length;
+    return bytes;
+  }
+
+  varint(): bigint {
+    let result = 0n;
+    for (let shift = 0n; ; shift += 7n) {
+      const byte = this.take(1)[0];
+      result |= BigInt(byte & 0x7f) << shift;
+      if (byte < 0x80) {
+        return BigInt.asUintN(64, result);
+      }
+    }
+  }
+
+  fixed(length: number): DataView {
+    const bytes = this.take(length);
+    return new DataView(bytes.buffer, bytes.byteOffset, length);
+  }
+
+  bytes(): Uint8Array {
+    return this.take(Number(this.varint()));
+  }
+
+  tag(): [number, number] {
+    const tag = this.varint();
+    return [Number(tag >> 3n), Number(tag & 7n)];
+  }
+
+  skip(wireType: number): void {
+    switch (wireType) {
+      case 0:
+        this.varint();
+        break;
+      case 1:
+        this.take(8);
+        break;
+      case 2:
+        this.bytes();
+        break;
+      case 5:
+        this.take(4);
+        break;
+      default:
+        throw new GrpcError(13, `The wire type ${wireType} isn't supported.`);
+    }
+  }
+
+  /** Reads one value of a repeated field, or all values if they are packed. */
+  repeated<T>(wireType: number, codec: Codec<T>, values: T[]): void {
+    if (wireType === 2 && codec.wireType !== 2) {
+      const packed = new Reader(this.bytes());
+      while (!packed.done()) {
+        values.push(codec.read(packed));
+      }
+    } else {
+      values.push(codec.read(this));
+    }
+  }
+}
+
+function varint<T>(write: (value: T) => bigint, read: (value: bigint) => T): Codec<T> {
+  return {
+    wireType: 0,
+    write: (writer, value) => writer.varint(write(value)),
+    read: (reader) => read(reader.varint()),
+  };
+}
+
+function fixed<T>(
+  length: 4 | 8,
+  write: (view: DataView, value: T) => void,
+  read: (view: DataView) => T,
+): Codec<T> {
+  return {
+    wireType: length === 4 ? 5 : 1,
+    write: (writer, value) => {
+      const bytes = new Uint8Array(length);
+      write(new DataView(bytes.buffer), value);
+      writer.raw(bytes);
+    },
+    read: (reader) => read(reader.fixed(length)),
+  };
+}
+
+function delimited<T>(write: (value: T) => Uint8Array, read: (bytes: Uint8Array) => T): Codec<T> {
+  return {
+    wireType: 2,
+    write: (writer, value) => writer.bytes(write(value)),
+    read: (reader) => read(reader.bytes()),
+  };
+}
+
+export const codecs = {
+  bool: varint<boolean>(
+    (value) => (value ? 1n : 0n),
+    (value) => value !== 0n,
+  ),
+  int32: varint<number>(BigInt, (value) => Number(BigInt.asIntN(32, value))),
+  int64: varint<bigint>(
+    (value) => value,
+    (value) => BigInt.asIntN(64, value),
+  ),
+  uint32: varint<number>(BigInt, (value) => Number(BigInt.asUintN(32, value))),
+  uint64: varint<bigint>(
+    (value) => value,
+    (value) => value,
+  ),
+  float: fixed<number>(
+    4,
+    (view, value) => view.setFloat32(0, value, true),
+    (view) => view.getFloat32(0, true),
+  ),
+  double: fixed<number>(
+    8,
+    (view, value) => view.setFloat64(0, value, true),
+    (view) => view.getFloat64(0, true),
+  ),
+  string: delimited<string>(
+    (value) => new TextEncoder().encode(value),
+    (bytes) => new TextDecoder().decode(bytes),
+  ),
+  bytes: delimited<Uint8Array>(
+    (value) => value,
+    (bytes) => bytes.slice(),
+  ),
+};
+
+export function message<T>(
+  write: (writer: Writer, value: T) => void,
+  read: (reader: Reader) => T,
+): MessageCodec<T> {
+  const encode = (value: T) => {
+    const writer = new Writer();
+    write(writer, value);
+    return writer.finish();
+  };
+  const decode = (bytes: Uint8Array) => read(new Reader(bytes));
+  return { ...delimited(encode, decode), encode, decode };
+}
+
+/** Options of a call, e.g. metadata like `authorization` as headers. */
+export interface CallOptions {
+  headers?: Record<string, string>;
+  signal?: AbortSignal;
+}
+
+function mergeOptions(base: CallOptions, options: CallOptions = {}): CallOptions {
+  return { ...base, ...options, headers: { ...base.headers, ...options.headers } };
+}
+
+/** The gRPC status for an HTTP status, like gRPC clients map them. */
+function statusOf(httpStatus: number): number {
+  switch (httpStatus) {
+    case 400:
+      return 13;
+    case 401:
+      return 16;
+    case 403:
+      return 7;
+    case 404:
+      return 12;
+    case 429:
+    case 502:
+    case 503:
+    case 504:
+      return 14;
+    default:
+      return 2;
+  }
+}
+
+function checkStatus(status: string | null | undefined, message: string | null | undefined): void {
+  if (status !== null && status !== undefined && status !== "0") {
+    throw new GrpcError(Number(status), decodeURIComponent(message ?? ""));
+  }
+}
+
+function parseTrailers(bytes: Uint8Array): Map<string, string> {
+  const trailers = new Map<string, string>();
+  for (const line of new TextDecoder().decode(bytes).split("\r\n")) {
+    const colon = line.indexOf(":");
+    if (colon > 0) {
+      trailers.set(line.slice(0, colon).trim().toLowerCase(), line.slice(colon + 1).trim());
+    }
+  }
+  return trailers;
+}
+
+/** Sends the request and yields the messages of the response until the trailers arrive. */
+async function* call<Req, Res>(
+  url: string,
+  request: MessageCodec<Req>,
+  response: MessageCodec<Res>,
+  value: Req,
+  options: CallOptions,
+): AsyncGenerator<Res> {
+  const body = request.encode(value);
+  const frame = new Uint8Array(5 + body.length);
+  new DataView(frame.buffer).setUint32(1, body.length);
+  frame.set(body, 5);
+  const result = await fetch(url, {
+    method: "POST",
+    headers: { ...options.headers, "content-type": "application/grpc-web+proto", "x-grpc-web": "1" },
+    body: frame,
+    signal: options.signal,
+  });
+  if (!result.ok) {
+    throw new GrpcError(statusOf(result.status), `The server answered with HTTP ${result.status}.`);
+  }
+  // responses without messages can have the status in the headers
+  checkStatus(result.headers.get("grpc-status"), result.headers.get("grpc-message"));
+  let finished = result.headers.has("grpc-status");
+  let buffer = new Uint8Array(0);
+  const reader = result.body?.getReader();
+  while (reader) {
+    const { done, value: chunk } = await reader.read();
+    if (done) {
+      break;
+    }
+    const joined = new Uint8Array(buffer.length + chunk.length);
+    joined.set(buffer);
+    joined.set(chunk, buffer.length);
+    buffer = joined;
+    while (buffer.length >= 5) {
+      const length = new DataView(buffer.buffer, buffer.byteOffset).getUint32(1);
+      if (buffer.length < 5 + length) {
+        break;
+      }
+      const flags = buffer[0];
+      const payload = buffer.slice(5, 5 + length);
+      buffer = buffer.slice(5 + length);
+      if (flags & 0x80) {
+        const trailers = parseTrailers(payload);
+        checkStatus(trailers.get("grpc-status"), trailers.get("grpc-message"));
+        finished = true;
+      } else {
+        yield response.decode(payload);
+      }
+    }
+  }
+  if (!finished || buffer.length > 0) {
+    throw new GrpcError(13, "The response ended without a status.");
+  }
+}
+
+async function unary<Req, Res>(
+  url: string,
+  request: MessageCodec<Req>,
+  response: MessageCodec<Res>,
+  value: Req,
+  options: CallOptions,
+): Promise<Res> {
+  let result: Res | undefined;
+  for await (const message of call(url, request, response, value, options)) {
+    result = message;
+  }
+  if (result === undefined) {
+    throw new GrpcError(13, "The response has no message.");
+  }
+  return result;
+}
+"#;
+
+fn ts_ident(name: &str) -> anyhow::Result<String> {
+    sanitize_ident(name, "typescript")
+}
+
+fn ts_docs(docs: &[String], indent: &str) -> String {
+    let docs = docs
+        .iter()
+        .map(|doc| doc.replace("*/", "*\\/"))
+        .collect::<Vec<_>>();
+    match docs.as_slice() {
+        [] => String::new(),
+        [doc] => format!("{indent}/** {doc} */\n"),
+        docs => {
+            let mut out = format!("{indent}/**\n");
+            for doc in docs {
+                out.push_str(format!("{indent} * {doc}").trim_end());
+                out.push('\n');
+            }
+            out.push_str(&format!("{indent} */\n"));
+            out
+        }
+    }
+}
+
+impl Kind {
+    fn ts_type(&self) -> anyhow::Result<String> {
+        match self {
+            Self::Scalar(scalar) => Ok(scalar.ts_type().to_owned()),
+            Self::Enum(name) | Self::Message(name) => ts_ident(name),
+        }
+    }
+
+    /// The expression of the codec in the runtime.
+    fn ts_codec(&self) -> anyhow::Result<String> {
+        match self {
+            Self::Scalar(scalar) => Ok(format!("codecs.{}", scalar.name())),
+            Self::Enum(_) => Ok("codecs.int32".to_owned()),
+            Self::Message(name) => ts_ident(name),
+        }
+    }
+}
+
+fn ts_message(message: &Message) -> anyhow::Result<String> {
+    let name = ts_ident(&message.name)?;
+    let mut interface = String::new();
+    let mut write = String::new();
+    let mut defaults = Vec::new();
+    let mut read = String::new();
+    for field in &message.fields {
+        let ident = to_camel_case(&field.name);
+        let value = format!("value.{ident}");
+        let typ = field.kind.ts_type()?;
+        let codec = field.kind.ts_codec()?;
+        let tag = field.tag;
+        interface.push_str(&ts_docs(&field.docs, "  "));
+        if field.repeated {
+            interface.push_str(&format!("  {ident}: {typ}[];\n"));
+            write.push_str(&format!("    writer.repeated({tag}, {codec}, {value});\n"));
+            defaults.push(format!("{ident}: []"));
+            read.push_str(&format!(
+                "        case {tag}:\n          reader.repeated(wireType, {codec}, {value});\n          break;\n"
+            ));
+            continue;
+        }
+        if field.has_presence() {
+            interface.push_str(&format!("  {ident}?: {typ};\n"));
+            write.push_str(&format!(
+                "    if ({value} !== undefined) {{\n      writer.field({tag}, {codec}, {value});\n    }}\n"
+            ));
+        } else {
+            let (default, is_set) = match &field.kind {
+                Kind::Scalar(Scalar::Bool) => ("false", value.clone()),
+                Kind::Scalar(Scalar::Bytes) => {
+                    ("new Uint8Array(0)", format!("{value}.length !== 0"))
+                }
+                Kind::Scalar(scalar) => (
+                    scalar.ts_default(),
+                    format!("{value} !== {}", scalar.ts_default()),
+                ),
+                Kind::Enum(_) => ("0", format!("{value} !== 0")),
+                Kind::Message(_) => unreachable!("singular messages have presence"),
+            };
+            interface.push_str(&format!("  {ident}: {typ};\n"));
+            write.push_str(&format!(
+                "    if ({is_set}) {{\n      writer.field({tag}, {codec}, {value});\n    }}\n"
+            ));
+            defaults.push(format!("{ident}: {default}"));
+        }
+        read.push_str(&format!(
+            "        case {tag}:\n          {value} = {codec}.read(reader);\n          break;\n"
+        ));
+    }
+
+    let mut out = ts_docs(&message.docs, "");
+    if interface.is_empty() {
+        out.push_str(&format!("export interface {name} {{}}\n\n"));
+    } else {
+        out.push_str(&format!("export interface {name} {{\n{interface}}}\n\n"));
+    }
+    let defaults = if defaults.is_empty() {
+        "{}".to_owned()
+    } else {
+        format!("{{ {} }}", defaults.join(", "))
+    };
+    let writer = if write.is_empty() {
+        "  () => {},\n".to_owned()
+    } else {
+        format!("  (writer, value) => {{\n{write}  }},\n")
+    };
+    out.push_str(&format!(
+        "export const {name}: MessageCodec<{name}> = message<{name}>(\n{writer}  (reader) => {{\n    const value: {name} = {defaults};\n    while (!reader.done()) {{\n      const [field, wireType] = reader.tag();\n      switch (field) {{\n{read}        default:\n          reader.skip(wireType);\n      }}\n    }}\n    return value;\n  }},\n);\n"
+    ));
+    Ok(out)
+}
+
+fn grpc_web(package: &Package) -> anyhow::Result<String> {
+    let mut out = String::from("// @generated by ssd grpc for grpc-web, needs ES2020 (BigInt).\n");
+    out.push_str(TS_RUNTIME);
+    for en in &package.enums {
+        out.push('\n');
+        out.push_str(&ts_docs(&en.docs, ""));
+        out.push_str(&format!("export enum {} {{\n", ts_ident(&en.name)?));
+        for (name, number, docs) in &en.values {
+            out.push_str(&ts_docs(docs, "  "));
+            out.push_str(&format!("  {} = {number},\n", ts_ident(name)?));
+        }
+        out.push_str("}\n");
+    }
+    for message in &package.messages {
+        out.push('\n');
+        out.push_str(&ts_message(message)?);
+    }
+    for service in &package.services {
+        out.push('\n');
+        out.push_str(&ts_docs(&service.docs, ""));
+        out.push_str(&format!(
+            "export class {}Client {{\n",
+            ts_ident(&service.name)?
+        ));
+        out.push_str("  /** `host` is the address of the grpc-web endpoint, e.g. `https://api.example.com`. */\n");
+        out.push_str("  constructor(\n    private readonly host: string,\n    private readonly options: CallOptions = {},\n  ) {}\n");
+        for method in &service.methods {
+            let ident = to_camel_case(&method.name);
+            let input = ts_ident(&method.input)?;
+            let output = ts_ident(&method.output)?;
+            let path = service.path(method);
+            out.push('\n');
+            out.push_str(&ts_docs(&method.docs, "  "));
+            let (request, result, function) = if method.streaming {
+                (
+                    format!("request: {input} = {{}}"),
+                    format!("AsyncGenerator<{output}>"),
+                    "call",
+                )
+            } else {
+                (
+                    format!("request: {input}"),
+                    format!("Promise<{output}>"),
+                    "unary",
+                )
+            };
+            out.push_str(&format!(
+                "  {ident}({request}, options?: CallOptions): {result} {{\n    return {function}(\n      this.host + \"{path}\",\n      {input},\n      {output},\n      request,\n      mergeOptions(this.options, options),\n    );\n  }}\n"
+            ));
+        }
+        out.push_str("}\n");
+    }
+    Ok(out)
+}
+
+/// Generates the messages and stubs for the services of the module.
+///
+/// `#[grpc(package = "...")]` on a service sets its package (by default the namespace of the
+/// module), `#[grpc(name = "...")]` renames services, functions and events and
+/// `#[grpc(tag = "N")]` sets the number of a property or argument.
+///
+/// # Errors
+///
+/// Returns an error if the model uses something protobuf can't express, e.g. nested lists or
+/// 128 bit integers, or the attributes are invalid.
+pub fn generate(module: &SsdModule, target: GrpcTarget) -> anyhow::Result<String> {
+    let package = Package::from_module(module)?;
+    match target {
+        GrpcTarget::Tonic => tonic(&package),
+        GrpcTarget::GrpcWeb => grpc_web(&package),
+    }
+}
+
+#[test]
+fn test_grpc() {
+    use ssd_data::Namespace;
+
+    let module = crate::parser::parse(
+        r#"
+        /// A node of a tree.
+        data Node {
+            #[grpc(tag = "3")]
+            name: string,
+            parent: Node,
+            children: list of Node,
+            flags: Access,
+            state: State,
+        };
+        enum State { Active = 1, Idle = 0, };
+        flags Access { Read, Write, };
+        #[grpc(package = "tree.v1")]
+        service Trees {
+            /// Looks a node up.
+            fn find(name: string) -> result of Node, string;
+            #[grpc(name = "RemoveAll")]
+            fn clear();
+            event changed(node: Node);
+        };
+        "#,
+        Namespace::new("tree"),
+    )
+    .unwrap();
+
+    let rust = generate(&module, GrpcTarget::Tonic).unwrap();
+    for snippet in [
+        "pub enum State {\n    Idle = 0,\n    Active = 1,\n}",
+        "    #[prost(string, tag = \"3\")]\n    pub name: ::prost::alloc::string::String,",
+        "    #[prost(message, optional, boxed, tag = \"4\")]\n    pub parent: ::core::option::Option<::prost::alloc::boxed::Box<Node>>,",
+        "    #[prost(message, repeated, tag = \"5\")]",
+        "    #[prost(uint64, tag = \"6\")]\n    pub flags: u64,",
+        "    #[prost(enumeration = \"State\", tag = \"7\")]\n    pub state: i32,",
+        "    #[prost(string, optional, tag = \"2\")]\n    pub error: ::core::option::Option<::prost::alloc::string::String>,",
+        "pub struct RemoveAllRequest {}",
+        "pub struct ChangedEvent {",
+        "pub mod trees_client {",
+        "        /// Looks a node up.\n        pub async fn find(",
+        "PathAndQuery::from_static(\"/tree.v1.Trees/RemoveAll\")",
+        "tonic::Response<tonic::codec::Streaming<super::ChangedEvent>>",
+        "        type ChangedStream: tonic::codegen::tokio_stream::Stream<",
+        "                \"/tree.v1.Trees/Changed\" => {",
+        "let res = grpc.server_streaming(method, req).await;",
+        "const NAME: &'static str = \"tree.v1.Trees\";",
+    ] {
+        assert!(rust.contains(snippet), "missing {snippet:?} in\n{rust}");
+    }
+    for placeholder in ["{docs}", "{methods}", "{routes}", "{service}"] {
+        assert!(!rust.contains(placeholder));
+    }
+
+    let ts = generate(&module, GrpcTarget::GrpcWeb).unwrap();
+    for snippet in [
+        "export enum State {\n  Idle = 0,\n  Active = 1,\n}",
+        "/** A node of a tree. */\nexport interface Node {\n  name: string;\n  parent?: Node;\n  children: Node[];\n  flags: bigint;\n  state: State;\n}",
+        "const value: Node = { name: \"\", children: [], flags: 0n, state: 0 };",
+        "      writer.field(3, codecs.string, value.name);",
+        "    writer.repeated(5, Node, value.children);",
+        "  error?: string;",
+        "  removeAll(request: RemoveAllRequest, options?: CallOptions): Promise<RemoveAllResponse> {",
+        "this.host + \"/tree.v1.Trees/Changed\"",
+        "  changed(request: ChangedRequest = {}, options?: CallOptions): AsyncGenerator<ChangedEvent> {",
+    ] {
+        assert!(ts.contains(snippet), "missing {snippet:?} in\n{ts}");
+    }
+
+    let error = |description: &str| {
+        let module = crate::parser::parse(description, Namespace::new("test")).unwrap();
+        generate(&module, GrpcTarget::Tonic)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error("data A { m: list of list of f32, };").contains("nested list"));
+    assert!(error("data A { big: i128, };").contains("i128"));
+    assert!(error("data A { a: u8, #[grpc(tag = \"1\")] b: u8, };").contains("same tag (1)"));
+    assert!(error("enum E { A = 1, B, };").contains("no value 0"));
+    assert!(error("data A { b: other::B, };").contains("isn't declared"));
+    assert!(
+        error("data GetRequest { id: u64, }; service S { fn get(); };")
+            .contains("S.get needs the message GetRequest")
+    );
+}
@@ -1,169 +1,1483 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use ssd_data::{Namespace, SsdModule, TypeName};
+use crate::exit::WithPath;
 
-#[derive(Serialize, Deserialize, Hash, Eq, PartialEq)]
+#[cfg(feature = "_bin")]
+use crate::options::DataFormat;
+
+use ssd_data::visit::walk_function_mut;
+use ssd_data::{
+    sort_data_types_topologically, Attribute, DataType, Defines, Function, Namespace, OrderedMap,
+    SsdModule, TypeName, VisitorMut,
+};
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
 #[serde(untagged)]
 enum StringOrVec {
     String(String),
     Vec(Vec<String>),
 }
 
+/// An error message meant to be shown to the user as is.
+///
+/// In contrast to a plain string error, the `Debug` output isn't escaped, so multi-line
+/// diagnostics stay readable when returned from `main`.
+pub struct Diagnostic(pub String);
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Serializes the value (e.g. a module or raw data) in the given format.
+///
+/// The result is utf-8, unless the format [is binary](DataFormat::is_binary).
+#[cfg(feature = "_bin")]
+pub fn serialize<T: Serialize>(format: DataFormat, value: T) -> anyhow::Result<Vec<u8>> {
+    let result = match format {
+        DataFormat::Json => serde_json::to_string(&value)?,
+        DataFormat::JsonPretty => serde_json::to_string_pretty(&value)?,
+        DataFormat::Yaml => serde_yaml::to_string(&value)?,
+        DataFormat::Toml => toml::to_string(&value)?,
+        DataFormat::TomlPretty => toml::to_string_pretty(&value)?,
+        #[cfg(feature = "ron")]
+        DataFormat::Ron => ron::to_string(&value)?,
+        #[cfg(feature = "ron")]
+        DataFormat::RonPretty => {
+            ron::ser::to_string_pretty(&value, ron::ser::PrettyConfig::default())?
+        }
+        DataFormat::Rsn => rsn::to_string(&value),
+        DataFormat::RsnPretty => rsn::to_string_pretty(&value),
+        DataFormat::Xml => crate::xml::to_string(&serde_json::to_value(&value)?),
+        // named, so maps stay maps instead of becoming arrays
+        #[cfg(feature = "msgpack")]
+        DataFormat::Msgpack => return Ok(rmp_serde::to_vec_named(&value)?),
+        #[cfg(feature = "cbor")]
+        DataFormat::Cbor => {
+            let mut result = Vec::new();
+            ciborium::into_writer(&value, &mut result)?;
+            return Ok(result);
+        }
+    };
+    Ok(result.into_bytes())
+}
+
+/// Converts a value from the serialized model into a string.
+///
+/// Namespaces (objects with `components`) get joined with `sep`.
+pub fn json_to_string(value: &serde_json::Value, sep: &str) -> String {
+    use serde_json::Value;
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Object(o) => match o.get("components").and_then(Value::as_array) {
+            Some(components) => components
+                .iter()
+                .map(|c| json_to_string(c, sep))
+                .collect::<Vec<_>>()
+                .join(sep),
+            None => value.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Takes either a list of attributes or an element with an `attributes` field
+/// from the serialized model.
+pub fn json_attributes(value: &serde_json::Value) -> Vec<Attribute> {
+    let attributes = match value {
+        serde_json::Value::Object(o) => o.get("attributes").cloned().unwrap_or_default(),
+        _ => value.clone(),
+    };
+    serde_json::from_value(attributes).unwrap_or_default()
+}
+
+/// Sorts a list of data types from the serialized model topologically.
+///
+/// See [`ssd_data::SsdModule::data_types_topologically_sorted`].
+pub fn json_sort_data_types(value: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let data_types: OrderedMap<DataType> = serde_json::from_value(value.clone())?;
+    let sorted = sort_data_types_topologically(&data_types)?;
+    Ok(serde_json::to_value(sorted)?)
+}
+
+/// Writes the file through a temporary file next to it, which replaces it once it's complete, so
+/// interrupted runs never leave half-written files behind. Missing directories get created and
+/// the permissions of a replaced file are kept.
+pub fn write_atomic(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} isn't a file name.", path.display()))?;
+    let temp = dir.join(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let result = std::fs::write(&temp, content).and_then(|()| {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        std::fs::rename(&temp, path)
+    });
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(anyhow::Error::new(e).context(format!("Could not write {}", path.display())));
+    }
+    Ok(())
+}
+
 pub fn print_or_write(out: Option<PathBuf>, result: &str) -> anyhow::Result<()> {
     if let Some(out) = out {
-        std::fs::write(out, result)?;
+        write_atomic(&out, result.as_bytes())?;
     } else {
         println!("{result}");
     }
     Ok(())
 }
 
-pub fn parse_raw_data(file: PathBuf) -> anyhow::Result<serde_value::Value> {
-    let content = std::fs::read_to_string(file)?;
-    let result = serde_json::from_str(&content)
-        .or_else(|_| toml::from_str(&content))
-        .or_else(|_| serde_yaml::from_str(&content))
-        .or_else(|_| rsn::from_str(&content));
-    #[cfg(feature = "ron")]
-    let result = result.or_else(|_| ron::from_str(&content));
-    Ok(result?)
+/// Like [`print_or_write`], but for binary data, which is written to stdout as is.
+pub fn print_or_write_bytes(out: Option<PathBuf>, result: &[u8]) -> anyhow::Result<()> {
+    if let Some(out) = out {
+        write_atomic(&out, result)?;
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(result)?;
+        stdout.flush()?;
+    }
+    Ok(())
 }
 
-#[cfg(not(feature = "_bin"))]
-pub fn update_types(mut module: SsdModule, typemap: &str) -> anyhow::Result<SsdModule> {
-    let mappings: HashMap<StringOrVec, StringOrVec> = toml::from_str(typemap)?;
-    let mappings: HashMap<String, String> = mappings
-        .iter()
-        .map(|(k, v)| match (k, v) {
-            (StringOrVec::Vec(k), StringOrVec::Vec(v)) => (k.join("::"), v.join("::")),
-            (StringOrVec::Vec(k), StringOrVec::String(v)) => (k.join("::"), v.clone()),
-            (StringOrVec::String(k), StringOrVec::Vec(v)) => (k.clone(), v.join("::")),
-            (StringOrVec::String(k), StringOrVec::String(v)) => (k.clone(), v.clone()),
+/// Checks that the paths of emitted files stay inside the output directory, so they have to be
+/// relative and can't contain `..`.
+pub fn check_emitted_paths<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(), Diagnostic> {
+    for path in paths {
+        let inside = Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if path.is_empty() || !inside {
+            return Err(Diagnostic(format!(
+                "The emitted file \"{path}\" is outside of the output directory, only relative paths without \"..\" can be used."
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Writes all `files` relative to `out_dir` with [`write_atomic`]. Nothing is written if one of
+/// the paths leaves `out_dir`, see [`check_emitted_paths`].
+pub fn write_files(out_dir: &Path, files: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    check_emitted_paths(files.keys())?;
+    for (path, content) in files {
+        write_atomic(&out_dir.join(path), content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Maps the items in parallel if the `rayon` feature is enabled. The results keep the order of
+/// the items.
+pub fn map_ordered<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Reads all files below `dir`, keyed by their path relative to `dir`.
+///
+/// Returns an empty map if `dir` does not exist.
+pub fn read_files(dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for path in glob::glob(&dir.join("**/*").to_string_lossy())? {
+        let path = path?;
+        if path.is_file() {
+            let name = path
+                .strip_prefix(dir)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(name, std::fs::read_to_string(&path).with_path(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+/// Creates a unified diff between the expected and the actual content.
+pub fn text_diff(name: &str, expected: &str, actual: &str) -> String {
+    similar::TextDiff::from_lines(expected, actual)
+        .unified_diff()
+        .header(&format!("{name} (expected)"), &format!("{name} (actual)"))
+        .to_string()
+}
+
+/// Compares the files with the expected ones (both keyed by their path) and returns a diff for
+/// every file which differs. Missing files count as empty.
+pub fn diff_files(
+    expected: &BTreeMap<String, String>,
+    actual: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let paths = expected
+        .keys()
+        .chain(actual.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let expected = expected.get(path).map_or("", String::as_str);
+            let actual = actual.get(path).map_or("", String::as_str);
+            (expected != actual).then(|| text_diff(path, expected, actual))
         })
-        .collect();
-    for (_dt_name, dt) in &mut module.data_types {
-        for (_name, prop) in &mut dt.properties {
-            let name = prop.typ.to_string();
-            if let Some(v) = mappings.get(&name) {
-                prop.typ = Namespace::new(v);
+        .collect()
+}
+
+/// Prints `ok` and the name of a test case if there are no differences (diffs or errors),
+/// otherwise `FAIL` and the name followed by the differences. Returns whether the case passed.
+pub fn report_case(name: &str, differences: &[String]) -> bool {
+    if differences.is_empty() {
+        println!("ok   {name}");
+        return true;
+    }
+    println!("FAIL {name}");
+    for difference in differences {
+        println!("{difference}");
+    }
+    false
+}
+
+/// Expands the given paths into a sorted list of files.
+///
+/// Directories are searched recursively for files with the given extension and paths
+/// containing `*`, `?` or `[` are treated as glob patterns.
+pub fn collect_files(paths: &[PathBuf], extension: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if path.is_dir() {
+            let pattern = path.join(format!("**/*.{extension}"));
+            for file in glob::glob(&pattern.to_string_lossy())? {
+                files.push(file?);
             }
+        } else if pattern.contains(['*', '?', '[']) {
+            let matches = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                anyhow::bail!("No files match \"{pattern}\".");
+            }
+            files.extend(matches);
+        } else {
+            files.push(path.clone());
         }
     }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
 
-    for (_service_name, service) in &mut module.services {
-        for (_handler_name, h) in &mut service.functions {
-            if let Some(TypeName {
-                typ,
-                is_list,
-                count,
-                attributes,
-                comments,
-            }) = &h.return_type
-            {
-                let name = typ.to_string();
-                let mut comments = comments.clone();
-                if let Some(v) = mappings.get(&name) {
-                    h.return_type = Some(
-                        TypeName::new(Namespace::new(v), *is_list, *count, attributes.clone())
-                            .with_comments(&mut comments),
-                    );
-                }
-            }
-            for (_arg_name, arg) in &mut h.arguments {
-                let name = arg.typ.to_string();
-                if let Some(v) = mappings.get(&name) {
-                    arg.typ = Namespace::new(v);
+/// The format of a raw data file.
+#[cfg_attr(feature = "_bin", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RawFormat {
+    /// Picked by the extension of the file, otherwise the first format which can parse it
+    #[default]
+    Auto,
+    Json,
+    Yaml,
+    Toml,
+    Rsn,
+    #[cfg(feature = "ron")]
+    /// only available with feature "ron" enabled
+    Ron,
+    /// A header row and records, read as list of maps from the header to the (string) values
+    Csv,
+    /// Newline-delimited json, read as list of the values of the lines
+    Ndjson,
+}
+
+impl RawFormat {
+    fn from_extension(file: &Path) -> Option<Self> {
+        let extension = file.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "rsn" => Some(Self::Rsn),
+            #[cfg(feature = "ron")]
+            "ron" => Some(Self::Ron),
+            "csv" => Some(Self::Csv),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the line and column (both starting at 1) of a byte offset.
+pub(crate) fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+fn parse_raw_content(content: &str, format: RawFormat) -> anyhow::Result<serde_value::Value> {
+    Ok(match format {
+        RawFormat::Auto => {
+            let result = serde_json::from_str(content)
+                .ok()
+                .or_else(|| toml::from_str(content).ok())
+                .or_else(|| serde_yaml::from_str(content).ok())
+                .or_else(|| rsn::from_str(content).ok());
+            #[cfg(feature = "ron")]
+            let result = result.or_else(|| ron::from_str(content).ok());
+            result.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "The data can't be parsed in any of the raw formats. \
+                     Use --raw-format to see the error of the intended format."
+                )
+            })?
+        }
+        RawFormat::Json => serde_json::from_str(content)?,
+        RawFormat::Yaml => serde_yaml::from_str(content)?,
+        RawFormat::Toml => toml::from_str(content)?,
+        // rsn only reports the byte offset of errors
+        RawFormat::Rsn => rsn::from_str(content).map_err(|e| {
+            let (line, column) = line_column(content, e.location.start);
+            anyhow::anyhow!("{} at line {line} column {column}", e.kind)
+        })?,
+        #[cfg(feature = "ron")]
+        RawFormat::Ron => ron::from_str(content)?,
+        RawFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            let headers = reader.headers()?.clone();
+            let rows = reader
+                .records()
+                .map(|record| {
+                    let row = headers
+                        .iter()
+                        .zip(record?.iter())
+                        .map(|(header, value)| {
+                            (
+                                serde_value::Value::String(header.to_owned()),
+                                serde_value::Value::String(value.to_owned()),
+                            )
+                        })
+                        .collect();
+                    Ok(serde_value::Value::Map(row))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            serde_value::Value::Seq(rows)
+        }
+        RawFormat::Ndjson => {
+            let values = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(index, line)| {
+                    serde_json::from_str(line).map_err(|e| {
+                        // the position reported by serde_json is relative to the line
+                        let message = e.to_string();
+                        let location = format!(" at line 1 column {}", e.column());
+                        let message = message.strip_suffix(&location).unwrap_or(&message);
+                        anyhow::anyhow!("{message} at line {} column {}", index + 1, e.column())
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            serde_value::Value::Seq(values)
+        }
+    })
+}
+
+/// Reads a raw data file, trying all formats (see [`RawFormat::Auto`]).
+pub fn parse_raw_data(file: PathBuf) -> anyhow::Result<serde_value::Value> {
+    parse_raw_data_as(file, RawFormat::Auto)
+}
+
+/// Reads a raw data file in the given format.
+///
+/// Errors of the format (e.g. the position of a syntax error) are reported as they are, unless
+/// the format is [`RawFormat::Auto`] and the file has no known extension.
+pub fn parse_raw_data_as(file: PathBuf, format: RawFormat) -> anyhow::Result<serde_value::Value> {
+    let content = std::fs::read_to_string(&file).with_path(&file)?;
+    let format = match format {
+        RawFormat::Auto => RawFormat::from_extension(&file).unwrap_or(RawFormat::Auto),
+        format => format,
+    };
+    parse_raw_content(&content, format).map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))
+}
+
+/// How multiple raw data files get combined.
+#[cfg_attr(feature = "_bin", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RawMerge {
+    /// Every file is put under its name without the extension, e.g. `config` for `config.yaml`
+    #[default]
+    Key,
+    /// Maps are merged recursively, other values of later files replace the ones of earlier files
+    Deep,
+}
+
+fn merge_deep(into: &mut serde_value::Value, value: serde_value::Value) {
+    match (into, value) {
+        (serde_value::Value::Map(into), serde_value::Value::Map(map)) => {
+            for (key, value) in map {
+                match into.get_mut(&key) {
+                    Some(existing) => merge_deep(existing, value),
+                    None => {
+                        into.insert(key, value);
+                    }
                 }
             }
         }
-        for (_event_name, h) in &mut service.events {
-            for (_arg_name, arg) in &mut h.arguments {
-                let name = arg.typ.to_string();
-                if let Some(v) = mappings.get(&name) {
-                    arg.typ = Namespace::new(v);
+        (into, value) => *into = value,
+    }
+}
+
+/// Reads the raw data files (directories stand for the files directly inside them) and
+/// combines them into one value.
+pub fn merge_raw_data(
+    paths: &[PathBuf],
+    format: RawFormat,
+    merge: RawMerge,
+) -> anyhow::Result<serde_value::Value> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|entry| entry.is_file());
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut result = serde_value::Value::Map(BTreeMap::new());
+    for file in files {
+        let value = parse_raw_data_as(file.clone(), format)?;
+        match merge {
+            RawMerge::Key => {
+                let key = file
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                let serde_value::Value::Map(map) = &mut result else {
+                    unreachable!("the result of key merging is a map");
+                };
+                if map
+                    .insert(serde_value::Value::String(key.clone()), value)
+                    .is_some()
+                {
+                    anyhow::bail!("There are multiple raw data files named \"{key}\".");
                 }
             }
+            RawMerge::Deep => merge_deep(&mut result, value),
         }
     }
+    Ok(result)
+}
 
-    Ok(module)
+/// A typemap entry containing placeholders (`{T}`) or wildcards (`*`).
+///
+/// e.g.: `"list of {T}" = "std::vector<{T}>"` or `"my::prefix::*" = "gen::*"`.
+struct TypePattern {
+    source: String,
+    pattern: Vec<PatternPart>,
+    replacement: Vec<PatternPart>,
+    specificity: usize,
 }
 
-pub fn update_types_from_file(
-    mut module: SsdModule,
-    no_map: bool,
-    typemap: Option<PathBuf>,
-    script: Option<&PathBuf>,
-) -> anyhow::Result<SsdModule> {
-    if let (false, Some(map_file)) = (
-        no_map,
-        typemap.or_else(|| {
-            script.and_then(|script| {
-                let mut typemap = script.clone();
-                typemap.set_extension("tym");
-                typemap.exists().then_some(typemap)
-            })
-        }),
-    ) {
-        let mappings: HashMap<StringOrVec, StringOrVec> =
-            toml::from_str(&std::fs::read_to_string(map_file)?)?;
-        let mappings: HashMap<String, String> = mappings
-            .iter()
-            .map(|(k, v)| match (k, v) {
-                (StringOrVec::Vec(k), StringOrVec::Vec(v)) => (k.join("::"), v.join("::")),
-                (StringOrVec::Vec(k), StringOrVec::String(v)) => (k.join("::"), v.clone()),
-                (StringOrVec::String(k), StringOrVec::Vec(v)) => (k.clone(), v.join("::")),
-                (StringOrVec::String(k), StringOrVec::String(v)) => (k.clone(), v.clone()),
-            })
-            .collect();
-        for (_dt_name, dt) in &mut module.data_types {
-            for (_name, prop) in &mut dt.properties {
-                let name = prop.typ.to_string();
-                if let Some(v) = mappings.get(&name) {
-                    prop.typ = Namespace::new(v);
+enum PatternPart {
+    Literal(String),
+    Capture(String),
+}
+
+/// Splits a pattern into literals and captures. Wildcards get numbered in order of appearance.
+fn split_pattern(pattern: &str) -> anyhow::Result<Vec<PatternPart>> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut wildcards = 0;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let capture = match c {
+            '*' => {
+                wildcards += 1;
+                format!("w{wildcards}")
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                if !closed
+                    || name.is_empty()
+                    || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                {
+                    anyhow::bail!(
+                        "Invalid placeholder \"{{{name}}}\" in typemap entry \"{pattern}\"."
+                    );
                 }
+                format!("p_{name}")
             }
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            parts.push(PatternPart::Literal(std::mem::take(&mut literal)));
         }
+        parts.push(PatternPart::Capture(capture));
+    }
+    if !literal.is_empty() {
+        parts.push(PatternPart::Literal(literal));
+    }
+    Ok(parts)
+}
 
-        for (_service_name, service) in &mut module.services {
-            for (_handler_name, h) in &mut service.functions {
-                if let Some(TypeName {
-                    typ,
-                    is_list,
-                    count,
-                    attributes,
-                    comments,
-                }) = &h.return_type
+/// Returns whether the brackets (`<>`, `[]` and `()`) of the type are balanced.
+fn is_balanced(typ: &str) -> bool {
+    let mut depth = 0usize;
+    for c in typ.chars() {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' | ']' | ')' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => (),
+        }
+    }
+    depth == 0
+}
+
+/// Matches the type against the parts of a pattern. Captures take the shortest non-empty part of
+/// the type with balanced brackets, so `{T}` in `"result of {T}, {E}"` captures all of
+/// `std::map<K, V>`. The count placeholder `{N}` only captures digits.
+fn match_parts<'a>(
+    parts: &'a [PatternPart],
+    typ: &'a str,
+    captures: &mut Vec<(&'a str, &'a str)>,
+) -> bool {
+    match parts.split_first() {
+        None => typ.is_empty(),
+        Some((PatternPart::Literal(literal), rest)) => typ
+            .strip_prefix(literal.as_str())
+            .is_some_and(|typ| match_parts(rest, typ, captures)),
+        Some((PatternPart::Capture(name), rest)) => {
+            let ends = typ
+                .char_indices()
+                .skip(1)
+                .map(|(i, _)| i)
+                .chain([typ.len()]);
+            for end in ends.filter(|&end| end > 0) {
+                if !is_balanced(&typ[..end])
+                    || (name == "p_N" && !typ[..end].bytes().all(|b| b.is_ascii_digit()))
                 {
-                    let name = typ.to_string();
-                    let mut comments = comments.clone();
-                    if let Some(v) = mappings.get(&name) {
-                        h.return_type = Some(
-                            TypeName::new(Namespace::new(v), *is_list, *count, attributes.clone())
-                                .with_comments(&mut comments),
-                        );
-                    }
+                    continue;
                 }
-                for (_arg_name, arg) in &mut h.arguments {
-                    let name = arg.typ.to_string();
-                    if let Some(v) = mappings.get(&name) {
-                        arg.typ = Namespace::new(v);
-                    }
+                captures.push((name, &typ[..end]));
+                if match_parts(rest, &typ[end..], captures) {
+                    return true;
                 }
+                captures.pop();
             }
-            for (_event_name, h) in &mut service.events {
-                for (_arg_name, arg) in &mut h.arguments {
-                    let name = arg.typ.to_string();
-                    if let Some(v) = mappings.get(&name) {
-                        arg.typ = Namespace::new(v);
+            false
+        }
+    }
+}
+
+impl TypePattern {
+    fn new(pattern: &str, replacement: &str) -> anyhow::Result<Self> {
+        let parts = split_pattern(pattern)?;
+        let mut names = Vec::new();
+        let mut specificity = 0;
+        for part in &parts {
+            match part {
+                PatternPart::Literal(literal) => specificity += literal.len(),
+                PatternPart::Capture(name) => {
+                    if names.contains(&name) {
+                        anyhow::bail!("\"{pattern}\" uses a placeholder more than once.");
                     }
+                    names.push(name);
+                }
+            }
+        }
+
+        let replacement_parts = split_pattern(replacement)?;
+        for part in &replacement_parts {
+            if let PatternPart::Capture(name) = part {
+                if !names.contains(&name) {
+                    anyhow::bail!(
+                        "\"{replacement}\" uses a placeholder which does not appear in \"{pattern}\"."
+                    );
                 }
             }
         }
+
+        Ok(Self {
+            source: pattern.to_owned(),
+            pattern: parts,
+            replacement: replacement_parts,
+            specificity,
+        })
+    }
+
+    fn apply(&self, typ: &str) -> Option<String> {
+        let mut captures = Vec::new();
+        if !match_parts(&self.pattern, typ, &mut captures) {
+            return None;
+        }
+        Some(
+            self.replacement
+                .iter()
+                .map(|part| match part {
+                    PatternPart::Literal(literal) => literal.as_str(),
+                    PatternPart::Capture(name) => captures
+                        .iter()
+                        .find(|(captured, _)| captured == name)
+                        .map_or("", |(_, value)| value),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Maps type names of the model to type names of the target language.
+#[derive(Default)]
+pub struct TypeMap {
+    exact: HashMap<String, String>,
+    /// Sorted by specificity, the most specific pattern comes first.
+    patterns: Vec<TypePattern>,
+}
+
+impl TypeMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a mapping. Entries containing `*` or `{placeholder}` are treated as patterns.
+    pub fn insert(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        if from.contains('*') || from.contains('{') {
+            self.insert_pattern(TypePattern::new(from, to)?);
+        } else {
+            self.exact.insert(from.to_owned(), to.to_owned());
+        }
+        Ok(())
     }
 
+    fn insert_pattern(&mut self, pattern: TypePattern) {
+        let index = self
+            .patterns
+            .partition_point(|p| p.specificity >= pattern.specificity);
+        self.patterns.insert(index, pattern);
+    }
+
+    /// Adds all mappings of `other`, replacing existing entries for the same types.
+    pub fn extend(&mut self, other: TypeMap) {
+        self.exact.extend(other.exact);
+        for pattern in other.patterns {
+            self.patterns.retain(|p| p.source != pattern.source);
+            self.insert_pattern(pattern);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Returns the mapped type, if there is a mapping for `typ`.
+    ///
+    /// Exact mappings take precedence over patterns.
+    pub fn get(&self, typ: &str) -> Option<String> {
+        self.lookup(typ).map(|(_, mapped)| mapped)
+    }
+
+    /// Like [`TypeMap::get`], but also returns the entry which was used.
+    fn lookup(&self, typ: &str) -> Option<(&str, String)> {
+        if let Some((key, mapped)) = self.exact.get_key_value(typ) {
+            return Some((key, mapped.clone()));
+        }
+        self.patterns
+            .iter()
+            .find_map(|p| p.apply(typ).map(|mapped| (p.source.as_str(), mapped)))
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &str> {
+        self.exact
+            .keys()
+            .map(String::as_str)
+            .chain(self.patterns.iter().map(|p| p.source.as_str()))
+    }
+}
+
+/// Collects which mappings of a typemap were used while applying it to a module.
+#[derive(Default)]
+struct TypemapReport {
+    applied: BTreeMap<String, String>,
+    unmapped: BTreeSet<String>,
+    used: HashSet<String>,
+}
+
+impl TypemapReport {
+    fn record(&mut self, typ: &str, lookup: Option<(&str, String)>) {
+        if let Some((entry, mapped)) = lookup {
+            self.used.insert(entry.to_owned());
+            self.applied.insert(typ.to_owned(), mapped);
+        } else {
+            self.unmapped.insert(typ.to_owned());
+        }
+    }
+
+    /// Formats the report. Types defined in `module` are not reported as unmapped.
+    fn format(&self, mappings: &TypeMap, module: &SsdModule) -> String {
+        let local = module
+            .data_types
+            .iter()
+            .map(|(name, _)| name)
+            .chain(module.enums.iter().map(|(name, _)| name))
+            .collect::<HashSet<_>>();
+        let unused = mappings
+            .entries()
+            .filter(|entry| !self.used.contains(*entry))
+            .collect::<BTreeSet<_>>();
+
+        let mut result = String::from("applied mappings:\n");
+        for (typ, mapped) in &self.applied {
+            result.push_str(&format!("    {typ} -> {mapped}\n"));
+        }
+        result.push_str("types without mapping:\n");
+        for typ in self.unmapped.iter().filter(|typ| !local.contains(typ)) {
+            result.push_str(&format!("    {typ}\n"));
+        }
+        result.push_str("unused mappings:\n");
+        for entry in unused {
+            result.push_str(&format!("    {entry}\n"));
+        }
+        result
+    }
+}
+
+/// Patterns of the same specificity are tried in the order of their source, which is why the
+/// mappings are sorted.
+fn flatten_typemap(mappings: &BTreeMap<StringOrVec, StringOrVec>) -> anyhow::Result<TypeMap> {
+    let join = |s: &StringOrVec| match s {
+        StringOrVec::Vec(v) => v.join("::"),
+        StringOrVec::String(s) => s.clone(),
+    };
+    let mut typemap = TypeMap::new();
+    for (k, v) in mappings {
+        typemap.insert(&join(k), &join(v))?;
+    }
+    Ok(typemap)
+}
+
+/// Parses the content of a typemap file.
+pub fn parse_typemap(typemap: &str) -> anyhow::Result<TypeMap> {
+    flatten_typemap(&toml::from_str(typemap)?)
+}
+
+const BUILTIN_PREFIX: &str = "builtin:";
+
+/// Returns the content of one of the typemaps shipped with ssd.
+fn builtin_typemap(name: &str) -> anyhow::Result<&'static str> {
+    match name {
+        "rust" | "rs" => Ok(include_str!("typemaps/rust.tym")),
+        "cpp" | "c++" => Ok(include_str!("typemaps/cpp.tym")),
+        "typescript" | "ts" => Ok(include_str!("typemaps/typescript.tym")),
+        _ => anyhow::bail!(
+            "Unknown builtin typemap \"{name}\". Available are: rust, cpp, typescript."
+        ),
+    }
+}
+
+/// Reads a typemap file. The format is chosen by the extension of the file:
+/// `json`, `yaml`/`yml` or toml for everything else (e.g. `tym`).
+///
+/// Paths of the form `builtin:<name>` refer to the builtin typemaps.
+pub fn read_typemap(path: &Path) -> anyhow::Result<TypeMap> {
+    if let Some(name) = path.to_str().and_then(|p| p.strip_prefix(BUILTIN_PREFIX)) {
+        return flatten_typemap(&toml::from_str(builtin_typemap(name)?)?);
+    }
+    let content = std::fs::read_to_string(path).with_path(path)?;
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let mappings = match extension.as_deref() {
+        Some("json") => serde_json::from_str(&content)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    flatten_typemap(&mappings)
+}
+
+/// Returns the typemap files that should be used.
+///
+/// If no typemap is given explicitly, a file with the same name as the script but with
+/// the extension `tym` is used, if it exists.
+pub fn typemap_paths(
+    no_map: bool,
+    typemaps: Vec<PathBuf>,
+    script: Option<&PathBuf>,
+) -> Vec<PathBuf> {
+    if no_map {
+        return Vec::new();
+    }
+    if !typemaps.is_empty() {
+        return typemaps;
+    }
+    script
+        .map(|script| script.with_extension("tym"))
+        .filter(|typemap| typemap.exists())
+        .into_iter()
+        .collect()
+}
+
+/// Loads the typemaps that should be used. See [`typemap_paths`].
+///
+/// Later typemaps override the mappings of earlier ones.
+pub fn load_typemap(
+    no_map: bool,
+    typemaps: Vec<PathBuf>,
+    script: Option<&PathBuf>,
+) -> anyhow::Result<TypeMap> {
+    let mut typemap = TypeMap::new();
+    for path in typemap_paths(no_map, typemaps, script) {
+        typemap.extend(read_typemap(&path)?);
+    }
+    Ok(typemap)
+}
+
+/// Maps a type. For lists, the element type gets mapped first and then the lists are looked up
+/// from the innermost one as `list of <element>` (or `<count> of <element>`), so containers can
+/// be mapped with patterns like `"list of {T}" = "std::vector<{T}>"`. Mapping stops at the first
+/// list without a mapping.
+fn map_type_name(type_name: &mut TypeName, mappings: &TypeMap, report: &mut TypemapReport) {
+    let element = type_name.typ.to_string();
+    let lookup = mappings.lookup(&element);
+    let mut mapped = lookup.as_ref().map(|(_, mapped)| mapped.clone());
+    report.record(&element, lookup);
+    let mut element = mapped.clone().unwrap_or(element);
+    while let Some(&list) = type_name.lists.last() {
+        let list = match list {
+            Some(count) => format!("{count} of {element}"),
+            None => format!("list of {element}"),
+        };
+        let Some((entry, list_type)) = mappings.lookup(&list) else {
+            break;
+        };
+        report.record(&list, Some((entry, list_type.clone())));
+        type_name.lists.pop();
+        element.clone_from(&list_type);
+        mapped = Some(list_type);
+    }
+    if mapped.is_some() {
+        type_name.typ = Namespace::new(&element);
+    }
+}
+
+struct TypeMapper<'a> {
+    mappings: &'a TypeMap,
+    report: &'a mut TypemapReport,
+}
+
+impl VisitorMut for TypeMapper<'_> {
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        map_type_name(type_name, self.mappings, self.report);
+    }
+
+    /// After mapping the ok and error type of `-> result of T, E`, the pair is looked up as
+    /// `result of <T>, <E>` (if neither is a list without mapping), e.g. for
+    /// `"result of {T}, {E}" = "Result<{T}, {E}>"`. With a mapping the function only has the
+    /// mapped return type left.
+    fn visit_function_mut(&mut self, name: &str, function: &mut Function) {
+        walk_function_mut(self, name, function);
+        let (Some(ok), Some(error)) = (&mut function.return_type, &function.error_type) else {
+            return;
+        };
+        if !ok.lists.is_empty() || !error.lists.is_empty() {
+            return;
+        }
+        let result = format!("result of {}, {}", ok.typ, error.typ);
+        let Some((entry, mapped)) = self.mappings.lookup(&result) else {
+            self.report.record(&result, None);
+            return;
+        };
+        self.report.record(&result, Some((entry, mapped.clone())));
+        ok.typ = Namespace::new(&mapped);
+        function.error_type = None;
+    }
+}
+
+fn apply_typemap(
+    mut module: SsdModule,
+    mappings: &TypeMap,
+    report: &mut TypemapReport,
+) -> SsdModule {
+    TypeMapper { mappings, report }.visit_module_mut(&mut module);
+    module
+}
+
+/// Applies the typemap (its content in toml) to the types of the module.
+pub fn update_types(module: SsdModule, typemap: &str) -> anyhow::Result<SsdModule> {
+    let mut report = TypemapReport::default();
+    Ok(apply_typemap(module, &parse_typemap(typemap)?, &mut report))
+}
+
+/// Parses the description and applies the typemap (its content), like the command line does
+/// before running a generator.
+#[cfg(feature = "web")]
+pub fn load_web_model(
+    defines: HashMap<String, String>,
+    namespace: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<ssd_data::SsdModel, Box<dyn std::error::Error>> {
+    let module = crate::parser::parse_limited(
+        data,
+        Namespace::new(namespace),
+        &crate::parser::ParseLimits::default(),
+    )?;
+    let module = update_types(module, typemap)?;
+    let meta = ssd_data::Meta {
+        tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+        namespace: namespace.to_owned(),
+        ..Default::default()
+    };
+    Ok(ssd_data::SsdModel {
+        module,
+        defines: defines
+            .into_iter()
+            .map(|(key, value)| (key, serde_value::Value::String(value)))
+            .collect(),
+        meta,
+        ast: None,
+    })
+}
+
+pub fn update_types_from_file(
+    module: SsdModule,
+    no_map: bool,
+    typemaps: Vec<PathBuf>,
+    script: Option<&PathBuf>,
+    report: bool,
+) -> anyhow::Result<SsdModule> {
+    let mappings = load_typemap(no_map, typemaps, script)?;
+    if mappings.is_empty() {
+        if report {
+            eprintln!("No typemap was used.");
+        }
+        return Ok(module);
+    }
+    let mut typemap_report = TypemapReport::default();
+    let module = apply_typemap(module, &mappings, &mut typemap_report);
+    if report {
+        eprint!("{}", typemap_report.format(&mappings, &module));
+    }
     Ok(module)
 }
+
+/// Reads a file containing defines. The format is picked by the extension:
+/// `json`, `yaml`/`yml` or toml for everything else.
+pub fn read_define_file(path: &Path) -> anyhow::Result<Defines> {
+    let content = std::fs::read_to_string(path).with_path(path)?;
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let defines = match extension.as_deref() {
+        Some("json") => serde_json::from_str(&content)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    Ok(defines)
+}
+
+/// Resolves the value of a `-D` define: `env:NAME` is replaced by the environment variable and
+/// `file:path` by the content of the file (without trailing line break), other values are kept.
+pub fn resolve_define(name: &str, value: String) -> anyhow::Result<String> {
+    if let Some(var) = value.strip_prefix("env:") {
+        std::env::var(var)
+            .map_err(|e| anyhow::anyhow!("Could not resolve define {name} from ${var}: {e}"))
+    } else if let Some(path) = value.strip_prefix("file:") {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Could not resolve define {name} from {path}: {e}"))?;
+        let content = content.strip_suffix('\n').unwrap_or(&content);
+        Ok(content.strip_suffix('\r').unwrap_or(content).to_owned())
+    } else {
+        Ok(value)
+    }
+}
+
+/// Returns the files read by `-D NAME=file:path` defines.
+pub fn define_value_files(defines: &[(String, String)]) -> Vec<PathBuf> {
+    defines
+        .iter()
+        .filter_map(|(_, value)| value.strip_prefix("file:").map(PathBuf::from))
+        .collect()
+}
+
+/// Combines the define files with the `-D` defines, whose values are resolved with
+/// [`resolve_define`].
+///
+/// Later files override earlier ones and `-D` defines override all files.
+pub fn load_defines(files: &[PathBuf], defines: Vec<(String, String)>) -> anyhow::Result<Defines> {
+    let mut result = Defines::new();
+    for file in files {
+        let defines = read_define_file(file)
+            .map_err(|e| anyhow::anyhow!("Could not read defines from {}: {e}", file.display()))?;
+        result.extend(defines);
+    }
+    for (name, value) in defines {
+        let value = resolve_define(&name, value)?;
+        result.insert(name, serde_value::Value::String(value));
+    }
+    Ok(result)
+}
+
+/// The error of functions which can't be used with `--reproducible`.
+pub fn not_reproducible(function: &str) -> String {
+    format!("{function} can't be used with --reproducible, its result changes between runs")
+}
+
+/// Formats the time in UTC as RFC 3339 with second precision, e.g. `2024-03-01T12:30:00Z`.
+pub fn utc_timestamp(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // the civil date of the day since 1970-01-01, counted in eras of 400 years from 0000-03-01
+    let days = days + 719_468;
+    let (era, day_of_era) = (days / 146_097, days % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[test]
+fn test_utc_timestamp() {
+    use std::time::{Duration, UNIX_EPOCH};
+    let at = |secs| utc_timestamp(UNIX_EPOCH + Duration::from_secs(secs));
+    assert_eq!(at(0), "1970-01-01T00:00:00Z");
+    assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+    assert_eq!(at(1_709_296_200), "2024-03-01T12:30:00Z");
+    assert_eq!(at(1_735_689_599), "2024-12-31T23:59:59Z");
+}
+
+#[test]
+fn test_typemap_patterns() {
+    let typemap = parse_typemap(
+        r#"
+        i32 = "int32_t"
+        "list of {T}" = "std::vector<{T}>"
+        "{N} of {T}" = "std::array<{T}, {N}>"
+        "my::prefix::*" = "gen::*"
+        "my::*" = "other::*"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(typemap.get("i32").unwrap(), "int32_t");
+    assert_eq!(
+        typemap.get("list of int32_t").unwrap(),
+        "std::vector<int32_t>"
+    );
+    assert_eq!(typemap.get("5 of u8").unwrap(), "std::array<u8, 5>");
+    assert_eq!(typemap.get("my::prefix::Type").unwrap(), "gen::Type");
+    assert_eq!(typemap.get("my::Type").unwrap(), "other::Type");
+    assert!(typemap.get("u8").is_none());
+    assert!(parse_typemap(r#""list of {T" = "x""#).is_err());
+    assert!(parse_typemap(r#""list of {T}" = "{U}""#).is_err());
+
+    // nested lists get mapped from the inside
+    let mut type_name = TypeName::from("list of 4 of i32");
+    map_type_name(&mut type_name, &typemap, &mut TypemapReport::default());
+    assert_eq!(
+        type_name.typ.to_string(),
+        "std::vector<std::array<int32_t, 4>>"
+    );
+    assert!(type_name.lists.is_empty());
+}
+
+#[test]
+fn test_typemap_result_types() {
+    let module = crate::parser::parse(
+        "service S { fn f() -> result of 4 of f32, Error; fn g() -> result of u8, list of Error; };",
+        Namespace::new("test"),
+    )
+    .unwrap();
+    let mapped = update_types(module.clone(), include_str!("typemaps/cpp.tym")).unwrap();
+    let functions = &mapped.services[0].functions;
+    assert_eq!(
+        functions[0].return_type.as_ref().unwrap().typ.to_string(),
+        "std::expected<std::array<float, 4>, Error>"
+    );
+    assert!(functions[0].error_type.is_none());
+    assert_eq!(
+        functions[1].return_type.as_ref().unwrap().typ.to_string(),
+        "std::expected<std::uint8_t, std::vector<Error>>"
+    );
+
+    // without a mapping for the pair, both types are mapped on their own
+    let mapped = update_types(module, r#"f32 = "float""#).unwrap();
+    let f = &mapped.services[0].functions[0];
+    assert_eq!(f.return_type.as_ref().unwrap().typ.to_string(), "float");
+    assert_eq!(f.error_type.as_ref().unwrap().typ.to_string(), "Error");
+
+    // the count pattern must not swallow results
+    let module = crate::parser::parse(
+        "service S { fn f() -> result of User, ApiError; };",
+        Namespace::new("test"),
+    )
+    .unwrap();
+    let mapped = update_types(module, include_str!("typemaps/typescript.tym")).unwrap();
+    let f = &mapped.services[0].functions[0];
+    assert_eq!(f.return_type.as_ref().unwrap().typ.to_string(), "User");
+    assert_eq!(f.error_type.as_ref().unwrap().typ.to_string(), "ApiError");
+}
+
+#[test]
+fn test_typemap_formats() {
+    let dir = std::env::temp_dir().join(format!("ssd-typemap-formats-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = [
+        (
+            "map.tym",
+            "i32 = \"int\"\n\"list of {T}\" = [\"std\", \"vector<{T}>\"]\n",
+        ),
+        (
+            "map.json",
+            r#"{"i32": "int", "list of {T}": ["std", "vector<{T}>"]}"#,
+        ),
+        (
+            "map.YAML",
+            "i32: int\n\"list of {T}\": [std, \"vector<{T}>\"]\n",
+        ),
+    ];
+    for (name, content) in files {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        let typemap = read_typemap(&path).unwrap();
+        assert_eq!(typemap.get("i32").unwrap(), "int", "{name}");
+        assert_eq!(
+            typemap.get("list of int").unwrap(),
+            "std::vector<int>",
+            "{name}"
+        );
+    }
+    // the extension decides the format
+    std::fs::write(dir.join("wrong.json"), files[0].1).unwrap();
+    assert!(read_typemap(&dir.join("wrong.json")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_builtin_typemaps() {
+    for name in ["rust", "rs", "cpp", "c++", "typescript", "ts"] {
+        let typemap = read_typemap(Path::new(&format!("builtin:{name}"))).unwrap();
+        assert!(typemap.get("bool").is_some(), "{name}");
+    }
+    let rust = read_typemap(Path::new("builtin:rust")).unwrap();
+    assert_eq!(rust.get("string").unwrap(), "String");
+    assert_eq!(rust.get("4 of u8").unwrap(), "[u8; 4]");
+    let cpp = read_typemap(Path::new("builtin:c++")).unwrap();
+    assert_eq!(cpp.get("u8").unwrap(), "std::uint8_t");
+    let ts = read_typemap(Path::new("builtin:ts")).unwrap();
+    assert_eq!(ts.get("list of string").unwrap(), "string[]");
+    let Err(error) = read_typemap(Path::new("builtin:go")) else {
+        panic!("builtin:go should not exist");
+    };
+    assert!(error.to_string().contains("Unknown builtin typemap \"go\""));
+}
+
+#[test]
+fn test_typemap_report() {
+    let module = crate::parser::parse(
+        "data Order { id: u64, items: list of Item, state: State, }; data Item { price: f32, };",
+        Namespace::new("orders"),
+    )
+    .unwrap();
+    let mappings = parse_typemap(
+        r#"
+        u64 = "uint64_t"
+        "list of {T}" = "std::vector<{T}>"
+        bool = "bool"
+        "#,
+    )
+    .unwrap();
+    let mut report = TypemapReport::default();
+    let module = apply_typemap(module, &mappings, &mut report);
+    // Item is defined in the module, State is missing
+    assert_eq!(
+        report.format(&mappings, &module),
+        "applied mappings:\n    list of Item -> std::vector<Item>\n    u64 -> uint64_t\n\
+         types without mapping:\n    State\n    f32\n\
+         unused mappings:\n    bool\n"
+    );
+}
+
+#[test]
+fn test_layered_typemaps() {
+    let dir = std::env::temp_dir().join(format!("ssd-layered-typemaps-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let base = dir.join("base.tym");
+    let overrides = dir.join("overrides.tym");
+    let script = dir.join("gen.rhai");
+    std::fs::write(
+        &base,
+        "u8 = \"uint8_t\"\nstring = \"std::string\"\n\"list of {T}\" = \"std::vector<{T}>\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &overrides,
+        "string = \"QString\"\n\"list of {T}\" = \"QList<{T}>\"\n",
+    )
+    .unwrap();
+
+    let typemap = load_typemap(false, vec![base.clone(), overrides.clone()], None).unwrap();
+    assert_eq!(typemap.get("u8").unwrap(), "uint8_t");
+    assert_eq!(typemap.get("string").unwrap(), "QString");
+    assert_eq!(typemap.get("list of u8").unwrap(), "QList<u8>");
+    let typemap = load_typemap(false, vec![overrides.clone(), base.clone()], None).unwrap();
+    assert_eq!(typemap.get("string").unwrap(), "std::string");
+    assert_eq!(typemap.get("list of u8").unwrap(), "std::vector<u8>");
+
+    // the typemap next to the script is only used without explicit ones
+    assert!(typemap_paths(false, Vec::new(), Some(&script)).is_empty());
+    std::fs::copy(&base, script.with_extension("tym")).unwrap();
+    assert_eq!(
+        typemap_paths(false, Vec::new(), Some(&script)),
+        [script.with_extension("tym")]
+    );
+    assert_eq!(
+        typemap_paths(false, vec![overrides.clone()], Some(&script)),
+        std::slice::from_ref(&overrides)
+    );
+    assert!(typemap_paths(true, vec![overrides], Some(&script)).is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(all(feature = "_bin", feature = "msgpack", feature = "cbor"))]
+#[test]
+fn test_serialize_binary() {
+    let module = crate::parser::parse("data Rect { x: i32, };", Namespace::new("shapes")).unwrap();
+
+    let msgpack = serialize(DataFormat::Msgpack, &module).unwrap();
+    let decoded: SsdModule = rmp_serde::from_slice(&msgpack).unwrap();
+    assert_eq!(decoded, module);
+
+    let cbor = serialize(DataFormat::Cbor, &module).unwrap();
+    let decoded: SsdModule = ciborium::from_reader(cbor.as_slice()).unwrap();
+    assert_eq!(decoded, module);
+
+    assert!(DataFormat::Cbor.is_binary());
+    assert!(!DataFormat::Json.is_binary());
+}
+
+#[test]
+fn test_ordered_map_format() {
+    let content = "data Rect { y: i32, x: i32, }; data Point { x: i32, };";
+    let module = crate::parser::parse(content, Namespace::new("shapes")).unwrap();
+    let rect = &module.data_types["Rect"];
+    assert_eq!(rect.properties.get_index_of("x"), Some(1));
+
+    // named elements stay lists of pairs in the order of the file
+    let json = serde_json::to_value(&module).unwrap();
+    assert_eq!(json["data_types"][0][0], "Rect");
+    assert_eq!(json["data_types"][1][0], "Point");
+    let properties = &json["data_types"][0][1]["properties"];
+    assert_eq!(properties[0][0], "y");
+    assert_eq!(properties[1][0], "x");
+    let decoded: SsdModule = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(decoded, module);
+
+    let sorted = json_sort_data_types(&json["data_types"]).unwrap();
+    assert_eq!(sorted[0][0], "Rect");
+    assert_eq!(sorted[0][1], json["data_types"][0][1]);
+
+    let mut reordered = module.clone();
+    reordered.data_types.reverse();
+    assert_ne!(reordered, module);
+}
+
+#[test]
+fn test_raw_format() {
+    assert_eq!(
+        parse_raw_content("[1, 2]", RawFormat::Auto).unwrap(),
+        serde_value::Value::Seq(vec![serde_value::Value::U64(1), serde_value::Value::U64(2)])
+    );
+    let error = parse_raw_content("{\n  \"a\": 1,\n}", RawFormat::Json).unwrap_err();
+    assert_eq!(error.to_string(), "trailing comma at line 3 column 1");
+    let error = parse_raw_content("{\n  a: 1,\n  b: ]", RawFormat::Rsn).unwrap_err();
+    assert!(error.to_string().ends_with("at line 3 column 6"), "{error}");
+    assert!(parse_raw_content("{", RawFormat::Auto).is_err());
+    let rows = parse_raw_content("name,age\na,1\nb,2\n", RawFormat::Csv).unwrap();
+    let rows = serde_json::to_value(rows).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([{"name": "a", "age": "1"}, {"name": "b", "age": "2"}])
+    );
+    let lines = parse_raw_content("{\"a\": 1}\n\n[2]\n", RawFormat::Ndjson).unwrap();
+    let lines = serde_json::to_value(lines).unwrap();
+    assert_eq!(lines, serde_json::json!([{"a": 1}, [2]]));
+    let error = parse_raw_content("{}\n{\"a\" 1}", RawFormat::Ndjson).unwrap_err();
+    assert_eq!(error.to_string(), "expected `:` at line 2 column 6");
+    assert_eq!(
+        RawFormat::from_extension(Path::new("data.YML")),
+        Some(RawFormat::Yaml)
+    );
+}
+
+#[test]
+fn test_merge_raw_data() {
+    let dir = std::env::temp_dir().join(format!("ssd-merge-test-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("more")).unwrap();
+    std::fs::write(dir.join("config.yaml"), "server:\n  port: 80\n  host: a\n").unwrap();
+    std::fs::write(
+        dir.join("more/inventory.json"),
+        r#"{"server": {"port": 8080}}"#,
+    )
+    .unwrap();
+    let paths = [dir.join("config.yaml"), dir.join("more")];
+
+    let merged = merge_raw_data(&paths, RawFormat::Auto, RawMerge::Key).unwrap();
+    let merged = serde_json::to_value(merged).unwrap();
+    assert_eq!(merged["config"]["server"]["host"], "a");
+    assert_eq!(merged["inventory"]["server"]["port"], 8080);
+
+    let merged = merge_raw_data(&paths, RawFormat::Auto, RawMerge::Deep).unwrap();
+    let merged = serde_json::to_value(merged).unwrap();
+    assert_eq!(merged["server"]["host"], "a");
+    assert_eq!(merged["server"]["port"], 8080);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_defines() {
+    let dir = std::env::temp_dir().join(format!("ssd-defines-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("defines.toml"), "TOKEN = \"a\"\nVERSION = 1\n").unwrap();
+    std::fs::write(dir.join("token.txt"), "secret\n").unwrap();
+    std::env::set_var("SSD_TEST_DEFINE_BUILD", "42");
+
+    let token = format!("file:{}", dir.join("token.txt").display());
+    let defines = vec![
+        ("TOKEN".to_owned(), token.clone()),
+        ("BUILD".to_owned(), "env:SSD_TEST_DEFINE_BUILD".to_owned()),
+        ("PLAIN".to_owned(), "value:env".to_owned()),
+    ];
+    assert_eq!(define_value_files(&defines), [dir.join("token.txt")]);
+    let loaded = load_defines(&[dir.join("defines.toml")], defines).unwrap();
+    let loaded = serde_json::to_value(loaded).unwrap();
+    assert_eq!(
+        loaded,
+        serde_json::json!({"BUILD": "42", "PLAIN": "value:env", "TOKEN": "secret", "VERSION": 1})
+    );
+
+    let error = resolve_define("X", "env:SSD_TEST_DEFINE_MISSING".to_owned()).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Could not resolve define X from $SSD_TEST_DEFINE_MISSING"));
+    assert!(resolve_define("X", format!("file:{}", dir.join("missing").display())).is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_atomic() {
+    let dir = std::env::temp_dir().join(format!("ssd-write-test-{}", std::process::id()));
+    let path = dir.join("nested/out.rs");
+    write_atomic(&path, b"first").unwrap();
+    write_atomic(&path, b"second").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+    let names = std::fs::read_dir(dir.join("nested"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["out.rs"]);
+
+    // e.g. a directory which is in the way
+    assert!(write_atomic(&dir.join("nested"), b"").is_err());
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_files_outside_out_dir() {
+    let dir = std::env::temp_dir().join(format!("ssd-write-files-{}", std::process::id()));
+    let files = |path: &str| BTreeMap::from([(path.to_owned(), "content".to_owned())]);
+    for path in [
+        "../escaped.txt",
+        "nested/../../escaped.txt",
+        "/tmp/absolute.txt",
+        "",
+    ] {
+        let error = write_files(&dir, &files(path)).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("outside of the output directory"));
+    }
+    assert!(!dir.exists());
+    assert!(!dir.parent().unwrap().join("escaped.txt").exists());
+
+    write_files(&dir, &files("./nested/file.txt")).unwrap();
+    assert!(dir.join("nested/file.txt").is_file());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_report_case() {
+    let expected = BTreeMap::from([("a.txt".to_owned(), "a\n".to_owned())]);
+    let mut actual = expected.clone();
+    assert!(report_case("same", &diff_files(&expected, &actual)));
+    actual.insert("b.txt".to_owned(), "b\n".to_owned());
+    let diffs = diff_files(&expected, &actual);
+    assert_eq!(diffs.len(), 1);
+    assert!(diffs[0].contains("+b"));
+    assert!(!report_case("added", &diffs));
+    assert!(!report_case("error", &["the generator failed".to_owned()]));
+}
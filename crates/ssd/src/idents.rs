@@ -0,0 +1,559 @@
+use std::fmt::{self, Display};
+
+use heck::{ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use ssd_data::SsdModule;
+
+pub fn to_snake_case(s: &str) -> String {
+    s.to_snake_case()
+}
+
+pub fn to_camel_case(s: &str) -> String {
+    s.to_lower_camel_case()
+}
+
+pub fn to_pascal_case(s: &str) -> String {
+    s.to_upper_camel_case()
+}
+
+pub fn to_kebab_case(s: &str) -> String {
+    s.to_kebab_case()
+}
+
+pub fn to_screaming_snake_case(s: &str) -> String {
+    s.to_shouty_snake_case()
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "char8_t",
+    "char16_t",
+    "char32_t",
+    "class",
+    "compl",
+    "concept",
+    "const",
+    "consteval",
+    "constexpr",
+    "constinit",
+    "const_cast",
+    "continue",
+    "co_await",
+    "co_return",
+    "co_yield",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "requires",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "thread_local",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "wchar_t",
+    "while",
+    "xor",
+    "xor_eq",
+];
+
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "true",
+    "false",
+    "null",
+];
+
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    // reserved in strict mode and modules
+    "implements",
+    "interface",
+    "let",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "static",
+    "yield",
+    "await",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+/// Returns the reserved words of the given target language.
+///
+/// Returns `None` if the language is unknown.
+pub fn keywords(target_lang: &str) -> Option<&'static [&'static str]> {
+    match target_lang.to_lowercase().as_str() {
+        "rust" | "rs" => Some(RUST_KEYWORDS),
+        "c" => Some(C_KEYWORDS),
+        "cpp" | "c++" => Some(CPP_KEYWORDS),
+        "csharp" | "cs" | "c#" => Some(CSHARP_KEYWORDS),
+        "java" => Some(JAVA_KEYWORDS),
+        "typescript" | "ts" | "javascript" | "js" => Some(TYPESCRIPT_KEYWORDS),
+        "python" | "py" => Some(PYTHON_KEYWORDS),
+        "go" => Some(GO_KEYWORDS),
+        _ => None,
+    }
+}
+
+/// Turns `ident` into a valid identifier for the given target language.
+///
+/// Invalid characters get replaced with `_`, identifiers starting with a digit get prefixed
+/// with `_` and reserved words get suffixed with `_`.
+pub fn sanitize_ident(ident: &str, target_lang: &str) -> anyhow::Result<String> {
+    let keywords = keywords(target_lang)
+        .ok_or_else(|| anyhow::anyhow!("Unknown target language \"{target_lang}\"."))?;
+
+    let mut result: String = ident
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    if keywords.contains(&result.as_str()) {
+        result.push('_');
+    }
+    Ok(result)
+}
+
+/// A name in the model which is a reserved word of target languages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordClash {
+    /// The named element, e.g. `Order.type` for the property `type` of the data type `Order`.
+    pub element: String,
+    pub targets: Vec<String>,
+}
+
+impl Display for KeywordClash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is a reserved word in {}",
+            self.element,
+            self.targets.join(", ")
+        )
+    }
+}
+
+/// Returns the names of data types, properties, enums, enum values, services, functions, events
+/// and arguments which are reserved words of any of the target languages.
+pub fn keyword_clashes(
+    module: &SsdModule,
+    targets: &[String],
+) -> anyhow::Result<Vec<KeywordClash>> {
+    let targets = targets
+        .iter()
+        .map(|target| {
+            keywords(target)
+                .map(|keywords| (target, keywords))
+                .ok_or_else(|| anyhow::anyhow!("Unknown target language \"{target}\"."))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // every name together with the path of its element
+    let mut names = Vec::new();
+    for (name, data_type) in &module.data_types {
+        names.push((name.clone(), name));
+        for (property, _) in &data_type.properties {
+            names.push((format!("{name}.{property}"), property));
+        }
+    }
+    for (name, en) in &module.enums {
+        names.push((name.clone(), name));
+        for (value, _) in &en.values {
+            names.push((format!("{name}.{value}"), value));
+        }
+    }
+    for (name, service) in &module.services {
+        names.push((name.clone(), name));
+        let members = service
+            .functions
+            .iter()
+            .map(|(member, function)| (member, &function.arguments))
+            .chain(
+                service
+                    .events
+                    .iter()
+                    .map(|(member, event)| (member, &event.arguments)),
+            );
+        for (member, arguments) in members {
+            names.push((format!("{name}.{member}"), member));
+            for (argument, _) in arguments {
+                names.push((format!("{name}.{member}.{argument}"), argument));
+            }
+        }
+    }
+
+    Ok(names
+        .into_iter()
+        .filter_map(|(element, name)| {
+            let clashing = targets
+                .iter()
+                .filter(|(_, keywords)| keywords.contains(&name.as_str()))
+                .map(|(target, _)| (*target).clone())
+                .collect::<Vec<_>>();
+            (!clashing.is_empty()).then_some(KeywordClash {
+                element,
+                targets: clashing,
+            })
+        })
+        .collect())
+}
+
+#[test]
+fn test_keyword_clashes() {
+    let module = crate::parser::parse(
+        r"
+        data Order { type: string, class: string, id: u64, };
+        enum State { None, Open, };
+        service Orders { fn find(async: bool) -> Order; event match(); };
+        ",
+        ssd_data::Namespace::new("orders"),
+    )
+    .unwrap();
+    let targets = ["rust", "cpp", "ts", "python"].map(String::from);
+    let clashes = keyword_clashes(&module, &targets).unwrap();
+    let clashes = clashes.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        clashes,
+        [
+            "Order.type is a reserved word in rust",
+            "Order.class is a reserved word in cpp, ts, python",
+            "State.None is a reserved word in python",
+            "Orders.find.async is a reserved word in rust, python",
+            "Orders.match is a reserved word in rust",
+        ]
+    );
+    assert!(keyword_clashes(&module, &["cobol".to_owned()]).is_err());
+
+    // contextual keywords of typescript are valid names
+    let module = crate::parser::parse(
+        "data Shape { string: u8, number: u8, get: u8, from: u8, of: u8, module: u8, };",
+        ssd_data::Namespace::new("shapes"),
+    )
+    .unwrap();
+    assert!(keyword_clashes(&module, &["ts".to_owned()])
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_sanitize_ident() {
+    assert_eq!(sanitize_ident("type", "rust").unwrap(), "type_");
+    assert_eq!(sanitize_ident("class", "cpp").unwrap(), "class_");
+    assert_eq!(sanitize_ident("1st-value", "python").unwrap(), "_1st_value");
+    assert_eq!(sanitize_ident("name", "ts").unwrap(), "name");
+    // contextual keywords are valid identifiers
+    assert_eq!(sanitize_ident("type", "ts").unwrap(), "type");
+    assert_eq!(sanitize_ident("yield", "ts").unwrap(), "yield_");
+    assert!(sanitize_ident("name", "cobol").is_err());
+}
@@ -0,0 +1,529 @@
+//! Resolution of imports to the description files of the imported modules.
+//!
+//! Like with [`parse_file`], the module `a::b` is described by `a/b.svc` below the base
+//! directory. An import refers to the module with the longest matching path, e.g.
+//! `import a::b::User;` to `a/b.svc`. Imports without such a file (e.g. of types which aren't
+//! described by ssd) are skipped, [`unknown_imports`] reports the ones which are probably typos.
+
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+use ssd_data::visit::{walk_data_type, walk_service};
+use ssd_data::{DataType, Namespace, Service, SsdModule, TypeName, Visitor, PRIMITIVE_TYPES};
+
+use crate::parser::parse_file;
+
+/// Modules which import each other, starting and ending with the same module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportCycle(pub Vec<Namespace>);
+
+impl Display for ImportCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modules = self
+            .0
+            .iter()
+            .rev()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "Cyclic imports: {}", modules.join(" imported by "))
+    }
+}
+
+impl std::error::Error for ImportCycle {}
+
+/// Returns the description file of the imported module, if there is one below `base`.
+#[must_use]
+pub fn module_file(base: &Path, path: &Namespace) -> Option<PathBuf> {
+    (1..=path.components.len()).rev().find_map(|len| {
+        let mut file = base.join(path.components[..len].join("/"));
+        file.set_extension("svc");
+        file.is_file().then_some(file)
+    })
+}
+
+struct Loader {
+    base: PathBuf,
+    /// The files which are being loaded, each one imported by the one before.
+    stack: Vec<(PathBuf, Namespace)>,
+    loaded: Vec<PathBuf>,
+    modules: Vec<SsdModule>,
+}
+
+impl Loader {
+    fn load(&mut self, file: PathBuf) -> anyhow::Result<()> {
+        if self.loaded.contains(&file) {
+            return Ok(());
+        }
+        if let Some(start) = self.stack.iter().position(|(f, _)| *f == file) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain([&self.stack[start]])
+                .map(|(_, namespace)| namespace.clone())
+                .collect();
+            return Err(ImportCycle(cycle).into());
+        }
+        let module = parse_file(&self.base, &file)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+
+        self.stack.push((file.clone(), module.namespace.clone()));
+        for import in &module.imports {
+            if let Some(imported) = module_file(&self.base, &import.path) {
+                self.load(std::fs::canonicalize(imported)?)?;
+            }
+        }
+        self.stack.pop();
+
+        self.loaded.push(file);
+        self.modules.push(module);
+        Ok(())
+    }
+}
+
+/// Parses the file and the files of all modules it imports (recursively), see the
+/// [module documentation](self).
+///
+/// The modules are returned in the order they can be processed: every module comes after the
+/// modules it imports, the module of `file` is the last one.
+///
+/// # Errors
+///
+/// Returns an [`ImportCycle`] if modules import each other, or an error if a file can't be read
+/// or parsed.
+pub fn load_with_imports(base: &Path, file: &Path) -> anyhow::Result<Vec<SsdModule>> {
+    let mut loader = Loader {
+        base: std::fs::canonicalize(base)?,
+        stack: Vec::new(),
+        loaded: Vec::new(),
+        modules: Vec::new(),
+    };
+    loader.load(std::fs::canonicalize(file)?)?;
+    Ok(loader.modules)
+}
+
+/// Returns the description files of the modules (e.g. the imported ones returned by
+/// [`load_with_imports`]), relative to `base` if they are below it.
+pub fn module_files<'a>(
+    base: &Path,
+    modules: impl IntoIterator<Item = &'a SsdModule>,
+) -> Vec<PathBuf> {
+    modules
+        .into_iter()
+        .filter_map(|module| {
+            let path = module_file(base, &module.namespace)?;
+            Some(
+                path.strip_prefix(base)
+                    .map_or(path.clone(), Path::to_path_buf),
+            )
+        })
+        .collect()
+}
+
+/// A reference to a type which is neither primitive nor declared by a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownType {
+    /// The data type or service with the reference.
+    pub element: String,
+    pub typ: Namespace,
+    /// A known type with a similar name, which was probably meant.
+    pub suggestion: Option<String>,
+}
+
+impl Display for UnknownType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} uses the unknown type {}", self.element, self.typ)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of inserted, removed and replaced characters which turn `a` into `b`, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.to_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_lowercase().chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(ca != cb);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns the candidate which is closest to `name`, if it is close enough to be a typo (at most
+/// one edit per three characters).
+pub(crate) fn did_you_mean<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// An import of a module or a name which doesn't exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownImport {
+    pub import: Namespace,
+    /// The unknown module or the unknown name, with the path of its module.
+    pub unknown: Namespace,
+    /// Whether `unknown` is a module without a description file (otherwise a name which the
+    /// module doesn't declare).
+    pub module: bool,
+    /// A known module or name which was probably meant.
+    pub suggestion: Option<String>,
+}
+
+impl Display for UnknownImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.module { "module" } else { "name" };
+        write!(
+            f,
+            "import {} uses the unknown {kind} {}",
+            self.import, self.unknown
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the namespaces of the description files below `dir`, skipping hidden directories.
+fn module_namespaces(dir: &Path, namespace: &[String], namespaces: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let mut components = namespace.to_vec();
+        components.push(name.to_owned());
+        if path.is_dir() && !name.starts_with('.') {
+            module_namespaces(&path, &components, namespaces);
+        } else if path.extension().is_some_and(|extension| extension == "svc") {
+            namespaces.push(components.join("::"));
+        }
+    }
+}
+
+/// Returns the imports of the last module (as returned by [`load_with_imports`]) of names which
+/// their module doesn't declare (as data type, enum or service), and the imports without a
+/// description file for which a module with a similar path exists below `base`.
+///
+/// Other imports without a description file (e.g. `import std::time::Instant;`) are of types
+/// which aren't described by ssd and aren't reported.
+#[must_use]
+pub fn unknown_imports(base: &Path, modules: &[SsdModule]) -> Vec<UnknownImport> {
+    let Some(module) = modules.last() else {
+        return Vec::new();
+    };
+    let mut namespaces = None;
+    let mut unknown = Vec::new();
+    for import in &module.imports {
+        let path = &import.path.components;
+        let declaring = modules
+            .iter()
+            .filter(|m| path.starts_with(&m.namespace.components))
+            .max_by_key(|m| m.namespace.components.len());
+        let Some(declaring) = declaring else {
+            let namespaces = namespaces.get_or_insert_with(|| {
+                let mut namespaces = Vec::new();
+                module_namespaces(base, &[], &mut namespaces);
+                namespaces
+            });
+            let suggestion = (1..=path.len()).rev().find_map(|len| {
+                let suggestion = did_you_mean(
+                    &path[..len].join("::"),
+                    namespaces.iter().map(String::as_str),
+                )?;
+                Some((len, suggestion))
+            });
+            if let Some((len, suggestion)) = suggestion {
+                let rest = path[len..]
+                    .iter()
+                    .map(|c| format!("::{c}"))
+                    .collect::<String>();
+                unknown.push(UnknownImport {
+                    import: import.path.clone(),
+                    unknown: Namespace::from_vec(path[..len].to_vec()),
+                    module: true,
+                    suggestion: Some(format!("{suggestion}{rest}")),
+                });
+            }
+            continue;
+        };
+
+        let len = declaring.namespace.components.len();
+        let names = match (&import.items, path.get(len)) {
+            (_, Some(name)) => vec![name],
+            (Some(items), None) => items.iter().collect(),
+            (None, None) => Vec::new(),
+        };
+        for name in names {
+            if declaring.data_types.contains_key(name)
+                || declaring.enums.contains_key(name)
+                || declaring.services.contains_key(name)
+            {
+                continue;
+            }
+            let candidates = declaring
+                .data_types
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain(declaring.enums.iter().map(|(name, _)| name.as_str()))
+                .chain(declaring.services.iter().map(|(name, _)| name.as_str()));
+            let namespace = declaring.namespace.to_string();
+            unknown.push(UnknownImport {
+                import: import.path.clone(),
+                unknown: declaring.namespace.join(&Namespace::new(name)),
+                module: false,
+                suggestion: did_you_mean(name, candidates)
+                    .map(|suggestion| format!("{namespace}::{suggestion}")),
+            });
+        }
+    }
+    unknown
+}
+
+/// Collects the types used by data types and services together with their name.
+#[derive(Default)]
+struct References {
+    element: String,
+    types: Vec<(String, Namespace)>,
+}
+
+impl Visitor for References {
+    fn visit_data_type(&mut self, name: &str, data_type: &DataType) {
+        name.clone_into(&mut self.element);
+        walk_data_type(self, name, data_type);
+    }
+
+    fn visit_service(&mut self, name: &str, service: &Service) {
+        name.clone_into(&mut self.element);
+        walk_service(self, name, service);
+    }
+
+    fn visit_type_name(&mut self, type_name: &TypeName) {
+        self.types
+            .push((self.element.clone(), type_name.typ.clone()));
+    }
+}
+
+/// Returns the references of the last module (as returned by [`load_with_imports`]) to types
+/// which are neither primitive (see [`TypeName::is_primitive`] and `primitives`), nor declared
+/// by the module itself or the module they are imported from.
+///
+/// Types imported from modules which aren't loaded (e.g. `import std::time::Instant;`) count as
+/// declared. Unknown types get a similar known name as suggestion: for paths one of the types of
+/// the loaded module, otherwise a primitive, a type of the module or an imported name.
+#[must_use]
+pub fn unknown_types(modules: &[SsdModule], primitives: &[String]) -> Vec<UnknownType> {
+    let Some(module) = modules.last() else {
+        return Vec::new();
+    };
+    let is_known = |typ: &Namespace| {
+        if TypeName::new(typ.clone(), Vec::new(), Vec::new()).is_primitive()
+            || primitives.contains(&typ.to_string())
+            || module.find_type(typ).is_some()
+        {
+            return true;
+        }
+        let path = module.resolve_type(typ);
+        let imported = path != *typ || module.imports.iter().any(|i| i.path == *typ);
+        let namespace = &path.components[..path.components.len().saturating_sub(1)];
+        match modules.iter().find(|m| m.namespace.components == namespace) {
+            Some(declaring) => declaring.find_type(&path).is_some(),
+            None => imported,
+        }
+    };
+
+    let suggest = |typ: &Namespace| {
+        let (name, namespace) = typ.components.split_last()?;
+        if namespace.is_empty() {
+            let imported = module
+                .imports
+                .iter()
+                .flat_map(|import| match &import.items {
+                    Some(items) => items.iter().map(String::as_str).collect(),
+                    None => vec![import.path.components.last().map_or("", String::as_str)],
+                });
+            let candidates = PRIMITIVE_TYPES
+                .iter()
+                .copied()
+                .chain(primitives.iter().map(String::as_str))
+                .chain(module.data_types.iter().map(|(name, _)| name.as_str()))
+                .chain(module.enums.iter().map(|(name, _)| name.as_str()))
+                .chain(imported);
+            return did_you_mean(name, candidates).map(ToOwned::to_owned);
+        }
+        let path = module.resolve_type(typ);
+        let declaring = &path.components[..path.components.len() - 1];
+        let declaring = modules
+            .iter()
+            .find(|m| m.namespace.components == declaring)?;
+        let candidates = declaring
+            .data_types
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(declaring.enums.iter().map(|(name, _)| name.as_str()));
+        let suggestion = did_you_mean(name, candidates)?;
+        Some(format!("{}::{suggestion}", namespace.join("::")))
+    };
+
+    let mut references = References::default();
+    references.visit_module(module);
+    references
+        .types
+        .into_iter()
+        .filter(|(_, typ)| !is_known(typ))
+        .map(|(element, typ)| UnknownType {
+            element,
+            suggestion: suggest(&typ),
+            typ,
+        })
+        .collect()
+}
+
+#[test]
+fn test_unknown_types() {
+    let users = crate::parser::parse("data User { id: uuid, };", Namespace::new("users")).unwrap();
+    let orders = crate::parser::parse(
+        r"
+        import std::time::Instant;
+        import users::{User, Group};
+        data Order {
+            id: u64,
+            created: Instant,
+            user: User,
+            group: Group,
+            price: Decimal,
+            item: Item,
+            tags: list of string,
+            creator: Usr,
+            count: u46,
+        };
+        enum State { Open, };
+        service Orders {
+            fn get(id: u64) -> Order;
+            fn state(order: Order) -> users::State;
+            fn owner(order: Order) -> users::Usr;
+            fn open() -> Stat;
+        };
+        ",
+        Namespace::new("orders"),
+    )
+    .unwrap();
+
+    let unknown = unknown_types(&[users.clone(), orders.clone()], &[]);
+    let unknown = unknown.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        unknown,
+        [
+            "Order uses the unknown type Group",
+            "Order uses the unknown type Decimal",
+            "Order uses the unknown type Item",
+            "Order uses the unknown type Usr, did you mean `User`?",
+            "Order uses the unknown type u46, did you mean `u16`?",
+            "Orders uses the unknown type users::State",
+            "Orders uses the unknown type users::Usr, did you mean `users::User`?",
+            "Orders uses the unknown type Stat, did you mean `State`?",
+        ]
+    );
+    let unknown = unknown_types(&[users, orders], &["Decimal".to_owned()]);
+    assert_eq!(unknown.len(), 7);
+}
+
+#[test]
+fn test_load_with_imports() {
+    let dir = std::env::temp_dir().join(format!("ssd-imports-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("shop")).unwrap();
+    let write = |file: &str, content: &str| std::fs::write(dir.join(file), content).unwrap();
+    write(
+        "users.svc",
+        "import std::time::Instant; data User { id: u64, };",
+    );
+    write(
+        "shop/orders.svc",
+        "import users::User; import shop::items as i; data Order { user: User, item: i::Item, };",
+    );
+    write("shop/items.svc", "import users; data Item { id: u64, };");
+
+    let modules = load_with_imports(&dir, &dir.join("shop/orders.svc")).unwrap();
+    let names = modules
+        .iter()
+        .map(|module| module.namespace.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["users", "shop::items", "shop::orders"]);
+
+    write(
+        "users.svc",
+        "import shop::orders::Order; data User { id: u64, };",
+    );
+    let error = load_with_imports(&dir, &dir.join("shop/orders.svc")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Cyclic imports: shop::orders imported by users imported by shop::orders"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_unknown_imports() {
+    let dir = std::env::temp_dir().join(format!("ssd-unknown-imports-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("shop")).unwrap();
+    let write = |file: &str, content: &str| std::fs::write(dir.join(file), content).unwrap();
+    write("users.svc", "data User { id: u64, }; enum Role { Admin, };");
+    write("shop/items.svc", "data Item { id: u64, };");
+    write(
+        "shop/orders.svc",
+        r"
+        import std::time::Instant;
+        import userss::User;
+        import shop::itms;
+        import users::Usr;
+        import users::{User, Rol, Group};
+        import users::Role;
+        import shop::items as i;
+        data Order { user: User, };
+        ",
+    );
+
+    let modules = load_with_imports(&dir, &dir.join("shop/orders.svc")).unwrap();
+    let unknown = unknown_imports(&dir, &modules);
+    let unknown = unknown.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        unknown,
+        [
+            "import userss::User uses the unknown module userss, did you mean `users::User`?",
+            "import shop::itms uses the unknown module shop::itms, did you mean `shop::items`?",
+            "import users::Usr uses the unknown name users::Usr, did you mean `users::User`?",
+            "import users uses the unknown name users::Rol, did you mean `users::Role`?",
+            "import users uses the unknown name users::Group",
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_did_you_mean() {
+    let names = ["UserId", "User", "Group"];
+    assert_eq!(did_you_mean("UserID", names), Some("UserId"));
+    assert_eq!(did_you_mean("Usr", names), Some("User"));
+    assert_eq!(did_you_mean("Groups", names), Some("Group"));
+    assert_eq!(did_you_mean("Order", names), None);
+    assert_eq!(did_you_mean("User", names), None);
+}
@@ -0,0 +1,160 @@
+//! Lifting of inline data types (`pos: { x: f32, y: f32, }`) and of data types declared in
+//! services into named data types of the module.
+//!
+//! Lifted data types are named by the scheme `{parent}{Name}`, where `{parent}` is the data type
+//! or service containing the declaration (for inline data types in inline data types the lifted
+//! name of the outer one), `{name}` the name of the property or the declared data type and `{Name}`
+//! the same in PascalCase. So the property `pos` of `Player` becomes `PlayerPos` and `data Request`
+//! in `service Users` becomes `UsersRequest`. Data types and services can change the scheme for
+//! everything declared in them with e.g. `#[inline(name = "{parent}_{name}")]`, properties the
+//! name of their inline data type (`#[inline(name = "Position")]`).
+//!
+//! Lifted data types get the `#[cfg(...)]` attributes of everything containing them, so they are
+//! removed together with their parent.
+
+use heck::ToUpperCamelCase;
+use ssd_data::visit::walk_type_name_mut;
+use ssd_data::{
+    find_attribute, Attribute, DataType, Namespace, OrderedMap, Service, TypeName, Visibility,
+    VisitorMut,
+};
+
+const DEFAULT_SCHEME: &str = "{parent}{Name}";
+/// The attribute with the conditions of an element, e.g. `#[cfg(PRO)]`.
+pub(crate) const CFG_ATTRIBUTE: &str = "cfg";
+
+/// Returns the naming scheme set by the attributes, or `scheme` if they don't set one.
+fn scheme<'a>(attributes: &'a [Attribute], scheme: &'a str) -> &'a str {
+    find_attribute(attributes, "inline")
+        .and_then(|inline| inline.parameters.iter().find(|p| p.name == "name"))
+        .and_then(|name| name.value.as_deref())
+        .unwrap_or(scheme)
+}
+
+/// The `#[cfg(...)]` attributes of the parent followed by the ones in `attributes`.
+fn with_cfg(cfg_of_parent: &[Attribute], attributes: &[Attribute]) -> Vec<Attribute> {
+    cfg_of_parent
+        .iter()
+        .chain(
+            attributes
+                .iter()
+                .filter(|a| a.name.to_string() == CFG_ATTRIBUTE),
+        )
+        .cloned()
+        .collect()
+}
+
+fn lifted_name(scheme: &str, parent: &str, name: &str) -> String {
+    scheme
+        .replace("{parent}", parent)
+        .replace("{name}", name)
+        .replace("{Name}", &name.to_upper_camel_case())
+}
+
+/// Replaces the inline data types of the properties by references to named data types, which are
+/// appended to `lifted` (inner ones first) with the cfg attributes `cfg_of_parent` and the ones
+/// of their property.
+fn lift_properties(
+    parent: &str,
+    properties: &mut OrderedMap<TypeName>,
+    visibility: Option<Visibility>,
+    cfg_of_parent: &[Attribute],
+    scheme_of_parent: &str,
+    lifted: &mut Vec<(String, DataType)>,
+) {
+    for (name, property) in properties {
+        let Some(mut inline) = property.inline.take() else {
+            continue;
+        };
+        let lifted_name = lifted_name(scheme(&property.attributes, scheme_of_parent), parent, name);
+        let cfg = with_cfg(cfg_of_parent, &property.attributes);
+        lift_properties(
+            &lifted_name,
+            &mut inline,
+            visibility,
+            &cfg,
+            scheme_of_parent,
+            lifted,
+        );
+        property.typ = Namespace::new(&lifted_name);
+        lifted.push((
+            lifted_name,
+            DataType::new(inline, cfg).with_visibility(visibility),
+        ));
+    }
+}
+
+fn lift(
+    name: &str,
+    mut data_type: DataType,
+    cfg_of_parent: &[Attribute],
+    scheme_of_parent: &str,
+) -> Vec<(String, DataType)> {
+    let mut lifted = Vec::new();
+    let scheme = scheme(&data_type.attributes, scheme_of_parent).to_owned();
+    let cfg = with_cfg(cfg_of_parent, &data_type.attributes);
+    lift_properties(
+        name,
+        &mut data_type.properties,
+        data_type.visibility,
+        &cfg,
+        &scheme,
+        &mut lifted,
+    );
+    lifted.push((name.to_owned(), data_type));
+    lifted
+}
+
+/// Lifts the inline data types of the data type, see the [module documentation](self).
+///
+/// Returns the data type together with the lifted ones, which come before it.
+pub(crate) fn lift_data_type(name: &str, data_type: DataType) -> Vec<(String, DataType)> {
+    lift(name, data_type, &[], DEFAULT_SCHEME)
+}
+
+/// Renames the references to data types declared in a service to their lifted names.
+struct Rename<'a> {
+    names: &'a [(String, String)],
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        if let [name] = type_name.typ.components.as_slice() {
+            if let Some((_, lifted)) = self.names.iter().find(|(declared, _)| declared == name) {
+                type_name.typ = Namespace::new(lifted);
+            }
+        }
+        walk_type_name_mut(self, type_name);
+    }
+}
+
+/// Lifts the data types declared in the service (see the [module documentation](self)) and
+/// renames the references to them in the service.
+///
+/// Returns the lifted data types together with their inline data types.
+pub(crate) fn lift_service(
+    name: &str,
+    service: &mut Service,
+    declared: OrderedMap<DataType>,
+) -> Vec<(String, DataType)> {
+    let scheme_of_service = scheme(&service.attributes, DEFAULT_SCHEME).to_owned();
+    let names = declared
+        .iter()
+        .map(|(declared_name, _)| {
+            let lifted_name = lifted_name(&scheme_of_service, name, declared_name);
+            (declared_name.clone(), lifted_name)
+        })
+        .collect::<Vec<_>>();
+    let mut rename = Rename { names: &names };
+    rename.visit_service_mut(name, service);
+
+    let cfg = with_cfg(&[], &service.attributes);
+    let mut lifted = Vec::new();
+    for ((_, mut data_type), (_, lifted_name)) in declared.into_iter().zip(&names) {
+        rename.visit_data_type_mut(lifted_name, &mut data_type);
+        // the declared data type keeps its own attributes after the ones of the service
+        data_type.attributes = [cfg.clone(), data_type.attributes].concat();
+        lifted.extend(lift(lifted_name, data_type, &[], &scheme_of_service));
+    }
+    lifted
+}
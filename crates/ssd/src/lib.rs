@@ -1,27 +1,162 @@
-#[cfg(feature = "_web")]
-mod generators;
-#[cfg(feature = "_web")]
-pub use generators::rhai::generate_web;
-
-mod ast;
-mod helper;
-mod parser;
-pub use parser::{parse, parse_file, parse_file_with_namespace};
-pub use helper::{update_types_from_file, print_or_write, parse_raw_data};
-#[cfg(not(feature = "_bin"))]
+// The modules are public for the `ssd` binary, the library's API are the re-exports below.
+#[cfg(any(feature = "web", feature = "_bin"))]
+#[doc(hidden)]
+pub mod generators;
+#[cfg(any(feature = "web", feature = "_bin"))]
+#[doc(hidden)]
+pub mod idents;
+
+#[cfg(all(
+    feature = "_bin",
+    any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    )
+))]
+#[doc(hidden)]
+pub mod api;
+#[cfg(all(
+    feature = "_bin",
+    any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    )
+))]
+mod build;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod cache;
+#[cfg(feature = "capi")]
+mod capi;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod cfg;
+#[doc(hidden)]
+pub mod exit;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod grpc;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod manifest;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod map_vec;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod mock;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod options;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod postprocess;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod schema;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod timings;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod transform;
+#[cfg(feature = "_bin")]
+#[doc(hidden)]
+pub mod xml;
+#[cfg(all(
+    feature = "_bin",
+    any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    )
+))]
+pub use api::{generate, GenerateOutput, GenerateRequest, Generator};
+#[cfg(all(
+    feature = "_bin",
+    any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    )
+))]
+pub use build::{build, Build};
+#[cfg(feature = "_bin")]
+pub use postprocess::PostProcess;
+#[cfg(feature = "_bin")]
+pub use transform::{Case, Transform};
+
+#[doc(hidden)]
+pub mod ast;
+#[doc(hidden)]
+pub mod bundle;
+#[doc(hidden)]
+pub mod helper;
+#[doc(hidden)]
+pub mod imports;
+mod inline;
+#[doc(hidden)]
+pub mod parser;
+#[cfg(any(
+    feature = "web",
+    feature = "_python",
+    feature = "testing",
+    feature = "_bin"
+))]
+#[doc(hidden)]
+pub mod pretty;
+mod rd_parser;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[doc(hidden)]
+pub mod validate;
+#[cfg(feature = "web")]
+mod web;
+pub use bundle::bundle;
 pub use helper::update_types;
+pub use helper::{merge_raw_data, parse_raw_data_as, RawFormat, RawMerge};
+pub use helper::{parse_raw_data, print_or_write, update_types_from_file, write_files};
+pub use imports::{
+    load_with_imports, module_file, unknown_imports, unknown_types, ImportCycle, UnknownImport,
+    UnknownType,
+};
+pub use parser::{parse, parse_file, parse_file_with_namespace};
+pub use parser::{parse_limited, parse_raw_limited, ParseError, ParseErrorType, ParseLimits};
+pub use parser::{parser_backend, set_parser_backend, ParserBackend};
+pub use validate::{validate, Violation};
+#[cfg(feature = "web")]
+pub use web::{
+    diagnostics_web, generate_wasm_web, generate_web, WebDiagnostic, WebGenerator, WebOutput,
+};
 
 #[cfg(feature = "_python")]
 mod python {
-    use std::path::Path;
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::{Path, PathBuf};
 
-    use pyo3::exceptions::PyException;
+    use clap::ValueEnum;
+    use pyo3::exceptions::{PyException, PyTypeError};
     use pyo3::prelude::*;
+    use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PySequence, PyString};
     use pyo3::Python;
+    use serde_value::Value;
 
     use ssd_data::Namespace;
     use ssd_data::SsdModule;
 
+    use crate::options::DataFormat;
+    use crate::{GenerateRequest, Generator};
+
+    fn to_py_err(e: impl ToString) -> PyErr {
+        PyException::new_err(e.to_string())
+    }
+
     #[pyfunction]
     pub fn parse(content: &str, namespace: &str) -> PyResult<SsdModule> {
         crate::parse(content, Namespace::new(namespace))
@@ -40,11 +175,222 @@ mod python {
             .map_err(|e| PyException::new_err(e.to_string()))
     }
 
+    /// Formats the description like `ssd pretty`.
+    #[pyfunction]
+    pub fn pretty(content: &str) -> PyResult<String> {
+        crate::parser::parse_raw(content)
+            .map(|raw| crate::pretty::pretty(&raw))
+            .map_err(to_py_err)
+    }
+
+    /// Applies the typemap (its content, not a path) to the types of the module.
+    #[pyfunction]
+    pub fn update_types(module: SsdModule, typemap: &str) -> PyResult<SsdModule> {
+        crate::update_types(module, typemap).map_err(to_py_err)
+    }
+
+    /// Serializes the module like `ssd data`, e.g. as `json`, `yaml` or `toml-pretty`.
+    ///
+    /// Binary formats (`msgpack` and `cbor`) are returned as bytes.
+    #[pyfunction]
+    #[pyo3(signature = (module, format = "json"))]
+    pub fn to_data(py: Python<'_>, module: SsdModule, format: &str) -> PyResult<PyObject> {
+        let format = DataFormat::from_str(format, true).map_err(to_py_err)?;
+        let result = crate::helper::serialize(format, module).map_err(to_py_err)?;
+        if format.is_binary() {
+            Ok(PyBytes::new(py, &result).into_py(py))
+        } else {
+            Ok(String::from_utf8(result).map_err(to_py_err)?.into_py(py))
+        }
+    }
+
+    /// Converts python objects (dicts, lists, strings, numbers, bools and `None`) into raw data.
+    fn to_value(obj: &PyAny) -> PyResult<Value> {
+        if obj.is_none() {
+            Ok(Value::Unit)
+        } else if let Ok(b) = obj.downcast::<PyBool>() {
+            Ok(Value::Bool(b.is_true()))
+        } else if let Ok(i) = obj.extract::<i64>() {
+            Ok(Value::I64(i))
+        } else if let Ok(i) = obj.extract::<u64>() {
+            Ok(Value::U64(i))
+        } else if let Ok(f) = obj.downcast::<PyFloat>() {
+            Ok(Value::F64(f.value()))
+        } else if let Ok(s) = obj.downcast::<PyString>() {
+            Ok(Value::String(s.to_str()?.to_owned()))
+        } else if let Ok(dict) = obj.downcast::<PyDict>() {
+            dict.iter()
+                .map(|(k, v)| Ok((to_value(k)?, to_value(v)?)))
+                .collect::<PyResult<BTreeMap<_, _>>>()
+                .map(Value::Map)
+        } else if let Ok(seq) = obj.downcast::<PySequence>() {
+            seq.iter()?
+                .map(|item| to_value(item?))
+                .collect::<PyResult<Vec<_>>>()
+                .map(Value::Seq)
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "Unsupported type {} in raw data.",
+                obj.get_type().name()?
+            )))
+        }
+    }
+
+    fn to_py(py: Python<'_>, value: &serde_json::Value) -> PyResult<PyObject> {
+        use serde_json::Value::{Array, Bool, Null, Number, Object, String};
+        Ok(match value {
+            Null => py.None(),
+            Bool(b) => b.into_py(py),
+            Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => i.into_py(py),
+                (_, Some(u)) => u.into_py(py),
+                _ => n.as_f64().unwrap_or_default().into_py(py),
+            },
+            String(s) => s.into_py(py),
+            Array(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| to_py(py, item))
+                    .collect::<PyResult<Vec<_>>>()?;
+                PyList::new(py, items).into_py(py)
+            }
+            Object(map) => {
+                let dict = PyDict::new(py);
+                for (key, value) in map {
+                    dict.set_item(key, to_py(py, value)?)?;
+                }
+                dict.into_py(py)
+            }
+        })
+    }
+
+    /// Converts the module into nested dicts and lists, like the json of `ssd data`.
+    #[pyfunction]
+    pub fn to_dict(py: Python<'_>, module: SsdModule) -> PyResult<PyObject> {
+        to_py(py, &serde_json::to_value(module).map_err(to_py_err)?)
+    }
+
+    /// `input` is either the path of a description file or the raw model (e.g. a dict), which
+    /// is passed to the generator like with `--raw`.
+    fn generate(
+        input: &PyAny,
+        generator: Generator,
+        typemaps: Vec<String>,
+        defines: HashMap<String, String>,
+        base: &str,
+        out_dir: Option<&str>,
+    ) -> PyResult<String> {
+        let mut request = if let Ok(file) = input.extract::<PathBuf>() {
+            GenerateRequest::new(file, generator)
+        } else {
+            let mut request = GenerateRequest::new("", generator);
+            request.raw = Some(to_value(input)?);
+            request
+        };
+        request.base = base.into();
+        request.typemaps = typemaps.into_iter().map(Into::into).collect();
+        request.defines = defines
+            .into_iter()
+            .map(|(key, value)| (key, Value::String(value)))
+            .collect();
+        let output = crate::generate(request).map_err(to_py_err)?;
+        if let Some(out_dir) = out_dir {
+            crate::write_files(Path::new(out_dir), &output.files).map_err(to_py_err)?;
+        }
+        Ok(output.output)
+    }
+
+    /// Runs the rhai script for the description file (or raw model) and returns its output.
+    ///
+    /// Files emitted by the script are written relative to `out_dir`, they are discarded
+    /// without it.
+    #[pyfunction]
+    #[pyo3(signature = (input, script, typemaps = Vec::new(), defines = HashMap::new(), base = "", out_dir = None))]
+    pub fn generate_rhai(
+        input: &PyAny,
+        script: &str,
+        typemaps: Vec<String>,
+        defines: HashMap<String, String>,
+        base: &str,
+        out_dir: Option<&str>,
+    ) -> PyResult<String> {
+        generate(
+            input,
+            Generator::rhai(script),
+            typemaps,
+            defines,
+            base,
+            out_dir,
+        )
+    }
+
+    /// Renders the handlebars template for the description file (or raw model).
+    #[pyfunction]
+    #[pyo3(signature = (input, template, typemaps = Vec::new(), defines = HashMap::new(), base = ""))]
+    pub fn generate_handlebars(
+        input: &PyAny,
+        template: &str,
+        typemaps: Vec<String>,
+        defines: HashMap<String, String>,
+        base: &str,
+    ) -> PyResult<String> {
+        generate(
+            input,
+            Generator::handlebars(template),
+            typemaps,
+            defines,
+            base,
+            None,
+        )
+    }
+
+    /// Renders the tera template for the description file (or raw model).
+    #[pyfunction]
+    #[pyo3(signature = (input, template, typemaps = Vec::new(), defines = HashMap::new(), base = ""))]
+    pub fn generate_tera(
+        input: &PyAny,
+        template: &str,
+        typemaps: Vec<String>,
+        defines: HashMap<String, String>,
+        base: &str,
+    ) -> PyResult<String> {
+        generate(
+            input,
+            Generator::tera(template),
+            typemaps,
+            defines,
+            base,
+            None,
+        )
+    }
+
     #[pymodule]
     fn py_ssd(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
         m.add_function(wrap_pyfunction!(parse, m)?)?;
         m.add_function(wrap_pyfunction!(parse_file, m)?)?;
         m.add_function(wrap_pyfunction!(parse_file_with_namespace, m)?)?;
+        m.add_function(wrap_pyfunction!(pretty, m)?)?;
+        m.add_function(wrap_pyfunction!(update_types, m)?)?;
+        m.add_function(wrap_pyfunction!(to_data, m)?)?;
+        m.add_function(wrap_pyfunction!(to_dict, m)?)?;
+        m.add_function(wrap_pyfunction!(generate_rhai, m)?)?;
+        m.add_function(wrap_pyfunction!(generate_handlebars, m)?)?;
+        m.add_function(wrap_pyfunction!(generate_tera, m)?)?;
+        m.add_class::<SsdModule>()?;
+        m.add_class::<ssd_data::Import>()?;
+        m.add_class::<ssd_data::Dependency>()?;
+        m.add_class::<ssd_data::Parameter>()?;
+        m.add_class::<ssd_data::Attribute>()?;
+        m.add_class::<ssd_data::DataType>()?;
+        m.add_class::<ssd_data::Enum>()?;
+        m.add_class::<ssd_data::EnumValue>()?;
+        m.add_class::<ssd_data::Service>()?;
+        m.add_class::<ssd_data::Function>()?;
+        m.add_class::<ssd_data::Event>()?;
+        m.add_class::<ssd_data::TypeName>()?;
+        m.add_class::<ssd_data::Visibility>()?;
+        m.add_class::<ssd_data::Radix>()?;
+        m.add_class::<Namespace>()?;
         Ok(())
     }
 }
@@ -0,0 +1,322 @@
+//! Naming-convention lints of `ssd check`, configured in the `[lints]` table of `ssd.toml`:
+//! ```toml
+//! [lints]
+//! type_case = "deny"         # data types, enums and services in PascalCase
+//! field_case = "warn"        # properties and arguments in snake_case
+//! function_case = "warn"     # functions and events in snake_case
+//! enum_value_case = "allow"  # enum values in SCREAMING_SNAKE_CASE
+//! ```
+//! Single elements can opt out with `#[allow(naming)]` (all rules) or e.g. `#[allow(field_case)]`,
+//! which applies to their members as well.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use ssd_data::{find_attribute, Attribute, SsdModule};
+
+use crate::idents::{to_pascal_case, to_screaming_snake_case, to_snake_case};
+
+/// The file in the base directory with the configuration.
+pub const CONFIG_FILE: &str = "ssd.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Allow,
+    /// Printed, but doesn't fail the check.
+    Warn,
+    /// Fails the check.
+    Deny,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Allow => write!(f, "allow"),
+            Level::Warn => write!(f, "warning"),
+            Level::Deny => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Lints {
+    pub type_case: Level,
+    pub field_case: Level,
+    pub function_case: Level,
+    pub enum_value_case: Level,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            type_case: Level::Warn,
+            field_case: Level::Warn,
+            function_case: Level::Warn,
+            enum_value_case: Level::Allow,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub lints: Lints,
+}
+
+impl Config {
+    /// Reads `ssd.toml` from the base directory, or returns the defaults if there is none.
+    pub fn load(base: &Path) -> anyhow::Result<Self> {
+        let path = base.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+    }
+}
+
+/// A name which doesn't follow the convention of its rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub level: Level,
+    pub rule: &'static str,
+    /// The named element, e.g. `Order.Type` for the property `Type` of the data type `Order`.
+    pub element: String,
+    pub expected: String,
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} should be named {} ({})",
+            self.element, self.expected, self.rule
+        )
+    }
+}
+
+/// The known findings of `ssd check` by file, stored as json.
+///
+/// Every entry suppresses one occurrence of the finding in the file, so a second violation of the
+/// same kind is reported again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline(BTreeMap<String, Vec<String>>);
+
+impl Baseline {
+    /// Reads the baseline file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("{}: {e} (create it with --update-baseline)", path.display())
+        })?;
+        serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, file: &str, finding: String) {
+        self.0.entry(file.to_owned()).or_default().push(finding);
+    }
+
+    /// Removes the finding of the file from the baseline, returns `false` if it isn't in it.
+    pub fn take(&mut self, file: &str, finding: &str) -> bool {
+        let Some(findings) = self.0.get_mut(file) else {
+            return false;
+        };
+        match findings.iter().position(|f| f == finding) {
+            Some(index) => {
+                findings.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The number of findings.
+    pub fn len(&self) -> usize {
+        self.0.values().map(Vec::len).sum()
+    }
+}
+
+struct Linter<'a> {
+    lints: &'a Lints,
+    found: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    /// Checks the name, unless the rule is allowed by the configuration or the attributes of the
+    /// element (or the elements containing it).
+    fn check(
+        &mut self,
+        rule: &'static str,
+        allowed: &[&[Attribute]],
+        element: String,
+        name: &str,
+        convert: fn(&str) -> String,
+    ) {
+        let level = match rule {
+            "type_case" => self.lints.type_case,
+            "field_case" => self.lints.field_case,
+            "function_case" => self.lints.function_case,
+            _ => self.lints.enum_value_case,
+        };
+        let is_allowed = allowed.iter().any(|attributes| {
+            find_attribute(attributes, "allow").is_some_and(|allow| {
+                allow
+                    .parameters
+                    .iter()
+                    .any(|p| p.name == "naming" || p.name == rule)
+            })
+        });
+        let expected = convert(name);
+        if level != Level::Allow && !is_allowed && expected != name {
+            self.found.push(Lint {
+                level,
+                rule,
+                element,
+                expected,
+            });
+        }
+    }
+}
+
+/// Returns the names of the module which break the naming conventions, see the
+/// [module documentation](self).
+pub fn lint_naming(module: &SsdModule, lints: &Lints) -> Vec<Lint> {
+    let mut linter = Linter {
+        lints,
+        found: Vec::new(),
+    };
+    for (name, data_type) in &module.data_types {
+        let outer = data_type.attributes.as_slice();
+        linter.check("type_case", &[outer], name.clone(), name, to_pascal_case);
+        for (property, typ) in &data_type.properties {
+            linter.check(
+                "field_case",
+                &[outer, &typ.attributes],
+                format!("{name}.{property}"),
+                property,
+                to_snake_case,
+            );
+        }
+    }
+    for (name, en) in &module.enums {
+        let outer = en.attributes.as_slice();
+        linter.check("type_case", &[outer], name.clone(), name, to_pascal_case);
+        for (value, enum_value) in &en.values {
+            linter.check(
+                "enum_value_case",
+                &[outer, &enum_value.attributes],
+                format!("{name}.{value}"),
+                value,
+                to_screaming_snake_case,
+            );
+        }
+    }
+    for (name, service) in &module.services {
+        let outer = service.attributes.as_slice();
+        linter.check("type_case", &[outer], name.clone(), name, to_pascal_case);
+        let members = service
+            .functions
+            .iter()
+            .map(|(member, f)| (member, &f.attributes, &f.arguments))
+            .chain(
+                service
+                    .events
+                    .iter()
+                    .map(|(member, e)| (member, &e.attributes, &e.arguments)),
+            );
+        for (member, attributes, arguments) in members {
+            linter.check(
+                "function_case",
+                &[outer, attributes],
+                format!("{name}.{member}"),
+                member,
+                to_snake_case,
+            );
+            for (argument, typ) in arguments {
+                linter.check(
+                    "field_case",
+                    &[outer, attributes, &typ.attributes],
+                    format!("{name}.{member}.{argument}"),
+                    argument,
+                    to_snake_case,
+                );
+            }
+        }
+    }
+    linter.found
+}
+
+#[test]
+fn test_lint_naming() {
+    let module = crate::parser::parse(
+        r"
+        data order { Id: u64, #[allow(field_case)] OrderDate: string, };
+        #[allow(naming)]
+        data legacy_type { SomeField: u8, };
+        enum State { Open, IN_PROGRESS, };
+        service Orders { fn GetOrder(orderId: u64) -> order; event created(); };
+        ",
+        ssd_data::Namespace::new("orders"),
+    )
+    .unwrap();
+
+    let lints = lint_naming(&module, &Lints::default());
+    let lints = lints.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        lints,
+        [
+            "order should be named Order (type_case)",
+            "order.Id should be named id (field_case)",
+            "Orders.GetOrder should be named get_order (function_case)",
+            "Orders.GetOrder.orderId should be named order_id (field_case)",
+        ]
+    );
+
+    let config: Config = toml::from_str(
+        r#"
+        [lints]
+        type_case = "deny"
+        field_case = "allow"
+        enum_value_case = "warn"
+        "#,
+    )
+    .unwrap();
+    let lints = lint_naming(&module, &config.lints);
+    assert_eq!(lints.len(), 3);
+    assert_eq!(lints[0].level, Level::Deny);
+    assert_eq!(
+        lints[1].to_string(),
+        "State.Open should be named OPEN (enum_value_case)"
+    );
+    assert!(toml::from_str::<Config>("[lints]\nfield_case = \"forbid\"").is_err());
+}
+
+#[test]
+fn test_baseline() {
+    let mut baseline = Baseline::default();
+    baseline.add(
+        "a.svc",
+        "order should be named Order (type_case)".to_owned(),
+    );
+    baseline.add(
+        "a.svc",
+        "order should be named Order (type_case)".to_owned(),
+    );
+    assert_eq!(baseline.len(), 2);
+    let mut baseline: Baseline =
+        serde_json::from_str(&serde_json::to_string(&baseline).unwrap()).unwrap();
+
+    assert!(!baseline.take("b.svc", "order should be named Order (type_case)"));
+    assert!(!baseline.take("a.svc", "item should be named Item (type_case)"));
+    assert!(baseline.take("a.svc", "order should be named Order (type_case)"));
+    assert!(baseline.take("a.svc", "order should be named Order (type_case)"));
+    assert!(!baseline.take("a.svc", "order should be named Order (type_case)"));
+}
@@ -1,106 +1,789 @@
-mod ast;
-mod generators;
-mod helper;
-mod map_vec;
-#[cfg(feature = "_bin")]
-mod options;
-mod parser;
-mod pretty;
+mod canonical;
+mod compat;
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+mod golden;
+mod lint;
+mod organize;
+mod rename;
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+mod serve;
+mod tree;
+
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+use ssd::api;
+use ssd::{
+    ast, bundle, cache, cfg, exit, generators, grpc, helper, idents, imports, manifest, mock,
+    options, parser, postprocess, pretty, schema, timings, transform, validate,
+};
 
 use clap::{CommandFactory, Parser};
 use clap_complete::generate;
 use generators::rhai::build_engine;
-use options::{Args, DataFormat, DataParameters, Generator, PrettyData};
-#[cfg(feature = "ron")]
-use ron::ser::PrettyConfig;
-use serde::Serialize;
+use options::{
+    Args, BundleData, CacheCommand, CheckData, CompatData, DataParameters, DebugData, Generator,
+    GrpcData, HashData, MockData, PrettyData, RenameData, SchemaData, ValidateData,
+};
+use ssd_data::Defines;
 
-use parser::parse_file;
+use parser::{parse_file, parse_file_with_namespace};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
 
-use ast::ComparableAstElement;
-use helper::parse_raw_data;
-use helper::print_or_write;
-use helper::{update_types_from_file};
-#[cfg(feature = "_bin")]
+use ast::{ComparableAstElement, Namespace};
+use cache::load_module;
+use cfg::apply_cfg;
+use exit::{Classify, ErrorCode, WithPath};
+use helper::{
+    collect_files, diff_files, map_ordered, parse_raw_data_as, report_case, serialize, Diagnostic,
+};
+use helper::{define_value_files, load_defines, update_types_from_file};
+use manifest::Manifest;
 use options::SubCommand;
-use parser::{parse_file_raw, parse_raw};
+use parser::parse_raw;
 use pretty::pretty;
+use timings::Phase;
 
-fn serialize<T: Serialize>(format: DataFormat, value: T) -> anyhow::Result<String> {
-    let result = match format {
-        options::DataFormat::Json => serde_json::to_string(&value)?,
-        options::DataFormat::JsonPretty => serde_json::to_string_pretty(&value)?,
-        options::DataFormat::Yaml => serde_yaml::to_string(&value)?,
-        options::DataFormat::Toml => toml::to_string(&value)?,
-        options::DataFormat::TomlPretty => toml::to_string_pretty(&value)?,
-        #[cfg(feature = "ron")]
-        options::DataFormat::Ron => ron::to_string(&value)?,
-        #[cfg(feature = "ron")]
-        options::DataFormat::RonPretty => {
-            ron::ser::to_string_pretty(&value, PrettyConfig::default())?
-        }
-        options::DataFormat::Rsn => rsn::to_string(&value),
-        options::DataFormat::RsnPretty => rsn::to_string_pretty(&value),
+/// Prints the module of `ssd debug`, as it's parsed or after the typemap or transforms.
+fn debug_file(
+    base: &PathBuf,
+    defines: &Defines,
+    DebugData {
+        tree,
+        after_typemap,
+        after_transforms,
+        mut input,
+    }: DebugData,
+) -> Result<(), Box<dyn Error>> {
+    input.file =
+        std::fs::canonicalize(shellexpand::full(input.file.to_str().unwrap())?.to_string())?;
+    let module = if after_transforms {
+        load_module(base, defines, &input, None)?
+    } else {
+        let module = match &input.namespace {
+            Some(namespace) => parse_file_with_namespace(&input.file, Namespace::new(namespace)),
+            None => parse_file(base, &input.file),
+        }
+        .map_err(anyhow::Error::from)
+        .classify(ErrorCode::Parse)?;
+        if after_typemap {
+            update_types_from_file(
+                apply_cfg(module, defines),
+                input.no_map,
+                input.typemap.clone(),
+                None,
+                input.typemap_report,
+            )
+            .classify(ErrorCode::InvalidData)?
+        } else {
+            module
+        }
     };
-    Ok(result)
+    if tree {
+        tree::print_tree(&module)?;
+    } else {
+        println!("{module:#?}");
+    }
+    Ok(())
 }
 
 fn generate_data(
     base: &PathBuf,
+    defines: &Defines,
     DataParameters { format, input, out }: DataParameters,
 ) -> Result<(), Box<dyn Error>> {
     let result = if input.raw {
-        let raw = parse_raw_data(input.file)?;
+        let raw = input.load_raw()?;
         serialize(format, raw)?
     } else {
-        let module = parse_file(base, &input.file)?;
-        let module = update_types_from_file(module, input.no_map, input.typemap, None)?;
+        let module = load_module(base, defines, &input, None)?;
         serialize(format, module)?
     };
 
-    print_or_write(out.out, &result)?;
+    if format.is_binary() {
+        out.write_bytes(&result)?;
+    } else {
+        out.write(&String::from_utf8(result)?)?;
+    }
+    Ok(())
+}
+
+/// Formats the files, returns `false` if `check` is set and some files aren't formatted.
+///
+/// Without `in_place` and `check` the formatted file is written to `out`, exactly as
+/// `in_place` would write it.
+fn pretty_files(
+    PrettyData {
+        in_place,
+        check,
+        organize,
+        sort_types,
+        files,
+    }: PrettyData,
+    out: &mut impl Write,
+) -> Result<bool, Box<dyn Error>> {
+    let files = collect_files(&files, "svc")?;
+    if files.len() > 1 && !in_place && !check {
+        return Err("Formatting multiple files requires --in-place or --check.".into());
+    }
+
+    // formatting can run in parallel, the results are handled in order afterwards
+    let results = map_ordered(&files, |file| -> anyhow::Result<(String, String)> {
+        let content = std::fs::read_to_string(file).with_path(file)?;
+        let raw =
+            parse_raw(&content).map_err(|e| Diagnostic(format!("{}: {e}", file.display())))?;
+        let raw = if organize {
+            organize::organize(raw, sort_types)
+        } else {
+            raw
+        };
+        let pretty = pretty(&raw);
+        let pretty_raw = parse_raw(&pretty)?;
+        assert_eq!(
+            raw.iter()
+                .map(ComparableAstElement::from)
+                .collect::<Vec<_>>(),
+            pretty_raw
+                .iter()
+                .map(ComparableAstElement::from)
+                .collect::<Vec<_>>(),
+        );
+        Ok((content, pretty))
+    });
+
+    let mut unformatted = Vec::new();
+    for (file, result) in files.into_iter().zip(results) {
+        // the io errors were classified on another thread
+        let (content, pretty) = result.classify(ErrorCode::Parse)?;
+        if check {
+            if content != pretty {
+                unformatted.push(file);
+            }
+        } else if in_place {
+            if content != pretty {
+                std::fs::write(&file, pretty).with_path(&file)?;
+            }
+        } else {
+            write!(out, "{pretty}")?;
+        }
+    }
+
+    for file in &unformatted {
+        writeln!(out, "{}", file.display())?;
+    }
+    Ok(unformatted.is_empty())
+}
+
+/// Renames the element in the files and writes the changed ones, whose names are printed.
+fn rename_files(RenameData { from, to, files }: RenameData) -> Result<(), Box<dyn Error>> {
+    let mut changed = Vec::new();
+    for file in collect_files(&files, "svc")? {
+        let content = std::fs::read_to_string(&file).with_path(&file)?;
+        let mut raw =
+            parse_raw(&content).map_err(|e| Diagnostic(format!("{}: {e}", file.display())))?;
+        let declared = rename::declares(&raw, &to);
+        if rename::rename(&mut raw, &from, &to) {
+            if declared {
+                return Err(Diagnostic(format!(
+                    "{}: \"{to}\" is already declared, renaming \"{from}\" would declare it twice.",
+                    file.display()
+                ))
+                .into());
+            }
+            let pretty = pretty(&raw);
+            // e.g. keywords or names with spaces
+            if let Err(e) = parse_raw(&pretty) {
+                return Err(Diagnostic(format!("\"{to}\" can't be used as a name: {e}")).into());
+            }
+            changed.push((file, pretty));
+        }
+    }
+
+    // nothing is written if any file would become invalid
+    for (file, pretty) in changed {
+        std::fs::write(&file, pretty).with_path(&file)?;
+        println!("{}", file.display());
+    }
+    Ok(())
+}
+
+/// Prints the hash (or the canonical form) of the files.
+fn hash_files(HashData { canonical, files }: HashData) -> Result<(), Box<dyn Error>> {
+    let files = collect_files(&files, "svc")?;
+    if canonical && files.len() > 1 {
+        return Err("--canonical can only be used with a single file.".into());
+    }
+
+    let results = map_ordered(&files, |file| -> anyhow::Result<String> {
+        let content = std::fs::read_to_string(file).with_path(file)?;
+        let raw =
+            parse_raw(&content).map_err(|e| Diagnostic(format!("{}: {e}", file.display())))?;
+        Ok(if canonical {
+            canonical::canonical_form(&raw)
+        } else {
+            canonical::fingerprint(&raw)
+        })
+    });
+
+    for (file, result) in files.iter().zip(results) {
+        let result = result.classify(ErrorCode::Parse)?;
+        if canonical {
+            print!("{result}");
+        } else if files.len() > 1 {
+            println!("{result}  {}", file.display());
+        } else {
+            println!("{result}");
+        }
+    }
+    Ok(())
+}
+
+/// Returns the given type, or the only data type of the module.
+fn choose_type(module: &ast::SsdModule, typ: Option<String>) -> Result<String, Box<dyn Error>> {
+    match (typ, module.data_types.first()) {
+        (Some(typ), _) => Ok(typ),
+        (None, Some((name, _))) if module.data_types.len() == 1 => Ok(name.clone()),
+        (None, _) => Err("The model has more than one data type, choose one with --type.".into()),
+    }
+}
+
+/// Parses the files together with the files they import and prints the errors and lint findings
+/// which aren't in the baseline, returns `false` if there are errors.
+fn check_files(
+    base: &Path,
+    defines: &Defines,
+    CheckData {
+        primitives,
+        targets,
+        #[cfg(feature = "rhai")]
+        lint_scripts,
+        baseline: baseline_file,
+        update_baseline,
+        files,
+    }: CheckData,
+) -> Result<bool, Box<dyn Error>> {
+    let config = lint::Config::load(base)?;
+    #[cfg(feature = "rhai")]
+    let lint_scripts = {
+        #[allow(unused_mut)]
+        let mut scripts = collect_files(&lint_scripts, "rhai")?;
+        #[cfg(feature = "wasm")]
+        {
+            scripts.extend(collect_files(&lint_scripts, "wasm")?);
+            scripts.sort();
+            scripts.dedup();
+        }
+        scripts
+    };
+    #[cfg(not(feature = "rhai"))]
+    let _ = defines;
+    let mut baseline = match &baseline_file {
+        Some(path) if !update_baseline => {
+            Some(lint::Baseline::load(path).map_err(|e| Diagnostic(e.to_string()))?)
+        }
+        _ => None,
+    };
+    let mut found = lint::Baseline::default();
+    let mut suppressed = 0;
+    let mut success = true;
+    for file in collect_files(&files, "svc")? {
+        let name = file.display().to_string();
+        let loaded = timings::measure(Phase::Imports, Some(&file), || {
+            imports::load_with_imports(base, &file)
+        });
+        match loaded {
+            Ok(modules) => {
+                let module = modules.last().expect("the file");
+                let mut findings = Vec::new();
+                #[cfg(feature = "rhai")]
+                for script in &lint_scripts {
+                    for report in run_lint(defines, script, module.clone())? {
+                        let level = if report.level == "deny" {
+                            lint::Level::Deny
+                        } else {
+                            lint::Level::Warn
+                        };
+                        let span = if report.span.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}: ", report.span)
+                        };
+                        let finding = format!("{span}{} ({})", report.message, script.display());
+                        findings.push((level, finding));
+                    }
+                }
+                for lint in lint::lint_naming(module, &config.lints) {
+                    findings.push((lint.level, lint.to_string()));
+                }
+                for unknown in imports::unknown_imports(base, &modules) {
+                    findings.push((lint::Level::Warn, unknown.to_string()));
+                }
+                for unknown in imports::unknown_types(&modules, &primitives) {
+                    findings.push((lint::Level::Warn, unknown.to_string()));
+                }
+                for clash in idents::keyword_clashes(module, &targets)? {
+                    findings.push((lint::Level::Warn, clash.to_string()));
+                }
+
+                for (level, finding) in findings {
+                    if baseline
+                        .as_mut()
+                        .is_some_and(|baseline| baseline.take(&name, &finding))
+                    {
+                        suppressed += 1;
+                        continue;
+                    }
+                    if update_baseline {
+                        found.add(&name, finding);
+                    } else {
+                        success &= level != lint::Level::Deny;
+                        println!("{name}: {level}: {finding}");
+                    }
+                }
+            }
+            Err(e) => {
+                success = false;
+                println!("{}: {e:#}", file.display());
+            }
+        }
+    }
+    if let (Some(path), true) = (&baseline_file, update_baseline) {
+        found.save(path)?;
+        eprintln!("Wrote {} findings to {}.", found.len(), path.display());
+    } else if suppressed > 0 {
+        eprintln!("{suppressed} known findings are suppressed by the baseline.");
+    }
+    Ok(success)
+}
+
+/// Runs a lint script or wasm plugin (`ssd check --lint`), picked by the extension of the file.
+#[cfg(feature = "rhai")]
+fn run_lint(
+    defines: &Defines,
+    lint: &Path,
+    module: ssd_data::SsdModule,
+) -> Result<Vec<ssd_data::LintDiagnostic>, Box<dyn Error>> {
+    #[cfg(feature = "wasm")]
+    if lint
+        .extension()
+        .is_some_and(|extension| extension == "wasm")
+    {
+        return Ok(generators::wasm::run_lint(defines, lint, module)?);
+    }
+    generators::rhai::run_lint(defines, lint, module)
+}
+
+/// Writes the module of the file bundled with the types it uses from imported modules.
+fn bundle_file(
+    base: &Path,
+    BundleData {
+        flatten,
+        format,
+        out,
+        file,
+    }: BundleData,
+) -> Result<(), Box<dyn Error>> {
+    let modules = timings::measure(Phase::Imports, Some(&file), || {
+        imports::load_with_imports(base, &file)
+    })
+    .classify(ErrorCode::Parse)?;
+    // the last module is the one of the file
+    let imported = modules.iter().rev().skip(1).rev();
+    let mut inputs = vec![file];
+    inputs.extend(imports::module_files(base, imported));
+    let module = bundle::bundle(modules, flatten)?;
+    let result = serialize(format, module)?;
+
+    if format.is_binary() {
+        out.write_bytes(&result)?;
+    } else {
+        out.write(&String::from_utf8(result)?)?;
+    }
+    out.write_manifest(inputs)?;
+    Ok(())
+}
+
+/// Prints the violations of the data file, returns `false` if there are any.
+fn validate_file(
+    base: &PathBuf,
+    ValidateData {
+        model,
+        typ,
+        raw_format,
+        file,
+    }: ValidateData,
+) -> Result<bool, Box<dyn Error>> {
+    let path = std::fs::canonicalize(shellexpand::full(model.to_str().unwrap())?.to_string())?;
+    let module = parse_file(base, &path)?;
+    let typ = choose_type(&module, typ)?;
+    let data = parse_raw_data_as(file, raw_format).classify(ErrorCode::InvalidData)?;
+
+    let violations = validate::validate(&module, &typ, &data)?;
+    for violation in &violations {
+        println!("{violation}");
+    }
+    Ok(violations.is_empty())
+}
+
+/// Writes random values of the type in the chosen format.
+fn mock_data(
+    base: &PathBuf,
+    MockData {
+        typ,
+        count,
+        format,
+        seed,
+        out,
+        file,
+    }: MockData,
+) -> Result<(), Box<dyn Error>> {
+    let path = std::fs::canonicalize(shellexpand::full(file.to_str().unwrap())?.to_string())?;
+    let module = parse_file(base, &path)?;
+    let typ = choose_type(&module, typ)?;
+    let mut values = mock::mock(&module, &typ, count.unwrap_or(1), seed)?;
+    let result = match count {
+        Some(_) => serialize(format, values)?,
+        None => serialize(format, values.remove(0))?,
+    };
+
+    if format.is_binary() {
+        out.write_bytes(&result)?;
+    } else {
+        out.write(&String::from_utf8(result)?)?;
+    }
+    out.write_manifest(vec![file])?;
+    Ok(())
+}
+
+/// Writes the gRPC messages and stubs for the services of the description file.
+fn grpc_file(
+    base: &PathBuf,
+    GrpcData { target, out, file }: GrpcData,
+) -> Result<(), Box<dyn Error>> {
+    let path = std::fs::canonicalize(shellexpand::full(file.to_str().unwrap())?.to_string())?;
+    let module = parse_file(base, &path)?;
+    out.write(&grpc::generate(&module, target)?)?;
+    out.write_manifest(vec![file])?;
+    Ok(())
+}
+
+/// Runs the generators of `ssd build` like `ssd::build()` does in build scripts.
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+fn build_files(base: &Path, defines: Defines, params: options::BuildData) -> anyhow::Result<()> {
+    let mut build = ssd::build()
+        .base(base)
+        .out_dir(params.out_dir)
+        .extension(&params.extension)
+        .defines(defines)
+        .rerun_if_changed(false);
+    for input in params.inputs {
+        build = build.input(input);
+    }
+    for generator in params.generators {
+        build = build.generator(generator);
+    }
+    for typemap in params.typemaps {
+        build = build.typemap(typemap);
+    }
+    for transform in params.transforms {
+        build = build.transform(transform);
+    }
+    if params.no_map {
+        build = build.no_map();
+    }
+    build.run()
+}
+
+/// Prints the breaking changes between the versions of a file, returns `false` if there are
+/// any without a new major version.
+fn compat_files(CompatData { old, new }: CompatData) -> Result<bool, Box<dyn Error>> {
+    let parse = |file: &PathBuf| -> anyhow::Result<ssd_data::SsdModule> {
+        let content = std::fs::read_to_string(file).with_path(file)?;
+        let module = parser::parse(&content, Namespace::new("compat"))
+            .map_err(|e| Diagnostic(format!("{}: {e}", file.display())))?;
+        Ok(module)
+    };
+    let old = parse(&old).classify(ErrorCode::Parse)?;
+    let new = parse(&new).classify(ErrorCode::Parse)?;
+
+    let changes = compat::breaking_changes(&old, &new);
+    if changes.is_empty() {
+        println!("No breaking changes.");
+        return Ok(true);
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+    match compat::allows_breaking_changes(old.version.as_deref(), new.version.as_deref()) {
+        Ok(true) => Ok(true),
+        Ok(false) => {
+            eprintln!(
+                "Breaking changes require a new major version, but the version changed from {} to {}.",
+                old.version.unwrap_or_default(),
+                new.version.unwrap_or_default()
+            );
+            Ok(false)
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            Ok(false)
+        }
+    }
+}
+
+/// Writes the description of the data passed to generators.
+fn schema_file(SchemaData { format, out }: SchemaData) -> Result<(), Box<dyn Error>> {
+    out.write(&schema::schema(format)?)?;
+    out.write_manifest(Vec::new())?;
+    Ok(())
+}
+
+/// Repeats the runs which wrote the manifests without writing anything and prints the
+/// differences to the files on disk, returns `false` if any file differs.
+fn verify_manifests(base: &PathBuf, manifests: Vec<PathBuf>) -> Result<bool, Box<dyn Error>> {
+    let mut success = true;
+    for path in manifests {
+        let name = path.display().to_string();
+        let manifest = Manifest::load(&path).classify(ErrorCode::InvalidData)?;
+        if manifest.command.is_empty() {
+            return Err(
+                Diagnostic(format!("{name} doesn't record the run which wrote it.")).into(),
+            );
+        }
+        let cli = Args::try_parse_from(std::iter::once("ssd".to_owned()).chain(manifest.command))
+            .map_err(|e| Diagnostic(format!("{name}: {e}")))?;
+        let defines =
+            load_defines(&cli.define_files, cli.defines).classify(ErrorCode::InvalidData)?;
+        let dry_run = Arc::new(Mutex::new(BTreeMap::new()));
+        match cli.command {
+            SubCommand::Generate(mut generator) => {
+                generator.out_mut().dry_run = Some(dry_run.clone());
+                generate_files(base, defines, generator, Vec::new())?;
+            }
+            SubCommand::Bundle(mut params) => {
+                params.out.dry_run = Some(dry_run.clone());
+                bundle_file(base, params)?;
+            }
+            SubCommand::Mock(mut params) => {
+                params.out.dry_run = Some(dry_run.clone());
+                mock_data(base, params)?;
+            }
+            SubCommand::Grpc(mut params) => {
+                params.out.dry_run = Some(dry_run.clone());
+                grpc_file(base, params)?;
+            }
+            SubCommand::Schema(mut params) => {
+                params.out.dry_run = Some(dry_run.clone());
+                schema_file(params)?;
+            }
+            _ => {
+                let message =
+                    format!("{name} wasn't written by ssd generate, bundle, mock, grpc or schema.");
+                return Err(Diagnostic(message).into());
+            }
+        }
+
+        let generated = dry_run
+            .lock()
+            .map(|files| files.clone())
+            .unwrap_or_default();
+        let paths = generated
+            .keys()
+            .chain(manifest.outputs.iter().map(|output| &output.path))
+            .collect::<BTreeSet<_>>();
+        // the regenerated content is expected, outputs which aren't generated anymore are
+        // expected to be removed
+        let mut expected = BTreeMap::new();
+        let mut on_disk = BTreeMap::new();
+        for path in paths {
+            let key = path.display().to_string();
+            if let Some(content) = generated.get(path) {
+                expected.insert(key.clone(), String::from_utf8_lossy(content).into_owned());
+            }
+            if let Ok(content) = std::fs::read(path) {
+                on_disk.insert(key, String::from_utf8_lossy(&content).into_owned());
+            }
+        }
+        success &= report_case(&name, &diff_files(&expected, &on_disk));
+    }
+    Ok(success)
+}
+
+/// Runs the generator and writes the `--emit-manifest` file, whose inputs get completed by the
+/// define files.
+fn generate_files(
+    base: &PathBuf,
+    defines: Defines,
+    generator: Generator,
+    define_inputs: Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    // shares the record of the written files with the generator
+    let out = generator.io().1.clone();
+    let mut inputs = generator.inputs(base);
+    inputs.extend(define_inputs);
+    let timing = timings::span(Phase::Generate, Some(&generator.io().0.file));
+    match generator {
+        #[cfg(feature = "handlebars")]
+        Generator::Handlebars(params) => {
+            generators::handlebars::generate(base, defines, params)
+                .classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "tera")]
+        Generator::Tera(params) => {
+            generators::tera::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "rhai")]
+        Generator::Rhai(params) => {
+            generators::rhai::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "lua")]
+        Generator::Lua(params) => {
+            generators::lua::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "js")]
+        Generator::Js(params) => {
+            generators::js::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "python")]
+        Generator::PythonScript(params) => {
+            generators::python::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        Generator::Exec(params) => {
+            generators::exec::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        Generator::Data(params) => {
+            generate_data(base, &defines, params).classify(ErrorCode::Generator)?;
+        }
+
+        #[cfg(feature = "wasm")]
+        Generator::Wasm(params) => {
+            generators::wasm::generate(base, defines, params).classify(ErrorCode::Generator)?;
+        }
+    }
+    drop(timing);
+    out.write_manifest(inputs)?;
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> ExitCode {
     let cli = Args::parse();
+    if cli.timings.is_some() {
+        timings::enable();
+    }
+    if let Some(backend) = cli.parser {
+        parser::set_parser_backend(backend);
+    }
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let code = exit::code_of(e.as_ref());
+            eprintln!("Error[{}]: {e:#}", code.id());
+            ExitCode::from(code.exit_code())
+        }
+    }
+}
 
+fn run(cli: Args) -> Result<(), Box<dyn Error>> {
     let base = std::fs::canonicalize(
         shellexpand::full(std::env::current_dir()?.to_str().unwrap())?.to_string(),
     )?;
-    let defines: HashMap<String, String> = cli.defines.into_iter().collect();
+    let mut define_inputs = cli.define_files.clone();
+    define_inputs.extend(define_value_files(&cli.defines));
+    let defines = load_defines(&cli.define_files, cli.defines).classify(ErrorCode::InvalidData)?;
     match cli.command {
-        SubCommand::Debug(data) => {
-            let path =
-                std::fs::canonicalize(shellexpand::full(data.file.to_str().unwrap())?.to_string())?;
+        SubCommand::Debug(params) => debug_file(&base, &defines, params)?,
 
-            match parse_file(&base, &path) {
-                Ok(ns) => println!("{ns:#?}"),
-                Err(e) => eprintln!("{e}"),
+        SubCommand::Pretty(params) => {
+            if !pretty_files(params, &mut std::io::stdout().lock()).classify(ErrorCode::Parse)? {
+                exit::fail(ErrorCode::Unformatted);
             }
         }
 
-        SubCommand::Pretty(PrettyData { in_place, input }) => {
-            let raw = parse_file_raw(&input.file)?;
-            let pretty = pretty(&raw);
-            let pretty_raw = parse_raw(&pretty)?;
-            assert_eq!(
-                raw.iter()
-                    .map(ComparableAstElement::from)
-                    .collect::<Vec<_>>(),
-                pretty_raw
-                    .iter()
-                    .map(ComparableAstElement::from)
-                    .collect::<Vec<_>>(),
-            );
-            if in_place {
-                std::fs::write(input.file, pretty)?;
-            } else {
-                println!("{pretty}");
+        SubCommand::Hash(params) => hash_files(params).classify(ErrorCode::Parse)?,
+
+        SubCommand::Mock(params) => mock_data(&base, params)?,
+
+        SubCommand::Grpc(params) => grpc_file(&base, params)?,
+
+        SubCommand::Check(params) => {
+            if !check_files(&base, &defines, params)? {
+                exit::fail(ErrorCode::CheckFailed);
+            }
+        }
+
+        SubCommand::Bundle(params) => bundle_file(&base, params)?,
+
+        SubCommand::Rename(params) => rename_files(params).classify(ErrorCode::Parse)?,
+
+        SubCommand::Compat(params) => {
+            if !compat_files(params)? {
+                exit::fail(ErrorCode::CheckFailed);
+            }
+        }
+
+        SubCommand::Validate(params) => {
+            if !validate_file(&base, params)? {
+                exit::fail(ErrorCode::Violations);
+            }
+        }
+
+        SubCommand::Cache(CacheCommand::Clear) => cache::clear(&base)?,
+
+        #[cfg(any(
+            feature = "rhai",
+            feature = "handlebars",
+            feature = "tera",
+            feature = "wasm"
+        ))]
+        SubCommand::Serve {
+            listen,
+            allow_origin,
+        } => serve::serve(&base, &defines, &listen, allow_origin.as_deref())?,
+
+        #[cfg(any(
+            feature = "rhai",
+            feature = "handlebars",
+            feature = "tera",
+            feature = "wasm"
+        ))]
+        SubCommand::Test {
+            manifest,
+            cases,
+            bless,
+        } => {
+            if !golden::run_tests(&manifest, &defines, &cases, bless)? {
+                exit::fail(ErrorCode::Mismatch);
             }
         }
 
@@ -110,41 +793,322 @@ fn main() -> Result<(), Box<dyn Error>> {
             generate(shell, &mut cli, name, &mut std::io::stdout());
         }
 
+        SubCommand::LanguageServer {
+            engine: Some(engine),
+            out,
+        } => {
+            let typescript = out.to_string_lossy().ends_with(".d.ts");
+            std::fs::write(out, schema::template_context(engine, typescript)?)?;
+        }
+
         #[cfg(feature = "rhai")]
-        SubCommand::LanguageServer { out } => {
+        SubCommand::LanguageServer { engine: None, out } => {
             use std::{cell::RefCell, rc::Rc};
             let messages = Rc::new(RefCell::new(Vec::new()));
+            let files = Rc::new(RefCell::new(generators::rhai::EmittedFiles::new()));
 
-            let engine = build_engine(messages.clone(), false);
-            engine.definitions().write_to_file(out).unwrap();
+            let engine = build_engine(messages.clone(), files, None, false);
+            std::fs::write(out, generators::rhai::definitions(&engine))?;
         }
 
-        SubCommand::Generate(generator) => match generator {
-            #[cfg(feature = "handlebars")]
-            Generator::Handlebars(params) => {
-                generators::handlebars::generate(&base, defines, params)?;
-            }
+        #[cfg(not(feature = "rhai"))]
+        SubCommand::LanguageServer { engine: None, .. } => {
+            return Err("The rhai definitions require the feature \"rhai\", use --engine.".into());
+        }
 
-            #[cfg(feature = "tera")]
-            Generator::Tera(params) => {
-                generators::tera::generate(&base, defines, params)?;
+        #[cfg(feature = "rhai")]
+        SubCommand::TestScript(params) => {
+            if !generators::rhai::test_script(&defines, params)? {
+                exit::fail(ErrorCode::Mismatch);
             }
+        }
 
-            #[cfg(feature = "rhai")]
-            Generator::Rhai(params) => {
-                generators::rhai::generate(&base, defines, params)?;
-            }
+        SubCommand::Generate(generator) => {
+            generate_files(&base, defines, generator, define_inputs)?;
+        }
 
-            Generator::Data(params) => {
-                generate_data(&base, params)?;
-            }
+        #[cfg(any(
+            feature = "rhai",
+            feature = "handlebars",
+            feature = "tera",
+            feature = "wasm"
+        ))]
+        SubCommand::Build(params) => build_files(&base, defines, params)?,
+
+        SubCommand::Schema(params) => schema_file(params)?,
 
-            #[cfg(feature = "wasm")]
-            Generator::Wasm(params) => {
-                generators::wasm::generate(&base, defines, params)?;
+        SubCommand::Verify { manifests } => {
+            if !verify_manifests(&base, manifests)? {
+                exit::fail(ErrorCode::Mismatch);
             }
-        },
+        }
     };
 
+    if let Some(format) = cli.timings {
+        if let Some(report) = timings::report(format)? {
+            eprint!("{report}");
+        }
+    }
     Ok(())
 }
+
+#[cfg(all(feature = "rhai", feature = "handlebars"))]
+#[test]
+fn test_manifest_inputs() {
+    let dir = std::env::temp_dir().join(format!("ssd-inputs-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("partials")).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let file = |name: &str, content: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+    let main_svc = file("main.svc", "import foo::Foo;\ndata Bar { foo: Foo, };");
+    file("foo.svc", "data Foo {};");
+    let template = file("n.hbs", "{{> decl}}");
+    let partial = file("partials/decl.hbs", "decl");
+    let script = file("gen.rhai", "import \"common\" as c;\nc::run();");
+    let common = file("common.rhai", "fn run() {}");
+    let inputs = |args: &[&str], files: &[&PathBuf]| {
+        let args = ["ssd", "generate"]
+            .iter()
+            .map(PathBuf::from)
+            .chain(args.iter().map(PathBuf::from))
+            .chain(files.iter().map(|file| file.to_path_buf()));
+        let SubCommand::Generate(generator) = Args::parse_from(args).command else {
+            panic!("not a generator");
+        };
+        generator.inputs(&dir)
+    };
+
+    assert_eq!(
+        inputs(
+            &[
+                "handlebars",
+                "--partials",
+                &dir.join("partials").to_string_lossy()
+            ],
+            &[&template, &main_svc],
+        ),
+        [
+            main_svc.clone(),
+            PathBuf::from("foo.svc"),
+            template,
+            partial
+        ]
+    );
+    assert_eq!(
+        inputs(&["rhai"], &[&script, &main_svc]),
+        [main_svc, PathBuf::from("foo.svc"), script, common]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "handlebars")]
+#[test]
+fn test_build_files() {
+    let dir = std::env::temp_dir().join(format!("ssd-build-files-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("api")).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    std::fs::write(dir.join("api/a.svc"), "data A { a: i32, };").unwrap();
+    std::fs::write(
+        dir.join("gen.hbs"),
+        "{{#each module.data_types}}{{this.[0]}}{{/each}}",
+    )
+    .unwrap();
+    let run = || {
+        let generator = format!("handlebars:{}", dir.join("gen.hbs").display());
+        let inputs = dir.join("api").display().to_string();
+        let out_dir = dir.join("out").display().to_string();
+        let args = [
+            "ssd",
+            "build",
+            &inputs,
+            "-g",
+            &generator,
+            "-o",
+            &out_dir,
+            "--extension",
+            "txt",
+        ];
+        let SubCommand::Build(params) = Args::parse_from(args).command else {
+            panic!("not build");
+        };
+        build_files(&dir, Defines::new(), params).unwrap();
+    };
+
+    run();
+    let output = dir.join("out/api/a.txt");
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "A");
+    assert!(dir.join("out/ssd-manifest.json").is_file());
+
+    std::fs::remove_file(dir.join("api/a.svc")).unwrap();
+    run();
+    assert!(!output.exists());
+
+    let error = Args::try_parse_from(["ssd", "build", "api", "-g", "gen.hbs", "-o", "out"]);
+    assert!(error.is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pretty_round_trip() {
+    let dir = std::env::temp_dir().join(format!("ssd-pretty-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("a.svc");
+    let formatted = dir.join("fmt.svc");
+    std::fs::write(&input, "data Rect{x:i32,};").unwrap();
+    let pretty = |check: bool, file: &PathBuf| {
+        let mut out = Vec::new();
+        let formatted = pretty_files(
+            PrettyData {
+                in_place: false,
+                check,
+                organize: false,
+                sort_types: false,
+                files: vec![file.clone()],
+            },
+            &mut out,
+        )
+        .unwrap();
+        (formatted, out)
+    };
+
+    // `ssd pretty a.svc > fmt.svc && ssd pretty --check fmt.svc`
+    let (_, out) = pretty(false, &input);
+    assert_eq!(
+        String::from_utf8_lossy(&out),
+        "data Rect {\n\tx: i32,\n};\n"
+    );
+    std::fs::write(&formatted, out).unwrap();
+    assert_eq!(pretty(true, &formatted), (true, Vec::new()));
+    let (formatted, out) = pretty(true, &input);
+    assert!(!formatted);
+    assert_eq!(
+        String::from_utf8_lossy(&out),
+        format!("{}\n", input.display())
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_rename_files() {
+    let dir = std::env::temp_dir().join(format!("ssd-rename-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let users = dir.join("users.svc");
+    let groups = dir.join("groups.svc");
+    let content = "data Account {};\ndata User {};\n";
+    std::fs::write(&users, content).unwrap();
+    std::fs::write(&groups, "data Group { owner: users::User, };\n").unwrap();
+    let rename = |from: &str, to: &str| {
+        rename_files(RenameData {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            files: vec![dir.clone()],
+        })
+        .map_err(|e| e.to_string())
+    };
+
+    // nothing is written if the new name is taken
+    let error = rename("User", "Account").unwrap_err();
+    assert!(error.contains("\"Account\" is already declared"), "{error}");
+    assert_eq!(std::fs::read_to_string(&users).unwrap(), content);
+    assert!(std::fs::read_to_string(&groups)
+        .unwrap()
+        .contains("users::User"));
+
+    rename("User", "Member").unwrap();
+    assert!(std::fs::read_to_string(&users)
+        .unwrap()
+        .contains("data Member"));
+    assert!(std::fs::read_to_string(&groups)
+        .unwrap()
+        .contains("users::Member"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(all(feature = "rhai", feature = "handlebars"))]
+#[test]
+fn test_exit_codes() {
+    let dir = std::env::temp_dir().join(format!("ssd-exit-codes-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("orders.svc");
+    let broken = dir.join("broken.svc");
+    let script = dir.join("orders.rhai");
+    std::fs::write(&input, "data Order { id: u64, };").unwrap();
+    std::fs::write(&broken, "data Order {").unwrap();
+    std::fs::write(&script, r#"emit("orders");"#).unwrap();
+    let missing = dir.join("missing");
+
+    // every run gets its own thread, because the code of a run is recorded per thread
+    let code = |args: Vec<PathBuf>| {
+        std::thread::spawn(move || {
+            let args = Args::parse_from([PathBuf::from("ssd")].into_iter().chain(args));
+            let error = run(args).unwrap_err();
+            (exit::code_of(error.as_ref()), error.to_string())
+        })
+        .join()
+        .unwrap()
+    };
+    let args = |args: &[&str], files: &[&PathBuf]| {
+        args.iter()
+            .map(PathBuf::from)
+            .chain(files.iter().map(|file| file.to_path_buf()))
+            .collect::<Vec<_>>()
+    };
+
+    let missing_svc = missing.with_extension("svc");
+    for (arguments, file) in [
+        (args(&["pretty", "--check"], &[&missing_svc]), &missing_svc),
+        (args(&["hash"], &[&missing_svc]), &missing_svc),
+        (
+            args(&["rename", "--from", "A", "--to", "B"], &[&missing_svc]),
+            &missing_svc,
+        ),
+        (
+            args(
+                &["generate", "rhai", "--no-cache"],
+                &[&script, &missing_svc],
+            ),
+            &missing_svc,
+        ),
+        (
+            args(&["generate", "rhai", "--no-cache"], &[&missing, &input]),
+            &missing,
+        ),
+        (
+            args(
+                &["generate", "handlebars", "--no-cache"],
+                &[&missing, &input],
+            ),
+            &missing,
+        ),
+    ] {
+        let (code, message) = code(arguments);
+        assert_eq!(code, ErrorCode::Io, "{message}");
+        assert_eq!(code.exit_code(), 7);
+        assert!(
+            message.starts_with(&file.display().to_string()),
+            "{message}"
+        );
+    }
+
+    let (code, _) = code(args(&["hash"], &[&broken]));
+    assert_eq!(code, ErrorCode::Parse);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parser_option() {
+    let parse = |args: &[&str]| Args::try_parse_from(["ssd"].iter().chain(args)).map(|a| a.parser);
+    assert_eq!(parse(&["hash", "a.svc"]).unwrap(), None);
+    assert_eq!(
+        parse(&["hash", "a.svc", "--parser", "rd"]).unwrap(),
+        Some(parser::ParserBackend::Rd)
+    );
+    assert_eq!(
+        parse(&["--parser", "pest", "hash", "a.svc"]).unwrap(),
+        Some(parser::ParserBackend::Pest)
+    );
+    assert!(parse(&["hash", "a.svc", "--parser", "c"]).is_err());
+}
@@ -0,0 +1,110 @@
+//! The manifest of a generator run (`--emit-manifest`), which lists the written files for build
+//! systems like Bazel, Buck or ninja:
+//! ```json
+//! {
+//!   "inputs": ["api/users.svc", "generators/rust.rhai", "generators/rust.tym"],
+//!   "outputs": [{"path": "out/users.rs", "sha256": "9f86d08..."}],
+//!   "command": ["generate", "rhai", "generators/rust.rhai", "api/users.svc", "-o", "out/users.rs"]
+//! }
+//! ```
+//! The inputs are the files the outputs were generated from: the description (or raw data)
+//! files, the script, template, plugin or program of the generator, the typemaps and the define
+//! files. Files included by scripts or templates aren't listed.
+//!
+//! The arguments of the run are recorded as `command`, so `ssd verify` can repeat it.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub inputs: Vec<PathBuf>,
+    /// Sorted by path.
+    pub outputs: Vec<Output>,
+    /// The arguments of the run which wrote the manifest (without `ssd`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    pub path: PathBuf,
+    /// The hash of the written content.
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Creates the manifest for the written files, reading them to hash their content.
+    ///
+    /// Inputs which don't exist (e.g. builtin typemaps) are left out, as are duplicates.
+    pub fn new(inputs: Vec<PathBuf>, written: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut unique_inputs = Vec::new();
+        for input in inputs {
+            if input.exists() && !unique_inputs.contains(&input) {
+                unique_inputs.push(input);
+            }
+        }
+        let mut written = written.to_vec();
+        written.sort();
+        written.dedup();
+        let outputs = written
+            .into_iter()
+            .map(|path| {
+                let content =
+                    std::fs::read(&path).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+                let sha256 = format!("{:x}", Sha256::digest(content));
+                Ok(Output { path, sha256 })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            inputs: unique_inputs,
+            outputs,
+            command: Vec::new(),
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let dir = std::env::temp_dir().join(format!("ssd-manifest-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("api.svc");
+    let output = dir.join("api.txt");
+    std::fs::write(&input, "data Rect { x: i32, };").unwrap();
+    std::fs::write(&output, "test").unwrap();
+
+    let mut manifest = Manifest::new(
+        vec![input.clone(), PathBuf::from("builtin:rust"), input.clone()],
+        &[output.clone(), output.clone()],
+    )
+    .unwrap();
+    assert_eq!(manifest.inputs, [input]);
+    assert_eq!(
+        manifest.outputs,
+        [Output {
+            path: output,
+            sha256: "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_owned(),
+        }]
+    );
+    manifest.command = vec!["generate".to_owned(), "rhai".to_owned()];
+    manifest.save(&dir.join("manifest.json")).unwrap();
+    assert_eq!(
+        Manifest::load(&dir.join("manifest.json")).unwrap(),
+        manifest
+    );
+    assert!(Manifest::new(Vec::new(), &[dir.join("missing.txt")]).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,287 @@
+//! Random data for the types of a model, e.g. to seed tests or demos.
+//!
+//! Values respect the types of the properties (integers stay within their range), fixed-size
+//! lists get exactly their number of items, other lists up to [`MAX_ITEMS`], and enums one of their
+//! values. Strings are picked by the name of the property (`email`, `name`, `city`, `url`, ...)
+//! to look realistic. Types which aren't defined in the model (e.g. imported ones) become `null`.
+
+use serde_json::{json, Value};
+use ssd_data::{DataType, Enum, Namespace, SsdModule, TypeName, TypeRef};
+
+const MAX_ITEMS: usize = 5;
+/// Data types nested deeper than this can only be generated with empty lists, e.g. trees.
+const MAX_DEPTH: usize = 8;
+/// Data types can't contain themselves without a list, so this is only reached for such types.
+const DEPTH_LIMIT: usize = 64;
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Barbara", "Dennis", "Edsger", "Frances", "Grace", "Guido", "John", "Ken",
+    "Linus", "Margaret", "Niklaus", "Radia", "Tim",
+];
+const LAST_NAMES: &[&str] = &[
+    "Allen", "Hamilton", "Hopper", "Knuth", "Lamport", "Liskov", "Lovelace", "Perlman", "Ritchie",
+    "Thompson", "Turing", "Wirth",
+];
+const CITIES: &[&str] = &[
+    "Amsterdam",
+    "Berlin",
+    "Buenos Aires",
+    "Cairo",
+    "Lisbon",
+    "Montreal",
+    "Nairobi",
+    "Oslo",
+    "Seoul",
+    "Sydney",
+    "Tokyo",
+    "Vienna",
+];
+const COUNTRIES: &[&str] = &[
+    "Argentina",
+    "Austria",
+    "Canada",
+    "Egypt",
+    "Germany",
+    "Japan",
+    "Kenya",
+    "Norway",
+    "Portugal",
+];
+const WORDS: &[&str] = &[
+    "alpha", "bright", "cloud", "delta", "ember", "forest", "granite", "harbor", "island", "jade",
+    "kernel", "lumen", "meadow", "nova", "orbit", "pixel", "quartz", "river", "summit", "tide",
+];
+
+struct Mocker<'a> {
+    module: &'a SsdModule,
+    rng: fastrand::Rng,
+}
+
+impl Mocker<'_> {
+    fn pick(&mut self, values: &[&str]) -> String {
+        values[self.rng.usize(..values.len())].to_owned()
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|_| self.pick(WORDS))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A string which fits the name of the property.
+    fn string(&mut self, name: &str) -> String {
+        let is_id = name == "id" || name.ends_with("_id") || name.ends_with("Id");
+        let name = name.to_lowercase();
+        let first = self.pick(FIRST_NAMES);
+        let last = self.pick(LAST_NAMES);
+        if name.contains("email") || name.contains("mail") {
+            format!(
+                "{}.{}@example.com",
+                first.to_lowercase(),
+                last.to_lowercase()
+            )
+        } else if name.contains("first_name") || name.contains("firstname") {
+            first
+        } else if name.contains("last_name") || name.contains("lastname") || name == "surname" {
+            last
+        } else if name.contains("user") || name.contains("login") {
+            format!("{}{}", first.to_lowercase(), self.rng.u16(1..1000))
+        } else if name.contains("name") || name.contains("author") {
+            format!("{first} {last}")
+        } else if name.contains("city") {
+            self.pick(CITIES)
+        } else if name.contains("country") {
+            self.pick(COUNTRIES)
+        } else if name.contains("url") || name.contains("link") {
+            format!("https://example.com/{}", self.pick(WORDS))
+        } else if name.contains("phone") {
+            format!("+1-555-{:04}", self.rng.u16(..10000))
+        } else if name.contains("date") || name.contains("time") || name.ends_with("_at") {
+            format!(
+                "20{:02}-{:02}-{:02}T{:02}:{:02}:00Z",
+                self.rng.u8(10..30),
+                self.rng.u8(1..=12),
+                self.rng.u8(1..=28),
+                self.rng.u8(..24),
+                self.rng.u8(..60)
+            )
+        } else if is_id {
+            (0..16).map(|_| self.rng.digit(16)).collect::<String>()
+        } else if name.contains("description") || name.contains("text") || name.contains("comment")
+        {
+            let count = self.rng.usize(4..12);
+            let mut text = self.words(count);
+            text[..1].make_ascii_uppercase();
+            text + "."
+        } else {
+            self.words(1)
+        }
+    }
+
+    /// An integer within the range of the type, small enough to look realistic.
+    fn integer(&mut self, name: &str, min: i64, max: i64) -> i64 {
+        let name = name.to_lowercase();
+        let (low, high) = if name == "age" || name.ends_with("_age") {
+            (18, 90)
+        } else if name.contains("year") {
+            (1970, 2030)
+        } else if min < 0 {
+            (-1000, 1000)
+        } else {
+            (0, 1000)
+        };
+        self.rng.i64(low.clamp(min, max)..=high.clamp(min, max))
+    }
+
+    fn builtin(&mut self, name: &str, typ: &Namespace) -> Value {
+        match typ.to_string().as_str() {
+            "bool" => json!(self.rng.bool()),
+            "string" | "String" => json!(self.string(name)),
+            "char" => json!(self.rng.alphabetic().to_string()),
+            "f32" | "f64" => json!((self.rng.f64() * 100_000.0).round() / 100.0),
+            "i8" => json!(self.integer(name, i8::MIN.into(), i8::MAX.into())),
+            "i16" => json!(self.integer(name, i16::MIN.into(), i16::MAX.into())),
+            "i32" => json!(self.integer(name, i32::MIN.into(), i32::MAX.into())),
+            "i64" | "i128" => json!(self.integer(name, i64::MIN, i64::MAX)),
+            "u8" => json!(self.integer(name, 0, u8::MAX.into())),
+            "u16" => json!(self.integer(name, 0, u16::MAX.into())),
+            "u32" => json!(self.integer(name, 0, u32::MAX.into())),
+            "u64" | "u128" => json!(self.integer(name, 0, i64::MAX)),
+            _ => Value::Null,
+        }
+    }
+
+    fn type_name(
+        &mut self,
+        name: &str,
+        type_name: &TypeName,
+        depth: usize,
+    ) -> anyhow::Result<Value> {
+        self.lists(name, &type_name.typ, &type_name.lists, depth)
+    }
+
+    /// Generates the lists (outermost first) of the type.
+    fn lists(
+        &mut self,
+        name: &str,
+        typ: &Namespace,
+        lists: &[Option<usize>],
+        depth: usize,
+    ) -> anyhow::Result<Value> {
+        let Some((list, inner)) = lists.split_first() else {
+            return self.typ(name, typ, depth);
+        };
+        let count = match *list {
+            Some(count) => count,
+            None if depth >= MAX_DEPTH => 0,
+            None => self.rng.usize(..=MAX_ITEMS),
+        };
+        (0..count)
+            .map(|_| self.lists(name, typ, inner, depth))
+            .collect::<anyhow::Result<_>>()
+            .map(Value::Array)
+    }
+
+    fn typ(&mut self, name: &str, typ: &Namespace, depth: usize) -> anyhow::Result<Value> {
+        match self.module.find_type(typ) {
+            Some(TypeRef::DataType(data_type)) => self.data_type(typ, data_type, depth + 1),
+            Some(TypeRef::Enum(en)) => Ok(self.enum_value(en)),
+            None => Ok(self.builtin(name, typ)),
+        }
+    }
+
+    fn data_type(
+        &mut self,
+        typ: &Namespace,
+        data_type: &DataType,
+        depth: usize,
+    ) -> anyhow::Result<Value> {
+        if depth > DEPTH_LIMIT {
+            anyhow::bail!(
+                "{} contains itself, so there is no finite value of it.",
+                typ.to_string()
+            );
+        }
+        data_type
+            .properties
+            .iter()
+            .map(|(name, property)| Ok((name.clone(), self.type_name(name, property, depth)?)))
+            .collect::<anyhow::Result<_>>()
+            .map(Value::Object)
+    }
+
+    fn enum_value(&mut self, en: &Enum) -> Value {
+        match self.rng.choice(&en.values) {
+            Some((name, _)) => json!(name),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Generates `count` random values of the type (a data type or enum of the module).
+///
+/// The same seed always gives the same values, without a seed they are different on every call.
+///
+/// # Errors
+///
+/// Returns an error if the module has no such type or a data type contains itself without a list.
+pub fn mock(
+    module: &SsdModule,
+    typ: &str,
+    count: usize,
+    seed: Option<u64>,
+) -> anyhow::Result<Vec<Value>> {
+    let typ = Namespace::new(typ);
+    if module.find_type(&typ).is_none() {
+        anyhow::bail!(
+            "The model has no data type or enum \"{}\".",
+            typ.to_string()
+        );
+    }
+    let mut mocker = Mocker {
+        module,
+        rng: seed.map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed),
+    };
+    (0..count).map(|_| mocker.typ("", &typ, 0)).collect()
+}
+
+#[test]
+fn test_mock() {
+    let module = crate::parser::parse(
+        r"
+        data User {
+            id: string,
+            email: string,
+            age: u8,
+            role: Role,
+            key: 4 of u8,
+            friends: list of Friend,
+        };
+        data Friend { name: string, since: i32, };
+        data Loop { next: Loop, };
+        enum Role { Admin, Guest, };
+        ",
+        Namespace::new("users"),
+    )
+    .unwrap();
+
+    let users = mock(&module, "User", 20, Some(7)).unwrap();
+    assert_eq!(users, mock(&module, "User", 20, Some(7)).unwrap());
+    assert_eq!(users.len(), 20);
+    for user in &users {
+        let user = serde_value::to_value(user).unwrap();
+        assert_eq!(
+            crate::validate::validate(&module, "User", &user).unwrap(),
+            []
+        );
+    }
+    assert!(users[0]["email"]
+        .as_str()
+        .unwrap()
+        .ends_with("@example.com"));
+    assert!((18..=90).contains(&users[0]["age"].as_u64().unwrap()));
+
+    assert!(mock(&module, "Loop", 1, None).is_err());
+    assert!(mock(&module, "Group", 1, None).is_err());
+}
@@ -1,7 +1,43 @@
 use clap::{Parser, ValueEnum};
 use clap_complete::Shell;
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use ssd_data::Meta;
+
+use crate::exit::{Classify, ErrorCode};
+use crate::grpc::GrpcTarget;
+use crate::helper::{
+    check_emitted_paths, merge_raw_data, parse_raw_data_as, print_or_write, print_or_write_bytes,
+    typemap_paths, utc_timestamp, write_files, RawFormat, RawMerge,
+};
+use crate::manifest::Manifest;
+use crate::parser::parse_raw;
+use crate::postprocess::{postprocess, postprocess_files, PostProcess};
+use crate::schema::{SchemaFormat, TemplateEngine};
+use crate::timings::{self, Phase, TimingsFormat};
+
+#[derive(Debug, Parser)]
+pub struct DebugData {
+    #[clap(long)]
+    /// Print a compact, colorized tree of the module instead of its debug representation.
+    pub tree: bool,
+
+    #[clap(long, conflicts_with = "after_transforms")]
+    /// Show the module after removing the elements disabled by `#[cfg(...)]` and applying the
+    /// typemap.
+    pub after_typemap: bool,
+
+    #[clap(long)]
+    /// Show the module exactly as generators get it, after `#[cfg(...)]`, the `--transform`s
+    /// and the typemap.
+    pub after_transforms: bool,
+
+    #[clap(flatten)]
+    pub input: BaseInputData,
+}
 
 #[derive(Debug, Parser)]
 pub struct PrettyData {
@@ -9,8 +45,217 @@ pub struct PrettyData {
     /// if true, write directly into file
     pub in_place: bool,
 
+    #[clap(long, conflicts_with = "in_place")]
+    /// Only check if the files are formatted. Lists the unformatted files and fails if there are any.
+    pub check: bool,
+
+    #[clap(long)]
+    /// Sort imports and group the members of services (dependencies, functions, events).
+    pub organize: bool,
+
+    #[clap(long, requires = "organize")]
+    /// Sort data types and enums alphabetically as well.
+    pub sort_types: bool,
+
+    #[clap(required = true)]
+    /// Files, directories (searched recursively for svc files) or glob patterns.
+    ///
+    /// Multiple files can only be formatted with --in-place or --check.
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct HashData {
+    #[clap(long)]
+    /// Print the canonical form the hash is computed from, instead of the hash.
+    pub canonical: bool,
+
+    #[clap(required = true)]
+    /// Files, directories (searched recursively for svc files) or glob patterns.
+    ///
+    /// For multiple files, every hash is followed by the name of the file.
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CheckData {
+    #[clap(long = "primitive")]
+    /// A type which counts as primitive in addition to the builtin ones (e.g. `Decimal`).
+    /// Can be given multiple times.
+    pub primitives: Vec<String>,
+
+    #[clap(long, value_delimiter = ',')]
+    /// Warn about names which are reserved words of these target languages, e.g.
+    /// `--targets rust,cpp,ts,python` (also c, csharp, java and go).
+    pub targets: Vec<String>,
+
+    #[cfg(feature = "rhai")]
+    #[clap(long = "lint")]
+    /// Rhai scripts or wasm plugins with custom rules (files, directories or glob patterns).
+    ///
+    /// Scripts get the model as `module` and report findings with `report(level, span, message)`,
+    /// where the level is `warn` or `deny`. Plugins export a `lint` function which gets the model
+    /// like generators and returns a list of `{level, span, message}`.
+    pub lint_scripts: Vec<PathBuf>,
+
+    #[clap(long)]
+    /// A file with known findings (lints and warnings), which are neither printed nor fail the
+    /// check. Only findings which aren't in the baseline are reported.
+    pub baseline: Option<PathBuf>,
+
+    #[clap(long, requires = "baseline")]
+    /// Write the current findings to the baseline file instead of reporting them.
+    pub update_baseline: bool,
+
+    #[clap(required = true)]
+    /// Files, directories (searched recursively for svc files) or glob patterns.
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct RenameData {
+    #[clap(long)]
+    /// The name of the data type, enum, service or function.
+    pub from: String,
+    #[clap(long)]
+    /// The new name.
+    pub to: String,
+    #[clap(required = true)]
+    /// Files, directories (searched recursively for svc files) or glob patterns.
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CompatData {
+    /// The description file of the old version.
+    pub old: PathBuf,
+    /// The description file of the new version.
+    pub new: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct BundleData {
+    #[clap(long)]
+    /// Give the types of imported modules their plain name (`User`) instead of their full path
+    /// (`users::User`). Fails if types with the same name are defined differently.
+    pub flatten: bool,
+    #[clap(long, value_enum, default_value = "json-pretty")]
+    /// The output format that should be used.
+    pub format: DataFormat,
     #[clap(flatten)]
-    pub input: BaseInputData,
+    pub out: BaseOutputData,
+    /// The description file of the root module.
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct ValidateData {
+    #[clap(long)]
+    /// The description file defining the type of the data.
+    pub model: PathBuf,
+    #[clap(long = "type")]
+    /// The data type (or enum) the data has to conform to. Can be omitted if the model has only
+    /// one data type.
+    pub typ: Option<String>,
+    #[clap(long, value_enum, default_value_t)]
+    /// The format of the data file. By default, it's picked by the extension of the file
+    /// or the first format which can parse it.
+    pub raw_format: RawFormat,
+    /// The data file to check.
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct MockData {
+    #[clap(long = "type")]
+    /// The data type (or enum) of the values. Can be omitted if the model has only one data type.
+    pub typ: Option<String>,
+    #[clap(long)]
+    /// Generate a list with this many values instead of a single value.
+    pub count: Option<usize>,
+    #[clap(long, value_enum, default_value = "json-pretty")]
+    /// The output format that should be used.
+    pub format: DataFormat,
+    #[clap(long)]
+    /// The same seed always generates the same data. Without it, the data is different on every run.
+    pub seed: Option<u64>,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+    /// The description file defining the type.
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct GrpcData {
+    #[clap(long, value_enum, default_value_t)]
+    /// The language and framework of the stubs.
+    pub target: GrpcTarget,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+    /// The description file defining the services.
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct SchemaData {
+    #[clap(long, value_enum, default_value_t)]
+    /// The format of the description.
+    pub format: SchemaFormat,
+    #[clap(flatten)]
+    pub out: BaseOutputData,
+}
+
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+#[derive(Debug, Parser)]
+pub struct BuildData {
+    #[clap(required = true)]
+    /// Description files, directories (searched recursively for svc files) or glob patterns.
+    pub inputs: Vec<PathBuf>,
+    #[clap(short, long = "generator", value_parser = parse_generator, required = true)]
+    /// A generator as `<name>:<path>` with the name `rhai`, `handlebars`, `tera` or `wasm`,
+    /// e.g. `rhai:generators/rust.rhai`. Can be given multiple times.
+    pub generators: Vec<crate::api::Generator>,
+    #[clap(short, long)]
+    /// The directory the generated files and the manifest of the run are written to.
+    pub out_dir: PathBuf,
+    #[clap(long, default_value = "rs")]
+    /// The extension of the generated files.
+    pub extension: String,
+    #[clap(long = "typemap", visible_alias = "tm")]
+    /// A file containing type mappings, later typemaps override the mappings of earlier ones.
+    pub typemaps: Vec<PathBuf>,
+    #[clap(long)]
+    /// do not use type mappings
+    pub no_map: bool,
+    #[clap(long = "transform")]
+    /// Transformations of the model which run in the given order before generating, like
+    /// with `generate`.
+    pub transforms: Vec<crate::transform::Transform>,
+}
+
+/// Parses a generator given as `<name>:<path>`, e.g. `rhai:generators/rust.rhai`.
+#[cfg(any(
+    feature = "rhai",
+    feature = "handlebars",
+    feature = "tera",
+    feature = "wasm"
+))]
+fn parse_generator(generator: &str) -> anyhow::Result<crate::api::Generator> {
+    let (name, source) = generator
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Expected <name>:<path>, e.g. rhai:generator.rhai."))?;
+    crate::api::Generator::from_name(name, source)
+}
+
+#[derive(Debug, Parser)]
+pub enum CacheCommand {
+    /// Remove the cache of parsed models.
+    Clear,
 }
 
 #[derive(Debug, Parser)]
@@ -18,26 +263,237 @@ pub struct BaseInputData {
     #[clap(long)]
     /// do not use type mappings
     pub no_map: bool,
-    #[clap(long = "tm", long)]
-    /// A file containing type mappings.
+    #[clap(long)]
+    /// Do not read or write the cache of parsed models in `.ssd-cache`.
+    pub no_cache: bool,
+    #[clap(long, conflicts_with = "raw")]
+    /// The namespace of the module (e.g. `my::api`), instead of the one derived from the path
+    /// of the file.
+    pub namespace: Option<String>,
+    #[clap(long = "typemap", visible_alias = "tm")]
+    /// A file containing type mappings (toml, or json/yaml if the file has that extension).
+    ///
+    /// Use `builtin:rust`, `builtin:cpp` or `builtin:typescript` for one of the builtin typemaps.
+    ///
+    /// Can be given multiple times, later typemaps override the mappings of earlier ones.
     ///
     /// If a file with the same name as the script file, but with the extension tym, it
     /// will be used automatically.
     /// e.g.: If there is a file `/generator/script.rhai` and a corresponding
     /// `/generator/script.tym`, it will get used automatically.
-    pub typemap: Option<PathBuf>,
+    pub typemap: Vec<PathBuf>,
+    #[clap(long)]
+    /// Print which mappings of the typemap were applied, which types have no mapping
+    /// and which mappings were never used (to stderr).
+    pub typemap_report: bool,
+    #[clap(long = "transform")]
+    /// Transformations of the model which run in the given order before generating:
+    /// `typemap`, `flatten-imports`, `public-only`, `exclude=<attribute>`,
+    /// `rename-case=<case>` (snake, camel, pascal, kebab, screaming-snake) or
+    /// `namespace-map=<old>=<new>`.
+    ///
+    /// The typemap is applied last, if `typemap` isn't part of the list.
+    pub transforms: Vec<crate::transform::Transform>,
     #[clap(short, long)]
     /// use raw data file as input instead of the ssd data format
     pub raw: bool,
+    #[clap(long, value_enum, default_value_t, requires = "raw")]
+    /// The format of the raw data file. By default, it's picked by the extension of the file
+    /// or the first format which can parse it.
+    pub raw_format: RawFormat,
+    #[clap(long = "raw-file", requires = "raw")]
+    /// More raw data files or directories (standing for the files directly inside them), which
+    /// get merged with `file`. Can be given multiple times.
+    ///
+    /// `file` can be a directory as well.
+    pub raw_files: Vec<PathBuf>,
+    #[clap(long, value_enum, default_value_t, requires = "raw")]
+    /// How multiple raw data files get merged.
+    pub raw_merge: RawMerge,
+    #[clap(skip)]
+    /// Raw data which is used instead of reading `file` (e.g. passed from python).
+    pub raw_data: Option<serde_value::Value>,
+    #[clap(long)]
+    /// Pass the current time to the generator as `meta.timestamp`. Without it, the timestamp is
+    /// empty, so the output doesn't change between runs.
+    pub timestamp: bool,
+    #[clap(long, conflicts_with = "timestamp")]
+    /// Make sure the output is the same on every run and machine: scripts and templates can't
+    /// use the current time, random numbers or environment variables.
+    pub reproducible: bool,
+    #[clap(long, conflicts_with = "raw")]
+    /// Pass the elements of the description file to the generator as `ast` as well: the data
+    /// types, enums, services and imports in their original order, interleaved with the
+    /// comments (e.g. for documentation generators).
+    pub with_ast: bool,
     /// which file to use.
     pub file: PathBuf,
 }
 
-#[derive(Debug, Parser)]
+impl BaseInputData {
+    /// Returns the information about the generator run which generators get as `meta`, for the
+    /// module with the namespace (empty for raw data).
+    pub fn meta(&self, namespace: &str) -> Meta {
+        Meta {
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            source: self.file.to_string_lossy().into_owned(),
+            namespace: namespace.to_owned(),
+            arguments: std::env::args().skip(1).collect(),
+            timestamp: self
+                .timestamp
+                .then(|| utc_timestamp(std::time::SystemTime::now())),
+        }
+    }
+
+    /// Returns the elements of the description file for `--with-ast`, `None` without it.
+    pub fn load_ast(&self) -> anyhow::Result<Option<serde_value::Value>> {
+        if !self.with_ast {
+            return Ok(None);
+        }
+        let _timing = timings::span(Phase::Parse, Some(&self.file));
+        let content = std::fs::read_to_string(&self.file)
+            .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", self.file.display()))
+            .classify(ErrorCode::Io)?;
+        let ast = parse_raw(&content)
+            .map_err(anyhow::Error::from)
+            .classify(ErrorCode::Parse)?;
+        Ok(Some(serde_value::to_value(ast)?))
+    }
+
+    /// Returns the raw data given in memory or reads it from the files (for `--raw`).
+    pub fn load_raw(self) -> anyhow::Result<serde_value::Value> {
+        let _timing = timings::span(Phase::Parse, Some(&self.file));
+        let raw = match self.raw_data {
+            Some(raw) => Ok(raw),
+            None if self.raw_files.is_empty() && !self.file.is_dir() => {
+                parse_raw_data_as(self.file, self.raw_format)
+            }
+            None => {
+                let mut paths = vec![self.file];
+                paths.extend(self.raw_files);
+                merge_raw_data(&paths, self.raw_format, self.raw_merge)
+            }
+        };
+        raw.classify(ErrorCode::InvalidData)
+    }
+}
+
+/// The content of the files of a dry run, keyed by their path.
+pub type DryRun = Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>;
+
+#[derive(Debug, Clone, Parser)]
 pub struct BaseOutputData {
     #[clap(long, short)]
     /// The file which should get written with the output from the generator.
     pub out: Option<PathBuf>,
+    #[clap(long)]
+    /// A command which gets the output on stdin and returns it processed on stdout before it's
+    /// written, e.g. a formatter like `rustfmt --edition 2021`.
+    ///
+    /// Prefix it with an extension (e.g. `ts=prettier --parser typescript`) to only run it for
+    /// outputs and emitted files with that extension. Can be given multiple times, the commands
+    /// run in the given order.
+    pub postprocess: Vec<PostProcess>,
+    #[clap(long, value_name = "FILE")]
+    /// Write a json file listing the written files with the hash of their content and the files
+    /// they were generated from, e.g. for build systems.
+    pub emit_manifest: Option<PathBuf>,
+    #[clap(skip)]
+    /// The files written so far, shared between clones.
+    pub written: Arc<Mutex<Vec<PathBuf>>>,
+    #[clap(skip)]
+    /// Collects the content of the files instead of writing them (for `ssd verify`), shared
+    /// between clones. Printed output is dropped.
+    pub dry_run: Option<DryRun>,
+}
+
+impl BaseOutputData {
+    fn record(&self, path: PathBuf) {
+        if let Ok(mut written) = self.written.lock() {
+            written.push(path);
+        }
+    }
+
+    /// Collects the content for the dry run, returns `false` if this isn't one.
+    fn capture(&self, path: Option<&Path>, content: &[u8]) -> bool {
+        let Some(dry_run) = &self.dry_run else {
+            return false;
+        };
+        if let (Some(path), Ok(mut files)) = (path, dry_run.lock()) {
+            files.insert(path.to_path_buf(), content.to_vec());
+        }
+        true
+    }
+
+    /// Runs the `--postprocess` commands on the result and prints it or writes it to `--out`.
+    pub fn write(&self, result: &str) -> anyhow::Result<()> {
+        let _timing = timings::span(Phase::Write, self.out.as_deref());
+        let result = postprocess(&self.postprocess, self.out.as_deref(), result)
+            .classify(ErrorCode::Generator)?;
+        if self.capture(self.out.as_deref(), result.as_bytes()) {
+            return Ok(());
+        }
+        print_or_write(self.out.clone(), &result).classify(ErrorCode::Io)?;
+        if let Some(out) = &self.out {
+            self.record(out.clone());
+        }
+        Ok(())
+    }
+
+    /// Like [`BaseOutputData::write`], for binary results, which can't be post-processed.
+    pub fn write_bytes(&self, result: &[u8]) -> anyhow::Result<()> {
+        if !self.postprocess.is_empty() {
+            anyhow::bail!("Binary output can't be post-processed.");
+        }
+        let _timing = timings::span(Phase::Write, self.out.as_deref());
+        if self.capture(self.out.as_deref(), result) {
+            return Ok(());
+        }
+        print_or_write_bytes(self.out.clone(), result).classify(ErrorCode::Io)?;
+        if let Some(out) = &self.out {
+            self.record(out.clone());
+        }
+        Ok(())
+    }
+
+    /// Runs the `--postprocess` commands on the files and writes them relative to `out_dir`.
+    pub fn write_files(
+        &self,
+        out_dir: &Path,
+        mut files: BTreeMap<String, String>,
+    ) -> anyhow::Result<()> {
+        let _timing = timings::span(Phase::Write, Some(out_dir));
+        postprocess_files(&self.postprocess, &mut files).classify(ErrorCode::Generator)?;
+        check_emitted_paths(files.keys())
+            .map_err(anyhow::Error::from)
+            .classify(ErrorCode::Generator)?;
+        if self.dry_run.is_some() {
+            for (path, content) in &files {
+                self.capture(Some(&out_dir.join(path)), content.as_bytes());
+            }
+            return Ok(());
+        }
+        write_files(out_dir, &files).classify(ErrorCode::Io)?;
+        for path in files.keys() {
+            self.record(out_dir.join(path));
+        }
+        Ok(())
+    }
+
+    /// Writes the `--emit-manifest` file (if given and not a dry run) for the files written so
+    /// far, which were generated from `inputs`.
+    pub fn write_manifest(&self, inputs: Vec<PathBuf>) -> anyhow::Result<()> {
+        let Some(path) = &self.emit_manifest else {
+            return Ok(());
+        };
+        if self.dry_run.is_some() {
+            return Ok(());
+        }
+        let written = self.written.lock().map(|w| w.clone()).unwrap_or_default();
+        let mut manifest = Manifest::new(inputs, &written)?;
+        manifest.command = std::env::args().skip(1).collect();
+        manifest.save(path)
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -55,6 +511,26 @@ pub enum DataFormat {
     RonPretty,
     Rsn,
     RsnPretty,
+    Xml,
+    #[cfg(feature = "msgpack")]
+    /// binary, only available with feature "msgpack" enabled
+    Msgpack,
+    #[cfg(feature = "cbor")]
+    /// binary, only available with feature "cbor" enabled
+    Cbor,
+}
+
+impl DataFormat {
+    /// Whether the format is binary instead of text.
+    pub fn is_binary(self) -> bool {
+        match self {
+            #[cfg(feature = "msgpack")]
+            DataFormat::Msgpack => true,
+            #[cfg(feature = "cbor")]
+            DataFormat::Cbor => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
@@ -72,6 +548,16 @@ pub enum Generator {
     /// Use a rhai based generator.
     #[cfg(feature = "rhai")]
     Rhai(crate::generators::rhai::Parameters),
+    /// Use a lua based generator.
+    #[cfg(feature = "lua")]
+    Lua(crate::generators::lua::Parameters),
+    /// Use a javascript based generator.
+    #[cfg(feature = "js")]
+    #[clap(aliases=["javascript"])]
+    Js(crate::generators::js::Parameters),
+    /// Use a python script based generator.
+    #[cfg(feature = "python")]
+    PythonScript(crate::generators::python::Parameters),
     /// Use a handlebars based template.
     /// https://handlebarsjs.com/
     #[cfg(feature = "handlebars")]
@@ -84,10 +570,128 @@ pub enum Generator {
     /// Use a wasm based generator
     #[cfg(feature = "wasm")]
     Wasm(crate::generators::wasm::Parameters),
+    /// Use an external program, which gets the model as json on stdin and answers with json
+    /// on stdout.
+    Exec(crate::generators::exec::Parameters),
     /// Output as serialized data for external use
     Data(DataParameters),
 }
 
+impl Generator {
+    /// The input and output options of the generator.
+    pub fn io(&self) -> (&BaseInputData, &BaseOutputData) {
+        match self {
+            #[cfg(feature = "rhai")]
+            Generator::Rhai(p) => (&p.input, &p.out),
+            #[cfg(feature = "lua")]
+            Generator::Lua(p) => (&p.input, &p.out),
+            #[cfg(feature = "js")]
+            Generator::Js(p) => (&p.input, &p.out),
+            #[cfg(feature = "python")]
+            Generator::PythonScript(p) => (&p.input, &p.out),
+            #[cfg(feature = "handlebars")]
+            Generator::Handlebars(p) => (&p.input, &p.out),
+            #[cfg(feature = "tera")]
+            Generator::Tera(p) => (&p.input, &p.out),
+            #[cfg(feature = "wasm")]
+            Generator::Wasm(p) => (&p.input, &p.out),
+            Generator::Exec(p) => (&p.input, &p.out),
+            Generator::Data(p) => (&p.input, &p.out),
+        }
+    }
+
+    pub fn out_mut(&mut self) -> &mut BaseOutputData {
+        match self {
+            #[cfg(feature = "rhai")]
+            Generator::Rhai(p) => &mut p.out,
+            #[cfg(feature = "lua")]
+            Generator::Lua(p) => &mut p.out,
+            #[cfg(feature = "js")]
+            Generator::Js(p) => &mut p.out,
+            #[cfg(feature = "python")]
+            Generator::PythonScript(p) => &mut p.out,
+            #[cfg(feature = "handlebars")]
+            Generator::Handlebars(p) => &mut p.out,
+            #[cfg(feature = "tera")]
+            Generator::Tera(p) => &mut p.out,
+            #[cfg(feature = "wasm")]
+            Generator::Wasm(p) => &mut p.out,
+            Generator::Exec(p) => &mut p.out,
+            Generator::Data(p) => &mut p.out,
+        }
+    }
+
+    /// The files the output is generated from: the input files and the modules they import, the
+    /// script, template, plugin or program of the generator with the scripts, partials or
+    /// templates it uses and the typemaps (see [`crate::manifest`]).
+    pub fn inputs(&self, base: &Path) -> Vec<PathBuf> {
+        // the source next to which a typemap is looked up, see `typemap_paths`
+        let (sources, typemap_source) = match self {
+            #[cfg(feature = "rhai")]
+            Generator::Rhai(p) => {
+                let mut sources = p.prelude.clone();
+                sources.push(p.script.clone());
+                let script_dir = p.script.parent().unwrap_or(Path::new(""));
+                sources.extend(crate::generators::rhai::imported_scripts(
+                    script_dir, &sources,
+                ));
+                (sources, Some(&p.script))
+            }
+            #[cfg(feature = "lua")]
+            Generator::Lua(p) => (vec![p.script.clone()], Some(&p.script)),
+            #[cfg(feature = "js")]
+            Generator::Js(p) => (vec![p.script.clone()], Some(&p.script)),
+            #[cfg(feature = "python")]
+            Generator::PythonScript(p) => (vec![p.script.clone()], Some(&p.script)),
+            #[cfg(feature = "handlebars")]
+            Generator::Handlebars(p) => {
+                let mut sources = vec![p.template.clone()];
+                // a template which isn't a file is the name of a partial
+                sources.retain(|template| template.is_file());
+                sources
+                    .extend(crate::helper::collect_files(&p.partials, "hbs").unwrap_or_default());
+                (sources, Some(&p.template))
+            }
+            #[cfg(feature = "tera")]
+            Generator::Tera(p) => match &p.templates {
+                // the template is the name of one of the matching templates
+                Some(templates) => (
+                    glob::glob(templates)
+                        .map(|paths| paths.flatten().filter(|path| path.is_file()).collect())
+                        .unwrap_or_default(),
+                    None,
+                ),
+                None => (vec![p.template.clone()], None),
+            },
+            #[cfg(feature = "wasm")]
+            Generator::Wasm(p) => (vec![p.wasm.clone()], Some(&p.wasm)),
+            Generator::Exec(p) => (vec![p.program.clone()], None),
+            Generator::Data(_) => (Vec::new(), None),
+        };
+        let (input, _) = self.io();
+        let mut inputs = vec![input.file.clone()];
+        inputs.extend(input.raw_files.iter().cloned());
+        if !input.raw {
+            // files which can't be loaded make the generator fail anyway
+            if let Ok(modules) = crate::imports::load_with_imports(base, &input.file) {
+                inputs.extend(crate::imports::module_files(
+                    base,
+                    &modules[..modules.len() - 1],
+                ));
+            }
+        }
+        inputs.extend(sources);
+        if !input.raw {
+            inputs.extend(typemap_paths(
+                input.no_map,
+                input.typemap.clone(),
+                typemap_source,
+            ));
+        }
+        inputs
+    }
+}
+
 type KV = (String, String);
 #[allow(clippy::unnecessary_wraps)]
 fn parse_key_val(env: &str) -> anyhow::Result<KV> {
@@ -104,23 +708,159 @@ pub struct Args {
     #[arg(global=true, num_args(0..))]
     #[clap(short = 'D', value_parser = parse_key_val, required = false)]
     pub defines: Vec<(String, String)>,
+    #[arg(global = true, long = "define-file")]
+    /// Files (toml, json or yaml) containing defines, which can be nested and typed.
+    ///
+    /// Later files override earlier ones, `-D` overrides all of them.
+    pub define_files: Vec<PathBuf>,
+    #[arg(
+        global = true,
+        long,
+        value_enum,
+        num_args(0..=1),
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    /// Print how long the phases of the run (parse, imports, cache, typemap, generate, write)
+    /// and the files took to stderr, as text or json.
+    pub timings: Option<TimingsFormat>,
+    #[arg(global = true, long, value_enum)]
+    /// The parser for description files, `rd` (the hand-written one) by default with the
+    /// `rd_parser` feature and `pest` (the grammar) without it.
+    pub parser: Option<crate::parser::ParserBackend>,
     #[clap(subcommand)]
     pub command: SubCommand,
 }
 
 #[derive(Debug, Parser)]
+#[allow(clippy::large_enum_variant)]
 pub enum SubCommand {
     /// Print debug representation of the parsed file.
-    Debug(BaseInputData),
+    ///
+    /// With `--after-typemap` or `--after-transforms` the module is shown as a generator sees
+    /// it. The typemap next to a script isn't picked up, it has to be given with `--typemap`.
+    Debug(DebugData),
     /// Pretty print the parsed file.
     Pretty(PrettyData),
+    /// Print a hash of the described model, which ignores comments, formatting and the order of elements where it doesn't matter.
+    Hash(HashData),
+    /// Check that description files and the files they import can be parsed and don't import
+    /// each other.
+    ///
+    /// Imports refer to files relative to the current directory, e.g. `import shop::orders;` to
+    /// `shop/orders.svc`. Every error is printed, the command fails if there are any.
+    ///
+    /// References to types which are neither primitive nor declared and names which are reserved
+    /// words of the `--targets` are printed as warnings. Names which break the naming conventions
+    /// are printed as configured in the `[lints]` of `ssd.toml` and fail the command if denied.
+    Check(CheckData),
+    /// Write the model of a description file together with the types it uses from imported
+    /// modules as one self-contained model, e.g. for the `--raw` input of generators.
+    ///
+    /// Imports refer to files like with `check`.
+    Bundle(BundleData),
+    /// Rename a data type, enum, service or function together with all references to it in the
+    /// files. Changed files get formatted like with `pretty`.
+    Rename(RenameData),
+    /// Check that the breaking changes between two versions of a description file come with a
+    /// new major version.
+    ///
+    /// Prints the breaking changes (removed elements and changed types, values, arguments and
+    /// return types) and fails if there are any, unless the major version declared with
+    /// `version "2.0.0";` (before 1.0.0 the minor version) was increased.
+    Compat(CompatData),
+    /// Check that a data file (e.g. json) conforms to a data type of a description file.
+    ///
+    /// Every violation is printed with the JSON path of the value, the command fails if there
+    /// are any.
+    Validate(ValidateData),
+    /// Generate random data for a data type of a description file, e.g. to seed tests.
+    Mock(MockData),
+    /// Generate gRPC messages and stubs for the services of a description file, for tonic (Rust)
+    /// or grpc-web (TypeScript), without a `.proto` file and protoc.
+    Grpc(GrpcData),
     /// Generate source code.
     #[command(subcommand)]
     Generate(Generator),
+    /// Run generators for many description files, skipping the ones whose inputs didn't change
+    /// since the last run.
+    ///
+    /// Like `ssd::build()` in build scripts: the output for `dir/file.svc` is written to
+    /// `<out-dir>/dir/file.<extension>` and the run is recorded in `ssd-manifest.json` in the
+    /// output directory, which is used to skip unchanged files and remove stale outputs.
+    #[cfg(any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    ))]
+    Build(BuildData),
+    /// Print a description of the data passed to generators (the model with `defines` and
+    /// `meta`), e.g. for autocompletion and validation in wasm plugins or exec generators.
+    Schema(SchemaData),
+    /// Check that the files written by runs with `--emit-manifest` are up to date.
+    ///
+    /// Repeats the runs which wrote the manifests without writing anything and prints the
+    /// differences to the files on disk. Fails if any file differs, e.g. to make sure
+    /// regenerated code was committed. Has to run in the directory the manifests were written
+    /// in, because the recorded paths are relative to it.
+    Verify {
+        #[clap(required = true)]
+        /// The manifests written with `--emit-manifest`.
+        manifests: Vec<PathBuf>,
+    },
+    /// Manage the cache of parsed models (`.ssd-cache`).
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Answer JSON-RPC requests (parse, check, pretty and generate) over HTTP, e.g. for IDE plugins.
+    #[cfg(any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    ))]
+    Serve {
+        #[clap(long, default_value = "127.0.0.1:7878")]
+        /// The address to listen on.
+        listen: String,
+        #[clap(long)]
+        /// The origin of the web page which may send requests (like the playground), no other
+        /// page gets CORS headers.
+        allow_origin: Option<String>,
+    },
+    /// Run the generator test cases of a manifest and compare the results with the expected
+    /// files.
+    #[cfg(any(
+        feature = "rhai",
+        feature = "handlebars",
+        feature = "tera",
+        feature = "wasm"
+    ))]
+    Test {
+        #[clap(default_value = "ssd-test.toml")]
+        /// The manifest listing the test cases.
+        manifest: PathBuf,
+        #[clap(long = "case")]
+        /// Only run the test cases with these names. Can be given multiple times.
+        cases: Vec<String>,
+        #[clap(long)]
+        /// Replace the expected files with the current results instead of comparing them.
+        bless: bool,
+    },
+    /// Run a rhai script against test cases and compare the results with golden files.
+    #[cfg(feature = "rhai")]
+    TestScript(crate::generators::rhai::TestParameters),
     /// Write language server file.
+    ///
+    /// The definitions of the rhai functions, or with `--engine` a description of the variables
+    /// templates get (as TypeScript if the file ends with `.d.ts`).
     #[clap(hide = true)]
-    #[cfg(feature = "rhai")]
-    LanguageServer { out: PathBuf },
+    LanguageServer {
+        #[clap(long, value_enum)]
+        /// Describe the variables of templates of this engine instead.
+        engine: Option<TemplateEngine>,
+        out: PathBuf,
+    },
     /// Print shell completions.
     #[clap(hide = true)]
     Completions { shell: Shell },
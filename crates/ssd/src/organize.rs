@@ -0,0 +1,149 @@
+use crate::ast::{AstElement, ServiceAstElement};
+
+/// Splits the elements into groups of an element and the comments right before it.
+///
+/// Comments after the last element are returned separately.
+fn group_with_comments<T>(elements: Vec<T>, is_comment: fn(&T) -> bool) -> (Vec<Vec<T>>, Vec<T>) {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    for element in elements {
+        let comment = is_comment(&element);
+        current.push(element);
+        if !comment {
+            groups.push(std::mem::take(&mut current));
+        }
+    }
+    (groups, current)
+}
+
+fn organize_service(service: Vec<ServiceAstElement>) -> Vec<ServiceAstElement> {
+    let (groups, trailing) = group_with_comments(service, |e| {
+        matches!(
+            e,
+            ServiceAstElement::Comment(_) | ServiceAstElement::DocComment(_)
+        )
+    });
+
+    let mut dependencies = Vec::new();
+    let mut data_types = Vec::new();
+    let mut functions = Vec::new();
+    let mut events = Vec::new();
+    for group in groups {
+        match group.last() {
+            Some(ServiceAstElement::Dependency(dependency)) => {
+                dependencies.push((dependency.name.to_string(), group));
+            }
+            Some(ServiceAstElement::DataType(_)) => data_types.push(group),
+            Some(ServiceAstElement::Event(_)) => events.push(group),
+            _ => functions.push(group),
+        }
+    }
+    dependencies.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    dependencies
+        .into_iter()
+        .map(|(_, group)| group)
+        .chain(data_types)
+        .chain(functions)
+        .chain(events)
+        .flatten()
+        .chain(trailing)
+        .collect()
+}
+
+/// Sorts imports and groups the members of services (dependencies, data types, functions,
+/// events).
+///
+/// If `sort_types` is set, data types and enums are sorted alphabetically and put before the
+/// services. Comments stay with the element right after them.
+pub fn organize(raw: Vec<AstElement>, sort_types: bool) -> Vec<AstElement> {
+    let (groups, trailing) = group_with_comments(raw, |e| {
+        matches!(e, AstElement::Comment(_) | AstElement::DocComment(_))
+    });
+
+    let mut version = Vec::new();
+    let mut imports = Vec::new();
+    let mut data_types = Vec::new();
+    let mut enums = Vec::new();
+    let mut others = Vec::new();
+    for mut group in groups {
+        match group.pop() {
+            Some(AstElement::Version(v)) => {
+                group.push(AstElement::Version(v));
+                version.push(group);
+            }
+            Some(AstElement::Import(import)) => {
+                let key = import.path.to_string();
+                group.push(AstElement::Import(import));
+                imports.push((key, group));
+            }
+            Some(AstElement::DataType(dt)) if sort_types => {
+                let key = dt.0.clone();
+                group.push(AstElement::DataType(dt));
+                data_types.push((key, group));
+            }
+            Some(AstElement::Enum(en)) if sort_types => {
+                let key = en.0.clone();
+                group.push(AstElement::Enum(en));
+                enums.push((key, group));
+            }
+            Some(AstElement::Service((name, service, attributes, visibility))) => {
+                group.push(AstElement::Service((
+                    name,
+                    organize_service(service),
+                    attributes,
+                    visibility,
+                )));
+                others.push(group);
+            }
+            Some(element) => {
+                group.push(element);
+                others.push(group);
+            }
+            None => (),
+        }
+    }
+    imports.sort_by(|(a, _), (b, _)| a.cmp(b));
+    data_types.sort_by(|(a, _), (b, _)| a.cmp(b));
+    enums.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let sorted = imports
+        .into_iter()
+        .chain(data_types)
+        .chain(enums)
+        .map(|(_, group)| group);
+    version
+        .into_iter()
+        .chain(sorted)
+        .chain(others)
+        .flatten()
+        .chain(trailing)
+        .collect()
+}
+
+#[test]
+fn test_organize() {
+    let raw = crate::parser::parse_raw(
+        r#"
+        version "1.0.0";
+        import b;
+        /// about a
+        import a;
+        data Z { x: i32, };
+        data A { x: i32, };
+        service S {
+            event e();
+            /// about f
+            fn f();
+            depends on Log;
+        };
+        "#,
+    )
+    .unwrap();
+    let organized = crate::pretty::pretty(&organize(raw, true));
+    assert_eq!(
+        organized,
+        "version \"1.0.0\";\n\n/// about a\nimport a;\nimport b;\n\ndata A {\n\tx: i32,\n};\n\ndata Z {\n\tx: i32,\n};\n\n\
+         service S {\n\tdepends on Log;\n\n\t/// about f\n\tfn f();\n\n\tevent e();\n};\n"
+    );
+}
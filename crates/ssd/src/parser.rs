@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::{io::Write, num::ParseIntError, path::Path};
 
 use once_cell::sync::Lazy;
@@ -11,10 +12,11 @@ use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 use crate::ast::{
     Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
-    OrderedMap, Service, SsdModule, TypeName,
+    OrderedMap, Radix, Service, SsdModule, TypeName, Visibility,
 };
 
 use crate::ast::{AstElement, ServiceAstElement};
+use crate::inline::{lift_data_type, lift_service};
 
 fn parse_attribute_arg(node: Pair<Rule>) -> Result<(String, Option<String>), ParseError> {
     let span = node.as_span();
@@ -49,6 +51,37 @@ fn parse_attributes(node: Pair<Rule>) -> Result<Vec<Attribute>, ParseError> {
     node.into_inner().map(parse_attribute).collect()
 }
 
+/// Like [`parse_name`] for data types, enums and services, which can have a visibility marker
+/// after their attributes.
+fn parse_declaration_name(
+    p: &mut Pairs<Rule>,
+    n: Pair<Rule>,
+) -> Result<(String, Vec<Attribute>, Option<Visibility>), ParseError> {
+    let span = n.as_span();
+    let (n, attributes) = if n.as_rule() == Rule::attributes {
+        let attributes = parse_attributes(n)?;
+        let n = p
+            .next()
+            .ok_or_else(|| ParseError::new(ParseErrorType::IncompleteName, span))?;
+        (n, attributes)
+    } else {
+        (n, Vec::new())
+    };
+    if n.as_rule() != Rule::visibility {
+        return Ok((n.as_str().to_string(), attributes, None));
+    }
+    let visibility = match n.as_str() {
+        "internal" => Visibility::Internal,
+        _ => Visibility::Public,
+    };
+    let name = p
+        .next()
+        .ok_or_else(|| ParseError::new(ParseErrorType::IncompleteName, span))?
+        .as_str()
+        .to_string();
+    Ok((name, attributes, Some(visibility)))
+}
+
 fn parse_name(p: &mut Pairs<Rule>, n: Pair<Rule>) -> Result<(String, Vec<Attribute>), ParseError> {
     let span = n.as_span();
     if n.as_rule() == Rule::attributes {
@@ -65,6 +98,83 @@ fn parse_name(p: &mut Pairs<Rule>, n: Pair<Rule>) -> Result<(String, Vec<Attribu
     }
 }
 
+/// Parses an enum value like `5`, `-1`, `0xFF` or `0b1010` together with its radix.
+pub(crate) fn parse_enum_number(number: &str) -> Result<(i64, Radix), ParseIntError> {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number),
+    };
+    let (radix, base, digits) = if let Some(digits) = digits.strip_prefix("0x") {
+        (Radix::Hex, 16, digits)
+    } else if let Some(digits) = digits.strip_prefix("0b") {
+        (Radix::Binary, 2, digits)
+    } else {
+        (Radix::Decimal, 10, digits)
+    };
+    // with the sign, so the smallest value doesn't overflow
+    let value = i64::from_str_radix(&format!("{sign}{digits}"), base)?;
+    Ok((value, radix))
+}
+
+/// Returns whether the comment documents the following element (`///`) together with its lines
+/// without the markers. Line (`//`) and block comments (`/* */`) are free comments, of block
+/// comments the `*` at the start of every line is removed as well.
+pub(crate) fn comment_lines(comment: &str) -> (bool, Vec<String>) {
+    if let Some(doc) = comment.strip_prefix("///") {
+        return (true, vec![doc.trim().to_string()]);
+    }
+    if let Some(line) = comment.strip_prefix("//") {
+        return (false, vec![line.trim().to_string()]);
+    }
+    let block = comment
+        .strip_prefix("/*")
+        .and_then(|c| c.strip_suffix("*/"))
+        .unwrap_or(comment);
+    let mut lines = block
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = if line == "*" { "" } else { line };
+            line.strip_prefix("* ").unwrap_or(line).to_string()
+        })
+        .collect::<Vec<_>>();
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    let start = lines.iter().position(|l| !l.is_empty()).unwrap_or(0);
+    (false, lines.split_off(start))
+}
+
+/// The comments before an element, in the order they were written.
+#[derive(Default)]
+pub(crate) struct Comments(Vec<(bool, String)>);
+
+impl Comments {
+    pub(crate) fn push(&mut self, comment: &str) {
+        let (is_doc, lines) = comment_lines(comment);
+        self.0.extend(lines.into_iter().map(|line| (is_doc, line)));
+    }
+
+    /// Returns the free comments and the docs of the following element.
+    pub(crate) fn take(&mut self) -> (Vec<String>, Vec<String>) {
+        let (docs, comments): (Vec<_>, Vec<_>) = self.0.drain(..).partition(|(is_doc, _)| *is_doc);
+        (
+            comments.into_iter().map(|(_, c)| c).collect(),
+            docs.into_iter().map(|(_, c)| c).collect(),
+        )
+    }
+
+    /// Returns all comments as free comments, for comments which aren't followed by an element
+    /// (e.g. after the last property of a data type), so doc comments have nothing to document.
+    pub(crate) fn take_free(&mut self) -> Vec<String> {
+        self.0.drain(..).map(|(_, c)| c).collect()
+    }
+
+    pub(crate) fn push_free(&mut self, comments: Vec<String>) {
+        self.0.extend(comments.into_iter().map(|c| (false, c)));
+    }
+}
+
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub(crate) struct FileParser;
@@ -73,6 +183,8 @@ pub(crate) struct FileParser;
 pub struct ParseError {
     pub error_type: ParseErrorType,
     pub span: String,
+    /// The byte offset in the content where the error was found, if it is known.
+    pub offset: Option<usize>,
 }
 
 impl ParseError {
@@ -80,6 +192,31 @@ impl ParseError {
         Self {
             error_type,
             span: format!("{span:?}"),
+            offset: Some(span.start()),
+        }
+    }
+
+    /// The error for a name which was already declared, at the second declaration.
+    fn duplicate(kind: &str, name: &str, span: Span) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        Self {
+            error_type: ParseErrorType::Duplicate {
+                kind: kind.to_string(),
+                name: name.to_string(),
+            },
+            span: format!("line {line}, column {column}"),
+            offset: Some(span.start()),
+        }
+    }
+
+    fn from_pest_error(err: pest::error::Error<Rule>) -> Self {
+        let offset = match err.location {
+            pest::error::InputLocation::Pos(offset) => offset,
+            pest::error::InputLocation::Span((start, _)) => start,
+        };
+        ParseError {
+            offset: Some(offset),
+            ..Self::from_dyn_error(err)
         }
     }
 }
@@ -102,6 +239,9 @@ pub enum ParseErrorType {
     IncompleteAttribute,
     IncompleteName,
     UnexpectedElement(String),
+    Expected { expected: String, found: String },
+    Duplicate { kind: String, name: String },
+    LimitExceeded(String),
     OtherError(String),
 }
 
@@ -140,6 +280,21 @@ impl std::fmt::Display for ParseError {
             ParseErrorType::InvalidEnumValue(info) => {
                 write!(f, "Invalid enum value. {} ({})", info, self.span)
             }
+            ParseErrorType::Expected { expected, found } => {
+                write!(f, "Expected {expected}, found {found}. ({})", self.span)
+            }
+            ParseErrorType::Duplicate { kind, name } if self.span.is_empty() => {
+                write!(f, "Duplicate {kind} {name}.")
+            }
+            ParseErrorType::Duplicate { kind, name } => {
+                write!(f, "Duplicate {kind} {name}. ({})", self.span)
+            }
+            ParseErrorType::LimitExceeded(info) if self.span.is_empty() => {
+                write!(f, "Limit exceeded, {info}.")
+            }
+            ParseErrorType::LimitExceeded(info) => {
+                write!(f, "Limit exceeded, {info}. ({})", self.span)
+            }
             ParseErrorType::OtherError(inner) => {
                 write!(f, "Other({inner})")
             }
@@ -152,6 +307,7 @@ impl ParseError {
         ParseError {
             error_type: ParseErrorType::OtherError(format!("{err}")),
             span: String::new(),
+            offset: None,
         }
     }
 }
@@ -166,31 +322,190 @@ impl std::error::Error for ParseError {
     }
 }
 
-fn parse_type(typ: &str) -> (&str, bool, Option<usize>) {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)\s+of").unwrap());
-    if let Some(stripped) = typ.strip_prefix("list of") {
-        (stripped.trim(), true, None)
-    } else if let Some(cap) = RE.captures(typ) {
-        let count_str = cap.get(1).unwrap().as_str();
-        let count = count_str.parse::<usize>().unwrap();
-        (typ[count_str.len() + 3..].trim(), true, Some(count))
+/// Parses a type like `u8`, `list of u8` or `4 of list of u8` into the name of the type and the
+/// lists around it (see [`TypeName::lists`]).
+fn parse_type(typ: &str) -> Result<(&str, Vec<Option<usize>>), ParseErrorType> {
+    let mut lists = Vec::new();
+    let mut typ = typ.trim();
+    // paths can't contain whitespace, so everything before it is `list of` or `<count> of`
+    while let Some((list, element)) = typ.split_once(char::is_whitespace) {
+        if list == "list" {
+            lists.push(None);
+        } else {
+            let count = list.parse().map_err(|_| ParseErrorType::Expected {
+                expected: "a smaller count".to_string(),
+                found: list.to_string(),
+            })?;
+            lists.push(Some(count));
+        }
+        let element = element.trim_start();
+        typ = element.strip_prefix("of").unwrap_or(element).trim_start();
+    }
+    Ok((typ, lists))
+}
+
+/// Parses the return type (or the ok or error type of `result of T, E`) of a function.
+fn parse_return_type(pair: &Pair<Rule>) -> Result<TypeName, ParseError> {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+    let typ = RE.replace_all(pair.as_str(), " ");
+    let (typ, lists) = parse_type(&typ).map_err(|e| ParseError::new(e, pair.as_span()))?;
+    Ok(TypeName::new(Namespace::new(typ), lists, Vec::new()))
+}
+
+/// Prints the warning for the deprecated `handles` keyword.
+pub(crate) fn print_handler_deprecation() {
+    const DEPRECATED: &str =
+        "Using 'handlers' is deprecated and will be removed in future versions. Use 'fn' instead.";
+    let mut stderr = StandardStream::stderr(ColorChoice::Always);
+    if stderr
+        .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
+        .is_ok()
+    {
+        writeln!(&mut stderr, "{DEPRECATED}").unwrap();
+
+        let _ = stderr.set_color(&ColorSpec::default());
     } else {
-        (typ, false, None)
+        eprintln!("{DEPRECATED}");
     }
 }
 
-#[allow(clippy::too_many_lines)]
+/// The parser which reads description files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "_bin", derive(clap::ValueEnum))]
+pub enum ParserBackend {
+    /// The pest grammar (`grammar.pest`).
+    Pest,
+    /// The hand-written recursive-descent parser (`rd_parser.rs`).
+    Rd,
+}
+
+impl Default for ParserBackend {
+    fn default() -> Self {
+        if cfg!(feature = "rd_parser") {
+            ParserBackend::Rd
+        } else {
+            ParserBackend::Pest
+        }
+    }
+}
+
+/// 0 is the default backend, otherwise the backend set with [`set_parser_backend`] plus one.
+static BACKEND: AtomicU8 = AtomicU8::new(0);
+
+/// Selects the parser for the rest of the process, e.g. from `--parser`.
+pub fn set_parser_backend(backend: ParserBackend) {
+    BACKEND.store(backend as u8 + 1, Ordering::Relaxed);
+}
+
+/// The parser [`parse_raw`] uses: the one given to [`set_parser_backend`], otherwise the hand-written
+/// parser with the `rd_parser` feature and the pest grammar without it.
+pub fn parser_backend() -> ParserBackend {
+    match BACKEND.load(Ordering::Relaxed) {
+        1 => ParserBackend::Pest,
+        2 => ParserBackend::Rd,
+        _ => ParserBackend::default(),
+    }
+}
+
+/// Parses the content into the raw elements (including comments) with the [`parser_backend`].
 pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
+    parse_raw_with(content, parser_backend())
+}
+
+/// Parses the content into the raw elements (including comments) with the given backend.
+pub fn parse_raw_with(
+    content: &str,
+    backend: ParserBackend,
+) -> Result<Vec<AstElement>, ParseError> {
+    match backend {
+        ParserBackend::Pest => parse_raw_pest(content),
+        ParserBackend::Rd => crate::rd_parser::parse_raw(content),
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+/// Parses the properties of a data type or an inline data type. Comments after the last
+/// property are left in `pending`.
+fn parse_properties(
+    pairs: Pairs<Rule>,
+    pending: &mut Comments,
+) -> Result<OrderedMap<TypeName>, ParseError> {
+    let mut properties = OrderedMap::new();
+    for p in pairs {
+        if let Rule::COMMENT = p.as_rule() {
+            pending.push(p.as_str());
+            continue;
+        }
+        let span = p.as_span();
+        let mut p = p.into_inner();
+        let n = p
+            .next()
+            .ok_or_else(|| ParseError::new(ParseErrorType::IncompleteProperty, span))?;
+        let (name, attributes) = parse_name(&mut p, n)?;
+        let typ = p
+            .next()
+            .ok_or_else(|| ParseError::new(ParseErrorType::MissingType(name.clone()), span))?;
+        let (mut comments, mut docs) = pending.take();
+        let property = if typ.as_rule() == Rule::inline_typ {
+            let mut inner = typ.into_inner();
+            let lists = inner.next().map_or("", |lists| lists.as_str());
+            let (_, lists) = parse_type(lists).map_err(|e| ParseError::new(e, span))?;
+            let mut inner_pending = Comments::default();
+            let inline = parse_properties(inner, &mut inner_pending)?;
+            // comments after the last inline property belong to the next property
+            pending.push_free(inner_pending.take_free());
+            TypeName::new(Namespace::new(""), lists, attributes).with_inline(inline)
+        } else {
+            let (typ, lists) = parse_type(typ.as_str()).map_err(|e| ParseError::new(e, span))?;
+            TypeName::new(Namespace::new(typ), lists, attributes)
+        };
+        let property = property.with_comments(&mut comments).with_docs(&mut docs);
+        if properties.insert(name.clone(), property).is_some() {
+            return Err(ParseError::duplicate("property", &name, span));
+        }
+    }
+    Ok(properties)
+}
+
+/// Parses a data type (at the top level or in a service) and returns it together with the
+/// comments after its last property.
+fn parse_data_type(p: Pair<Rule>) -> Result<((String, DataType), Vec<String>), ParseError> {
+    let span = p.as_span();
+    let mut p = p.into_inner();
+    let n = p
+        .next()
+        .ok_or_else(|| ParseError::new(ParseErrorType::IncompleteDatatype, span))?;
+    let (name, attributes, visibility) = parse_declaration_name(&mut p, n)?;
+    let mut pending = Comments::default();
+    let properties = parse_properties(p, &mut pending)?;
+    Ok((
+        (
+            name,
+            DataType::new(properties, attributes).with_visibility(visibility),
+        ),
+        pending.take_free(),
+    ))
+}
+
+pub(crate) fn parse_raw_pest(content: &str) -> Result<Vec<AstElement>, ParseError> {
     use ParseErrorType::{
-        IncompleteArgumentIdent, IncompleteCall, IncompleteDatatype, IncompleteDepends,
-        IncompleteEnum, IncompleteEnumValue, IncompleteEvent, IncompleteImport, IncompleteProperty,
-        IncompleteService, InvalidEnumValue, MissingType, UnexpectedElement,
+        IncompleteArgumentIdent, IncompleteCall, IncompleteDepends, IncompleteEnum,
+        IncompleteEnumValue, IncompleteEvent, IncompleteImport, IncompleteService,
+        InvalidEnumValue, UnexpectedElement,
     };
-    let pairs = FileParser::parse(Rule::file, content).map_err(ParseError::from_dyn_error)?;
+    let pairs = FileParser::parse(Rule::file, content).map_err(ParseError::from_pest_error)?;
     let mut result = Vec::new();
 
     for p in pairs {
         match p.as_rule() {
+            Rule::version => {
+                let version = p
+                    .into_inner()
+                    .next()
+                    .map(|string| string.into_inner().as_str().to_string())
+                    .unwrap_or_default();
+                result.push(AstElement::Version(version));
+            }
             Rule::import => {
                 let span = p.as_span();
                 let mut p = p.into_inner();
@@ -198,70 +513,49 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                     .next()
                     .ok_or_else(|| ParseError::new(IncompleteImport, span))?;
                 let (name, attributes) = parse_name(&mut p, n)?;
-                result.push(AstElement::Import(Import::new(
-                    Namespace::new(&name),
-                    attributes,
-                )));
+                let mut import = Import::new(Namespace::new(&name), attributes);
+                if let Some(p) = p.next() {
+                    let mut names = p
+                        .clone()
+                        .into_inner()
+                        .filter(|n| n.as_rule() == Rule::ident)
+                        .map(|n| n.as_str().to_string());
+                    import = match p.as_rule() {
+                        Rule::import_alias => import.with_alias(
+                            names
+                                .next()
+                                .ok_or_else(|| ParseError::new(IncompleteImport, span))?,
+                        ),
+                        Rule::import_items => import.with_items(names.collect()),
+                        _ => Err(ParseError::new(
+                            UnexpectedElement(format!("while parsing import \"{name}\"! {p}")),
+                            p.as_span(),
+                        ))?,
+                    };
+                }
+                result.push(AstElement::Import(import));
             }
             Rule::data => {
-                let span = p.as_span();
-                let mut p = p.into_inner();
-                let n = p
-                    .next()
-                    .ok_or_else(|| ParseError::new(IncompleteDatatype, span))?;
-                let (name, attributes) = parse_name(&mut p, n)?;
-
-                let mut properties = OrderedMap::new();
-                let mut comments = Vec::new();
-
-                for p in p {
-                    if let Rule::COMMENT = p.as_rule() {
-                        comments.push(p.as_span().as_str()[3..].trim().to_string());
-                        continue;
-                    }
-                    let span = p.as_span();
-                    let mut p = p.into_inner();
-                    let n = p
-                        .next()
-                        .ok_or_else(|| ParseError::new(IncompleteProperty, span))?;
-                    let (name, attributes) = parse_name(&mut p, n)?;
-                    let typ = p
-                        .next()
-                        .ok_or_else(|| ParseError::new(MissingType(name.clone()), span))?
-                        .as_str()
-                        .to_string();
-                    let (typ, is_list, count) = parse_type(typ.as_str());
-                    properties.push((
-                        name,
-                        TypeName::new(Namespace::new(typ), is_list, count, attributes)
-                            .with_comments(&mut comments),
-                    ));
-                    // properties.insert(
-                    //     name,
-                    //     TypeName::new(Namespace::new(&typ), attributes)
-                    //         .with_comments(&mut comments),
-                    // );
-                }
-
-                result.push(AstElement::DataType((
-                    name,
-                    DataType::new(properties, attributes),
-                )));
+                let (data_type, trailing) = parse_data_type(p)?;
+                result.push(AstElement::DataType(data_type));
+                // comments after the last property are kept right after the data type
+                result.extend(trailing.into_iter().map(AstElement::Comment));
             }
-            Rule::enum_ => {
+            Rule::enum_ | Rule::flags => {
+                let is_flags = p.as_rule() == Rule::flags;
                 let span = p.as_span();
                 let mut p = p.into_inner();
                 let n = p
                     .next()
                     .ok_or_else(|| ParseError::new(IncompleteEnum, span))?;
-                let (name, attributes) = parse_name(&mut p, n)?;
+                let (name, attributes, visibility) = parse_declaration_name(&mut p, n)?;
 
                 let mut values = OrderedMap::new();
 
-                let mut comments = Vec::new();
+                let mut pending = Comments::default();
                 for p in p {
                     if let Rule::COMMENT = p.as_rule() {
-                        comments.push(p.as_span().as_str()[3..].trim().to_string());
+                        pending.push(p.as_str());
                         continue;
                     }
                     let span = p.as_span();
@@ -270,24 +564,32 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                         .next()
                         .ok_or_else(|| ParseError::new(IncompleteEnumValue, span))?;
                     let (name, attributes) = parse_name(&mut p, n)?;
-                    let value = if let Some(v) = p.next() {
-                        Some(v.as_str().parse().map_err(|err: ParseIntError| {
+                    let (value, radix) = if let Some(v) = p.next() {
+                        let (value, radix) = parse_enum_number(v.as_str()).map_err(|err| {
                             ParseError::new(InvalidEnumValue(err.to_string()), span)
-                        })?)
+                        })?;
+                        (Some(value), radix)
                     } else {
-                        None
+                        (None, Radix::Decimal)
                     };
-                    values.push((
-                        name,
-                        EnumValue::new(value, attributes).with_comments(&mut comments),
-                    ));
-                    // values.insert(
-                    //     name,
-                    //     EnumValue::new(value, attributes).with_comments(&mut comments),
-                    // );
+                    let (mut comments, mut docs) = pending.take();
+                    let enum_value = EnumValue::new(value, attributes)
+                        .with_radix(radix)
+                        .with_comments(&mut comments)
+                        .with_docs(&mut docs);
+                    if values.insert(name.clone(), enum_value).is_some() {
+                        return Err(ParseError::duplicate("enum value", &name, span));
+                    }
                 }
 
-                result.push(AstElement::Enum((name, Enum::new(values, attributes))));
+                result.push(AstElement::Enum((
+                    name,
+                    Enum::new(values, attributes)
+                        .with_visibility(visibility)
+                        .with_flags(is_flags),
+                )));
+                // comments after the last value are kept right after the enum
+                result.extend(pending.take_free().into_iter().map(AstElement::Comment));
             }
             Rule::service => {
                 let span = p.as_span();
@@ -295,7 +597,7 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                 let n = p
                     .next()
                     .ok_or_else(|| ParseError::new(IncompleteService, span))?;
-                let (service_name, attributes) = parse_name(&mut p, n)?;
+                let (service_name, attributes, visibility) = parse_declaration_name(&mut p, n)?;
 
                 let mut service_parts = Vec::new();
 
@@ -316,28 +618,32 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                         }
                         Rule::function | Rule::handler => {
                             if rule == Rule::handler {
-                                const DEPRECATED: &str =  "Using 'handlers' is deprecated and will be removed in future versions. Use 'fn' instead.";
-                                let mut stderr = StandardStream::stderr(ColorChoice::Always);
-                                if stderr
-                                    .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
-                                    .is_ok()
-                                {
-                                    writeln!(&mut stderr, "{DEPRECATED}").unwrap();
-
-                                    let _ = stderr.set_color(&ColorSpec::default());
-                                } else {
-                                    eprintln!("{DEPRECATED}");
-                                }
+                                print_handler_deprecation();
                             }
                             let span = p.as_span();
                             let mut p = p.into_inner();
-                            let n = p
+                            let mut n = p
                                 .next()
                                 .ok_or_else(|| ParseError::new(IncompleteCall, span))?;
-                            let (call_name, call_attributes) = parse_name(&mut p, n)?;
+                            let mut call_attributes = Vec::new();
+                            if n.as_rule() == Rule::attributes {
+                                call_attributes = parse_attributes(n)?;
+                                n = p
+                                    .next()
+                                    .ok_or_else(|| ParseError::new(IncompleteCall, span))?;
+                            }
+                            let is_oneway = n.as_rule() == Rule::oneway;
+                            if is_oneway {
+                                n = p
+                                    .next()
+                                    .ok_or_else(|| ParseError::new(IncompleteCall, span))?;
+                            }
+                            let call_name = n.as_str().to_string();
                             let mut arguments = OrderedMap::new();
                             let mut return_type = None;
+                            let mut error_type = None;
                             let mut attributes = Vec::new();
+                            let mut pending = Comments::default();
                             for p in p.by_ref() {
                                 match p.as_rule() {
                                     Rule::argument => {
@@ -348,14 +654,20 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                                                 Rule::ident => {
                                                     let name = n.as_str().to_string();
                                                     let typ = p.next().ok_or_else(|| ParseError::new(IncompleteArgumentIdent, span))?.as_str().to_string();
-                                                    let (typ, is_list, count) = parse_type(typ.as_str());
-                                                    arguments.push((name, TypeName::new(Namespace::new(typ), is_list, count, attributes.clone())));
-                                                    // arguments.insert(name, TypeName::new(Namespace::new(&typ), attributes.clone()));
+                                                    let (typ, lists) = parse_type(typ.as_str()).map_err(|e| ParseError::new(e, span))?;
+                                                    let (mut comments, mut docs) = pending.take();
+                                                    let argument = TypeName::new(Namespace::new(typ), lists, attributes.clone()).with_comments(&mut comments).with_docs(&mut docs);
+                                                    if arguments.insert(name.clone(), argument).is_some() {
+                                                        return Err(ParseError::duplicate("argument", &name, span));
+                                                    }
                                                     attributes.clear();
                                                 }
                                                 Rule::attributes => {
                                                     attributes = parse_attributes(n)?;
                                                 }
+                                                Rule::COMMENT => {
+                                                    pending.push(n.as_str());
+                                                }
                                                 _ => Err(ParseError::new(
                                                     UnexpectedElement(format!(
                                                         "while parsing argument for call \"{call_name}\" in service \"{service_name}\"! {p}"
@@ -365,16 +677,18 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                                             }
                                         }
                                     }
+                                    Rule::COMMENT => {
+                                        pending.push(p.as_str());
+                                    }
                                     Rule::typ => {
-                                        static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
-                                        let typ = RE.replace_all(p.as_str(), " ");
-                                        let (typ, is_list, count) = parse_type(&typ);
-                                        return_type = Some(TypeName::new(
-                                            Namespace::new(typ),
-                                            is_list,
-                                            count,
-                                            Vec::new(),
-                                        ));
+                                        return_type = Some(parse_return_type(&p)?);
+                                    }
+                                    Rule::result_typ => {
+                                        let span = p.as_span();
+                                        let mut types = p.into_inner();
+                                        let mut next = || types.next().ok_or_else(|| ParseError::new(IncompleteCall, span));
+                                        return_type = Some(parse_return_type(&next()?)?);
+                                        error_type = Some(parse_return_type(&next()?)?);
                                     }
                                     _ => Err(ParseError::new(
                                         UnexpectedElement(format!(
@@ -387,13 +701,10 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
 
                             if let Some(p) = p.next() {
                                 if p.as_rule() == Rule::typ {
-                                    let (typ, is_list, count) = parse_type(p.as_str());
-                                    return_type = Some(TypeName::new(
-                                        Namespace::new(typ),
-                                        is_list,
-                                        count,
-                                        Vec::new(),
-                                    ));
+                                    let (typ, lists) = parse_type(p.as_str())
+                                        .map_err(|e| ParseError::new(e, p.as_span()))?;
+                                    return_type =
+                                        Some(TypeName::new(Namespace::new(typ), lists, Vec::new()));
                                 } else {
                                     Err(ParseError::new(
                                         UnexpectedElement(format!(
@@ -405,8 +716,17 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                             }
                             service_parts.push(ServiceAstElement::Function((
                                 call_name,
-                                Function::new(arguments, return_type, call_attributes),
+                                Function::new(arguments, return_type, call_attributes)
+                                    .with_error_type(error_type)
+                                    .with_oneway(is_oneway),
                             )));
+                            // comments after the last argument are kept right after the function
+                            service_parts.extend(
+                                pending
+                                    .take_free()
+                                    .into_iter()
+                                    .map(ServiceAstElement::Comment),
+                            );
                         }
                         Rule::event => {
                             let span = p.as_span();
@@ -417,6 +737,7 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                             let (event_name, event_attributes) = parse_name(&mut p, n)?;
                             let mut arguments = OrderedMap::new();
                             let mut attributes = Vec::new();
+                            let mut pending = Comments::default();
                             for p in p.by_ref() {
                                 match p.as_rule() {
                                     Rule::argument => {
@@ -427,14 +748,20 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                                                 Rule::ident => {
                                                     let name = n.as_str().to_string();
                                                     let typ = p.next().ok_or_else(|| ParseError::new(IncompleteArgumentIdent, span))?.as_str().to_string();
-                                                    let (typ, is_list, count) = parse_type(typ.as_str());
-                                                    arguments.push((name, TypeName::new(Namespace::new(typ), is_list, count, attributes.clone())));
-                                                    // arguments.insert(name, TypeName::new(Namespace::new(&typ), attributes.clone()));
+                                                    let (typ, lists) = parse_type(typ.as_str()).map_err(|e| ParseError::new(e, span))?;
+                                                    let (mut comments, mut docs) = pending.take();
+                                                    let argument = TypeName::new(Namespace::new(typ), lists, attributes.clone()).with_comments(&mut comments).with_docs(&mut docs);
+                                                    if arguments.insert(name.clone(), argument).is_some() {
+                                                        return Err(ParseError::duplicate("argument", &name, span));
+                                                    }
                                                     attributes.clear();
                                                 }
                                                 Rule::attributes => {
                                                     attributes = parse_attributes(n)?;
                                                 }
+                                                Rule::COMMENT => {
+                                                    pending.push(n.as_str());
+                                                }
                                                 _ => Err(ParseError::new(
                                                     UnexpectedElement(format!(
                                                         "while parsing argument for event \"{event_name}\" in service \"{service_name}\"! {p}"
@@ -444,6 +771,9 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                                             }
                                         }
                                     }
+                                    Rule::COMMENT => {
+                                        pending.push(p.as_str());
+                                    }
                                     _ => Err(ParseError::new(
                                         UnexpectedElement(format!(
                                             "while parsing event \"{event_name}\" in service \"{service_name}\"! {p}"
@@ -457,10 +787,28 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                                 event_name,
                                 Event::new(arguments, event_attributes),
                             )));
+                            // comments after the last argument are kept right after the event
+                            service_parts.extend(
+                                pending
+                                    .take_free()
+                                    .into_iter()
+                                    .map(ServiceAstElement::Comment),
+                            );
+                        }
+                        Rule::nested_data => {
+                            let (data_type, trailing) = parse_data_type(p)?;
+                            service_parts.push(ServiceAstElement::DataType(data_type));
+                            service_parts
+                                .extend(trailing.into_iter().map(ServiceAstElement::Comment));
+                        }
+                        Rule::COMMENT => {
+                            let (is_doc, lines) = comment_lines(p.as_str());
+                            service_parts.extend(lines.into_iter().map(if is_doc {
+                                ServiceAstElement::DocComment
+                            } else {
+                                ServiceAstElement::Comment
+                            }));
                         }
-                        Rule::COMMENT => service_parts.push(ServiceAstElement::Comment(
-                            p.as_span().as_str()[3..].trim().to_string(),
-                        )),
                         _ => Err(ParseError::new(
                             UnexpectedElement(format!(
                                 "while parsing service \"{service_name}\"! {p}"
@@ -474,12 +822,17 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
                     service_name,
                     service_parts,
                     attributes,
+                    visibility,
                 )));
             }
             Rule::EOI => {}
             Rule::COMMENT => {
-                let span = p.as_span();
-                result.push(AstElement::Comment(span.as_str()[3..].trim().to_string()));
+                let (is_doc, lines) = comment_lines(p.as_str());
+                result.extend(lines.into_iter().map(if is_doc {
+                    AstElement::DocComment
+                } else {
+                    AstElement::Comment
+                }));
             }
             _ => Err(ParseError::new(
                 UnexpectedElement(format!("{p}")),
@@ -494,9 +847,275 @@ pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
 #[allow(unused)]
 pub fn parse(content: &str, namespace: Namespace) -> Result<SsdModule, ParseError> {
     let raw = parse_raw(content)?;
+    check_duplicates(content, &raw)?;
     Ok(raw_to_ssd_file(namespace, &raw))
 }
 
+/// Limits for parsing untrusted input, e.g. in the playground or `ssd serve`, see
+/// [`parse_limited`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The maximum size of the input in bytes.
+    pub max_size: usize,
+    /// The maximum length of identifiers and numbers.
+    pub max_token_len: usize,
+    /// The maximum nesting of brackets (`{`, `(` and `[`).
+    pub max_depth: usize,
+    /// The maximum count of fixed-size lists, e.g. `4 of u8`.
+    pub max_count: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1 << 20,
+            max_token_len: 256,
+            max_depth: 16,
+            max_count: 1 << 16,
+        }
+    }
+}
+
+fn location(content: &str, pos: usize) -> String {
+    let before = &content[..pos];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    format!("line {line}, column {column}")
+}
+
+/// Checks the size, the length of tokens and the nesting of the content before it is parsed.
+fn check_limits(content: &str, limits: &ParseLimits) -> Result<(), ParseError> {
+    let exceeded = |info: String, pos: usize| ParseError {
+        error_type: ParseErrorType::LimitExceeded(info),
+        span: location(content, pos),
+        offset: Some(pos),
+    };
+    if content.len() > limits.max_size {
+        return Err(ParseError {
+            error_type: ParseErrorType::LimitExceeded(format!(
+                "the input has {} bytes, at most {} are allowed",
+                content.len(),
+                limits.max_size
+            )),
+            span: String::new(),
+            offset: None,
+        });
+    }
+
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut token_start = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_alphanumeric() || c == b'_' {
+            let start = *token_start.get_or_insert(i);
+            if i - start >= limits.max_token_len {
+                return Err(exceeded(
+                    format!("tokens can have at most {} bytes", limits.max_token_len),
+                    start,
+                ));
+            }
+            i += 1;
+            continue;
+        }
+        token_start = None;
+        match c {
+            // comments can contain anything
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'{' | b'(' | b'[' => {
+                depth += 1;
+                if depth > limits.max_depth {
+                    return Err(exceeded(
+                        format!("brackets can be nested at most {} levels", limits.max_depth),
+                        i,
+                    ));
+                }
+            }
+            b'}' | b')' | b']' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Returns the offset of the `nth` declaration of `name` (e.g. `data Player`) in the content.
+fn declaration_offset(content: &str, keyword: &str, name: &str, nth: usize) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    content
+        .match_indices(keyword)
+        .filter_map(|(start, _)| {
+            if content[..start].ends_with(is_ident) {
+                return None;
+            }
+            let rest = &content[start + keyword.len()..];
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() || !trimmed.starts_with(name) {
+                return None;
+            }
+            if trimmed[name.len()..].starts_with(is_ident) {
+                return None;
+            }
+            Some(start)
+        })
+        .nth(nth)
+}
+
+fn duplicate(kind: &str, name: &str, content: &str, offset: Option<usize>) -> ParseError {
+    ParseError {
+        error_type: ParseErrorType::Duplicate {
+            kind: kind.to_string(),
+            name: name.to_string(),
+        },
+        span: offset.map_or_else(String::new, |offset| location(content, offset)),
+        offset,
+    }
+}
+
+/// Returns the errors [`raw_to_ssd_file`] would panic on or silently accept, e.g. inline data
+/// types whose lifted name collides with a declared data type. The errors point to the
+/// declaration in `content` containing the duplicate.
+fn check_duplicates(content: &str, raw: &[AstElement]) -> Result<(), ParseError> {
+    let mut names = std::collections::HashSet::new();
+    // how often each declaration was seen, to find the right one in the content
+    let mut declarations = std::collections::HashMap::new();
+    for element in raw {
+        let (keyword, declaration) = match element {
+            AstElement::DataType((name, _)) => ("data", name),
+            AstElement::Enum((name, _)) => ("enum", name),
+            AstElement::Service((name, ..)) => ("service", name),
+            _ => continue,
+        };
+        let nth = declarations
+            .entry((keyword, declaration))
+            .and_modify(|n| *n += 1)
+            .or_insert(0usize);
+        let offset = || declaration_offset(content, keyword, declaration, *nth);
+        let mut insert = |kind: &'static str, name: String| {
+            if names.insert((kind, name.clone())) {
+                Ok(())
+            } else {
+                Err(duplicate(kind, &name, content, offset()))
+            }
+        };
+        match element {
+            AstElement::DataType((name, data_type)) => {
+                // including the lifted inline data types
+                for (name, _) in lift_data_type(name, data_type.clone()) {
+                    insert("datatype", name)?;
+                }
+            }
+            AstElement::Enum((name, _)) => insert("enum", name.clone())?,
+            AstElement::Service((name, elements, attributes, _)) => {
+                let mut members = std::collections::HashSet::new();
+                for element in elements {
+                    let (kind, name) = match element {
+                        ServiceAstElement::Function((name, _)) => ("function", name),
+                        ServiceAstElement::Event((name, _)) => ("event", name),
+                        ServiceAstElement::DataType((name, _)) => ("datatype", name),
+                        _ => continue,
+                    };
+                    if !members.insert((kind, name)) {
+                        return Err(duplicate(kind, name, content, offset()));
+                    }
+                }
+                let mut service = raw_service_to_service(elements, attributes);
+                for (name, _) in lift_service(name, &mut service, service_data_types(elements)) {
+                    insert("datatype", name)?;
+                }
+                insert("service", name.clone())?;
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn check_counts(module: &SsdModule, limits: &ParseLimits) -> Result<(), ParseError> {
+    let check = |name: String, typ: &TypeName| match typ.lists.iter().flatten().max() {
+        Some(&count) if count > limits.max_count => Err(ParseError {
+            error_type: ParseErrorType::LimitExceeded(format!(
+                "the count of {name} is {count}, at most {} is allowed",
+                limits.max_count
+            )),
+            span: String::new(),
+            offset: None,
+        }),
+        _ => Ok(()),
+    };
+    for (dt_name, dt) in &module.data_types {
+        for (name, property) in &dt.properties {
+            check(format!("{dt_name}.{name}"), property)?;
+        }
+    }
+    for (service_name, service) in &module.services {
+        for (fn_name, function) in &service.functions {
+            for (name, argument) in &function.arguments {
+                check(format!("{service_name}.{fn_name}({name})"), argument)?;
+            }
+            for typ in function.return_type.iter().chain(&function.error_type) {
+                check(format!("{service_name}.{fn_name}"), typ)?;
+            }
+        }
+        for (event_name, event) in &service.events {
+            for (name, argument) in &event.arguments {
+                check(format!("{service_name}.{event_name}({name})"), argument)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Like [`parse_raw`], but returns an error instead of using unbounded resources or panicking
+/// for pathological input (huge inputs, megabyte-long identifiers, deeply nested brackets or
+/// duplicate names).
+pub fn parse_raw_limited(
+    content: &str,
+    limits: &ParseLimits,
+) -> Result<Vec<AstElement>, ParseError> {
+    check_limits(content, limits)?;
+    let raw = parse_raw(content)?;
+    check_duplicates(content, &raw)?;
+    Ok(raw)
+}
+
+/// Like [`parse`], but safe for untrusted input, see [`parse_raw_limited`].
+///
+/// Fixed-size lists are limited as well, so the module can't make code working with its types
+/// allocate huge amounts of memory.
+pub fn parse_limited(
+    content: &str,
+    namespace: Namespace,
+    limits: &ParseLimits,
+) -> Result<SsdModule, ParseError> {
+    let raw = parse_raw_limited(content, limits)?;
+    let module = raw_to_ssd_file(namespace, &raw);
+    check_counts(&module, limits)?;
+    Ok(module)
+}
+
 pub(crate) fn raw_service_to_service(
     raw: &[ServiceAstElement],
     attributes: &[Attribute],
@@ -506,98 +1125,121 @@ pub(crate) fn raw_service_to_service(
     let mut events = OrderedMap::new();
 
     let mut comments = Vec::new();
+    let mut docs = Vec::new();
     for element in raw {
         match element {
             ServiceAstElement::Dependency(import) => {
-                dependencies.push(import.clone().with_comments(&mut comments));
+                dependencies.push(
+                    import
+                        .clone()
+                        .with_comments(&mut comments)
+                        .with_docs(&mut docs),
+                );
             }
             ServiceAstElement::Function((key, value)) => {
+                let function = value
+                    .clone()
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs);
                 assert!(
-                    !functions.iter().any(|(name, _)| name == key),
+                    functions.insert(key.clone(), function).is_none(),
                     "Duplicate function {key}!"
                 );
-                functions.push((key.clone(), value.clone().with_comments(&mut comments)));
-                // assert!(
-                //     functions
-                //         .insert(key.clone(), value.clone().with_comments(&mut comments))
-                //         .is_none(),
-                //     "Duplicate function {key}!"
-                // );
             }
             ServiceAstElement::Event((key, value)) => {
+                let event = value
+                    .clone()
+                    .with_comments(&mut comments)
+                    .with_docs(&mut docs);
                 assert!(
-                    !events.iter().any(|(name, _)| name == key),
+                    events.insert(key.clone(), event).is_none(),
                     "Duplicate event {key}!"
                 );
-                events.push((key.clone(), value.clone().with_comments(&mut comments)));
-                // assert!(
-                //     events
-                //         .insert(key.clone(), value.clone().with_comments(&mut comments))
-                //         .is_none(),
-                //     "Duplicate event {key}!"
-                // );
+            }
+            // lifted into the module, see `service_data_types`
+            ServiceAstElement::DataType(_) => {
+                comments.clear();
+                docs.clear();
             }
             ServiceAstElement::Comment(c) => comments.push(c.to_string()),
+            ServiceAstElement::DocComment(c) => docs.push(c.to_string()),
         }
     }
 
     Service::new(dependencies, functions, events, attributes.into())
 }
 
-pub(crate) fn raw_to_ssd_file(namespace: Namespace, raw: &[AstElement]) -> SsdModule {
+/// Returns the data types declared in the service together with their doc comments.
+pub(crate) fn service_data_types(raw: &[ServiceAstElement]) -> OrderedMap<DataType> {
+    let mut data_types = OrderedMap::new();
+    let mut docs = Vec::new();
+    for element in raw {
+        match element {
+            ServiceAstElement::DataType((name, data_type)) => {
+                data_types.insert(name.clone(), data_type.clone().with_docs(&mut docs));
+            }
+            ServiceAstElement::DocComment(c) => docs.push(c.clone()),
+            ServiceAstElement::Comment(_) => (),
+            _ => docs.clear(),
+        }
+    }
+    data_types
+}
+
+pub fn raw_to_ssd_file(namespace: Namespace, raw: &[AstElement]) -> SsdModule {
     let mut imports = Vec::new();
     let mut datatypes = OrderedMap::new();
     let mut enums = OrderedMap::new();
     let mut services = OrderedMap::new();
+    // the doc comments of the next data type, enum or service
+    let mut docs = Vec::new();
 
+    let mut version = None;
     for element in raw {
         match element {
-            AstElement::Import(import) => imports.push(import.clone()),
+            AstElement::Version(v) => {
+                version = Some(v.clone());
+                docs.clear();
+            }
+            AstElement::Import(import) => {
+                imports.push(import.clone());
+                docs.clear();
+            }
             AstElement::DataType((key, value)) => {
-                assert!(
-                    !datatypes.iter().any(|(name, _)| name == key),
-                    "Duplicate datatype {key}!"
-                );
-                datatypes.push((key.clone(), value.clone()));
+                // duplicates are reported by `check_duplicates`
+                datatypes.extend(lift_data_type(key, value.clone().with_docs(&mut docs)));
                 // assert!(
                 //     datatypes.insert(key.clone(), value.clone()).is_none(),
                 //     "Duplicate datatype {key}!"
                 // );
             }
             AstElement::Enum((key, value)) => {
+                let mut en = value.clone().with_docs(&mut docs);
+                if en.is_flags {
+                    en.assign_flag_values();
+                }
                 assert!(
-                    !enums.iter().any(|(name, _)| name == key),
+                    enums.insert(key.clone(), en).is_none(),
                     "Duplicate enum {key}!"
                 );
-                enums.push((key.clone(), value.clone()));
-                // assert!(
-                //     enums.insert(key.clone(), value.clone()).is_none(),
-                //     "Duplicate enum {key}!"
-                // );
             }
 
-            AstElement::Service((key, value, attributes)) => {
+            AstElement::Service((key, value, attributes, visibility)) => {
+                let mut service = raw_service_to_service(value, attributes)
+                    .with_visibility(*visibility)
+                    .with_docs(&mut docs);
+                datatypes.extend(lift_service(key, &mut service, service_data_types(value)));
                 assert!(
-                    !services.iter().any(|(name, _)| name == key),
+                    services.insert(key.clone(), service).is_none(),
                     "Duplicate service {key}!"
                 );
-                services.push((key.clone(), raw_service_to_service(value, attributes)));
-                // assert!(
-                //     services.insert(key.clone(), raw_service_to_service(value, attributes)).is_none(),
-                //     "Duplicate service {key}!"
-                // );
             }
             AstElement::Comment(_) => (),
+            AstElement::DocComment(c) => docs.push(c.clone()),
         }
     }
 
-    SsdModule::new(namespace, imports, datatypes, enums, services)
-}
-
-pub fn parse_file_raw<P: AsRef<Path>>(path: P) -> Result<Vec<AstElement>, ParseError> {
-    let content = std::fs::read_to_string(path).map_err(ParseError::from_dyn_error)?;
-
-    parse_raw(&content)
+    SsdModule::new(namespace, imports, datatypes, enums, services).with_version(version)
 }
 
 /// Parses the given file and returns the corresponding `SsdModule`.
@@ -633,7 +1275,9 @@ pub fn parse_file_with_namespace<P: AsRef<Path>>(
     path: P,
     namespace: Namespace,
 ) -> Result<SsdModule, ParseError> {
-    let raw = parse_file_raw(path)?;
+    let content = std::fs::read_to_string(path).map_err(ParseError::from_dyn_error)?;
+    let raw = parse_raw(&content)?;
+    check_duplicates(&content, &raw)?;
 
     Ok(raw_to_ssd_file(namespace, &raw))
 }
@@ -651,3 +1295,400 @@ fn test_simple() {
 fn test_raw() {
     insta::assert_json_snapshot!(parse_raw(include_str!("../../../data/test.svc"),).unwrap());
 }
+
+#[test]
+fn test_parse_limited() {
+    let limits = ParseLimits::default();
+    let module = parse_limited(
+        include_str!("../../../data/test.svc"),
+        Namespace::new("__test__"),
+        &limits,
+    )
+    .unwrap();
+    assert_eq!(
+        module,
+        parse(
+            include_str!("../../../data/test.svc"),
+            Namespace::new("__test__")
+        )
+        .unwrap()
+    );
+
+    let error = |content: &str| {
+        parse_limited(content, Namespace::new("test"), &limits)
+            .unwrap_err()
+            .to_string()
+    };
+    let long = format!("data {} {{}};", "A".repeat(1 << 20));
+    assert!(error(&long).starts_with("Limit exceeded, the input has"));
+    let long = format!("data {} {{}};", "A".repeat(1000));
+    assert_eq!(
+        error(&long),
+        "Limit exceeded, tokens can have at most 256 bytes. (line 1, column 6)"
+    );
+    let nested = format!("{}data A {{}};", "#[".repeat(100));
+    assert!(error(&nested).starts_with("Limit exceeded, brackets can be nested at most 16"));
+    // with both parsers
+    assert!(error("data A { x: 99999999999999999999999 of u8, };")
+        .starts_with("Expected a smaller count"));
+    assert_eq!(
+        error("data A { x: 100000 of u8, };"),
+        "Limit exceeded, the count of A.x is 100000, at most 65536 is allowed."
+    );
+    assert_eq!(
+        error("data A {}; data A {};"),
+        "Duplicate datatype A. (line 1, column 12)"
+    );
+    assert_eq!(
+        error("service S { fn f(); event e(); fn f(); };"),
+        "Duplicate function f. (line 1, column 1)"
+    );
+    // comments and strings aren't tokens
+    let comment = format!("/// {}\ndata A {{}};", "(".repeat(1000));
+    assert!(parse_limited(&comment, Namespace::new("test"), &limits).is_ok());
+}
+
+#[test]
+fn test_imports() {
+    let content = "import a::b as c;\nimport a::{B, C,};\nimport d;";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    assert_eq!(module.imports[0].alias.as_deref(), Some("c"));
+    assert_eq!(
+        module.imports[1].items,
+        Some(vec!["B".to_string(), "C".to_string()])
+    );
+    assert_eq!(
+        module.imports[2],
+        Import::new(Namespace::new("d"), Vec::new())
+    );
+}
+
+#[test]
+fn test_visibility() {
+    let content = "pub data A {};\ninternal enum E { A, };\nservice S {};";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    assert_eq!(module.data_types[0].visibility, Some(Visibility::Public));
+    assert_eq!(module.enums[0].visibility, Some(Visibility::Internal));
+    assert_eq!(module.services[0].visibility, None);
+}
+
+#[test]
+fn test_enum_values() {
+    let content = "enum E { A = 0xFF, B = 0b101, C = -1, D = -0x10, E = 7, F, };";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    let values = module.enums[0]
+        .values
+        .iter()
+        .map(|(_, v)| (v.value, v.radix))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [
+            (Some(255), Radix::Hex),
+            (Some(5), Radix::Binary),
+            (Some(-1), Radix::Decimal),
+            (Some(-16), Radix::Hex),
+            (Some(7), Radix::Decimal),
+            (None, Radix::Decimal),
+        ]
+    );
+    let error = parse(
+        "enum E { A = 0x8000000000000000, };",
+        Namespace::new("test"),
+    );
+    assert!(error.unwrap_err().to_string().contains("too large"));
+}
+
+#[test]
+fn test_flags() {
+    let content = "flags Permission { None = 0, Read, Write, All = 0b11, Execute, };";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    let permission = &module.enums[0];
+    assert!(permission.is_flags);
+    let values = permission
+        .values
+        .iter()
+        .map(|(_, v)| v.value)
+        .collect::<Vec<_>>();
+    assert_eq!(values, [Some(0), Some(1), Some(2), Some(3), Some(4)]);
+    assert!(permission.is_flag_combination(7));
+    assert!(!permission.is_flag_combination(8));
+
+    // the source keeps the values as written
+    let raw = parse_raw(content).unwrap();
+    let AstElement::Enum((_, permission)) = &raw[0] else {
+        panic!("expected an enum");
+    };
+    assert_eq!(permission.values[1].value, None);
+}
+
+#[test]
+fn test_lists() {
+    let module = parse(
+        "data Matrix { rows: list of list of f32, cells: 4 of 4 of f32, ids: list of 3 of u8, };",
+        Namespace::new("test"),
+    )
+    .unwrap();
+    let lists = module.data_types[0]
+        .properties
+        .iter()
+        .map(|(_, typ)| typ.lists.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lists,
+        [
+            vec![None, None],
+            vec![Some(4), Some(4)],
+            vec![None, Some(3)]
+        ]
+    );
+    let ids = &module.data_types[0].properties[2];
+    let element = ids.element().unwrap();
+    assert_eq!(element.lists, [Some(3)]);
+    assert_eq!(element.element().unwrap().typ.to_string(), "u8");
+    assert!(element.element().unwrap().element().is_none());
+
+    // the fields from before nested lists are still serialized
+    let cells = serde_json::to_value(&module.data_types[0].properties[1]).unwrap();
+    assert_eq!(cells["is_list"], true);
+    assert_eq!(cells["count"], 4);
+    let json = serde_json::to_string(&module).unwrap();
+    assert_eq!(serde_json::from_str::<SsdModule>(&json).unwrap(), module);
+
+    let limits = ParseLimits {
+        max_count: 8,
+        ..ParseLimits::default()
+    };
+    let content = "data A { a: 4 of 16 of u8, };";
+    assert!(parse_limited(content, Namespace::new("test"), &limits).is_err());
+}
+
+#[test]
+fn test_result_types() {
+    let module = parse(
+        "service Users { fn fetch(id: u64) -> result of User, list of ApiError; fn result() -> result; };",
+        Namespace::new("test"),
+    )
+    .unwrap();
+    let functions = &module.services[0].functions;
+    let fetch = &functions[0];
+    assert_eq!(fetch.return_type.as_ref().unwrap().typ.to_string(), "User");
+    let error = fetch.error_type.as_ref().unwrap();
+    assert_eq!(error.typ.to_string(), "ApiError");
+    assert_eq!(error.lists, [None]);
+    let result = &functions[1];
+    assert_eq!(
+        result.return_type.as_ref().unwrap().typ.to_string(),
+        "result"
+    );
+    assert!(result.error_type.is_none());
+
+    assert!(parse(
+        "service S { fn f() -> result of A; };",
+        Namespace::new("test")
+    )
+    .is_err());
+}
+
+#[test]
+fn test_oneway_functions() {
+    let module = parse(
+        "service Events { #[x] oneway fn notify(message: string); fn get() -> string; };",
+        Namespace::new("test"),
+    )
+    .unwrap();
+    let functions = &module.services[0].functions;
+    assert!(functions[0].is_oneway);
+    assert_eq!(functions[0].attributes.len(), 1);
+    assert!(!functions[1].is_oneway);
+
+    let content = "service S { oneway fn f() -> i32; };";
+    assert!(parse(content, Namespace::new("test")).is_err());
+}
+
+#[test]
+fn test_version() {
+    let content = "/// the api\nversion \"2.1.0\";\ndata A {};";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    assert_eq!(module.version.as_deref(), Some("2.1.0"));
+    assert!(module.data_types[0].docs.is_empty());
+    assert!(parse("data A {};", Namespace::new("test"))
+        .unwrap()
+        .version
+        .is_none());
+
+    let content = "data A {};\nversion \"2.1.0\";";
+    assert!(parse(content, Namespace::new("test")).is_err());
+}
+
+#[test]
+fn test_inline_types() {
+    let content = r#"
+        data Player {
+            pos: { x: f32, y: f32, },
+            #[inline(name = "Waypoint")]
+            path: list of { at: { x: f32, }, },
+        };
+        service Users {
+            /// A request
+            data Request { user: { id: u64, }, };
+            fn get(request: Request) -> list of Request;
+        };
+        #[inline(name = "{parent}_{name}")]
+        service Groups { data Request { id: u64, }; event created(request: Request); };
+        "#;
+    let module = parse(content, Namespace::new("test")).unwrap();
+    let names = module
+        .data_types
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "PlayerPos",
+            "WaypointAt",
+            "Waypoint",
+            "Player",
+            "UsersRequestUser",
+            "UsersRequest",
+            "Groups_Request"
+        ]
+    );
+    let player = &module.data_types[3].properties;
+    assert_eq!(player[0].typ.to_string(), "PlayerPos");
+    assert_eq!(player[1].typ.to_string(), "Waypoint");
+    assert_eq!(player[1].lists, [None]);
+    assert!(player.iter().all(|(_, typ)| typ.inline.is_none()));
+    assert_eq!(module.data_types[5].docs, ["A request"]);
+    let get = &module.services[0].functions[0];
+    assert_eq!(get.arguments[0].typ.to_string(), "UsersRequest");
+    assert_eq!(
+        get.return_type.as_ref().unwrap().typ.to_string(),
+        "UsersRequest"
+    );
+    let created = &module.services[1].events[0];
+    assert_eq!(created.arguments[0].typ.to_string(), "Groups_Request");
+
+    let conflicting = "data PlayerPos {}; data Player { pos: { x: f32, }, };";
+    let error = parse_limited(conflicting, Namespace::new("test"), &ParseLimits::default());
+    assert_eq!(
+        error.unwrap_err().to_string(),
+        "Duplicate datatype PlayerPos. (line 1, column 20)"
+    );
+}
+
+#[test]
+fn test_inline_type_collisions() {
+    let error = |content: &str| parse(content, Namespace::new("test")).unwrap_err();
+
+    let lifted = error("data Player { pos: { x: f32, }, };\ndata PlayerPos { a: u8, };");
+    assert_eq!(
+        lifted.to_string(),
+        "Duplicate datatype PlayerPos. (line 2, column 1)"
+    );
+    assert_eq!(lifted.offset, Some(35));
+
+    let nested = error("data UsersRequest {};\nservice Users { data Request {}; };");
+    assert_eq!(
+        nested.to_string(),
+        "Duplicate datatype UsersRequest. (line 2, column 1)"
+    );
+
+    // parse_file reports them as well
+    let dir = std::env::temp_dir().join(format!("ssd-collisions-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("player.svc");
+    std::fs::write(
+        &path,
+        "data Player { pos: { x: f32, }, }; data PlayerPos {};",
+    )
+    .unwrap();
+    let result = parse_file(&dir, &path);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_comments() {
+    let content = r"
+        // free
+        /// A rectangle
+        /* free as well */
+        data Rect {
+            /** x */
+            // the width
+            /// in pixels
+            width: u32,
+            // after the last property
+        };
+        service S {
+            /// Gets the rectangle.
+            fn get(
+                /// in the collection
+                /* or not */
+                id: u64
+            ) -> Rect;
+        };";
+    let module = parse(content, Namespace::new("test")).unwrap();
+    let rect = &module.data_types["Rect"];
+    assert_eq!(rect.docs, ["A rectangle"]);
+    let width = &rect.properties[0];
+    assert_eq!(width.comments, ["x", "the width"]);
+    assert_eq!(width.docs, ["in pixels"]);
+    let get = &module.services[0].functions[0];
+    assert_eq!(get.docs, ["Gets the rectangle."]);
+    assert!(get.comments.is_empty());
+    assert_eq!(get.arguments[0].comments, ["or not"]);
+    assert_eq!(get.arguments[0].docs, ["in the collection"]);
+
+    let raw = parse_raw(content).unwrap();
+    assert!(matches!(&raw[0], AstElement::Comment(c) if c == "free"));
+    assert!(matches!(&raw[1], AstElement::DocComment(c) if c == "A rectangle"));
+    assert!(matches!(&raw[2], AstElement::Comment(c) if c == "free as well"));
+    assert!(matches!(&raw[4], AstElement::Comment(c) if c == "after the last property"));
+    assert!(parse_raw("/* unterminated\ndata A {};").is_err());
+}
+
+#[test]
+fn test_backends() {
+    assert_eq!(
+        ParserBackend::default() == ParserBackend::Rd,
+        cfg!(feature = "rd_parser")
+    );
+    let content =
+        "/// a\ndata A { a: list of i32, };\nservice S { fn f(a: A) -> result of A, E; };";
+    assert_eq!(
+        serde_json::to_value(parse_raw_with(content, ParserBackend::Pest).unwrap()).unwrap(),
+        serde_json::to_value(parse_raw_with(content, ParserBackend::Rd).unwrap()).unwrap(),
+    );
+    assert!(parse_raw_with("data A {", ParserBackend::Pest).is_err());
+    assert!(parse_raw_with("data A {", ParserBackend::Rd).is_err());
+}
+
+#[test]
+fn test_duplicate_members() {
+    for backend in [ParserBackend::Pest, ParserBackend::Rd] {
+        let error = |content: &str| parse_raw_with(content, backend).unwrap_err().to_string();
+        assert_eq!(
+            error("data A { a: i32, a: u8, };"),
+            "Duplicate property a. (line 1, column 18)"
+        );
+        assert_eq!(
+            error("data A { pos: { x: f32, x: f32, }, };"),
+            "Duplicate property x. (line 1, column 25)"
+        );
+        assert_eq!(
+            error("service S { fn f(x: i32, x: u8); };"),
+            "Duplicate argument x. (line 1, column 26)"
+        );
+        assert_eq!(
+            error("service S { event e(x: i32, #[a] x: u8); };"),
+            "Duplicate argument x. (line 1, column 29)"
+        );
+        assert_eq!(
+            error("enum E { X, X = 5, };"),
+            "Duplicate enum value X. (line 1, column 13)"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Commands which post-process the generated content before it's written (`--postprocess`),
+//! e.g. formatters like `rustfmt`, `clang-format` or `prettier`.
+//!
+//! A command gets the content on stdin and answers with the processed content on stdout. It is
+//! given as program and arguments separated by spaces (`rustfmt --edition 2021`), optionally
+//! prefixed by the extension of the outputs it is run for (`ts=prettier --parser typescript`).
+//! Commands without extension run for all outputs, including the printed one. Multiple commands
+//! run in the given order.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostProcess {
+    /// Only outputs with this extension get processed.
+    pub extension: Option<String>,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl FromStr for PostProcess {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let Some(first) = words.next() else {
+            anyhow::bail!("The postprocess command is empty.");
+        };
+        let (extension, program) = match first.split_once('=') {
+            Some((extension, program))
+                if !extension.is_empty()
+                    && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                let program = match program {
+                    "" => words.next(),
+                    program => Some(program),
+                };
+                let Some(program) = program else {
+                    anyhow::bail!("The postprocess command for {extension} files is empty.");
+                };
+                (Some(extension.to_owned()), program)
+            }
+            _ => (None, first),
+        };
+        Ok(Self {
+            extension,
+            program: program.to_owned(),
+            args: words.map(ToOwned::to_owned).collect(),
+        })
+    }
+}
+
+impl PostProcess {
+    /// Whether the command runs for the output written to `path` (`None` for printed output).
+    fn applies_to(&self, path: Option<&Path>) -> bool {
+        match &self.extension {
+            None => true,
+            Some(extension) => path
+                .and_then(Path::extension)
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension)),
+        }
+    }
+
+    fn run(&self, name: &str, content: &str) -> anyhow::Result<String> {
+        let command = std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Could not start `{command}` for {name}: {e}"))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // written from another thread, so commands answering early can't block on a full pipe
+        let result = std::thread::scope(|scope| {
+            scope.spawn(move || {
+                // the command may exit without reading everything, e.g. on errors
+                let _ = stdin.write_all(content.as_bytes());
+            });
+            child.wait_with_output()
+        })?;
+        if !result.status.success() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            let mut message = format!("`{command}` failed for {name} ({}).", result.status);
+            if !stderr.trim().is_empty() {
+                message = format!("{message}\n{}", stderr.trim_end());
+            }
+            anyhow::bail!(message);
+        }
+        String::from_utf8(result.stdout)
+            .map_err(|_| anyhow::anyhow!("`{command}` answered with invalid utf-8 for {name}."))
+    }
+}
+
+/// Runs the commands which apply to the output written to `path` (`None` for printed output) on
+/// the content, see the [module documentation](self).
+///
+/// # Errors
+///
+/// Returns an error if a command can't be started or fails.
+pub fn postprocess(
+    commands: &[PostProcess],
+    path: Option<&Path>,
+    content: &str,
+) -> anyhow::Result<String> {
+    let name = path.map_or_else(
+        || "the output".to_owned(),
+        |path| path.display().to_string(),
+    );
+    let mut content = content.to_owned();
+    for command in commands.iter().filter(|c| c.applies_to(path)) {
+        content = command.run(&name, &content)?;
+    }
+    Ok(content)
+}
+
+/// Like [`postprocess`], for the files emitted by a generator, keyed by their relative path.
+///
+/// # Errors
+///
+/// Returns an error if a command can't be started or fails.
+pub fn postprocess_files(
+    commands: &[PostProcess],
+    files: &mut BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    for (path, content) in files {
+        *content = postprocess(commands, Some(Path::new(path)), content)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_postprocess() {
+    let command = "rustfmt --edition 2021".parse::<PostProcess>().unwrap();
+    assert_eq!(command.extension, None);
+    assert_eq!(command.program, "rustfmt");
+    assert_eq!(command.args, ["--edition", "2021"]);
+
+    let command = "ts=prettier --parser typescript"
+        .parse::<PostProcess>()
+        .unwrap();
+    assert_eq!(command.extension.as_deref(), Some("ts"));
+    assert_eq!(command.program, "prettier");
+    assert!(command.applies_to(Some(Path::new("api/users.TS"))));
+    assert!(!command.applies_to(Some(Path::new("api/users.rs"))));
+    assert!(!command.applies_to(None));
+
+    let command = "cpp= clang-format".parse::<PostProcess>().unwrap();
+    assert_eq!(command.program, "clang-format");
+    let command = "./fix.sh --define=x".parse::<PostProcess>().unwrap();
+    assert_eq!(command.extension, None);
+    assert!("".parse::<PostProcess>().is_err());
+    assert!("rs=".parse::<PostProcess>().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_postprocess() {
+    let commands = ["tr a-z A-Z", "rs=sed s/^/#/"]
+        .iter()
+        .map(|c| c.parse::<PostProcess>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(postprocess(&commands, None, "data").unwrap(), "DATA");
+    let mut files = BTreeMap::from([
+        ("a.rs".to_owned(), "fn a() {}\n".to_owned()),
+        ("b.txt".to_owned(), "b\n".to_owned()),
+    ]);
+    postprocess_files(&commands, &mut files).unwrap();
+    assert_eq!(files["a.rs"], "#FN A() {}\n");
+    assert_eq!(files["b.txt"], "B\n");
+
+    let failing = ["sh -c false".parse::<PostProcess>().unwrap()];
+    let error = postprocess(&failing, Some(Path::new("out.rs")), "x").unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("`sh -c false` failed for out.rs"));
+    let missing = ["ssd-no-such-formatter".parse::<PostProcess>().unwrap()];
+    assert!(postprocess(&missing, None, "x").is_err());
+}
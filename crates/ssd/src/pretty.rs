@@ -1,9 +1,8 @@
 use crate::ast::{AstElement, ServiceAstElement};
 use crate::ast::{
-    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Namespace, Parameter,
-    TypeName,
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Namespace, OrderedMap,
+    Parameter, TypeName, Visibility,
 };
-use crate::parser::raw_service_to_service;
 
 const INDENT: &str = "\t";
 
@@ -23,7 +22,7 @@ fn parameters_to_string(parameters: &[Parameter]) -> String {
         .join(", ")
 }
 
-fn attributes_to_string(attributes: &[Attribute]) -> String {
+pub fn attributes_to_string(attributes: &[Attribute]) -> String {
     let attr_string = attributes
         .iter()
         .map(|attribute| {
@@ -39,52 +38,69 @@ fn attributes_to_string(attributes: &[Attribute]) -> String {
     format!("#[{attr_string}]")
 }
 
+fn comment_line(marker: &str, comment: &str, indent: &str) -> String {
+    format!("{indent}{marker} {comment}").trim_end().to_string()
+}
+
+/// Pushes the free comments followed by the doc comments of an element.
+fn push_comments(result: &mut Vec<String>, comments: &[String], docs: &[String], indent: &str) {
+    result.extend(comments.iter().map(|c| comment_line("//", c, indent)));
+    result.extend(docs.iter().map(|c| comment_line("///", c, indent)));
+}
+
+fn lists_to_string(lists: &[Option<usize>]) -> String {
+    lists
+        .iter()
+        .map(|list| match list {
+            Some(count) => format!("{count} of "),
+            None => "list of ".to_string(),
+        })
+        .collect()
+}
+
+pub fn type_to_string(TypeName { typ, lists, .. }: &TypeName) -> String {
+    format!(
+        "{}{}",
+        lists_to_string(lists),
+        namespace_to_string(typ.clone())
+    )
+}
+
+fn visibility_to_string(visibility: Option<Visibility>) -> String {
+    visibility.map_or_else(String::new, |v| format!("{v} "))
+}
+
 fn datatype_to_string(name: &str, datatype: &DataType) -> String {
     let mut result = Vec::new();
 
     if !datatype.attributes.is_empty() {
         result.push(attributes_to_string(&datatype.attributes));
     }
-    result.push(format!("data {name} {{"));
-    for (
-        name,
-        TypeName {
-            typ,
-            is_list,
-            count,
-            attributes,
-            comments,
-        },
-    ) in &datatype.properties
-    {
-        for c in comments {
-            result.push(
-                c.lines()
-                    .map(|l| format!("{INDENT}/// {l}"))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            );
+    result.push(format!(
+        "{}data {name} {{",
+        visibility_to_string(datatype.visibility)
+    ));
+    push_properties(&mut result, &datatype.properties, INDENT);
+    result.push("};".to_string());
+    result.join("\n")
+}
+
+/// Pushes the properties of a data type, inline data types are put on separate lines.
+fn push_properties(result: &mut Vec<String>, properties: &OrderedMap<TypeName>, indent: &str) {
+    for (name, typ) in properties {
+        push_comments(result, &typ.comments, &typ.docs, indent);
+        if !typ.attributes.is_empty() {
+            result.push(format!("{indent}{}", attributes_to_string(&typ.attributes)));
         }
-        if !attributes.is_empty() {
-            result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+        match &typ.inline {
+            Some(inline) => {
+                result.push(format!("{indent}{name}: {}{{", lists_to_string(&typ.lists)));
+                push_properties(result, inline, &format!("{indent}{INDENT}"));
+                result.push(format!("{indent}}},"));
+            }
+            None => result.push(format!("{indent}{name}: {},", type_to_string(typ))),
         }
-        let list_string = if *is_list {
-            format!(
-                "{} of ",
-                count
-                    .map(|v| format!("{v}"))
-                    .unwrap_or_else(|| "list".to_string())
-            )
-        } else {
-            String::new()
-        };
-        result.push(format!(
-            "{INDENT}{name}: {list_string}{},",
-            namespace_to_string(typ.clone())
-        ));
     }
-    result.push("};".to_string());
-    result.join("\n")
 }
 
 fn enum_to_string(name: &str, en: &Enum) -> String {
@@ -93,31 +109,31 @@ fn enum_to_string(name: &str, en: &Enum) -> String {
     if !en.attributes.is_empty() {
         result.push(attributes_to_string(&en.attributes));
     }
-    result.push(format!("enum {name} {{"));
+    result.push(format!(
+        "{}{} {name} {{",
+        visibility_to_string(en.visibility),
+        if en.is_flags { "flags" } else { "enum" }
+    ));
     for (
         name,
         EnumValue {
             value,
             attributes,
             comments,
+            docs,
+            radix,
         },
     ) in &en.values
     {
         let mut attr_string = String::new();
 
-        for c in comments {
-            result.push(
-                c.lines()
-                    .map(|l| format!("{INDENT}/// {l}"))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            );
-        }
+        push_comments(&mut result, comments, docs, INDENT);
 
         if !attributes.is_empty() {
             attr_string = format!("{} ", attributes_to_string(attributes));
         }
         if let Some(value) = value {
+            let value = radix.format(*value);
             result.push(format!("{INDENT}{attr_string}{name} = {value},"));
         } else {
             result.push(format!("{INDENT}{attr_string}{name},"));
@@ -134,136 +150,151 @@ fn argument_to_string(name: &str, arg: &TypeName) -> String {
         attr_string = format!("{} ", attributes_to_string(&arg.attributes));
     }
 
-    format!(
-        "{attr_string}{name}: {}",
-        namespace_to_string(arg.typ.clone())
-    )
+    format!("{attr_string}{name}: {}", type_to_string(arg))
 }
 
-#[allow(clippy::too_many_lines)]
+/// Formats an argument list including the parentheses.
+///
+/// Arguments are put on separate lines, if any of them has comments.
+fn arguments_to_string(arguments: &OrderedMap<TypeName>) -> String {
+    if arguments
+        .iter()
+        .all(|(_, arg)| arg.comments.is_empty() && arg.docs.is_empty())
+    {
+        let arg_str = arguments
+            .iter()
+            .map(|(name, arg)| argument_to_string(name, arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return format!("({arg_str})");
+    }
+
+    let mut result = vec!["(".to_string()];
+    for (i, (name, arg)) in arguments.iter().enumerate() {
+        push_comments(&mut result, &arg.comments, &arg.docs, &INDENT.repeat(2));
+        let separator = if i + 1 < arguments.len() { "," } else { "" };
+        result.push(format!(
+            "{INDENT}{INDENT}{}{separator}",
+            argument_to_string(name, arg)
+        ));
+    }
+    result.push(format!("{INDENT})"));
+    result.join("\n")
+}
+
+/// Formats the service in the order of the raw elements, so comments stay where they are.
+///
+/// Groups of dependencies, functions and events are separated by an empty line.
 fn service_to_string(
     name: &str,
     service: &[ServiceAstElement],
     attributes: &[Attribute],
+    visibility: Option<Visibility>,
 ) -> String {
-    let service = raw_service_to_service(service, attributes);
     let mut result = Vec::new();
 
     if !attributes.is_empty() {
         result.push(attributes_to_string(attributes));
     }
 
-    result.push(format!("service {name} {{"));
+    result.push(format!(
+        "{}service {name} {{",
+        visibility_to_string(visibility)
+    ));
 
-    for Dependency {
-        name,
-        attributes,
-        comments,
-    } in &service.dependencies
-    {
-        for c in comments {
-            result.push(
-                c.lines()
-                    .map(|l| format!("{INDENT}/// {l}"))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            );
-        }
-
-        if !attributes.is_empty() {
-            result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
-        }
-        result.push(format!(
-            "{INDENT}depends on {};",
-            namespace_to_string(name.clone())
-        ));
-    }
-
-    result.push(String::new());
-
-    for (
-        name,
-        Function {
-            arguments,
-            return_type,
-            attributes,
-            comments,
-        },
-    ) in &service.functions
-    {
-        for c in comments {
-            result.push(
-                c.lines()
-                    .map(|l| format!("{INDENT}/// {l}"))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            );
+    let mut last_kind = None;
+    let mut comments = Vec::new();
+    for element in service {
+        match element {
+            ServiceAstElement::Comment(c) => {
+                comments.push(comment_line("//", c, INDENT));
+                continue;
+            }
+            ServiceAstElement::DocComment(c) => {
+                comments.push(comment_line("///", c, INDENT));
+                continue;
+            }
+            _ => (),
         }
 
-        if !attributes.is_empty() {
-            result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+        let kind = std::mem::discriminant(element);
+        if last_kind.is_some_and(|last| last != kind) {
+            result.push(String::new());
         }
-        let arg_str = arguments
-            .iter()
-            .map(|(name, arg)| argument_to_string(name, arg))
-            .collect::<Vec<_>>()
-            .join(", ");
-        if let Some(TypeName {
-            typ,
-            is_list,
-            count,
-            ..
-        }) = return_type
-        {
-            let list_string = if *is_list {
-                format!(
-                    "{} of ",
-                    count
-                        .map(|v| format!("{v}"))
-                        .unwrap_or_else(|| "list".to_string())
-                )
-            } else {
-                String::new()
-            };
-            result.push(format!(
-                "{INDENT}fn {name}({arg_str}) -> {list_string}{};",
-                namespace_to_string(typ.clone())
-            ));
-        } else {
-            result.push(format!("{INDENT}fn {name}({arg_str});"));
-        }
-    }
-
-    result.push(String::new());
+        last_kind = Some(kind);
+        result.append(&mut comments);
 
-    for (
-        name,
-        Event {
-            arguments,
-            attributes,
-            comments,
-        },
-    ) in &service.events
-    {
-        for c in comments {
-            result.push(
-                c.lines()
-                    .map(|l| format!("{INDENT}/// {l}"))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            );
-        }
-
-        if !attributes.is_empty() {
-            result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+        match element {
+            ServiceAstElement::Dependency(Dependency {
+                name, attributes, ..
+            }) => {
+                if !attributes.is_empty() {
+                    result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+                }
+                result.push(format!(
+                    "{INDENT}depends on {};",
+                    namespace_to_string(name.clone())
+                ));
+            }
+            ServiceAstElement::Function((
+                name,
+                Function {
+                    arguments,
+                    return_type,
+                    error_type,
+                    attributes,
+                    is_oneway,
+                    ..
+                },
+            )) => {
+                if !attributes.is_empty() {
+                    result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+                }
+                let arg_str = arguments_to_string(arguments);
+                if let (Some(return_type), Some(error_type)) = (return_type, error_type) {
+                    result.push(format!(
+                        "{INDENT}fn {name}{arg_str} -> result of {}, {};",
+                        type_to_string(return_type),
+                        type_to_string(error_type)
+                    ));
+                } else if let Some(return_type) = return_type {
+                    result.push(format!(
+                        "{INDENT}fn {name}{arg_str} -> {};",
+                        type_to_string(return_type)
+                    ));
+                } else if *is_oneway {
+                    result.push(format!("{INDENT}oneway fn {name}{arg_str};"));
+                } else {
+                    result.push(format!("{INDENT}fn {name}{arg_str};"));
+                }
+            }
+            ServiceAstElement::Event((
+                name,
+                Event {
+                    arguments,
+                    attributes,
+                    ..
+                },
+            )) => {
+                if !attributes.is_empty() {
+                    result.push(format!("{INDENT}{}", attributes_to_string(attributes)));
+                }
+                result.push(format!(
+                    "{INDENT}event {name}{};",
+                    arguments_to_string(arguments)
+                ));
+            }
+            ServiceAstElement::DataType((name, dt)) => {
+                result.extend(
+                    datatype_to_string(name, dt)
+                        .lines()
+                        .map(|line| format!("{INDENT}{line}")),
+                );
+            }
+            ServiceAstElement::Comment(_) | ServiceAstElement::DocComment(_) => unreachable!(),
         }
-        let arg_str = arguments
-            .iter()
-            .map(|(name, arg)| argument_to_string(name, arg))
-            .collect::<Vec<_>>()
-            .join(", ");
-        result.push(format!("{INDENT}event {name}({arg_str});"));
     }
+    result.append(&mut comments);
 
     result.push("};".to_string());
     result.join("\n")
@@ -276,6 +307,14 @@ pub fn pretty(raw: &[AstElement]) -> String {
     let mut result = Vec::new();
     for element in raw {
         match element {
+            AstElement::Version(version) => {
+                if !first_element && !last_element_comment {
+                    result.push(String::new());
+                }
+                result.push(format!("version \"{version}\";"));
+                last_element_import = false;
+                last_element_comment = false;
+            }
             AstElement::Import(import) => {
                 if !last_element_import && !first_element && !last_element_comment {
                     result.push(String::new());
@@ -283,10 +322,12 @@ pub fn pretty(raw: &[AstElement]) -> String {
                 if !import.attributes.is_empty() {
                     result.push(attributes_to_string(&import.attributes));
                 }
-                result.push(format!(
-                    "import {};",
-                    namespace_to_string(import.path.clone())
-                ));
+                let path = namespace_to_string(import.path.clone());
+                result.push(match (&import.alias, &import.items) {
+                    (Some(alias), _) => format!("import {path} as {alias};"),
+                    (None, Some(items)) => format!("import {path}::{{{}}};", items.join(", ")),
+                    (None, None) => format!("import {path};"),
+                });
                 last_element_import = true;
                 last_element_comment = false;
             }
@@ -306,19 +347,24 @@ pub fn pretty(raw: &[AstElement]) -> String {
                 last_element_import = false;
                 last_element_comment = false;
             }
-            AstElement::Service((name, svc, attributes)) => {
+            AstElement::Service((name, svc, attributes, visibility)) => {
                 if !first_element && !last_element_comment {
                     result.push(String::new());
                 }
-                result.push(service_to_string(name, svc, attributes));
+                result.push(service_to_string(name, svc, attributes, *visibility));
                 last_element_import = false;
                 last_element_comment = false;
             }
-            AstElement::Comment(c) => {
+            AstElement::Comment(c) | AstElement::DocComment(c) => {
                 if !first_element && !last_element_comment {
                     result.push(String::new());
                 }
-                result.push(format!("/// {}", c.clone()));
+                let marker = if matches!(element, AstElement::Comment(_)) {
+                    "//"
+                } else {
+                    "///"
+                };
+                result.push(comment_line(marker, c, ""));
                 last_element_import = false;
                 last_element_comment = true;
             }
@@ -328,3 +374,77 @@ pub fn pretty(raw: &[AstElement]) -> String {
     result.push(String::new());
     result.join("\n")
 }
+
+#[test]
+fn test_pretty_keeps_comments() {
+    let pretty_once = pretty(
+        &crate::parser::parse_raw(
+            r"
+            /* free */
+            /// documented
+            service S {
+                /// uses logger
+                depends on log::Logger;
+                fn a(
+                    /// first
+                    // not a doc
+                    x: i32, y: list of u8) -> i32;
+                /// trailing
+            };
+            ",
+        )
+        .unwrap(),
+    );
+    assert!(pretty_once.contains("\t/// uses logger\n\tdepends on log::Logger;"));
+    assert!(pretty_once.starts_with("// free\n/// documented\nservice S {"));
+    assert!(pretty_once
+        .contains("\t\t// not a doc\n\t\t/// first\n\t\tx: i32,\n\t\ty: list of u8\n\t) -> i32;"));
+    assert!(pretty_once.contains("\t/// trailing\n};"));
+    let pretty_twice = pretty(&crate::parser::parse_raw(&pretty_once).unwrap());
+    assert_eq!(pretty_once, pretty_twice);
+}
+
+#[test]
+fn test_pretty_keeps_radix() {
+    let content = "enum E { A = 0xff, B = 0b101, C = -1, D = -0x10, };";
+    let pretty = pretty(&crate::parser::parse_raw(content).unwrap());
+    assert!(pretty.contains("\tA = 0xFF,\n\tB = 0b101,\n\tC = -1,\n\tD = -0x10,"));
+}
+
+#[test]
+fn test_pretty_keeps_inline_types() {
+    let content = r"
+        data Player { pos: { x: f32, /// up
+        y: f32, }, path: list of { x: f32, }, };
+        service S { data Request { id: u64, }; fn get(request: Request); };
+        ";
+    let pretty_once = pretty(&crate::parser::parse_raw(content).unwrap());
+    assert!(pretty_once.contains("\tpos: {\n\t\tx: f32,\n\t\t/// up\n\t\ty: f32,\n\t},"));
+    assert!(pretty_once.contains("\tpath: list of {\n\t\tx: f32,\n\t},"));
+    assert!(pretty_once.contains("service S {\n\tdata Request {\n\t\tid: u64,\n\t};\n"));
+    let pretty_twice = pretty(&crate::parser::parse_raw(&pretty_once).unwrap());
+    assert_eq!(pretty_once, pretty_twice);
+}
+
+#[test]
+fn test_pretty_keeps_result_types() {
+    let content = "service S { fn fetch(id: u64) -> result of User, list of ApiError; };";
+    let pretty_once = pretty(&crate::parser::parse_raw(content).unwrap());
+    assert!(pretty_once.contains("\tfn fetch(id: u64) -> result of User, list of ApiError;\n"));
+    let pretty_twice = pretty(&crate::parser::parse_raw(&pretty_once).unwrap());
+    assert_eq!(pretty_once, pretty_twice);
+}
+
+#[test]
+fn test_pretty_keeps_oneway() {
+    let content = "service S { #[x] oneway fn notify(message: string); };";
+    let pretty = pretty(&crate::parser::parse_raw(content).unwrap());
+    assert!(pretty.contains("\t#[x]\n\toneway fn notify(message: string);\n"));
+}
+
+#[test]
+fn test_pretty_keeps_version() {
+    let content = "// api\nversion \"2.1.0\"; import a; data A {};";
+    let pretty = pretty(&crate::parser::parse_raw(content).unwrap());
+    assert!(pretty.starts_with("// api\nversion \"2.1.0\";\n\nimport a;\n"));
+}
@@ -0,0 +1,796 @@
+//! A hand-written recursive-descent parser for the description language.
+//!
+//! It produces the same elements as the pest grammar (`grammar.pest`), but works directly on the
+//! source without building a tree of pairs first, and reports the line and column together with
+//! what was expected when parsing fails. [`crate::parser::parse_raw`] uses it with `--parser rd`
+//! or by default with the `rd_parser` feature.
+
+use std::ops::Range;
+
+use crate::ast::{AstElement, ServiceAstElement};
+use crate::ast::{
+    Attribute, DataType, Dependency, Enum, EnumValue, Event, Function, Import, Namespace,
+    OrderedMap, Radix, TypeName, Visibility,
+};
+use crate::parser::{
+    comment_lines, parse_enum_number, print_handler_deprecation, ParseError, ParseErrorType,
+};
+
+type ParseResult<T> = Result<T, ParseError>;
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+fn is_ident_continue(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+/// The length of the identifier at the start of `s`, 0 if there is none.
+fn ident_len(s: &str) -> usize {
+    if s.bytes().next().is_some_and(is_ident_start) {
+        s.bytes().take_while(|c| is_ident_continue(*c)).count()
+    } else {
+        0
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// Lines of comments skipped since they were taken the last time, whether they are doc
+    /// comments and the byte range of the comment.
+    comments: Vec<(bool, String, Range<usize>)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            comments: Vec::new(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// Skips whitespace and collects comments (`///`, `//` and `/* */`).
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+            self.pos += rest.len() - trimmed.len();
+            let end = if trimmed.starts_with("//") {
+                trimmed.find('\n').unwrap_or(trimmed.len())
+            } else if let Some(block) = trimmed.strip_prefix("/*") {
+                // an unterminated block comment is left for the error
+                match block.find("*/") {
+                    Some(end) => end + 4,
+                    None => return,
+                }
+            } else {
+                return;
+            };
+            let start = self.pos;
+            self.pos += end;
+            let (is_doc, lines) = comment_lines(&trimmed[..end]);
+            self.comments.extend(
+                lines
+                    .into_iter()
+                    .map(|line| (is_doc, line, start..self.pos)),
+            );
+        }
+    }
+
+    /// Returns the free comments and the docs of the following element.
+    fn take_comments(&mut self) -> (Vec<String>, Vec<String>) {
+        let (docs, comments): (Vec<_>, Vec<_>) =
+            self.comments.drain(..).partition(|(is_doc, _, _)| *is_doc);
+        (
+            comments.into_iter().map(|(_, text, _)| text).collect(),
+            docs.into_iter().map(|(_, text, _)| text).collect(),
+        )
+    }
+
+    /// Turns the collected comments into free comments, for comments after the last member of
+    /// an element, which have nothing to document.
+    fn free_comments(&mut self) {
+        for (is_doc, _, _) in &mut self.comments {
+            *is_doc = false;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_trivia();
+        self.pos == self.src.len()
+    }
+
+    /// Describes the token at the current position for error messages.
+    fn found(&self) -> String {
+        let rest = self.rest();
+        match ident_len(rest) {
+            0 => rest
+                .chars()
+                .next()
+                .map_or_else(|| "end of file".to_string(), |c| format!("{c:?}")),
+            len => format!("\"{}\"", &rest[..len]),
+        }
+    }
+
+    fn location(&self) -> String {
+        let before = &self.src[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        format!("line {line}, column {column}")
+    }
+
+    fn error(&mut self, expected: &str) -> ParseError {
+        self.skip_trivia();
+        ParseError {
+            error_type: ParseErrorType::Expected {
+                expected: expected.to_string(),
+                found: self.found(),
+            },
+            span: self.location(),
+            offset: Some(self.pos),
+        }
+    }
+
+    /// The error for a name which was already declared, pointing at the second declaration
+    /// starting at `start`.
+    fn duplicate(&mut self, kind: &str, name: &str, start: usize) -> ParseError {
+        self.pos = start;
+        ParseError {
+            error_type: ParseErrorType::Duplicate {
+                kind: kind.to_string(),
+                name: name.to_string(),
+            },
+            span: self.location(),
+            offset: Some(start),
+        }
+    }
+
+    /// Consumes `token` if it comes next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_trivia();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str, context: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("\"{token}\" {context}")))
+        }
+    }
+
+    fn peek_ident(&mut self) -> Option<&'a str> {
+        self.skip_trivia();
+        let rest = self.rest();
+        match ident_len(rest) {
+            0 => None,
+            len => Some(&rest[..len]),
+        }
+    }
+
+    fn ident(&mut self, expected: &str) -> ParseResult<&'a str> {
+        let ident = self.peek_ident().ok_or_else(|| self.error(expected))?;
+        self.pos += ident.len();
+        Ok(ident)
+    }
+
+    /// Consumes the keyword if it comes next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_ident() == Some(keyword) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses `ident::ident::...` (without whitespace in between).
+    fn path(&mut self, expected: &str) -> ParseResult<&'a str> {
+        let first = self.ident(expected)?;
+        let start = self.pos - first.len();
+        let mut end = self.pos;
+        while self.src[end..].starts_with("::") {
+            // `import path::{...};`
+            if self.src[end + 2..].starts_with('{') {
+                break;
+            }
+            let len = ident_len(&self.src[end + 2..]);
+            if len == 0 {
+                self.pos = end + 2;
+                return Err(self.error("an identifier after \"::\""));
+            }
+            end += 2 + len;
+        }
+        self.pos = end;
+        Ok(&self.src[start..end])
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Parses a string and returns its content as written (escape sequences are kept).
+    fn string(&mut self) -> ParseResult<&'a str> {
+        if !self.eat("\"") {
+            return Err(self.error("a string"));
+        }
+        let bytes = self.src.as_bytes();
+        let start = self.pos;
+        let mut end = start;
+        loop {
+            match bytes.get(end) {
+                None => {
+                    self.pos = end;
+                    return Err(self.error("\"\\\"\" to close the string"));
+                }
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    if !matches!(
+                        bytes.get(end + 1),
+                        Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't')
+                    ) {
+                        self.pos = end;
+                        return Err(self.error("a valid escape sequence"));
+                    }
+                    end += 2;
+                }
+                Some(_) => end += 1,
+            }
+        }
+        self.pos = end + 1;
+        Ok(&self.src[start..end])
+    }
+
+    /// Parses the digits of a number, if there are any.
+    fn digits(&mut self) -> Option<&'a str> {
+        self.skip_trivia();
+        let rest = self.rest();
+        match rest.bytes().take_while(u8::is_ascii_digit).count() {
+            0 => None,
+            len => {
+                self.pos += len;
+                Some(&rest[..len])
+            }
+        }
+    }
+
+    /// Parses an enum value like `5`, `-1`, `0xFF` or `0b1010`, if there is one.
+    fn enum_number(&mut self) -> Option<&'a str> {
+        self.skip_trivia();
+        let rest = self.rest();
+        let sign = usize::from(rest.starts_with('-'));
+        let number = &rest.as_bytes()[sign..];
+        let is_binary = |b: &u8| matches!(b, b'0' | b'1');
+        let (prefix, is_digit): (usize, fn(&u8) -> bool) =
+            if number.starts_with(b"0x") && number.get(2).is_some_and(u8::is_ascii_hexdigit) {
+                (2, u8::is_ascii_hexdigit)
+            } else if number.starts_with(b"0b") && number.get(2).is_some_and(is_binary) {
+                (2, is_binary)
+            } else {
+                (0, u8::is_ascii_digit)
+            };
+        match number[prefix..].iter().take_while(|b| is_digit(b)).count() {
+            0 => None,
+            len => {
+                let len = sign + prefix + len;
+                self.pos += len;
+                Some(&rest[..len])
+            }
+        }
+    }
+
+    /// Parses `#[name, name(arg, arg = "value")]`, if it comes next.
+    fn attributes(&mut self) -> ParseResult<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        if !self.eat("#[") {
+            return Ok(attributes);
+        }
+        loop {
+            let name = self.path("an attribute")?;
+            let mut arguments = Vec::new();
+            if self.eat("(") {
+                loop {
+                    let name = self.path("an attribute argument")?;
+                    let value = if self.eat("=") {
+                        Some(self.string()?.to_string())
+                    } else {
+                        None
+                    };
+                    arguments.push((name.to_string(), value));
+                    if !self.eat(",") {
+                        self.expect(")", "or \",\" after the attribute argument")?;
+                        break;
+                    }
+                }
+            }
+            attributes.push(Attribute::new(Namespace::new(name), arguments));
+            if !self.eat(",") {
+                self.expect("]", "or \",\" after the attribute")?;
+                break;
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// Parses `type`, `list of type` or `<count> of type`, where the element type can be a list
+    /// again.
+    ///
+    /// With `inline`, it stops before the `{` of an inline data type, the type is empty then.
+    fn typ(&mut self, inline: bool) -> ParseResult<(Namespace, Vec<Option<usize>>)> {
+        let mut lists = Vec::new();
+        loop {
+            self.skip_trivia();
+            if inline && self.rest().starts_with('{') {
+                return Ok((Namespace::new(""), lists));
+            }
+            let start = self.pos;
+            if let Some(digits) = self.digits() {
+                let Ok(count) = digits.parse() else {
+                    self.pos = start;
+                    return Err(self.error("a smaller count"));
+                };
+                if !self.keyword("of") {
+                    return Err(self.error("\"of\" after the count"));
+                }
+                lists.push(Some(count));
+                continue;
+            }
+            let typ = self.path("a type")?;
+            if typ == "list" && self.keyword("of") {
+                lists.push(None);
+                continue;
+            }
+            return Ok((Namespace::new(typ), lists));
+        }
+    }
+
+    /// Parses `(name: type, ...)`. Comments before an argument are attached to it.
+    fn arguments(&mut self) -> ParseResult<OrderedMap<TypeName>> {
+        self.expect("(", "before the arguments")?;
+        let mut arguments = OrderedMap::new();
+        if self.eat(")") {
+            return Ok(arguments);
+        }
+        loop {
+            self.skip_trivia();
+            let start = self.pos;
+            let attributes = self.attributes()?;
+            let name = self.ident("an argument")?;
+            self.expect(":", "after the argument name")?;
+            let (typ, lists) = self.typ(false)?;
+            let (mut comments, mut docs) = self.take_comments();
+            let argument = TypeName::new(typ, lists, attributes)
+                .with_comments(&mut comments)
+                .with_docs(&mut docs);
+            if arguments.insert(name.to_string(), argument).is_some() {
+                return Err(self.duplicate("argument", name, start));
+            }
+            if !self.eat(",") {
+                self.expect(")", "or \",\" after the argument")?;
+                return Ok(arguments);
+            }
+        }
+    }
+
+    /// Parses `path;`, `path as alias;` or `path::{names};` after `import`.
+    fn import(&mut self, attributes: Vec<Attribute>) -> ParseResult<Import> {
+        let path = self.path("the path to import")?;
+        let mut import = Import::new(Namespace::new(path), attributes);
+        if self.keyword("as") {
+            import = import.with_alias(self.ident("an alias")?);
+        } else if self.eat("::") {
+            self.expect("{", "before the imported names")?;
+            let mut items = Vec::new();
+            loop {
+                items.push(self.ident("a name to import")?.to_string());
+                if !self.eat(",") {
+                    self.expect("}", "or \",\" after the imported name")?;
+                    break;
+                }
+                if self.eat("}") {
+                    break;
+                }
+            }
+            import = import.with_items(items);
+        }
+        self.expect(";", "after the import")?;
+        Ok(import)
+    }
+
+    /// Parses the properties of a data type or an inline data type up to the closing `}`.
+    fn properties(&mut self) -> ParseResult<OrderedMap<TypeName>> {
+        let mut properties = OrderedMap::new();
+        while !self.eat("}") {
+            let start = self.pos;
+            let attributes = self.attributes()?;
+            let name = self.ident("a property or \"}\"")?;
+            self.expect(":", "after the property name")?;
+            let (typ, lists) = self.typ(true)?;
+            let ((mut comments, mut docs), inline) = if typ == Namespace::new("") {
+                self.expect("{", "before the inline data type")?;
+                let comments = self.take_comments();
+                let inline = self.properties()?;
+                // comments after the last inline property belong to the next property
+                self.free_comments();
+                self.expect(",", "after the property")?;
+                (comments, Some(inline))
+            } else {
+                self.expect(",", "after the property")?;
+                (self.take_comments(), None)
+            };
+            let mut property = TypeName::new(typ, lists, attributes)
+                .with_comments(&mut comments)
+                .with_docs(&mut docs);
+            property.inline = inline;
+            if properties.insert(name.to_string(), property).is_some() {
+                return Err(self.duplicate("property", name, start));
+            }
+        }
+        Ok(properties)
+    }
+
+    fn data_type(
+        &mut self,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+    ) -> ParseResult<(String, DataType)> {
+        let name = self.ident("the name of the data type")?;
+        self.expect("{", "after the name of the data type")?;
+        let properties = self.properties()?;
+        self.expect(";", "after the data type")?;
+        self.free_comments();
+        Ok((
+            name.to_string(),
+            DataType::new(properties, attributes).with_visibility(visibility),
+        ))
+    }
+
+    fn enum_(
+        &mut self,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+        is_flags: bool,
+    ) -> ParseResult<AstElement> {
+        let name = self.ident("the name of the enum")?;
+        self.expect("{", "after the name of the enum")?;
+        let mut values = OrderedMap::new();
+        // enums need at least one value
+        while values.is_empty() || !self.eat("}") {
+            self.skip_trivia();
+            let start = self.pos;
+            let attributes = self.attributes()?;
+            let name = self.ident("an enum value")?;
+            let (value, radix) = if self.eat("=") {
+                let number = self.enum_number().ok_or_else(|| self.error("a number"))?;
+                let (value, radix) = parse_enum_number(number).map_err(|err| {
+                    self.pos -= number.len();
+                    ParseError {
+                        error_type: ParseErrorType::InvalidEnumValue(err.to_string()),
+                        span: self.location(),
+                        offset: Some(self.pos),
+                    }
+                })?;
+                (Some(value), radix)
+            } else {
+                (None, Radix::Decimal)
+            };
+            self.expect(",", "after the enum value")?;
+            let (mut comments, mut docs) = self.take_comments();
+            let enum_value = EnumValue::new(value, attributes)
+                .with_radix(radix)
+                .with_comments(&mut comments)
+                .with_docs(&mut docs);
+            if values.insert(name.to_string(), enum_value).is_some() {
+                return Err(self.duplicate("enum value", name, start));
+            }
+        }
+        self.expect(";", "after the enum")?;
+        self.free_comments();
+        Ok(AstElement::Enum((
+            name.to_string(),
+            Enum::new(values, attributes)
+                .with_visibility(visibility)
+                .with_flags(is_flags),
+        )))
+    }
+
+    fn service(
+        &mut self,
+        attributes: Vec<Attribute>,
+        visibility: Option<Visibility>,
+    ) -> ParseResult<AstElement> {
+        let name = self.ident("the name of the service")?;
+        self.expect("{", "after the name of the service")?;
+        let mut parts = Vec::new();
+        loop {
+            self.skip_trivia();
+            parts.extend(self.comments.drain(..).map(|(is_doc, text, _)| {
+                if is_doc {
+                    ServiceAstElement::DocComment(text)
+                } else {
+                    ServiceAstElement::Comment(text)
+                }
+            }));
+            if self.eat("};") {
+                break;
+            }
+            let attributes = self.attributes()?;
+            let part = if self.keyword("depends") {
+                if !self.keyword("on") {
+                    return Err(self.error("\"on\" after \"depends\""));
+                }
+                let path = self.path("the service to depend on")?;
+                self.expect(";", "after the dependency")?;
+                ServiceAstElement::Dependency(Dependency::new(Namespace::new(path), attributes))
+            } else if let Some(keyword @ ("fn" | "handles" | "oneway")) = self.peek_ident() {
+                self.pos += keyword.len();
+                if keyword == "handles" {
+                    print_handler_deprecation();
+                }
+                let is_oneway = keyword == "oneway";
+                if is_oneway && !self.keyword("fn") {
+                    return Err(self.error("\"fn\" after \"oneway\""));
+                }
+                let name = self.ident("the name of the function")?;
+                let arguments = self.arguments()?;
+                // `oneway` functions don't return anything
+                let (return_type, error_type) = if !is_oneway && self.eat("->") {
+                    let (typ, lists) = self.typ(false)?;
+                    if typ == Namespace::new("result") && lists.is_empty() && self.keyword("of") {
+                        let (ok, ok_lists) = self.typ(false)?;
+                        self.expect(",", "between the ok and the error type")?;
+                        let (error, error_lists) = self.typ(false)?;
+                        (
+                            Some(TypeName::new(ok, ok_lists, Vec::new())),
+                            Some(TypeName::new(error, error_lists, Vec::new())),
+                        )
+                    } else {
+                        (Some(TypeName::new(typ, lists, Vec::new())), None)
+                    }
+                } else {
+                    (None, None)
+                };
+                self.expect(";", "after the function")?;
+                self.free_comments();
+                ServiceAstElement::Function((
+                    name.to_string(),
+                    Function::new(arguments, return_type, attributes)
+                        .with_error_type(error_type)
+                        .with_oneway(is_oneway),
+                ))
+            } else if self.keyword("event") {
+                let name = self.ident("the name of the event")?;
+                let arguments = self.arguments()?;
+                self.expect(";", "after the event")?;
+                self.free_comments();
+                ServiceAstElement::Event((name.to_string(), Event::new(arguments, attributes)))
+            } else if self.keyword("data") {
+                ServiceAstElement::DataType(self.data_type(attributes, None)?)
+            } else {
+                return Err(self.error("\"fn\", \"event\", \"depends\", \"data\" or \"};\""));
+            };
+            parts.push(part);
+        }
+        Ok(AstElement::Service((
+            name.to_string(),
+            parts,
+            attributes,
+            visibility,
+        )))
+    }
+
+    fn parse(mut self) -> ParseResult<Vec<(AstElement, Range<usize>)>> {
+        let mut result = Vec::new();
+        loop {
+            // includes the comments after the last property or value of the previous element
+            self.skip_trivia();
+            result.extend(self.comments.drain(..).map(|(is_doc, text, span)| {
+                if is_doc {
+                    (AstElement::DocComment(text), span)
+                } else {
+                    (AstElement::Comment(text), span)
+                }
+            }));
+            if self.at_end() {
+                return Ok(result);
+            }
+
+            // only comments can come before the version
+            let is_first = result.iter().all(|(element, _)| {
+                matches!(element, AstElement::Comment(_) | AstElement::DocComment(_))
+            });
+            let start = self.pos;
+            let attributes = self.attributes()?;
+            let visibility = if self.keyword("pub") {
+                Some(Visibility::Public)
+            } else if self.keyword("internal") {
+                Some(Visibility::Internal)
+            } else {
+                None
+            };
+            let element = if is_first
+                && attributes.is_empty()
+                && visibility.is_none()
+                && self.keyword("version")
+            {
+                let version = self.string()?.to_string();
+                self.expect(";", "after the version")?;
+                AstElement::Version(version)
+            } else if visibility.is_none() && self.keyword("import") {
+                AstElement::Import(self.import(attributes)?)
+            } else if self.keyword("data") {
+                AstElement::DataType(self.data_type(attributes, visibility)?)
+            } else if self.keyword("enum") {
+                self.enum_(attributes, visibility, false)?
+            } else if self.keyword("flags") {
+                self.enum_(attributes, visibility, true)?
+            } else if self.keyword("service") {
+                self.service(attributes, visibility)?
+            } else if visibility.is_some() {
+                return Err(self.error("\"data\", \"enum\", \"flags\" or \"service\""));
+            } else {
+                return Err(self.error("\"import\", \"data\", \"enum\", \"flags\" or \"service\""));
+            };
+            result.push((element, start..self.pos));
+        }
+    }
+}
+
+/// Parses the content like [`parse_raw`], together with the byte range of every element.
+pub fn parse_raw_spanned(content: &str) -> Result<Vec<(AstElement, Range<usize>)>, ParseError> {
+    Parser::new(content).parse()
+}
+
+/// Parses the content into the raw elements (including comments).
+pub fn parse_raw(content: &str) -> Result<Vec<AstElement>, ParseError> {
+    Ok(parse_raw_spanned(content)?
+        .into_iter()
+        .map(|(element, _)| element)
+        .collect())
+}
+
+#[test]
+fn test_same_as_pest() {
+    let comments = r#"
+        data A {
+            /// a
+            a: i32,
+            /// after a
+        };
+        enum E { A = 1, /// after A
+        /* also after A */ };
+        // free
+        /* block
+         * comment */
+        service S {
+            /// f
+            fn f(/// x
+                x: list of i32, #[attr(a = "\"")] y: 5 of u8) -> list of A;
+            event e();
+            /// after e
+        };
+        /// at the end"#;
+    for content in [include_str!("../../../data/test.svc"), comments] {
+        assert_eq!(
+            serde_json::to_value(parse_raw(content).unwrap()).unwrap(),
+            serde_json::to_value(crate::parser::parse_raw_pest(content).unwrap()).unwrap(),
+        );
+    }
+
+    let error = parse_raw("data Rect {\n    x: i32\n};").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Expected \",\" after the property, found '}'. (line 3, column 1)"
+    );
+    let spans = parse_raw_spanned("/// doc\nimport a::b;").unwrap();
+    assert_eq!(spans[0].1, 0..7);
+    assert_eq!(spans[1].1, 8..20);
+}
+
+#[test]
+fn test_conformance() {
+    let valid = [
+        "",
+        "/// only a comment",
+        "version \"2.1.0\";",
+        "// c\n/// d\nversion \"1.0.0-beta\"; import a;",
+        "import a;\n/// a\n/// b\nimport b::C;\n/// at the end",
+        r#"#[a, b::c(d, e = "f\"")] import a::b::C;"#,
+        "import a::b as c;\nimport a::{B, C};\nimport a::b::{ C, D, };",
+        "data Empty {};",
+        "data A { a: B, b: list of C, c: 10 of d::E, d: list, };",
+        "data A { a: list of list of u8, b: 4 of 4 of f32, c: list of 3 of i32, d: list of list, };",
+        "data A { a: { b: i32, c: list of 2 of { d: u8, }, }, e: {}, };",
+        "data A {\n// a\na: { // b\n/// c\nb: i32,\n// d\n},\n/// e\ne: u8, };",
+        "service S { /// d\n#[x] data D { a: { b: i32, }, }; fn f(d: D); };",
+        "data A {\r\n    /// c\r\n    a: i32,\r\n};\r\n",
+        "enum E { A, B = 2, #[x] C = 3, };",
+        "/// f\n#[x] pub flags F { A, B, AB = 0b11, };",
+        "enum E { A = 0xfF, B = 0b1010, C = -1, D = -0x8000000000000000, };",
+        "service S {};",
+        "service S { depends on a::B; fn f(); fn g(a: i32) -> list of i32; event e(a: i32, b: 5 of u8); };",
+        "service S { fn f() -> result of A, list of b::E; fn g() -> result; fn h() -> list of result; };",
+        "service S { /// n\n#[x] oneway fn n(a: i32); oneway fn m(); fn oneway(); };",
+        "#[a] pub data A {};\ninternal enum E { A, };\npub service S {};",
+        "// not a doc comment\nimport a;",
+        "/* block */ data A { /* a\n * b */ a: i32, // c\n};\n/**/",
+        "/// doc\n// free\nservice S { // f\n/// g\nfn f(/* x */ x: i32); /// after\n};",
+    ];
+    for content in valid {
+        assert_eq!(
+            serde_json::to_value(parse_raw(content).unwrap()).unwrap(),
+            serde_json::to_value(crate::parser::parse_raw_pest(content).unwrap()).unwrap(),
+            "{content}"
+        );
+    }
+
+    let invalid = [
+        "import a",
+        "import a; version \"1\";",
+        "version \"1\"; version \"2\";",
+        "version 1;",
+        "version \"1\"",
+        "#[x] version \"1\";",
+        "pub version \"1\";",
+        "import a::;",
+        "import a as;",
+        "import a as b::c;",
+        "import a::{};",
+        "import a::{B C};",
+        "import a::{B} as c;",
+        "#[a] #[b] import c;",
+        "#[a(b = c)] import d;",
+        "#[a,] import d;",
+        "data 1A {};",
+        "data A { a: i32 };",
+        "data A { a: list of, };",
+        "data A { a: 5 u8, };",
+        "data A { a: list of 4 of, };",
+        "data A { a: list of list u8, };",
+        "data A { a: { b: i32 }, };",
+        "data A { a: list of { b: i32, } };",
+        "data A { a: b { c: i32, }, };",
+        "service S { pub data D {}; };",
+        "service S { fn f(a: { b: i32, }); };",
+        "data A { a: i32, }",
+        "enum E {};",
+        "flags F {};",
+        "enum E { A = B, };",
+        "enum E { A = 0x, };",
+        "enum E { A = - 1, };",
+        "enum E { A = 0b12, };",
+        "enum E { A = 0x8000000000000000, };",
+        "service S { fn f(a: i32,); };",
+        "service S { fn f() };",
+        "service S { fn f() -> result of A; };",
+        "service S { fn f() -> result of A, ; };",
+        "service S { fn f() -> list of result of A, B; };",
+        "service S { oneway fn f() -> i32; };",
+        "service S { oneway f(); };",
+        "service S { oneway event e(); };",
+        "service S { fn f(); } ;",
+        "service S { depends a; };",
+        "struct A {};",
+        "pub import a;",
+        "pub pub data A {};",
+        "pub #[a] data A {};",
+        "internal fn f();",
+        "/* unterminated\ndata A {};",
+        "data A { a: i32, }; */",
+    ];
+    for content in invalid {
+        assert!(parse_raw(content).is_err(), "{content}");
+        assert!(crate::parser::parse_raw_pest(content).is_err(), "{content}");
+    }
+}
@@ -0,0 +1,171 @@
+//! `ssd rename`: renames a data type, enum, service or function in the raw elements of a file.
+//!
+//! References are matched by the last component of their path, so `User`, `users::User` and
+//! `import users::User;` all refer to `User`. Attribute parameters with the name as key or value
+//! (e.g. `#[extends(User)]`) are renamed as well.
+
+use ssd_data::visit::{walk_attributes_mut, walk_type_name_mut};
+use ssd_data::{Attribute, Dependency, Import, Namespace, TypeName, VisitorMut};
+
+use crate::ast::{AstElement, ServiceAstElement};
+
+struct Rename<'a> {
+    from: &'a str,
+    to: &'a str,
+    changed: bool,
+}
+
+impl Rename<'_> {
+    fn name(&mut self, name: &mut String) {
+        if name == self.from {
+            self.to.clone_into(name);
+            self.changed = true;
+        }
+    }
+
+    fn path(&mut self, path: &mut Namespace) {
+        if let Some(last) = path.components.last_mut() {
+            self.name(last);
+        }
+    }
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_import_mut(&mut self, import: &mut Import) {
+        self.path(&mut import.path);
+        for item in import.items.iter_mut().flatten() {
+            self.name(item);
+        }
+        walk_attributes_mut(self, &mut import.attributes);
+    }
+
+    fn visit_dependency_mut(&mut self, dependency: &mut Dependency) {
+        self.path(&mut dependency.name);
+        walk_attributes_mut(self, &mut dependency.attributes);
+    }
+
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        self.path(&mut type_name.typ);
+        walk_type_name_mut(self, type_name);
+    }
+
+    fn visit_attribute_mut(&mut self, attribute: &mut Attribute) {
+        for parameter in &mut attribute.parameters {
+            self.name(&mut parameter.name);
+            if let Some(value) = &mut parameter.value {
+                self.name(value);
+            }
+        }
+    }
+}
+
+/// Whether a data type, enum, service or function (or a data type declared in a service) of the
+/// elements is named `name`.
+pub fn declares(elements: &[AstElement], name: &str) -> bool {
+    elements.iter().any(|element| match element {
+        AstElement::DataType((declared, _)) | AstElement::Enum((declared, _)) => declared == name,
+        AstElement::Service((declared, service, ..)) => {
+            declared == name
+                || service.iter().any(|element| match element {
+                    ServiceAstElement::Function((declared, _))
+                    | ServiceAstElement::DataType((declared, _)) => declared == name,
+                    _ => false,
+                })
+        }
+        _ => false,
+    })
+}
+
+/// Renames the data type, enum, service or function `from` to `to` together with all references
+/// to it, see the [module documentation](self).
+///
+/// Returns `false` if nothing was renamed.
+pub fn rename(elements: &mut [AstElement], from: &str, to: &str) -> bool {
+    let mut rename = Rename {
+        from,
+        to,
+        changed: false,
+    };
+    for element in elements {
+        match element {
+            AstElement::Comment(_) | AstElement::DocComment(_) | AstElement::Version(_) => (),
+            AstElement::Import(import) => rename.visit_import_mut(import),
+            AstElement::DataType((name, data_type)) => {
+                rename.name(name);
+                rename.visit_data_type_mut(name, data_type);
+            }
+            AstElement::Enum((name, en)) => {
+                rename.name(name);
+                rename.visit_enum_mut(name, en);
+            }
+            AstElement::Service((name, service, attributes, _)) => {
+                rename.name(name);
+                walk_attributes_mut(&mut rename, attributes);
+                for element in service {
+                    match element {
+                        ServiceAstElement::Comment(_) | ServiceAstElement::DocComment(_) => (),
+                        ServiceAstElement::Dependency(dependency) => {
+                            rename.visit_dependency_mut(dependency);
+                        }
+                        ServiceAstElement::Function((name, function)) => {
+                            rename.name(name);
+                            rename.visit_function_mut(name, function);
+                        }
+                        ServiceAstElement::Event((name, event)) => {
+                            rename.visit_event_mut(name, event);
+                        }
+                        ServiceAstElement::DataType((name, data_type)) => {
+                            rename.name(name);
+                            rename.visit_data_type_mut(name, data_type);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    rename.changed
+}
+
+#[test]
+fn test_rename() {
+    let mut raw = crate::parser::parse_raw(
+        r#"
+        import users::User;
+        import accounts::{User, Role};
+        /// A user
+        #[extends(User)]
+        data User { name: string, };
+        data Group { owner: users::User, #[ref(type = "User")] members: list of User, };
+        service Users {
+            depends on users::User;
+            fn User(id: u64) -> User;
+            event created(user: User);
+        };
+        "#,
+    )
+    .unwrap();
+    assert!(rename(&mut raw, "User", "Account"));
+    let renamed = crate::pretty::pretty(&raw);
+    assert!(!renamed.contains("User;"));
+    assert!(!renamed.contains("User,"));
+    assert!(renamed.contains("/// A user\n#[extends(Account)]\ndata Account {"));
+    assert!(renamed.contains("import accounts::{Account, Role};"));
+    assert!(renamed.contains("#[ref(type = \"Account\")]"));
+    assert!(renamed.contains("fn Account(id: u64) -> Account;"));
+    assert!(renamed.contains("event created(user: Account);"));
+
+    assert!(!rename(&mut raw, "User", "Account"));
+}
+
+#[test]
+fn test_declares() {
+    let raw = crate::parser::parse_raw(
+        "data User {}; enum Role { Admin, }; service Users { data Request {}; fn get(); };",
+    )
+    .unwrap();
+    for name in ["User", "Role", "Users", "Request", "get"] {
+        assert!(declares(&raw, name), "{name}");
+    }
+    assert!(!declares(&raw, "Admin"));
+    assert!(!declares(&raw, "Account"));
+}
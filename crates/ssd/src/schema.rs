@@ -0,0 +1,934 @@
+//! The description of the data ssd passes to generators (`ssd schema`), as JSON Schema or
+//! TypeScript definitions, e.g. for autocompletion in wasm plugins and exec generators.
+//!
+//! The structures are described once in [`DEFINITIONS`] in the shape of their serialization:
+//! named elements ([`ssd_data::OrderedMap`]) are lists of `[name, value]` pairs, namespaces
+//! objects with their `components`, and the raw elements (`ast`) objects with their kind as only
+//! key.
+
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SchemaFormat {
+    /// A JSON Schema (draft 2020-12) with the structures in `$defs`
+    #[default]
+    JsonSchema,
+    /// A `.d.ts` file with an interface per structure
+    Typescript,
+}
+
+/// The type of a serialized value.
+#[derive(Debug)]
+enum Type {
+    String,
+    Integer,
+    Boolean,
+    /// Any value, e.g. defines or raw data.
+    Any,
+    /// One of the [`DEFINITIONS`].
+    Ref(&'static str),
+    List(&'static Type),
+    /// The value or `null`.
+    Nullable(&'static Type),
+    /// An [`ssd_data::OrderedMap`]: a list of `[name, value]` pairs.
+    Named(&'static Type),
+    /// An object with any keys.
+    Map(&'static Type),
+    Tuple(&'static [Type]),
+}
+
+#[derive(Debug)]
+struct Field {
+    name: &'static str,
+    typ: Type,
+    doc: &'static str,
+    /// Whether the field is left out when it's not set, instead of being `null`.
+    optional: bool,
+}
+
+#[derive(Debug)]
+enum Shape {
+    Object(&'static [Field]),
+    /// One of the strings.
+    Strings(&'static [&'static str]),
+    /// An enum serialized as an object with the variant as its only key.
+    Variants(&'static [(&'static str, Type)]),
+}
+
+#[derive(Debug)]
+struct Definition {
+    name: &'static str,
+    doc: &'static str,
+    shape: Shape,
+}
+
+const fn field(name: &'static str, typ: Type, doc: &'static str) -> Field {
+    Field {
+        name,
+        typ,
+        doc,
+        optional: false,
+    }
+}
+
+const fn optional(name: &'static str, typ: Type, doc: &'static str) -> Field {
+    Field {
+        name,
+        typ,
+        doc,
+        optional: true,
+    }
+}
+
+const ATTRIBUTES: Field = field(
+    "attributes",
+    Type::List(&Type::Ref("Attribute")),
+    "The attributes of the element, e.g. `#[table(name = \"orders\")]`.",
+);
+const COMMENTS: Field = field(
+    "comments",
+    Type::List(&Type::String),
+    "Free comments (`//` and `/* */`) before the element.",
+);
+const DOCS: Field = field(
+    "docs",
+    Type::List(&Type::String),
+    "Doc comments (`///`) of the element.",
+);
+const VISIBILITY: Field = field(
+    "visibility",
+    Type::Nullable(&Type::Ref("Visibility")),
+    "`pub` or `internal`, `null` if the declaration has no marker (which counts as published).",
+);
+const DEFINES: Field = field(
+    "defines",
+    Type::Map(&Type::Any),
+    "The values given with `-D` or `--define-file`, sorted by name.",
+);
+const META: Field = field("meta", Type::Ref("Meta"), "Information about the run.");
+
+const DEFINITIONS: &[Definition] = &[
+    Definition {
+        name: "SsdModel",
+        doc: "What generators get for a description file.",
+        shape: Shape::Object(&[
+            field("module", Type::Ref("SsdModule"), "The parsed module."),
+            DEFINES,
+            META,
+            optional(
+                "ast",
+                Type::List(&Type::Ref("AstElement")),
+                "The elements of the description file in their original order, including \
+                 comments. Only set with `--with-ast`.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "RawModel",
+        doc: "What generators get for raw data (`--raw`).",
+        shape: Shape::Object(&[
+            field("raw", Type::Any, "The data as read from the files."),
+            DEFINES,
+            META,
+        ]),
+    },
+    Definition {
+        name: "Meta",
+        doc: "Information about the generator run (e.g. for \"generated by\" headers).",
+        shape: Shape::Object(&[
+            field("tool_version", Type::String, "The version of ssd."),
+            field(
+                "source",
+                Type::String,
+                "The input file as given on the command line.",
+            ),
+            field(
+                "namespace",
+                Type::String,
+                "The namespace of the module, empty for raw data.",
+            ),
+            field(
+                "arguments",
+                Type::List(&Type::String),
+                "The arguments ssd was called with, without the program name.",
+            ),
+            field(
+                "timestamp",
+                Type::Nullable(&Type::String),
+                "The time of the run in UTC (RFC 3339), only set with `--timestamp`.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "SsdModule",
+        doc: "A parsed description file.",
+        shape: Shape::Object(&[
+            field(
+                "namespace",
+                Type::Ref("Namespace"),
+                "The namespace of the module.",
+            ),
+            field(
+                "imports",
+                Type::List(&Type::Ref("Import")),
+                "The imports of the module.",
+            ),
+            field(
+                "data_types",
+                Type::Named(&Type::Ref("DataType")),
+                "The data types by name.",
+            ),
+            field(
+                "enums",
+                Type::Named(&Type::Ref("Enum")),
+                "The enums by name.",
+            ),
+            field(
+                "services",
+                Type::Named(&Type::Ref("Service")),
+                "The services by name.",
+            ),
+            field(
+                "version",
+                Type::Nullable(&Type::String),
+                "The version declared with `version \"2.1.0\";`.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Namespace",
+        doc: "A path like `shop::orders::Order`.",
+        shape: Shape::Object(&[field(
+            "components",
+            Type::List(&Type::String),
+            "The components of the path, e.g. `[\"shop\", \"orders\", \"Order\"]`.",
+        )]),
+    },
+    Definition {
+        name: "Import",
+        doc: "An import like `import shop::orders::{Order};`.",
+        shape: Shape::Object(&[
+            field("path", Type::Ref("Namespace"), "The imported path."),
+            ATTRIBUTES,
+            field(
+                "alias",
+                Type::Nullable(&Type::String),
+                "The name given with `as`.",
+            ),
+            field(
+                "items",
+                Type::Nullable(&Type::List(&Type::String)),
+                "The names imported with `{...}`.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Attribute",
+        doc: "An attribute like `#[table(name = \"orders\")]`.",
+        shape: Shape::Object(&[
+            field("name", Type::Ref("Namespace"), "The name of the attribute."),
+            field(
+                "parameters",
+                Type::List(&Type::Ref("Parameter")),
+                "The parameters in parentheses.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Parameter",
+        doc: "A parameter of an attribute like `name = \"orders\"` or `optional`.",
+        shape: Shape::Object(&[
+            field("name", Type::String, "The name of the parameter."),
+            field(
+                "value",
+                Type::Nullable(&Type::String),
+                "The value after `=`, if there is one.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Visibility",
+        doc: "Whether an element is part of the published API or a helper.",
+        shape: Shape::Strings(&["pub", "internal"]),
+    },
+    Definition {
+        name: "DataType",
+        doc: "A data type like `data Order { id: u64, };`.",
+        shape: Shape::Object(&[
+            field(
+                "properties",
+                Type::Named(&Type::Ref("TypeName")),
+                "The properties by name.",
+            ),
+            ATTRIBUTES,
+            VISIBILITY,
+            DOCS,
+        ]),
+    },
+    Definition {
+        name: "Enum",
+        doc: "An enum like `enum State { Open, Closed, };` or `flags`.",
+        shape: Shape::Object(&[
+            field(
+                "values",
+                Type::Named(&Type::Ref("EnumValue")),
+                "The values by name.",
+            ),
+            ATTRIBUTES,
+            VISIBILITY,
+            DOCS,
+            field(
+                "is_flags",
+                Type::Boolean,
+                "Declared with `flags`: the values are bits, which can be combined.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "EnumValue",
+        doc: "A value of an enum.",
+        shape: Shape::Object(&[
+            field(
+                "value",
+                Type::Nullable(&Type::Integer),
+                "The number of the value, if it has one.",
+            ),
+            ATTRIBUTES,
+            COMMENTS,
+            DOCS,
+            field(
+                "radix",
+                Type::Ref("Radix"),
+                "How the number is written in the description.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Radix",
+        doc: "How an enum value is written, e.g. `255`, `0xFF` or `0b11111111`.",
+        shape: Shape::Strings(&["decimal", "hex", "binary"]),
+    },
+    Definition {
+        name: "Service",
+        doc: "A service with its dependencies, functions and events.",
+        shape: Shape::Object(&[
+            field(
+                "dependencies",
+                Type::List(&Type::Ref("Dependency")),
+                "The services it depends on.",
+            ),
+            field(
+                "functions",
+                Type::Named(&Type::Ref("Function")),
+                "The functions by name.",
+            ),
+            field(
+                "events",
+                Type::Named(&Type::Ref("Event")),
+                "The events by name.",
+            ),
+            ATTRIBUTES,
+            VISIBILITY,
+            DOCS,
+        ]),
+    },
+    Definition {
+        name: "Dependency",
+        doc: "A dependency like `depends on shop::Customers;`.",
+        shape: Shape::Object(&[
+            field("name", Type::Ref("Namespace"), "The service depended on."),
+            ATTRIBUTES,
+            COMMENTS,
+            DOCS,
+        ]),
+    },
+    Definition {
+        name: "Function",
+        doc: "A function of a service.",
+        shape: Shape::Object(&[
+            field(
+                "arguments",
+                Type::Named(&Type::Ref("TypeName")),
+                "The arguments by name.",
+            ),
+            field(
+                "return_type",
+                Type::Nullable(&Type::Ref("TypeName")),
+                "The returned type, for `-> result of T, E` the ok type `T`.",
+            ),
+            ATTRIBUTES,
+            COMMENTS,
+            DOCS,
+            field(
+                "error_type",
+                Type::Nullable(&Type::Ref("TypeName")),
+                "The error type `E` of `-> result of T, E`.",
+            ),
+            field(
+                "is_oneway",
+                Type::Boolean,
+                "Declared with `oneway fn`: a notification without a response.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "Event",
+        doc: "An event of a service.",
+        shape: Shape::Object(&[
+            field(
+                "arguments",
+                Type::Named(&Type::Ref("TypeName")),
+                "The arguments by name.",
+            ),
+            ATTRIBUTES,
+            COMMENTS,
+            DOCS,
+        ]),
+    },
+    Definition {
+        name: "TypeName",
+        doc: "The type of a property or argument.",
+        shape: Shape::Object(&[
+            field(
+                "typ",
+                Type::Ref("Namespace"),
+                "The name of the type (of the elements for lists).",
+            ),
+            field(
+                "lists",
+                Type::List(&Type::Nullable(&Type::Integer)),
+                "The lists around the type, outermost first: `null` for `list of` and the count \
+                 for `4 of`.",
+            ),
+            field("is_list", Type::Boolean, "Whether `lists` isn't empty."),
+            field(
+                "count",
+                Type::Nullable(&Type::Integer),
+                "Deprecated, the count of the outermost list (`lists[0]`).",
+            ),
+            ATTRIBUTES,
+            COMMENTS,
+            DOCS,
+            optional(
+                "inline",
+                Type::Named(&Type::Ref("TypeName")),
+                "The properties of an inline data type, only in the raw elements.",
+            ),
+        ]),
+    },
+    Definition {
+        name: "AstElement",
+        doc: "An element of a description file (`--with-ast`).",
+        shape: Shape::Variants(&[
+            ("Comment", Type::String),
+            ("DocComment", Type::String),
+            ("Version", Type::String),
+            ("Import", Type::Ref("Import")),
+            (
+                "DataType",
+                Type::Tuple(&[Type::String, Type::Ref("DataType")]),
+            ),
+            ("Enum", Type::Tuple(&[Type::String, Type::Ref("Enum")])),
+            (
+                "Service",
+                Type::Tuple(&[
+                    Type::String,
+                    Type::List(&Type::Ref("ServiceAstElement")),
+                    Type::List(&Type::Ref("Attribute")),
+                    Type::Nullable(&Type::Ref("Visibility")),
+                ]),
+            ),
+        ]),
+    },
+    Definition {
+        name: "ServiceAstElement",
+        doc: "An element of a service in a description file (`--with-ast`).",
+        shape: Shape::Variants(&[
+            ("Comment", Type::String),
+            ("DocComment", Type::String),
+            ("Dependency", Type::Ref("Dependency")),
+            (
+                "Function",
+                Type::Tuple(&[Type::String, Type::Ref("Function")]),
+            ),
+            ("Event", Type::Tuple(&[Type::String, Type::Ref("Event")])),
+            (
+                "DataType",
+                Type::Tuple(&[Type::String, Type::Ref("DataType")]),
+            ),
+        ]),
+    },
+];
+
+/// Returns the description of the data passed to generators in the format.
+pub fn schema(format: SchemaFormat) -> Result<String, serde_json::Error> {
+    match format {
+        SchemaFormat::JsonSchema => serde_json::to_string_pretty(&json_schema()),
+        SchemaFormat::Typescript => Ok(typescript()),
+    }
+}
+
+fn json_schema() -> Value {
+    let definitions = DEFINITIONS
+        .iter()
+        .map(|definition| (definition.name.to_owned(), json_definition(definition)))
+        .collect::<Map<_, _>>();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "ssd model",
+        "description": format!(
+            "The data ssd v{} passes to generators: SsdModel, or RawModel with --raw.",
+            env!("CARGO_PKG_VERSION")
+        ),
+        "oneOf": [
+            {"$ref": "#/$defs/SsdModel"},
+            {"$ref": "#/$defs/RawModel"},
+        ],
+        "$defs": definitions,
+    })
+}
+
+fn json_definition(definition: &Definition) -> Value {
+    match &definition.shape {
+        Shape::Object(fields) => {
+            let properties = fields
+                .iter()
+                .map(|field| {
+                    let mut schema = json_type(&field.typ);
+                    schema["description"] = field.doc.into();
+                    (field.name.to_owned(), schema)
+                })
+                .collect::<Map<_, _>>();
+            let required = fields
+                .iter()
+                .filter(|field| !field.optional)
+                .map(|field| field.name)
+                .collect::<Vec<_>>();
+            json!({
+                "description": definition.doc,
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+        Shape::Strings(values) => json!({
+            "description": definition.doc,
+            "enum": values,
+        }),
+        Shape::Variants(variants) => {
+            let variants = variants
+                .iter()
+                .map(|(name, typ)| {
+                    json!({
+                        "type": "object",
+                        "properties": {*name: json_type(typ)},
+                        "required": [name],
+                        "additionalProperties": false,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "description": definition.doc,
+                "oneOf": variants,
+            })
+        }
+    }
+}
+
+fn json_type(typ: &Type) -> Value {
+    match typ {
+        Type::String => json!({"type": "string"}),
+        Type::Integer => json!({"type": "integer"}),
+        Type::Boolean => json!({"type": "boolean"}),
+        Type::Any => json!({}),
+        Type::Ref(name) => json!({"$ref": format!("#/$defs/{name}")}),
+        Type::List(typ) => json!({"type": "array", "items": json_type(typ)}),
+        Type::Nullable(typ) => json!({"anyOf": [json_type(typ), {"type": "null"}]}),
+        Type::Named(typ) => json!({
+            "type": "array",
+            "items": json_tuple(&[json!({"type": "string"}), json_type(typ)]),
+        }),
+        Type::Map(typ) => json!({"type": "object", "additionalProperties": json_type(typ)}),
+        Type::Tuple(types) => json_tuple(&types.iter().map(json_type).collect::<Vec<_>>()),
+    }
+}
+
+fn json_tuple(items: &[Value]) -> Value {
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": items.len(),
+    })
+}
+
+fn typescript() -> String {
+    let mut out = format!(
+        "// The data ssd v{} passes to generators: SsdModel, or RawModel with --raw.\n\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    out.push_str(&ts_definitions());
+    out
+}
+
+fn ts_definitions() -> String {
+    let mut out = String::new();
+    out.push_str("/** Named elements in the order of the description file. */\n");
+    out.push_str("export type OrderedMap<T> = [string, T][];\n");
+    for definition in DEFINITIONS {
+        out.push_str(&format!("\n/** {} */\n", definition.doc));
+        match &definition.shape {
+            Shape::Object(fields) => {
+                out.push_str(&format!("export interface {} {{\n", definition.name));
+                for field in *fields {
+                    let optional = if field.optional { "?" } else { "" };
+                    out.push_str(&format!("    /** {} */\n", field.doc));
+                    out.push_str(&format!(
+                        "    {}{optional}: {};\n",
+                        field.name,
+                        ts_type(&field.typ)
+                    ));
+                }
+                out.push_str("}\n");
+            }
+            Shape::Strings(values) => {
+                out.push_str(&format!(
+                    "export type {} = {};\n",
+                    definition.name,
+                    ts_strings(values)
+                ));
+            }
+            Shape::Variants(variants) => {
+                out.push_str(&format!("export type {} =\n", definition.name));
+                for (name, typ) in *variants {
+                    out.push_str(&format!("    | {{ {name}: {} }}\n", ts_type(typ)));
+                }
+                // the last variant ends the declaration
+                out.pop();
+                out.push_str(";\n");
+            }
+        }
+    }
+    out
+}
+
+fn ts_strings(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| format!("\"{value}\""))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn ts_type(typ: &Type) -> String {
+    match typ {
+        Type::String => "string".to_owned(),
+        Type::Integer => "number".to_owned(),
+        Type::Boolean => "boolean".to_owned(),
+        Type::Any => "unknown".to_owned(),
+        Type::Ref(name) => (*name).to_owned(),
+        Type::List(typ @ Type::Nullable(_)) => format!("({})[]", ts_type(typ)),
+        Type::List(typ) => format!("{}[]", ts_type(typ)),
+        Type::Nullable(typ) => format!("{} | null", ts_type(typ)),
+        Type::Named(typ) => format!("OrderedMap<{}>", ts_type(typ)),
+        Type::Map(typ) => format!("Record<string, {}>", ts_type(typ)),
+        Type::Tuple(types) => {
+            let types = types.iter().map(ts_type).collect::<Vec<_>>();
+            format!("[{}]", types.join(", "))
+        }
+    }
+}
+
+/// The template engines whose variables `ssd language-server --engine` describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TemplateEngine {
+    Handlebars,
+    Tera,
+}
+
+impl TemplateEngine {
+    fn name(self) -> &'static str {
+        match self {
+            TemplateEngine::Handlebars => "handlebars",
+            TemplateEngine::Tera => "tera",
+        }
+    }
+
+    /// How the pairs of named elements are accessed in templates of the engine.
+    fn pairs_example(self) -> &'static str {
+        match self {
+            TemplateEngine::Handlebars => {
+                "{{#each module.data_types}}{{this.[0]}}: {{this.[1].docs}}{{/each}}"
+            }
+            TemplateEngine::Tera => {
+                "{% for pair in module.data_types %}{{ pair.0 }}: {{ pair.1.docs }}{% endfor %}"
+            }
+        }
+    }
+}
+
+const RAW_VARIABLE: Field = optional(
+    "raw",
+    Type::Any,
+    "The data as read from the files, instead of `module` with `--raw`.",
+);
+
+/// The variables templates get: the fields of the model, and `raw` with `--raw`.
+fn template_variables() -> impl Iterator<Item = &'static Field> {
+    let fields = match DEFINITIONS.iter().find(|d| d.name == "SsdModel") {
+        Some(Definition {
+            shape: Shape::Object(fields),
+            ..
+        }) => *fields,
+        _ => &[],
+    };
+    fields.iter().chain([&RAW_VARIABLE])
+}
+
+/// Returns the description of the variables templates of the engine get, as `.d.ts` with
+/// `typescript` or as json otherwise, e.g. for completion in editors.
+pub fn template_context(
+    engine: TemplateEngine,
+    typescript: bool,
+) -> Result<String, serde_json::Error> {
+    let description = format!(
+        "The variables of {} templates rendered by ssd v{}. Named elements (like \
+         `module.data_types`) are lists of [name, value] pairs.",
+        engine.name(),
+        env!("CARGO_PKG_VERSION")
+    );
+    if typescript {
+        let mut out = format!("// {description}\n// {}\n\n", engine.pairs_example());
+        for variable in template_variables() {
+            let typ = ts_type(&variable.typ);
+            let typ = if variable.optional && typ != "unknown" {
+                format!("{typ} | undefined")
+            } else {
+                typ
+            };
+            out.push_str(&format!("/** {} */\n", variable.doc));
+            out.push_str(&format!("declare const {}: {typ};\n", variable.name));
+        }
+        out.push('\n');
+        out.push_str(&ts_definitions());
+        return Ok(out);
+    }
+
+    let described = |field: &Field| {
+        json!({
+            "type": ts_type(&field.typ),
+            "description": field.doc,
+            "optional": field.optional,
+        })
+    };
+    let variables = template_variables()
+        .map(|variable| (variable.name.to_owned(), described(variable)))
+        .collect::<Map<_, _>>();
+    let types = DEFINITIONS
+        .iter()
+        .map(|definition| {
+            let mut described_type = json!({"description": definition.doc});
+            match &definition.shape {
+                Shape::Object(fields) => {
+                    described_type["fields"] = fields
+                        .iter()
+                        .map(|field| (field.name.to_owned(), described(field)))
+                        .collect::<Map<_, _>>()
+                        .into();
+                }
+                Shape::Strings(values) => described_type["values"] = json!(values),
+                Shape::Variants(variants) => {
+                    described_type["variants"] = variants
+                        .iter()
+                        .map(|(name, typ)| ((*name).to_owned(), ts_type(typ).into()))
+                        .collect::<Map<_, _>>()
+                        .into();
+                }
+            }
+            (definition.name.to_owned(), described_type)
+        })
+        .collect::<Map<_, _>>();
+    serde_json::to_string_pretty(&json!({
+        "engine": engine.name(),
+        "description": description,
+        "example": engine.pairs_example(),
+        "variables": variables,
+        "types": types,
+    }))
+}
+
+/// Checks that the value has the shape of the type, returns the path of the first mismatch.
+#[cfg(test)]
+fn mismatch(value: &Value, typ: &Type, path: &str) -> Option<String> {
+    match (typ, value) {
+        (Type::String, Value::String(_))
+        | (Type::Integer, Value::Number(_))
+        | (Type::Boolean, Value::Bool(_))
+        | (Type::Any, _)
+        | (Type::Nullable(_), Value::Null) => None,
+        (Type::Nullable(typ), value) => mismatch(value, typ, path),
+        (Type::List(typ), Value::Array(values)) => values
+            .iter()
+            .enumerate()
+            .find_map(|(i, value)| mismatch(value, typ, &format!("{path}[{i}]"))),
+        (Type::Named(typ), Value::Array(values)) => {
+            values
+                .iter()
+                .enumerate()
+                .find_map(|(i, value)| match value {
+                    Value::Array(pair) if pair.len() == 2 && pair[0].is_string() => {
+                        mismatch(&pair[1], typ, &format!("{path}[{i}][1]"))
+                    }
+                    _ => Some(format!("{path}[{i}]")),
+                })
+        }
+        (Type::Map(typ), Value::Object(map)) => map
+            .iter()
+            .find_map(|(key, value)| mismatch(value, typ, &format!("{path}.{key}"))),
+        (Type::Tuple(types), Value::Array(values)) if types.len() == values.len() => values
+            .iter()
+            .zip(types.iter())
+            .enumerate()
+            .find_map(|(i, (value, typ))| mismatch(value, typ, &format!("{path}[{i}]"))),
+        (Type::Ref(name), value) => {
+            let definition = DEFINITIONS.iter().find(|d| d.name == *name)?;
+            match (&definition.shape, value) {
+                (Shape::Object(fields), Value::Object(map)) => {
+                    let unknown = map
+                        .keys()
+                        .find(|key| !fields.iter().any(|field| field.name == key.as_str()));
+                    if let Some(key) = unknown {
+                        return Some(format!("{path}.{key}"));
+                    }
+                    fields.iter().find_map(|field| {
+                        let path = format!("{path}.{}", field.name);
+                        match map.get(field.name) {
+                            Some(value) => mismatch(value, &field.typ, &path),
+                            None if field.optional => None,
+                            None => Some(path),
+                        }
+                    })
+                }
+                (Shape::Strings(values), Value::String(value))
+                    if values.contains(&value.as_str()) =>
+                {
+                    None
+                }
+                (Shape::Variants(variants), Value::Object(map)) if map.len() == 1 => {
+                    let (key, value) = map.iter().next()?;
+                    let path = format!("{path}.{key}");
+                    match variants.iter().find(|(name, _)| name == key) {
+                        Some((_, typ)) => mismatch(value, typ, &path),
+                        None => Some(path),
+                    }
+                }
+                _ => Some(path.to_owned()),
+            }
+        }
+        _ => Some(path.to_owned()),
+    }
+}
+
+#[test]
+fn test_schema_matches_serialization() {
+    use crate::parser::{parse, parse_raw};
+    use ssd_data::{Meta, Namespace, SsdModel};
+
+    let source = r#"
+version "1.0.0";
+import shop::customers::{Customer};
+import shop::payments as p;
+// orders
+/// An order.
+#[table(name = "orders")]
+pub data Order {
+    id: u64,
+    items: list of 4 of Item,
+    pos: { x: f32, y: f32, },
+};
+flags Permissions { Read, Write = 0x2, };
+internal service Orders {
+    depends on shop::Customers;
+    fn get(id: u64) -> result of Order, string;
+    oneway fn ping();
+    event changed(order: Order);
+};
+"#;
+    let module = parse(source, Namespace::new("shop::orders")).unwrap();
+    let ast = serde_value::to_value(parse_raw(source).unwrap()).unwrap();
+    let model = SsdModel {
+        module,
+        defines: [("target".to_owned(), serde_value::Value::I64(1))].into(),
+        meta: Meta {
+            timestamp: Some("2024-01-01T00:00:00Z".to_owned()),
+            ..Meta::default()
+        },
+        ast: Some(ast),
+    };
+    let value = serde_json::to_value(&model).unwrap();
+    assert_eq!(mismatch(&value, &Type::Ref("SsdModel"), "$"), None);
+
+    let raw = json!({"raw": [1, {"a": null}], "defines": {}, "meta": Meta::default()});
+    assert_eq!(mismatch(&raw, &Type::Ref("RawModel"), "$"), None);
+    let wrong = json!({"module": {"namespace": "shop"}, "defines": {}, "meta": Meta::default()});
+    assert_eq!(
+        mismatch(&wrong, &Type::Ref("SsdModel"), "$").as_deref(),
+        Some("$.module.namespace")
+    );
+}
+
+#[test]
+fn test_schema_formats() {
+    let json: Value = serde_json::from_str(&schema(SchemaFormat::JsonSchema).unwrap()).unwrap();
+    // every reference points to a definition
+    let text = json.to_string();
+    for reference in text.split("\"#/$defs/").skip(1) {
+        let name = &reference[..reference.find('"').unwrap()];
+        assert!(json["$defs"].get(name).is_some(), "{name}");
+    }
+    assert_eq!(
+        json["$defs"]["SsdModel"]["required"],
+        json!(["module", "defines", "meta"])
+    );
+
+    let typescript = schema(SchemaFormat::Typescript).unwrap();
+    assert!(typescript.contains("export interface SsdModel {\n"));
+    assert!(typescript.contains("    ast?: AstElement[];\n"));
+    assert!(typescript.contains("    lists: (number | null)[];\n"));
+    assert!(typescript.contains("    data_types: OrderedMap<DataType>;\n"));
+    assert!(typescript.contains("export type Visibility = \"pub\" | \"internal\";\n"));
+    assert!(typescript.contains("    | { Comment: string }\n"));
+    assert!(typescript.contains("    | { DataType: [string, DataType] };\n"));
+}
+
+#[test]
+fn test_template_context() {
+    let context: Value =
+        serde_json::from_str(&template_context(TemplateEngine::Tera, false).unwrap()).unwrap();
+    assert_eq!(context["engine"], "tera");
+    let variables = context["variables"].as_object().unwrap();
+    assert_eq!(
+        variables.keys().collect::<Vec<_>>(),
+        ["module", "defines", "meta", "ast", "raw"]
+    );
+    assert_eq!(context["variables"]["ast"]["optional"], true);
+    assert_eq!(
+        context["types"]["SsdModule"]["fields"]["data_types"]["type"],
+        "OrderedMap<DataType>"
+    );
+    assert_eq!(
+        context["types"]["Radix"]["values"],
+        json!(["decimal", "hex", "binary"])
+    );
+    assert_eq!(
+        context["types"]["AstElement"]["variants"]["Enum"],
+        "[string, Enum]"
+    );
+
+    let typescript = template_context(TemplateEngine::Handlebars, true).unwrap();
+    assert!(typescript.contains("{{this.[0]}}"));
+    assert!(typescript.contains("declare const module: SsdModule;\n"));
+    assert!(typescript.contains("declare const ast: AstElement[] | undefined;\n"));
+    assert!(typescript.contains("export interface DataType {\n"));
+}
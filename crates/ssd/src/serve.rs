@@ -0,0 +1,419 @@
+//! `ssd serve`: a long-lived process answering JSON-RPC 2.0 requests over HTTP, so IDE plugins
+//! and the playground backend don't have to start the command for every request.
+//!
+//! Every request is a `POST` with a JSON-RPC body, the path is ignored:
+//! ```json
+//! {"jsonrpc": "2.0", "id": 1, "method": "pretty", "params": {"content": "data Rect{x:i32,};"}}
+//! ```
+//! The methods are `parse`, `check`, `pretty` and `generate`, see [`Request`].
+//!
+//! Bodies have to be sent as `application/json`, which browsers only allow for other origins
+//! after a preflight request, and only the origin given with `--allow-origin` gets CORS headers.
+//! So other web pages can't use the server to read the files it can access.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use ssd_data::{Defines, Namespace};
+
+use crate::api::{generate, GenerateRequest, Generator};
+use crate::parser::{parse_limited, parse_raw_limited, ParseLimits};
+use crate::pretty::pretty;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Errors of ssd itself, e.g. a description which can't be parsed or a failing generator.
+const SSD_ERROR: i64 = -32000;
+/// Descriptions are limited to 1 MiB, which can take up to 6 MiB in a JSON string.
+const MAX_BODY_SIZE: usize = 8 << 20;
+/// How long a client can take to send its request, requests are answered one after another.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum Request {
+    /// Parses the description and returns the module.
+    Parse {
+        content: String,
+        #[serde(default)]
+        namespace: String,
+    },
+    /// Checks whether the description can be parsed, returns `{"valid": bool, "error": ...}`.
+    Check { content: String },
+    /// Formats the description like `ssd pretty`.
+    Pretty { content: String },
+    /// Runs a generator for a description file or a raw model and returns
+    /// `{"output": ..., "files": {path: content}}`.
+    Generate(GenerateParams),
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateParams {
+    /// `rhai`, `handlebars`, `tera` or `wasm`, depending on the enabled features.
+    generator: String,
+    /// The script, template or plugin of the generator, relative to the directory the server
+    /// was started in.
+    source: PathBuf,
+    /// The description file, relative to the directory the server was started in.
+    #[serde(default)]
+    file: Option<PathBuf>,
+    /// Data used as raw model instead of `file`, like with `--raw`.
+    #[serde(default)]
+    raw: Option<serde_value::Value>,
+    #[serde(default)]
+    typemaps: Vec<PathBuf>,
+    #[serde(default)]
+    no_map: bool,
+    /// Merged into the defines the server was started with.
+    #[serde(default)]
+    defines: Defines,
+}
+
+/// Resolves a path of a request, which has to stay inside of `base` (after following links),
+/// since anyone who can reach the server can send requests.
+fn resolve(base: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    let resolved = base
+        .join(path)
+        .canonicalize()
+        .with_context(|| format!("Can't open {}.", path.display()))?;
+    if !resolved.starts_with(base) {
+        anyhow::bail!(
+            "{} is outside of the directory the server was started in.",
+            path.display()
+        );
+    }
+    Ok(resolved)
+}
+
+fn run_generator(base: &Path, defines: &Defines, params: GenerateParams) -> anyhow::Result<Value> {
+    let base = base.canonicalize()?;
+    let file = match (params.file, &params.raw) {
+        (Some(file), None) => resolve(&base, &file)?,
+        (None, Some(_)) => PathBuf::new(),
+        _ => anyhow::bail!("Either file or raw has to be given."),
+    };
+    let mut request = GenerateRequest::new(
+        file,
+        Generator::from_name(&params.generator, resolve(&base, &params.source)?)?,
+    );
+    request.defines = defines.clone();
+    request.defines.extend(params.defines);
+    request.typemaps = params
+        .typemaps
+        .iter()
+        .map(|typemap| resolve(&base, typemap))
+        .collect::<anyhow::Result<_>>()?;
+    request.base = base;
+    request.no_map = params.no_map;
+    request.raw = params.raw;
+    let output = generate(request)?;
+    Ok(json!({ "output": output.output, "files": output.files }))
+}
+
+fn handle(base: &Path, defines: &Defines, request: Request) -> anyhow::Result<Value> {
+    // the content comes from anyone who can reach the server
+    let limits = ParseLimits::default();
+    Ok(match request {
+        Request::Parse { content, namespace } => serde_json::to_value(parse_limited(
+            &content,
+            Namespace::new(&namespace),
+            &limits,
+        )?)?,
+        Request::Check { content } => match parse_raw_limited(&content, &limits) {
+            Ok(_) => json!({ "valid": true, "error": null }),
+            Err(e) => json!({ "valid": false, "error": e.to_string() }),
+        },
+        Request::Pretty { content } => {
+            Value::String(pretty(&parse_raw_limited(&content, &limits)?))
+        }
+        Request::Generate(params) => run_generator(base, defines, params)?,
+    })
+}
+
+fn error(id: Value, code: i64, message: impl ToString) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.to_string() },
+    })
+}
+
+/// Answers a JSON-RPC request (the body of an HTTP request).
+fn respond(base: &Path, defines: &Defines, body: &str) -> Value {
+    let message: Value = match serde_json::from_str(body) {
+        Ok(message) => message,
+        Err(e) => return error(Value::Null, PARSE_ERROR, e),
+    };
+    let id = message.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return error(id, INVALID_REQUEST, "The request has no method.");
+    };
+    if !matches!(method, "parse" | "check" | "pretty" | "generate") {
+        return error(
+            id,
+            METHOD_NOT_FOUND,
+            format!("Unknown method \"{method}\"."),
+        );
+    }
+    let request = match Request::deserialize(&message) {
+        Ok(request) => request,
+        Err(e) => return error(id, INVALID_PARAMS, e),
+    };
+    match handle(base, defines, request) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error(id, SSD_ERROR, format!("{e:#}")),
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    allow_origin: Option<&str>,
+    status: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let cors = allow_origin
+        .map(|origin| {
+            format!(
+                "Access-Control-Allow-Origin: {origin}\r\n\
+                 Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+                 Access-Control-Allow-Headers: Content-Type\r\n"
+            )
+        })
+        .unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         {cors}\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn handle_connection(
+    base: &Path,
+    defines: &Defines,
+    allow_origin: Option<&str>,
+    mut stream: TcpStream,
+) -> anyhow::Result<()> {
+    // a stalled client would block all others
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let method = request_line.split_whitespace().next().unwrap_or_default();
+
+    let mut content_length = 0;
+    let mut json = false;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            } else if name.eq_ignore_ascii_case("content-type") {
+                let mime = value.split(';').next().unwrap_or_default();
+                json = mime.trim().eq_ignore_ascii_case("application/json");
+            }
+        }
+    }
+
+    let mut write =
+        |status: &str, body: &str| write_response(&mut stream, allow_origin, status, body);
+    match method {
+        // preflight requests of browsers
+        "OPTIONS" => write("204 No Content", "")?,
+        "POST" if content_length > MAX_BODY_SIZE => write(
+            "413 Payload Too Large",
+            &error(
+                Value::Null,
+                INVALID_REQUEST,
+                format!("The request has more than {MAX_BODY_SIZE} bytes."),
+            )
+            .to_string(),
+        )?,
+        // browsers send other types to other origins without asking first
+        "POST" if !json => write(
+            "415 Unsupported Media Type",
+            &error(
+                Value::Null,
+                INVALID_REQUEST,
+                "The request has to be sent as application/json.",
+            )
+            .to_string(),
+        )?,
+        "POST" => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            let response = respond(base, defines, &String::from_utf8_lossy(&body));
+            write("200 OK", &response.to_string())?;
+        }
+        _ => write(
+            "405 Method Not Allowed",
+            &error(Value::Null, INVALID_REQUEST, "Only POST is supported.").to_string(),
+        )?,
+    }
+    Ok(())
+}
+
+/// Answers the requests of the listener one after another. Only `allow_origin` gets CORS
+/// headers, so browsers don't let other web pages read the responses.
+pub fn run(
+    base: &Path,
+    defines: &Defines,
+    allow_origin: Option<&str>,
+    listener: TcpListener,
+) -> anyhow::Result<()> {
+    for stream in listener.incoming() {
+        // a broken connection shouldn't stop the server
+        if let Err(e) = handle_connection(base, defines, allow_origin, stream?) {
+            eprintln!("{e:#}");
+        }
+    }
+    Ok(())
+}
+
+pub fn serve(
+    base: &Path,
+    defines: &Defines,
+    listen: &str,
+    allow_origin: Option<&str>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    eprintln!("Listening on {}", listener.local_addr()?);
+    run(base, defines, allow_origin, listener)
+}
+
+#[test]
+fn test_serve() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || run(Path::new("."), &Defines::new(), None, listener));
+
+    let send = |content_type: &str, body: &str| -> (String, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.to_owned(), body.to_owned())
+    };
+    let request = |body: &str| -> Value {
+        let (head, body) = send("application/json; charset=utf-8", body);
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(!head.contains("Access-Control-Allow-Origin"));
+        serde_json::from_str(&body).unwrap()
+    };
+
+    let response = request(
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "pretty", "params": {"content": "data Rect{x:i32,};"}}"#,
+    );
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"], "data Rect {\n\tx: i32,\n};\n");
+
+    let response = request(
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "check", "params": {"content": "data Rect {"}}"#,
+    );
+    assert_eq!(response["result"]["valid"], false);
+
+    let content = "data A {}; data A {};";
+    let response = request(&format!(
+        r#"{{"jsonrpc": "2.0", "id": 3, "method": "parse", "params": {{"content": "{content}"}}}}"#
+    ));
+    assert_eq!(
+        response["error"]["message"],
+        "Duplicate datatype A. (line 1, column 12)"
+    );
+
+    let response = request(r#"{"jsonrpc": "2.0", "id": 4, "method": "build"}"#);
+    assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    let response = request("{");
+    assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+    // browsers send text/plain to other origins without a preflight request
+    let (head, _) = send(
+        "text/plain",
+        r#"{"jsonrpc": "2.0", "id": 5, "method": "check", "params": {"content": ""}}"#,
+    );
+    assert!(head.starts_with("HTTP/1.1 415 Unsupported Media Type"));
+}
+
+#[test]
+fn test_serve_cors() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        run(
+            Path::new("."),
+            &Defines::new(),
+            Some("https://play.example.com"),
+            listener,
+        )
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "OPTIONS / HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 204 No Content"));
+    assert!(response.contains("Access-Control-Allow-Origin: https://play.example.com\r\n"));
+}
+
+#[cfg(feature = "handlebars")]
+#[test]
+fn test_serve_paths() {
+    let dir = std::env::temp_dir().join(format!("ssd-serve-{}", std::process::id()));
+    let base = dir.join("base");
+    std::fs::create_dir_all(&base).unwrap();
+    std::fs::write(base.join("shapes.svc"), "data Rect {};").unwrap();
+    std::fs::write(
+        base.join("names.hbs"),
+        "{{#each module.data_types}}{{this.[0]}}{{/each}}",
+    )
+    .unwrap();
+    std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+    let generate = |source: &Path, file: &str| {
+        let params = GenerateParams {
+            generator: "handlebars".to_owned(),
+            source: source.to_path_buf(),
+            file: Some(PathBuf::from(file)),
+            raw: None,
+            typemaps: Vec::new(),
+            no_map: false,
+            defines: Defines::new(),
+        };
+        run_generator(&base, &Defines::new(), params).map_err(|e| e.to_string())
+    };
+    assert_eq!(
+        generate(Path::new("names.hbs"), "shapes.svc").unwrap()["output"],
+        "Rect"
+    );
+    for (source, file) in [
+        (dir.join("secret.txt"), "shapes.svc"),
+        (PathBuf::from("../secret.txt"), "shapes.svc"),
+        (PathBuf::from("names.hbs"), "../secret.txt"),
+    ] {
+        let error = generate(&source, file).unwrap_err();
+        assert!(error.contains("is outside of the directory"), "{error}");
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
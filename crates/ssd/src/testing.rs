@@ -0,0 +1,112 @@
+//! Property testing of the parser and of generators, enabled with the `testing` feature.
+//!
+//! The model types implement proptest's `Arbitrary` (see `ssd_data::testing`), [`svc_source`]
+//! generates valid `.svc` sources from them:
+//! ```
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(source in ssd::testing::svc_source())| {
+//!     prop_assert!(ssd::parse(&source, ssd_data::Namespace::new("test")).is_ok());
+//! });
+//! ```
+
+use proptest::prelude::*;
+
+use crate::ast::{AstElement, ServiceAstElement, SsdModule};
+use crate::pretty::pretty;
+
+fn service_elements(service: &ssd_data::Service) -> Vec<ServiceAstElement> {
+    // comments and docs of service members are separate elements in the source
+    let with_comments = |comments: &[String], docs: &[String], element: ServiceAstElement| {
+        comments
+            .iter()
+            .cloned()
+            .map(ServiceAstElement::Comment)
+            .chain(docs.iter().cloned().map(ServiceAstElement::DocComment))
+            .chain(std::iter::once(element))
+            .collect::<Vec<_>>()
+    };
+    let dependencies = service.dependencies.iter().flat_map(|dependency| {
+        with_comments(
+            &dependency.comments,
+            &dependency.docs,
+            ServiceAstElement::Dependency(dependency.clone()),
+        )
+    });
+    let functions = service.functions.iter().flat_map(|(name, function)| {
+        with_comments(
+            &function.comments,
+            &function.docs,
+            ServiceAstElement::Function((name.clone(), function.clone())),
+        )
+    });
+    let events = service.events.iter().flat_map(|(name, event)| {
+        with_comments(
+            &event.comments,
+            &event.docs,
+            ServiceAstElement::Event((name.clone(), event.clone())),
+        )
+    });
+    dependencies.chain(functions).chain(events).collect()
+}
+
+/// Writes the module as `.svc` source, formatted like `ssd pretty`.
+///
+/// Parsing the source with the namespace of the module gives the module back, as long as it is
+/// representable (which is the case for all modules generated by `any::<SsdModule>()`).
+#[must_use]
+pub fn to_source(module: &SsdModule) -> String {
+    // docs of data types, enums and services are separate elements in the source as well
+    let with_docs = |docs: &[String], element: AstElement| {
+        docs.iter()
+            .cloned()
+            .map(AstElement::DocComment)
+            .chain(std::iter::once(element))
+            .collect::<Vec<_>>()
+    };
+    let version = module.version.clone().map(AstElement::Version);
+    let imports = module.imports.iter().cloned().map(AstElement::Import);
+    let data_types = module.data_types.iter().flat_map(|(name, dt)| {
+        with_docs(&dt.docs, AstElement::DataType((name.clone(), dt.clone())))
+    });
+    let enums = module
+        .enums
+        .iter()
+        .flat_map(|(name, en)| with_docs(&en.docs, AstElement::Enum((name.clone(), en.clone()))));
+    let services = module.services.iter().flat_map(|(name, service)| {
+        with_docs(
+            &service.docs,
+            AstElement::Service((
+                name.clone(),
+                service_elements(service),
+                service.attributes.clone(),
+                service.visibility,
+            )),
+        )
+    });
+    let raw = version
+        .into_iter()
+        .chain(imports)
+        .chain(data_types)
+        .chain(enums)
+        .chain(services)
+        .collect::<Vec<_>>();
+    pretty(&raw)
+}
+
+/// Valid `.svc` sources, which shrink together with the module they are written from.
+pub fn svc_source() -> impl Strategy<Value = String> {
+    any::<SsdModule>().prop_map(|module| to_source(&module))
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn test_round_trip(module in any::<SsdModule>()) {
+        let source = to_source(&module);
+        let parsed = crate::parser::parse(&source, module.namespace.clone());
+        prop_assert_eq!(parsed.ok(), Some(module));
+        let raw = crate::parser::parse_raw(&source).unwrap();
+        prop_assert_eq!(pretty(&raw), source);
+    }
+}
@@ -0,0 +1,301 @@
+//! Durations of the phases of a run (`--timings`), e.g. to find slow generator scripts or to see
+//! what the cache saves.
+//!
+//! The phases are measured with [`span`], which can be nested: the time of a phase doesn't
+//! include the phases measured within it, so `generate` is the time the script or template took
+//! without parsing the input and writing the output. Nothing is recorded unless [`enable`] was
+//! called on the same thread.
+
+use std::cell::RefCell;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Parsing description files and reading raw data.
+    Parse,
+    /// Loading the modules imported by a file (including parsing them).
+    Imports,
+    /// Looking up and storing modules in the cache.
+    Cache,
+    /// Applying the typemap.
+    Typemap,
+    /// Running the script, template, plugin or program of the generator.
+    Generate,
+    /// Post-processing and writing the output.
+    Write,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Parse => "parse",
+            Phase::Imports => "imports",
+            Phase::Cache => "cache",
+            Phase::Typemap => "typemap",
+            Phase::Generate => "generate",
+            Phase::Write => "write",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TimingsFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+struct Timing {
+    phase: Phase,
+    /// The processed file, `None` for printed output.
+    file: Option<PathBuf>,
+    duration: Duration,
+}
+
+#[derive(Debug)]
+struct Recorder {
+    started: Instant,
+    timings: Vec<Timing>,
+    /// The time taken by nested phases, for every open span.
+    nested: Vec<Duration>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Starts recording the timings of the phases which run on this thread.
+pub fn enable() {
+    RECORDER.set(Some(Recorder {
+        started: Instant::now(),
+        timings: Vec::new(),
+        nested: Vec::new(),
+    }));
+}
+
+/// Measures a phase until the returned guard is dropped.
+#[must_use = "the phase ends when the span is dropped"]
+pub fn span(phase: Phase, file: Option<&Path>) -> Span {
+    let enabled = RECORDER.with_borrow_mut(|recorder| {
+        let Some(recorder) = recorder else {
+            return false;
+        };
+        recorder.nested.push(Duration::ZERO);
+        true
+    });
+    Span(enabled.then(|| (phase, file.map(Path::to_path_buf), Instant::now())))
+}
+
+/// Runs `f` as the phase, see [`span`].
+pub fn measure<T>(phase: Phase, file: Option<&Path>, f: impl FnOnce() -> T) -> T {
+    let _span = span(phase, file);
+    f()
+}
+
+pub struct Span(Option<(Phase, Option<PathBuf>, Instant)>);
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some((phase, file, started)) = self.0.take() else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        RECORDER.with_borrow_mut(|recorder| {
+            let Some(recorder) = recorder else {
+                return;
+            };
+            let nested = recorder.nested.pop().unwrap_or_default();
+            if let Some(outer) = recorder.nested.last_mut() {
+                *outer += elapsed;
+            }
+            recorder.timings.push(Timing {
+                phase,
+                file,
+                duration: elapsed.saturating_sub(nested),
+            });
+        });
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseTotal {
+    phase: Phase,
+    count: usize,
+    ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct FileTotal {
+    file: String,
+    ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    total_ms: f64,
+    phases: Vec<PhaseTotal>,
+    /// Sorted by time, the slowest first.
+    files: Vec<FileTotal>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl Report {
+    fn new(total: Duration, timings: &[Timing]) -> Self {
+        let mut phases: Vec<PhaseTotal> = Vec::new();
+        let mut files: Vec<FileTotal> = Vec::new();
+        let mut sorted = timings.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|timing| timing.phase);
+        for timing in sorted {
+            let ms = millis(timing.duration);
+            match phases.last_mut() {
+                Some(total) if total.phase == timing.phase => {
+                    total.count += 1;
+                    total.ms += ms;
+                }
+                _ => phases.push(PhaseTotal {
+                    phase: timing.phase,
+                    count: 1,
+                    ms,
+                }),
+            }
+            let file = timing
+                .file
+                .as_ref()
+                .map_or_else(|| "<stdout>".to_owned(), |file| file.display().to_string());
+            match files.iter_mut().find(|total| total.file == file) {
+                Some(total) => total.ms += ms,
+                None => files.push(FileTotal { file, ms }),
+            }
+        }
+        files.sort_by(|a, b| b.ms.total_cmp(&a.ms));
+        Self {
+            total_ms: millis(total),
+            phases,
+            files,
+        }
+    }
+
+    fn format(&self) -> String {
+        let width = self
+            .files
+            .iter()
+            .map(|total| total.file.len())
+            .chain([8])
+            .max()
+            .unwrap_or_default();
+        let mut result = String::from("Timings:\n");
+        for total in &self.phases {
+            let phase = total.phase.to_string();
+            result += &format!(
+                "  {phase:<width$}  {:>10.2}ms  ({}x)\n",
+                total.ms, total.count
+            );
+        }
+        result += &format!("  {:<width$}  {:>10.2}ms\n", "total", self.total_ms);
+        if !self.files.is_empty() {
+            result += "Files:\n";
+            for total in &self.files {
+                result += &format!("  {:<width$}  {:>10.2}ms\n", total.file, total.ms);
+            }
+        }
+        result
+    }
+}
+
+/// Returns the report of the timings recorded since [`enable`], if it was called.
+pub fn report(format: TimingsFormat) -> anyhow::Result<Option<String>> {
+    let report = RECORDER.with_borrow(|recorder| {
+        recorder
+            .as_ref()
+            .map(|recorder| Report::new(recorder.started.elapsed(), &recorder.timings))
+    });
+    let Some(report) = report else {
+        return Ok(None);
+    };
+    Ok(Some(match format {
+        TimingsFormat::Text => report.format(),
+        TimingsFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+    }))
+}
+
+#[test]
+fn test_report() {
+    let file = |name: &str| Some(PathBuf::from(name));
+    let timings = [
+        Timing {
+            phase: Phase::Generate,
+            file: file("api.svc"),
+            duration: Duration::from_millis(30),
+        },
+        Timing {
+            phase: Phase::Parse,
+            file: file("api.svc"),
+            duration: Duration::from_millis(2),
+        },
+        Timing {
+            phase: Phase::Write,
+            file: file("out/api.rs"),
+            duration: Duration::from_millis(1),
+        },
+        Timing {
+            phase: Phase::Write,
+            file: None,
+            duration: Duration::from_millis(1),
+        },
+    ];
+    let report = Report::new(Duration::from_millis(40), &timings);
+    let phases = report
+        .phases
+        .iter()
+        .map(|total| (total.phase, total.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        [(Phase::Parse, 1), (Phase::Generate, 1), (Phase::Write, 2)]
+    );
+    assert_eq!(report.files[0].file, "api.svc");
+    assert!((report.files[0].ms - 32.0).abs() < 1e-9);
+    assert_eq!(
+        report.format(),
+        "Timings:
+  parse             2.00ms  (1x)
+  generate         30.00ms  (1x)
+  write             2.00ms  (2x)
+  total            40.00ms
+Files:
+  api.svc          32.00ms
+  out/api.rs        1.00ms
+  <stdout>          1.00ms
+"
+    );
+}
+
+#[test]
+fn test_nested_spans() {
+    enable();
+    {
+        let _generate = span(Phase::Generate, Some(Path::new("api.svc")));
+        measure(Phase::Parse, Some(Path::new("api.svc")), || {
+            std::thread::sleep(Duration::from_millis(20));
+        });
+    }
+    RECORDER.with_borrow(|recorder| {
+        let timings = &recorder.as_ref().unwrap().timings;
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].phase, Phase::Parse);
+        assert!(timings[0].duration >= Duration::from_millis(20));
+        assert!(timings[1].duration < Duration::from_millis(20));
+    });
+    assert!(report(TimingsFormat::Json).unwrap().is_some());
+}
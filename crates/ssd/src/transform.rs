@@ -0,0 +1,359 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ssd_data::visit::{
+    walk_data_type_mut, walk_enum_mut, walk_event_mut, walk_function_mut, walk_module_mut,
+    walk_service_mut,
+};
+use ssd_data::{
+    find_attribute, Attribute, DataType, Dependency, Enum, Event, Function, Import, Namespace,
+    OrderedMap, Service, SsdModule, TypeName, Visibility, VisitorMut,
+};
+
+use crate::exit::{Classify, ErrorCode};
+use crate::helper::update_types_from_file;
+use crate::idents::{
+    to_camel_case, to_kebab_case, to_pascal_case, to_screaming_snake_case, to_snake_case,
+};
+use crate::options::BaseInputData;
+use crate::timings::{self, Phase};
+
+/// A transformation of the model, which runs between parsing and generating.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transform {
+    /// Applies the typemap (`typemap`).
+    Typemap,
+    /// Refers to imported types by their name only (`flatten-imports`).
+    FlattenImports,
+    /// Removes the data types, enums and services marked `internal` (`public-only`).
+    PublicOnly,
+    /// Removes all elements with the given attribute (`exclude=<attribute>`).
+    Exclude(String),
+    /// Converts the names of properties and arguments to a case (`rename-case=<case>`).
+    RenameCase(Case),
+    /// Replaces the prefix of the module namespace, imports, dependencies and types
+    /// (`namespace-map=<old>=<new>`, e.g. `namespace-map=api::v1=company::api`).
+    NamespaceMap(Namespace, Namespace),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Snake,
+    Camel,
+    Pascal,
+    Kebab,
+    ScreamingSnake,
+}
+
+impl Case {
+    fn convert(self, s: &str) -> String {
+        match self {
+            Case::Snake => to_snake_case(s),
+            Case::Camel => to_camel_case(s),
+            Case::Pascal => to_pascal_case(s),
+            Case::Kebab => to_kebab_case(s),
+            Case::ScreamingSnake => to_screaming_snake_case(s),
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = s
+            .split_once('=')
+            .map_or((s, None), |(name, arg)| (name, Some(arg)));
+        let transform = match (name, arg) {
+            ("typemap", None) => Transform::Typemap,
+            ("flatten-imports", None) => Transform::FlattenImports,
+            ("public-only", None) => Transform::PublicOnly,
+            ("exclude", Some(attribute)) if !attribute.is_empty() => {
+                Transform::Exclude(attribute.to_owned())
+            }
+            ("rename-case", Some(case)) => Transform::RenameCase(match case {
+                "snake" => Case::Snake,
+                "camel" => Case::Camel,
+                "pascal" => Case::Pascal,
+                "kebab" => Case::Kebab,
+                "screaming-snake" => Case::ScreamingSnake,
+                _ => anyhow::bail!(
+                    "Unknown case \"{case}\". Use snake, camel, pascal, kebab or screaming-snake."
+                ),
+            }),
+            ("namespace-map", Some(map)) => match map.split_once('=') {
+                Some((old, new)) if !old.is_empty() && !new.is_empty() => {
+                    Transform::NamespaceMap(Namespace::new(old), Namespace::new(new))
+                }
+                _ => anyhow::bail!(
+                    "Invalid namespace map \"{map}\". Use namespace-map=<old>=<new>, e.g. namespace-map=api::v1=company::api."
+                ),
+            },
+            _ => anyhow::bail!(
+                "Unknown transform \"{s}\". Use typemap, flatten-imports, public-only, exclude=<attribute>, rename-case=<case> or namespace-map=<old>=<new>."
+            ),
+        };
+        Ok(transform)
+    }
+}
+
+/// Removes all elements for which the predicate returns `false` for their attributes.
+pub struct Filter<F>(pub F);
+
+impl<F: Fn(&[Attribute]) -> bool> VisitorMut for Filter<F> {
+    fn visit_module_mut(&mut self, module: &mut SsdModule) {
+        let keep = &self.0;
+        module.imports.retain(|i| keep(&i.attributes));
+        module.data_types.retain(|_, dt| keep(&dt.attributes));
+        module.enums.retain(|_, e| keep(&e.attributes));
+        module.services.retain(|_, s| keep(&s.attributes));
+        walk_module_mut(self, module);
+    }
+
+    fn visit_data_type_mut(&mut self, name: &str, data_type: &mut DataType) {
+        let keep = &self.0;
+        data_type.properties.retain(|_, p| keep(&p.attributes));
+        walk_data_type_mut(self, name, data_type);
+    }
+
+    fn visit_enum_mut(&mut self, name: &str, en: &mut Enum) {
+        let keep = &self.0;
+        en.values.retain(|_, v| keep(&v.attributes));
+        walk_enum_mut(self, name, en);
+    }
+
+    fn visit_service_mut(&mut self, name: &str, service: &mut Service) {
+        let keep = &self.0;
+        service.dependencies.retain(|d| keep(&d.attributes));
+        service.functions.retain(|_, f| keep(&f.attributes));
+        service.events.retain(|_, e| keep(&e.attributes));
+        walk_service_mut(self, name, service);
+    }
+
+    fn visit_function_mut(&mut self, name: &str, function: &mut Function) {
+        let keep = &self.0;
+        function.arguments.retain(|_, a| keep(&a.attributes));
+        walk_function_mut(self, name, function);
+    }
+
+    fn visit_event_mut(&mut self, name: &str, event: &mut Event) {
+        let keep = &self.0;
+        event.arguments.retain(|_, a| keep(&a.attributes));
+        walk_event_mut(self, name, event);
+    }
+}
+
+struct FlattenImports(Vec<Import>);
+
+impl VisitorMut for FlattenImports {
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        // written with the full path or with a name brought into scope by the import
+        let imported = self.0.iter().find_map(|import| {
+            (import.path == type_name.typ)
+                .then(|| import.path.clone())
+                .or_else(|| import.resolve(&type_name.typ))
+        });
+        if let Some(name) = imported.and_then(|path| path.components.last().cloned()) {
+            type_name.typ = Namespace::new(&name);
+        }
+    }
+}
+
+struct RenameCase(Case);
+
+impl RenameCase {
+    fn convert_names<T>(&self, map: &mut OrderedMap<T>) {
+        *map = std::mem::take(map)
+            .into_iter()
+            .map(|(name, element)| (self.0.convert(&name), element))
+            .collect();
+    }
+}
+
+impl VisitorMut for RenameCase {
+    fn visit_data_type_mut(&mut self, name: &str, data_type: &mut DataType) {
+        self.convert_names(&mut data_type.properties);
+        walk_data_type_mut(self, name, data_type);
+    }
+
+    fn visit_function_mut(&mut self, name: &str, function: &mut Function) {
+        self.convert_names(&mut function.arguments);
+        walk_function_mut(self, name, function);
+    }
+
+    fn visit_event_mut(&mut self, name: &str, event: &mut Event) {
+        self.convert_names(&mut event.arguments);
+        walk_event_mut(self, name, event);
+    }
+}
+
+struct NamespaceMap<'a>(&'a Namespace, &'a Namespace);
+
+impl NamespaceMap<'_> {
+    fn map(&self, namespace: &mut Namespace) {
+        if namespace.components.starts_with(&self.0.components) {
+            namespace
+                .components
+                .splice(..self.0.components.len(), self.1.components.clone());
+        }
+    }
+}
+
+impl VisitorMut for NamespaceMap<'_> {
+    fn visit_module_mut(&mut self, module: &mut SsdModule) {
+        self.map(&mut module.namespace);
+        walk_module_mut(self, module);
+    }
+
+    fn visit_import_mut(&mut self, import: &mut Import) {
+        self.map(&mut import.path);
+    }
+
+    fn visit_dependency_mut(&mut self, dependency: &mut Dependency) {
+        self.map(&mut dependency.name);
+    }
+
+    fn visit_type_name_mut(&mut self, type_name: &mut TypeName) {
+        self.map(&mut type_name.typ);
+    }
+}
+
+/// Runs the transforms given with `--transform` in order.
+///
+/// The typemap runs where `typemap` appears in the list, or after all other transforms.
+pub fn transform_module(
+    mut module: SsdModule,
+    input: &BaseInputData,
+    script: Option<&PathBuf>,
+) -> anyhow::Result<SsdModule> {
+    let mut transforms = input.transforms.clone();
+    if !transforms.contains(&Transform::Typemap) {
+        transforms.push(Transform::Typemap);
+    }
+
+    for transform in transforms {
+        match transform {
+            Transform::Typemap => {
+                let _timing = timings::span(Phase::Typemap, Some(&input.file));
+                module = update_types_from_file(
+                    module,
+                    input.no_map,
+                    input.typemap.clone(),
+                    script,
+                    input.typemap_report,
+                )
+                .classify(ErrorCode::InvalidData)?;
+            }
+            Transform::FlattenImports => {
+                FlattenImports(module.imports.clone()).visit_module_mut(&mut module);
+            }
+            Transform::PublicOnly => {
+                let public =
+                    |visibility: Option<Visibility>| visibility != Some(Visibility::Internal);
+                module.data_types.retain(|_, dt| public(dt.visibility));
+                module.enums.retain(|_, e| public(e.visibility));
+                module.services.retain(|_, s| public(s.visibility));
+            }
+            Transform::Exclude(attribute) => {
+                Filter(|attributes: &[Attribute]| find_attribute(attributes, &attribute).is_none())
+                    .visit_module_mut(&mut module);
+            }
+            Transform::RenameCase(case) => RenameCase(case).visit_module_mut(&mut module),
+            Transform::NamespaceMap(old, new) => {
+                NamespaceMap(&old, &new).visit_module_mut(&mut module);
+            }
+        }
+    }
+    Ok(module)
+}
+
+#[test]
+fn test_transforms() {
+    let module = crate::parser::parse(
+        r"
+        import std::path::Path;
+        import std::io as sio;
+        data Config {
+            file_path: std::path::Path,
+            error_kind: sio::ErrorKind,
+            #[internal]
+            secret_key: string,
+        };
+        #[internal]
+        data Internal { a: i32, };
+        internal enum Helper { A, };
+        pub enum Mode { A, };
+        ",
+        Namespace::new("test"),
+    )
+    .unwrap();
+
+    let input = BaseInputData {
+        no_map: true,
+        no_cache: false,
+        namespace: None,
+        typemap: Vec::new(),
+        typemap_report: false,
+        transforms: vec![
+            "exclude=internal".parse().unwrap(),
+            "flatten-imports".parse().unwrap(),
+            "public-only".parse().unwrap(),
+            "rename-case=camel".parse().unwrap(),
+            "namespace-map=std=core".parse().unwrap(),
+            "namespace-map=test=my::api".parse().unwrap(),
+        ],
+        raw: false,
+        raw_format: crate::helper::RawFormat::Auto,
+        raw_files: Vec::new(),
+        raw_merge: crate::helper::RawMerge::Key,
+        raw_data: None,
+        timestamp: false,
+        reproducible: false,
+        with_ast: false,
+        file: PathBuf::new(),
+    };
+    let module = transform_module(module, &input, None).unwrap();
+    assert_eq!(module.data_types.len(), 1);
+    let properties = &module.data_types[0].properties;
+    assert_eq!(properties.len(), 2);
+    assert_eq!(properties.get_index_of("filePath"), Some(0));
+    assert_eq!(properties[0].typ.to_string(), "Path");
+    assert_eq!(properties[1].typ.to_string(), "ErrorKind");
+    assert_eq!(module.enums.len(), 1);
+    assert!(module.enums.contains_key("Mode"));
+    assert_eq!(module.namespace, Namespace::new("my::api"));
+    assert_eq!(module.imports[0].path, Namespace::new("core::path::Path"));
+    assert!("rename-case=title".parse::<Transform>().is_err());
+    assert!("namespace-map=std".parse::<Transform>().is_err());
+}
+
+#[test]
+fn test_typemap_position() {
+    use clap::Parser as _;
+
+    let dir = std::env::temp_dir().join(format!("ssd-transform-order-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let typemap = dir.join("map.tym");
+    std::fs::write(&typemap, "\"core::path::Path\" = \"PathBuf\"\n").unwrap();
+    let module =
+        crate::parser::parse("data A { path: std::path::Path, };", Namespace::new("test")).unwrap();
+
+    let transform = |transforms: &[&str]| {
+        let input = BaseInputData::parse_from(
+            ["input", "--typemap", typemap.to_str().unwrap()]
+                .into_iter()
+                .chain(transforms.iter().flat_map(|t| ["--transform", t]))
+                .chain(["test.svc"]),
+        );
+        let module = transform_module(module.clone(), &input, None).unwrap();
+        module.data_types["A"].properties["path"].typ.to_string()
+    };
+    // without `typemap` in the list, it runs after the other transforms
+    assert_eq!(transform(&["namespace-map=std=core"]), "PathBuf");
+    assert_eq!(transform(&["namespace-map=std=core", "typemap"]), "PathBuf");
+    assert_eq!(
+        transform(&["typemap", "namespace-map=std=core"]),
+        "core::path::Path"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
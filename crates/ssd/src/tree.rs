@@ -0,0 +1,317 @@
+//! The compact tree of a module printed by `ssd debug --tree`:
+//! ```text
+//! module shop::orders
+//! ├─ import shop::customers::{Customer}
+//! ├─ data Order
+//! │  ├─ id: u64
+//! │  └─ items: list of Item #[optional]
+//! └─ service Orders
+//!    └─ fn get(id: u64) -> Order
+//! ```
+//! Keywords, names, types and attributes are colored, if stdout is a terminal. Comments and
+//! doc comments are left out.
+
+use std::io::{IsTerminal, Write};
+
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::ast::{Attribute, OrderedMap, SsdModule, TypeName};
+use crate::pretty::{attributes_to_string, type_to_string};
+
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    Plain,
+    Keyword,
+    Name,
+    Type,
+    Attribute,
+}
+
+impl Style {
+    fn spec(self) -> ColorSpec {
+        let mut spec = ColorSpec::new();
+        match self {
+            Style::Plain => {}
+            Style::Keyword => {
+                spec.set_fg(Some(Color::Magenta));
+            }
+            Style::Name => {
+                spec.set_bold(true);
+            }
+            Style::Type => {
+                spec.set_fg(Some(Color::Cyan));
+            }
+            Style::Attribute => {
+                spec.set_fg(Some(Color::Yellow));
+            }
+        }
+        spec
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    label: Vec<(Style, String)>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn push(mut self, style: Style, text: impl Into<String>) -> Self {
+        self.label.push((style, text.into()));
+        self
+    }
+
+    /// Starts the label with the keyword, preceded by the visibility if there is one.
+    fn element(keyword: &str, visibility: Option<impl ToString>, name: &str) -> Self {
+        let node = Node::default();
+        let node = match visibility {
+            Some(visibility) => node.push(Style::Keyword, format!("{} ", visibility.to_string())),
+            None => node,
+        };
+        node.push(Style::Keyword, format!("{keyword} "))
+            .push(Style::Name, name)
+    }
+
+    fn attributes(self, attributes: &[Attribute]) -> Self {
+        if attributes.is_empty() {
+            self
+        } else {
+            self.push(Style::Plain, " ")
+                .push(Style::Attribute, attributes_to_string(attributes))
+        }
+    }
+
+    fn children(mut self, children: Vec<Node>) -> Self {
+        self.children = children;
+        self
+    }
+
+    fn typ(self, typ: &TypeName) -> Self {
+        self.push(Style::Type, type_to_string(typ))
+    }
+
+    fn arguments(mut self, arguments: &OrderedMap<TypeName>) -> Self {
+        self = self.push(Style::Plain, "(");
+        for (i, (name, typ)) in arguments.iter().enumerate() {
+            if i > 0 {
+                self = self.push(Style::Plain, ", ");
+            }
+            self = self.push(Style::Plain, format!("{name}: ")).typ(typ);
+        }
+        self.push(Style::Plain, ")")
+    }
+}
+
+/// The nodes of the properties. Inline types are declared as data types of the module, so they
+/// aren't repeated below their property.
+fn property_nodes(properties: &OrderedMap<TypeName>) -> Vec<Node> {
+    properties
+        .iter()
+        .map(|(name, typ)| {
+            Node::default()
+                .push(Style::Name, name.as_str())
+                .push(Style::Plain, ": ")
+                .typ(typ)
+                .attributes(&typ.attributes)
+        })
+        .collect()
+}
+
+fn module_tree(module: &SsdModule) -> Node {
+    let mut root = Node::default()
+        .push(Style::Keyword, "module ")
+        .push(Style::Name, module.namespace.to_string());
+    if let Some(version) = &module.version {
+        root = root
+            .push(Style::Keyword, " version ")
+            .push(Style::Plain, format!("\"{version}\""));
+    }
+
+    for import in &module.imports {
+        let mut path = import.path.to_string();
+        if let Some(items) = &import.items {
+            path = format!("{path}::{{{}}}", items.join(", "));
+        }
+        let mut node = Node::default()
+            .push(Style::Keyword, "import ")
+            .push(Style::Type, path);
+        if let Some(alias) = &import.alias {
+            node = node
+                .push(Style::Keyword, " as ")
+                .push(Style::Name, alias.as_str());
+        }
+        root.children.push(node.attributes(&import.attributes));
+    }
+
+    for (name, data_type) in &module.data_types {
+        root.children.push(
+            Node::element("data", data_type.visibility, name)
+                .attributes(&data_type.attributes)
+                .children(property_nodes(&data_type.properties)),
+        );
+    }
+
+    for (name, en) in &module.enums {
+        let keyword = if en.is_flags { "flags" } else { "enum" };
+        let values = en
+            .values
+            .iter()
+            .map(|(name, value)| {
+                let node = Node::default().push(Style::Name, name.as_str());
+                let node = match value.formatted_value() {
+                    Some(value) => node.push(Style::Plain, format!(" = {value}")),
+                    None => node,
+                };
+                node.attributes(&value.attributes)
+            })
+            .collect();
+        root.children.push(
+            Node::element(keyword, en.visibility, name)
+                .attributes(&en.attributes)
+                .children(values),
+        );
+    }
+
+    for (name, service) in &module.services {
+        let mut children = Vec::new();
+        for dependency in &service.dependencies {
+            children.push(
+                Node::default()
+                    .push(Style::Keyword, "depends on ")
+                    .push(Style::Type, dependency.name.to_string())
+                    .attributes(&dependency.attributes),
+            );
+        }
+        for (name, function) in &service.functions {
+            let keyword = if function.is_oneway {
+                "oneway fn"
+            } else {
+                "fn"
+            };
+            let mut node =
+                Node::element(keyword, None::<String>, name).arguments(&function.arguments);
+            match (&function.return_type, &function.error_type) {
+                (Some(typ), Some(error)) => {
+                    node = node
+                        .push(Style::Plain, " -> ")
+                        .push(Style::Keyword, "result of ")
+                        .typ(typ)
+                        .push(Style::Plain, ", ")
+                        .typ(error);
+                }
+                (Some(typ), None) => node = node.push(Style::Plain, " -> ").typ(typ),
+                (None, _) => {}
+            }
+            children.push(node.attributes(&function.attributes));
+        }
+        for (name, event) in &service.events {
+            children.push(
+                Node::element("event", None::<String>, name)
+                    .arguments(&event.arguments)
+                    .attributes(&event.attributes),
+            );
+        }
+        root.children.push(
+            Node::element("service", service.visibility, name)
+                .attributes(&service.attributes)
+                .children(children),
+        );
+    }
+
+    root
+}
+
+fn write_label(out: &mut impl WriteColor, node: &Node) -> std::io::Result<()> {
+    for (style, text) in &node.label {
+        out.set_color(&style.spec())?;
+        write!(out, "{text}")?;
+    }
+    out.reset()?;
+    writeln!(out)
+}
+
+fn write_children(out: &mut impl WriteColor, node: &Node, prefix: &str) -> std::io::Result<()> {
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        write!(out, "{prefix}{}", if last { "└─ " } else { "├─ " })?;
+        write_label(out, child)?;
+        let prefix = format!("{prefix}{}", if last { "   " } else { "│  " });
+        write_children(out, child, &prefix)?;
+    }
+    Ok(())
+}
+
+/// Writes the tree of the module, see the [module documentation](self).
+pub fn write_tree(out: &mut impl WriteColor, module: &SsdModule) -> std::io::Result<()> {
+    let root = module_tree(module);
+    write_label(out, &root)?;
+    write_children(out, &root, "")
+}
+
+/// Prints the tree of the module to stdout, colored if it's a terminal.
+pub fn print_tree(module: &SsdModule) -> std::io::Result<()> {
+    let choice = if std::io::stdout().is_terminal() {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    let mut out = StandardStream::stdout(choice);
+    write_tree(&mut out, module)?;
+    out.flush()
+}
+
+#[test]
+fn test_write_tree() {
+    let module = crate::parser::parse(
+        r#"
+version "1.2.0";
+import shop::customers::{Customer};
+
+/// An order.
+data Order {
+    id: u64,
+    #[serde(rename = "lines")]
+    items: list of Item,
+    address: {
+        city: string,
+    },
+};
+
+enum State {
+    Open = 1,
+    Done = 0x2,
+};
+
+service Orders {
+    depends on shop::Customers;
+    fn get(id: u64) -> result of Order, string;
+    oneway fn touch(id: u64, state: State);
+    event Changed(id: u64);
+};
+"#,
+        crate::ast::Namespace::new("shop::orders"),
+    )
+    .unwrap();
+    let mut out = termcolor::Buffer::no_color();
+    write_tree(&mut out, &module).unwrap();
+    assert_eq!(
+        String::from_utf8(out.into_inner()).unwrap(),
+        r#"module shop::orders version "1.2.0"
+├─ import shop::customers::{Customer}
+├─ data OrderAddress
+│  └─ city: string
+├─ data Order
+│  ├─ id: u64
+│  ├─ items: list of Item #[serde(rename = "lines")]
+│  └─ address: OrderAddress
+├─ enum State
+│  ├─ Open = 1
+│  └─ Done = 0x2
+└─ service Orders
+   ├─ depends on shop::Customers
+   ├─ fn get(id: u64) -> result of Order, string
+   ├─ oneway fn touch(id: u64, state: State)
+   └─ event Changed(id: u64)
+"#
+    );
+}
@@ -0,0 +1,322 @@
+//! Checks whether raw data (e.g. a json document) has the structure of a type of a model.
+//!
+//! Data types need a map with all of their properties and no others, enums the name or the
+//! value of one of their values, lists a sequence (with the exact number of items for fixed-size
+//! lists). Builtin types (`bool`, `string`, `char`, integers and floats) are checked by their
+//! value. Types which aren't defined in the model (e.g. imported ones) accept any value.
+
+use std::fmt::{self, Display};
+
+use serde_value::Value;
+use ssd_data::{DataType, Enum, Namespace, SsdModule, TypeName, TypeRef};
+
+/// A place where the data doesn't conform to the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The JSON path of the value, e.g. `$.users[0].name`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "bool",
+        Value::U8(_)
+        | Value::U16(_)
+        | Value::U32(_)
+        | Value::U64(_)
+        | Value::I8(_)
+        | Value::I16(_)
+        | Value::I32(_)
+        | Value::I64(_) => "integer",
+        Value::F32(_) | Value::F64(_) => "float",
+        Value::Char(_) => "char",
+        Value::String(_) => "string",
+        Value::Unit | Value::Option(None) => "null",
+        Value::Option(Some(value)) | Value::Newtype(value) => kind(value),
+        Value::Seq(_) => "list",
+        Value::Map(_) => "map",
+        Value::Bytes(_) => "bytes",
+    }
+}
+
+fn as_integer(value: &Value) -> Option<i128> {
+    Some(match *value {
+        Value::U8(v) => v.into(),
+        Value::U16(v) => v.into(),
+        Value::U32(v) => v.into(),
+        Value::U64(v) => v.into(),
+        Value::I8(v) => v.into(),
+        Value::I16(v) => v.into(),
+        Value::I32(v) => v.into(),
+        Value::I64(v) => v.into(),
+        _ => return None,
+    })
+}
+
+fn integer_range(typ: &str) -> Option<(i128, i128)> {
+    Some(match typ {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" => (i64::MIN.into(), i64::MAX.into()),
+        "i128" => (i128::MIN, i128::MAX),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        "u128" => (0, i128::MAX),
+        _ => return None,
+    })
+}
+
+/// Appends the member to the path, with brackets if it isn't an identifier.
+fn member_path(path: &str, name: &str) -> String {
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!("{path}.{name}")
+    } else {
+        format!("{path}[{}]", serde_json::Value::String(name.to_owned()))
+    }
+}
+
+struct Validator<'a> {
+    module: &'a SsdModule,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, path: &str, message: String) {
+        self.violations.push(Violation {
+            path: path.to_owned(),
+            message,
+        });
+    }
+
+    fn check_type_name(&mut self, path: &str, type_name: &TypeName, value: &Value) {
+        self.check_lists(path, &type_name.typ, &type_name.lists, value);
+    }
+
+    /// Checks the value against the lists (outermost first) of the type.
+    fn check_lists(&mut self, path: &str, typ: &Namespace, lists: &[Option<usize>], value: &Value) {
+        let Some((list, inner)) = lists.split_first() else {
+            return self.check_type(path, typ, value);
+        };
+        let Value::Seq(items) = value else {
+            return self.report(path, format!("expected a list, found {}", kind(value)));
+        };
+        if let Some(count) = *list {
+            if items.len() != count {
+                self.report(
+                    path,
+                    format!("expected {count} items, found {}", items.len()),
+                );
+            }
+        }
+        for (index, item) in items.iter().enumerate() {
+            self.check_lists(&format!("{path}[{index}]"), typ, inner, item);
+        }
+    }
+
+    fn check_type(&mut self, path: &str, typ: &Namespace, value: &Value) {
+        let value = match value {
+            Value::Option(Some(value)) | Value::Newtype(value) => value,
+            value => value,
+        };
+        match self.module.find_type(typ) {
+            Some(TypeRef::DataType(data_type)) => self.check_data_type(path, typ, data_type, value),
+            Some(TypeRef::Enum(en)) => self.check_enum(path, typ, en, value),
+            None => self.check_builtin(path, typ, value),
+        }
+    }
+
+    fn check_data_type(
+        &mut self,
+        path: &str,
+        typ: &Namespace,
+        data_type: &DataType,
+        value: &Value,
+    ) {
+        let typ = typ.to_string();
+        let Value::Map(members) = value else {
+            return self.report(path, format!("expected {typ}, found {}", kind(value)));
+        };
+        for (name, property) in &data_type.properties {
+            match members.get(&Value::String(name.clone())) {
+                Some(member) => self.check_type_name(&member_path(path, name), property, member),
+                None => self.report(path, format!("missing field `{name}` of {typ}")),
+            }
+        }
+        for key in members.keys() {
+            match key {
+                Value::String(key) if data_type.properties.iter().any(|(name, _)| name == key) => {}
+                Value::String(key) => {
+                    self.report(&member_path(path, key), format!("unknown field of {typ}"))
+                }
+                key => self.report(path, format!("expected field names, found {}", kind(key))),
+            }
+        }
+    }
+
+    fn check_enum(&mut self, path: &str, typ: &Namespace, en: &Enum, value: &Value) {
+        let legal = match value {
+            Value::String(name) => en.values.iter().any(|(value_name, _)| value_name == name),
+            value => as_integer(value).is_some_and(|number| {
+                if en.is_flags {
+                    return i64::try_from(number).is_ok_and(|n| en.is_flag_combination(n));
+                }
+                en.values
+                    .iter()
+                    .any(|(_, value)| value.value.is_some_and(|v| i128::from(v) == number))
+            }),
+        };
+        if !legal {
+            let names = en
+                .values
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            let found = match value {
+                Value::String(name) => format!("\"{name}\""),
+                value => {
+                    as_integer(value).map_or_else(|| kind(value).to_owned(), |n| n.to_string())
+                }
+            };
+            self.report(
+                path,
+                format!(
+                    "expected one of {} ({}), found {found}",
+                    typ,
+                    names.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn check_builtin(&mut self, path: &str, typ: &Namespace, value: &Value) {
+        let name = typ.to_string();
+        let matches = match name.as_str() {
+            "bool" => matches!(value, Value::Bool(_)),
+            "string" | "String" => matches!(value, Value::String(_)),
+            "char" => match value {
+                Value::Char(_) => true,
+                Value::String(s) => s.chars().count() == 1,
+                _ => false,
+            },
+            "f32" | "f64" => {
+                matches!(value, Value::F32(_) | Value::F64(_)) || as_integer(value).is_some()
+            }
+            name => match integer_range(name) {
+                Some((min, max)) => match as_integer(value) {
+                    Some(number) if number < min || number > max => {
+                        return self.report(path, format!("{number} is out of range for {name}"));
+                    }
+                    Some(_) => true,
+                    None => false,
+                },
+                // not defined in the model, e.g. imported
+                None => true,
+            },
+        };
+        if !matches {
+            self.report(path, format!("expected {name}, found {}", kind(value)));
+        }
+    }
+}
+
+/// Checks the value against the type (a data type or enum of the module) and returns all
+/// violations, which is empty if the value conforms to the type.
+///
+/// # Errors
+///
+/// Returns an error if the module has no such type.
+pub fn validate(module: &SsdModule, typ: &str, value: &Value) -> anyhow::Result<Vec<Violation>> {
+    let typ = Namespace::new(typ);
+    if module.find_type(&typ).is_none() {
+        anyhow::bail!(
+            "The model has no data type or enum \"{}\".",
+            typ.to_string()
+        );
+    }
+    let mut validator = Validator {
+        module,
+        violations: Vec::new(),
+    };
+    validator.check_type("$", &typ, value);
+    Ok(validator.violations)
+}
+
+#[test]
+fn test_validate() {
+    let module = crate::parser::parse(
+        r"
+        data User {
+            name: string,
+            age: u8,
+            role: Role,
+            tags: list of string,
+            key: 4 of u8,
+            grid: list of 2 of u8,
+        };
+        enum Role { Admin, Guest = 5, };
+        flags Permission { Read, Write, Execute, };
+        ",
+        Namespace::new("users"),
+    )
+    .unwrap();
+
+    let valid: Value = serde_json::from_str(
+        r#"{"name": "a", "age": 30, "role": "Admin", "tags": [], "key": [1, 2, 3, 4], "grid": [[1, 2]]}"#,
+    )
+    .unwrap();
+    assert!(validate(&module, "User", &valid).unwrap().is_empty());
+    let numeric_enum: Value = serde_json::from_str("5").unwrap();
+    assert!(validate(&module, "Role", &numeric_enum).unwrap().is_empty());
+    // combinations of flags
+    assert!(validate(&module, "Permission", &numeric_enum)
+        .unwrap()
+        .is_empty());
+    let unknown_flag: Value = serde_json::from_str("8").unwrap();
+    assert_eq!(
+        validate(&module, "Permission", &unknown_flag)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let invalid: Value = serde_json::from_str(
+        r#"{"name": 1, "age": 300, "role": "Root", "tags": ["a", null], "key": [1], "grid": [[1, 2], [3]], "x y": 0}"#,
+    )
+    .unwrap();
+    let violations = validate(&module, "users::User", &invalid)
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        violations,
+        [
+            "$.name: expected string, found integer",
+            "$.age: 300 is out of range for u8",
+            "$.role: expected one of Role (Admin, Guest), found \"Root\"",
+            "$.tags[1]: expected string, found null",
+            "$.key: expected 4 items, found 1",
+            "$.grid[1]: expected 2 items, found 1",
+            "$[\"x y\"]: unknown field of users::User",
+        ]
+    );
+
+    let missing: Value = serde_json::from_str("{}").unwrap();
+    assert_eq!(validate(&module, "User", &missing).unwrap().len(), 6);
+    assert!(validate(&module, "Group", &missing).is_err());
+}
@@ -0,0 +1,383 @@
+//! Bindings for the browser, built for `wasm32-unknown-unknown` with the `web` feature and
+//! `wasm-bindgen`. Errors are thrown as javascript exceptions.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::str::FromStr;
+
+use serde::Serialize;
+use ssd_data::Namespace;
+use wasm_bindgen::prelude::*;
+
+use crate::generators::{handlebars, rhai, tera, wasm_web};
+use crate::helper::line_column;
+use crate::parser::{parse_limited, parse_raw_limited, ParseLimits};
+use crate::pretty::pretty as pretty_raw;
+
+/// The generators which run in the browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WebGenerator {
+    #[default]
+    Rhai,
+    Handlebars,
+    Tera,
+}
+
+impl WebGenerator {
+    pub const ALL: [WebGenerator; 3] = [
+        WebGenerator::Rhai,
+        WebGenerator::Handlebars,
+        WebGenerator::Tera,
+    ];
+
+    /// The name of the generator, like on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            WebGenerator::Rhai => "rhai",
+            WebGenerator::Handlebars => "handlebars",
+            WebGenerator::Tera => "tera",
+        }
+    }
+}
+
+impl FromStr for WebGenerator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WebGenerator::ALL
+            .into_iter()
+            .find(|generator| generator.name() == s)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Unknown generator \"{s}\". Available are: rhai, handlebars, tera.")
+            })
+    }
+}
+
+/// A problem found in a description, with its position for editors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebDiagnostic {
+    /// The byte offset of the problem in the description.
+    pub offset: usize,
+    /// The line of the problem, starting at 1.
+    pub line: usize,
+    /// The column of the problem in characters, starting at 1.
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parses the description like the generators do and returns the problems it has, which is
+/// at most the first parse error.
+pub fn diagnostics_web(content: &str) -> Vec<WebDiagnostic> {
+    let Err(error) = parse_limited(content, Namespace::new(""), &ParseLimits::default()) else {
+        return Vec::new();
+    };
+    let offset = error.offset.unwrap_or_default().min(content.len());
+    let (line, column) = line_column(content, offset);
+    vec![WebDiagnostic {
+        offset,
+        line,
+        column,
+        message: error.to_string(),
+    }]
+}
+
+/// The result of a generator in the browser.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WebOutput {
+    /// The output of the generator.
+    pub output: String,
+    /// Files emitted by rhai scripts with `emit(path, content)`, keyed by their relative path.
+    pub files: BTreeMap<String, String>,
+}
+
+impl From<String> for WebOutput {
+    fn from(output: String) -> Self {
+        Self {
+            output,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+/// Runs the generator (the source of a rhai script or a template) for the description and
+/// returns its output. `debug` enables `print` and `debug` in rhai scripts.
+pub fn generate_web(
+    generator: WebGenerator,
+    defines: HashMap<String, String>,
+    namespace: &str,
+    source: &str,
+    typemap: &str,
+    data: &str,
+    debug: bool,
+) -> Result<WebOutput, Box<dyn Error>> {
+    match generator {
+        WebGenerator::Rhai => {
+            let (output, files) =
+                rhai::generate_web(defines, namespace, source, typemap, data, debug)?;
+            Ok(WebOutput { output, files })
+        }
+        WebGenerator::Handlebars => {
+            handlebars::generate_web(defines, namespace, source, typemap, data).map(Into::into)
+        }
+        WebGenerator::Tera => {
+            tera::generate_web(defines, namespace, source, typemap, data).map(Into::into)
+        }
+    }
+}
+
+/// Runs the function of an extism plugin for the description and returns its output, like
+/// `ssd generate wasm`. Plugins can't make http requests and their log messages are dropped.
+pub async fn generate_wasm_web(
+    plugin: &[u8],
+    function: &str,
+    defines: HashMap<String, String>,
+    namespace: &str,
+    typemap: &str,
+    data: &str,
+) -> Result<String, Box<dyn Error>> {
+    wasm_web::generate_web(plugin, function, defines, namespace, typemap, data).await
+}
+
+fn js_error(e: impl ToString) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn parse_defines(defines: &str) -> Result<HashMap<String, String>, JsError> {
+    if defines.trim().is_empty() {
+        Ok(HashMap::new())
+    } else {
+        serde_json::from_str(defines).map_err(js_error)
+    }
+}
+
+/// Parses the description and returns the module serialized as json.
+#[wasm_bindgen]
+pub fn parse(content: &str, namespace: &str) -> Result<String, JsError> {
+    let module = parse_limited(content, Namespace::new(namespace), &ParseLimits::default())
+        .map_err(js_error)?;
+    serde_json::to_string(&module).map_err(js_error)
+}
+
+/// Returns the problems of the description as a json array of objects with `offset`, `line`,
+/// `column` and `message`, for markers in editors.
+#[wasm_bindgen]
+pub fn diagnostics(content: &str) -> Result<String, JsError> {
+    serde_json::to_string(&diagnostics_web(content)).map_err(js_error)
+}
+
+/// Formats the description like `ssd pretty`.
+#[wasm_bindgen]
+pub fn pretty(content: &str) -> Result<String, JsError> {
+    parse_raw_limited(content, &ParseLimits::default())
+        .map(|raw| pretty_raw(&raw))
+        .map_err(js_error)
+}
+
+/// Runs the rhai script for the description and returns its output.
+///
+/// `defines` is a json object of strings, e.g. `{"target": "web"}`, or empty.
+#[wasm_bindgen(js_name = generateRhai)]
+pub fn generate_rhai(
+    script: &str,
+    typemap: &str,
+    content: &str,
+    namespace: &str,
+    defines: &str,
+    debug: bool,
+) -> Result<String, JsError> {
+    let defines = parse_defines(defines)?;
+    rhai::generate_web(defines, namespace, script, typemap, content, debug)
+        .map(|(output, _)| output)
+        .map_err(js_error)
+}
+
+/// Like `generateRhai`, but returns a json object with the `output` and the `files` emitted with
+/// `emit(path, content)`, keyed by their path.
+#[wasm_bindgen(js_name = generateRhaiFiles)]
+pub fn generate_rhai_files(
+    script: &str,
+    typemap: &str,
+    content: &str,
+    namespace: &str,
+    defines: &str,
+    debug: bool,
+) -> Result<String, JsError> {
+    let defines = parse_defines(defines)?;
+    let output = generate_web(
+        WebGenerator::Rhai,
+        defines,
+        namespace,
+        script,
+        typemap,
+        content,
+        debug,
+    )
+    .map_err(js_error)?;
+    serde_json::to_string(&output).map_err(js_error)
+}
+
+/// Renders the handlebars template for the description, `defines` like for `generateRhai`.
+#[wasm_bindgen(js_name = generateHandlebars)]
+pub fn generate_handlebars(
+    template: &str,
+    typemap: &str,
+    content: &str,
+    namespace: &str,
+    defines: &str,
+) -> Result<String, JsError> {
+    let defines = parse_defines(defines)?;
+    handlebars::generate_web(defines, namespace, template, typemap, content).map_err(js_error)
+}
+
+/// Renders the tera template for the description, `defines` like for `generateRhai`.
+#[wasm_bindgen(js_name = generateTera)]
+pub fn generate_tera(
+    template: &str,
+    typemap: &str,
+    content: &str,
+    namespace: &str,
+    defines: &str,
+) -> Result<String, JsError> {
+    let defines = parse_defines(defines)?;
+    tera::generate_web(defines, namespace, template, typemap, content).map_err(js_error)
+}
+
+/// Runs the function (`generate` if empty) of the extism plugin for the description and returns
+/// a promise with its output, `defines` like for `generateRhai`.
+#[wasm_bindgen(js_name = generateWasm)]
+pub async fn generate_wasm(
+    plugin: Vec<u8>,
+    function: String,
+    typemap: String,
+    content: String,
+    namespace: String,
+    defines: String,
+) -> Result<String, JsError> {
+    let defines = parse_defines(&defines)?;
+    let function = if function.is_empty() {
+        crate::generators::DEFAULT_FUNCTION
+    } else {
+        &function
+    };
+    generate_wasm_web(&plugin, function, defines, &namespace, &typemap, &content)
+        .await
+        .map_err(js_error)
+}
+
+#[test]
+fn test_web() {
+    let content = "data Rect { x: i32, };";
+    assert!(parse(content, "shapes").ok().unwrap().contains("\"Rect\""));
+    assert_eq!(
+        pretty(content).ok().unwrap(),
+        pretty_raw(&crate::parser::parse_raw(content).unwrap())
+    );
+    let output = generate_rhai(
+        r#"for dt in module.data_types { emit(dt.first + defines.suffix + NL); }"#,
+        "",
+        content,
+        "shapes",
+        r#"{"suffix": "!"}"#,
+        false,
+    )
+    .ok()
+    .unwrap();
+    assert_eq!(output, "Rect!\n");
+
+    let typemap = "i32 = \"int\"";
+    let defines = HashMap::from([("suffix".to_owned(), "!".to_owned())]);
+    let run = |generator, template| {
+        generate_web(
+            generator,
+            defines.clone(),
+            "shapes",
+            template,
+            typemap,
+            content,
+            false,
+        )
+        .unwrap()
+        .output
+    };
+    assert_eq!(
+        run(
+            WebGenerator::Handlebars,
+            "{{#each module.data_types}}{{this.[0]}}{{@root.defines.suffix}} {{map_type \"i32\"}}{{/each}}"
+        ),
+        "Rect! int"
+    );
+    assert_eq!(
+        run(
+            WebGenerator::Tera,
+            "{% for pair in module.data_types %}{{ pair.0 }}{{ defines.suffix }} {{ 'i32' | map_type }}{% endfor %}"
+        ),
+        "Rect! int"
+    );
+
+    let output: serde_json::Value = serde_json::from_str(
+        &generate_rhai_files(
+            r#"emit("header"); for dt in module.data_types { emit(dt.first + ".h", "struct"); }"#,
+            "",
+            content,
+            "shapes",
+            "",
+            false,
+        )
+        .ok()
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        output,
+        serde_json::json!({"output": "header", "files": {"Rect.h": "struct"}})
+    );
+
+    assert!(diagnostics_web(content).is_empty());
+    let broken = "data Rect {\n    x: i32\n};";
+    let found = diagnostics_web(broken);
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].line, found[0].column), (3, 1));
+    assert_eq!(found[0].offset, broken.find("};").unwrap());
+
+    for generator in WebGenerator::ALL {
+        assert_eq!(generator.name().parse::<WebGenerator>().unwrap(), generator);
+    }
+    let error = generate_web(
+        WebGenerator::Tera,
+        HashMap::new(),
+        "shapes",
+        "{{ missing }}",
+        "",
+        content,
+        false,
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("caused by: Variable `missing` not found"));
+}
+
+#[test]
+fn test_diagnostics_web() {
+    // columns count characters, offsets bytes
+    let content = "#[derive(x = \"Maß\")] data Rect { x: i32 };";
+    let found = diagnostics_web(content);
+    assert_eq!(found.len(), 1, "{found:?}");
+    let offset = content.find('}').unwrap();
+    assert_eq!(found[0].offset, offset);
+    assert_eq!((found[0].line, found[0].column), (1, offset));
+
+    // errors at the end of the description point right after it
+    let content = "data Rect {\n    x: i32,\n";
+    let found = diagnostics_web(content);
+    assert_eq!(found[0].offset, content.len());
+    assert_eq!((found[0].line, found[0].column), (3, 1));
+
+    // duplicates point at the second definition
+    let found = diagnostics_web("data Rect {};\ndata Rect {};");
+    assert_eq!(
+        (found[0].offset, found[0].line, found[0].column),
+        (14, 2, 1)
+    );
+    assert!(found[0].message.contains("Rect"), "{found:?}");
+}
@@ -0,0 +1,100 @@
+//! XML output of the `data` generator.
+//!
+//! The value is converted element by element: object members become elements named like the
+//! key (or `<entry key="...">` if the key isn't a valid XML name), array items become `<item>`
+//! elements and everything else becomes text. `null` is an empty element.
+
+use serde_json::Value;
+
+const ROOT: &str = "model";
+const INDENT: &str = "  ";
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '&' => result.push_str("&amp;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_ascii_lowercase().starts_with("xml")
+}
+
+fn write_element(out: &mut String, depth: usize, tag: &str, attribute: &str, value: &Value) {
+    let indent = INDENT.repeat(depth);
+    out.push_str(&format!("{indent}<{tag}{attribute}"));
+    match value {
+        Value::Null => out.push_str("/>\n"),
+        Value::Bool(b) => out.push_str(&format!(">{b}</{tag}>\n")),
+        Value::Number(n) => out.push_str(&format!(">{n}</{tag}>\n")),
+        Value::String(s) => out.push_str(&format!(">{}</{tag}>\n", escape(s))),
+        Value::Array(items) if items.is_empty() => out.push_str("/>\n"),
+        Value::Object(members) if members.is_empty() => out.push_str("/>\n"),
+        Value::Array(items) => {
+            out.push_str(">\n");
+            for item in items {
+                write_element(out, depth + 1, "item", "", item);
+            }
+            out.push_str(&format!("{indent}</{tag}>\n"));
+        }
+        Value::Object(members) => {
+            out.push_str(">\n");
+            for (key, member) in members {
+                if is_name(key) {
+                    write_element(out, depth + 1, key, "", member);
+                } else {
+                    let attribute = format!(" key=\"{}\"", escape(key));
+                    write_element(out, depth + 1, "entry", &attribute, member);
+                }
+            }
+            out.push_str(&format!("{indent}</{tag}>\n"));
+        }
+    }
+}
+
+/// Converts the value into an XML document with the root element `<model>`.
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut out, 0, ROOT, "", value);
+    out
+}
+
+#[test]
+fn test_xml() {
+    let value = serde_json::json!({
+        "name": "a < b",
+        "list": [1, true, null],
+        "empty": [],
+        "with space": {"x": "y"},
+    });
+    assert_eq!(
+        to_string(&value),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<model>
+  <name>a &lt; b</name>
+  <list>
+    <item>1</item>
+    <item>true</item>
+    <item/>
+  </list>
+  <empty/>
+  <entry key="with space">
+    <x>y</x>
+  </entry>
+</model>
+"#
+    );
+}
@@ -0,0 +1,15 @@
+emit("namespace " .. module.name:join("::") .. " {" .. NL .. NL)
+
+for _, data_type in ipairs(module.data_types) do
+    emit("struct " .. data_type.first .. " {" .. NL)
+    for _, property in ipairs(data_type.second.properties) do
+        local typ = property.second.type:join("::")
+        for _, count in ipairs(property.second.lists) do
+            typ = typ .. "[" .. (count or "") .. "]"
+        end
+        emit("    " .. typ .. " " .. property.first .. ";" .. NL)
+    end
+    emit("};" .. NL .. NL)
+end
+
+emit("}" .. NL)
@@ -0,0 +1,268 @@
+use dioxus::prelude::*;
+use ssd::WebDiagnostic;
+
+pub const STYLE: &str = r#"
+.editor {
+    position: relative;
+    overflow-x: auto;
+    margin-bottom: 10px;
+    border: 1px solid #ddd;
+    background: white;
+}
+
+.editor pre, .editor textarea {
+    font-family: monospace;
+    font-size: 14px;
+    line-height: 1.5;
+    padding: 10px;
+    margin: 0;
+    border: none;
+    white-space: pre;
+    box-sizing: border-box;
+    tab-size: 4;
+}
+
+.editor textarea {
+    position: absolute;
+    top: 0;
+    left: 0;
+    width: 100%;
+    height: 100%;
+    resize: none;
+    overflow: hidden;
+    color: transparent;
+    background: transparent;
+    caret-color: #333;
+}
+
+.editor .comment { color: #6a737d; }
+.editor .doc { color: #22863a; }
+.editor .keyword { color: #d73a49; font-weight: bold; }
+.editor .type { color: #6f42c1; }
+.editor .attribute { color: #e36209; }
+.editor .string { color: #032f62; }
+.editor .number { color: #005cc5; }
+.editor .error { text-decoration: underline wavy red; }
+
+.diagnostics {
+    color: #cb2431;
+    margin: -5px 0 10px 0;
+    white-space: pre-wrap;
+}
+"#;
+
+const KEYWORDS: &[&str] = &[
+    "as", "data", "depends", "enum", "event", "flags", "fn", "handles", "import", "internal",
+    "list", "of", "on", "oneway", "pub", "result", "service", "version",
+];
+
+/// Splits a description into tokens with the css class they are highlighted with.
+///
+/// This is only meant for highlighting, so it accepts everything and doesn't need to agree with
+/// the parser on invalid input.
+pub fn highlight(content: &str) -> Vec<(&'static str, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = content;
+    let mut in_attribute = false;
+    // the class of the next identifier, e.g. a type after `:`
+    let mut next_ident = "";
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("//") || rest.starts_with("/*") {
+            if rest.starts_with("/*") {
+                rest.find("*/").map_or(rest.len(), |end| end + 2)
+            } else {
+                rest.find('\n').unwrap_or(rest.len())
+            }
+        } else if c == '"' {
+            let mut escaped = false;
+            rest[1..]
+                .find(|c| {
+                    let end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    end
+                })
+                .map_or(rest.len(), |end| end + 2)
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len())
+        } else if c.is_whitespace() {
+            rest.find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len())
+        } else if ["#[", "::", "->"].iter().any(|t| rest.starts_with(t)) {
+            2
+        } else {
+            c.len_utf8()
+        };
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+
+        let class = if token.starts_with("///") {
+            "doc"
+        } else if token.starts_with("//") || token.starts_with("/*") {
+            "comment"
+        } else if token.starts_with('"') {
+            "string"
+        } else if token.starts_with(|c: char| c.is_ascii_digit()) {
+            "number"
+        } else if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            if in_attribute {
+                "attribute"
+            } else if KEYWORDS.contains(&token) {
+                match token {
+                    "of" => next_ident = "type",
+                    // `list of` and `result of` are part of the type
+                    "list" | "result" => {}
+                    _ => next_ident = "",
+                }
+                "keyword"
+            } else {
+                let class = next_ident;
+                next_ident = "";
+                class
+            }
+        } else if token == "#[" {
+            in_attribute = true;
+            "attribute"
+        } else if token == "]" && in_attribute {
+            in_attribute = false;
+            "attribute"
+        } else {
+            if !in_attribute && !token.trim().is_empty() {
+                next_ident = match token {
+                    ":" | "->" => "type",
+                    // keeps the class for the rest of the path
+                    "::" => tokens
+                        .iter()
+                        .rev()
+                        .find(|(_, t): &&(_, &str)| !t.trim().is_empty())
+                        .map_or("", |(class, _)| *class),
+                    _ => "",
+                };
+            }
+            ""
+        };
+        tokens.push((class, token));
+    }
+    tokens
+}
+
+/// Adds the `error` class to the tokens the diagnostics point into.
+fn mark_errors<'a>(
+    tokens: Vec<(&str, &'a str)>,
+    diagnostics: &[WebDiagnostic],
+) -> Vec<(String, &'a str)> {
+    let mut offset = 0;
+    tokens
+        .into_iter()
+        .map(|(class, token)| {
+            let start = offset;
+            offset += token.len();
+            let marked = diagnostics
+                .iter()
+                .any(|d| d.offset >= start && d.offset < offset);
+            let class = if marked {
+                format!("{class} error")
+            } else {
+                class.to_string()
+            };
+            (class, token)
+        })
+        .collect()
+}
+
+/// A text area with highlighting and markers for the diagnostics of descriptions.
+///
+/// The highlighted text is rendered below a transparent text area of the same size, which grows
+/// with its content instead of scrolling, so both always line up. Long lines scroll the whole
+/// editor.
+#[component]
+pub fn Editor<'a>(
+    cx: Scope<'a>,
+    value: &'a str,
+    #[props(default)] ssd: bool,
+    oninput: EventHandler<'a, String>,
+) -> Element<'a> {
+    let diagnostics = if *ssd {
+        ssd::diagnostics_web(value)
+    } else {
+        Vec::new()
+    };
+    let tokens = if *ssd {
+        highlight(value)
+    } else {
+        vec![("", *value)]
+    };
+    let spans = mark_errors(tokens, &diagnostics);
+    // errors at the end of the description get marked on an extra space
+    let marked_end = diagnostics.iter().any(|d| d.offset >= value.len());
+
+    let lines = value.lines().count().max(1) + usize::from(value.ends_with('\n'));
+    let columns = value.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let size =
+        format!("height: calc({lines} * 1.5em + 20px); min-width: calc({columns}ch + 20px);");
+
+    cx.render(rsx! {
+        div { class: "editor",
+            pre { style: "{size}",
+                for (class, token) in spans {
+                    span { class: "{class}", "{token}" }
+                }
+                if marked_end {
+                    rsx! { span { class: "error", " " } }
+                }
+            }
+            textarea {
+                style: "{size}",
+                spellcheck: "false",
+                value: "{value}",
+                oninput: move |e| oninput.call(e.value.clone()),
+            }
+        }
+        for WebDiagnostic { line, column, message, .. } in diagnostics {
+            div { class: "diagnostics", "line {line}, column {column}: {message}" }
+        }
+    })
+}
+
+#[test]
+fn test_highlight() {
+    let content = "/// A point.\n#[derive(x = \"]\")]\ndata P { x: list of my::I32, };";
+    let tokens = highlight(content);
+    assert_eq!(
+        tokens.iter().map(|(_, token)| *token).collect::<String>(),
+        content
+    );
+    let class_of = |token: &str| {
+        tokens
+            .iter()
+            .filter(|(_, t)| *t == token)
+            .map(|(class, _)| *class)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(class_of("/// A point."), ["doc"]);
+    assert_eq!(class_of("derive"), ["attribute"]);
+    assert_eq!(class_of("\"]\""), ["string"]);
+    assert_eq!(class_of("data"), ["keyword"]);
+    assert_eq!(class_of("P"), [""]);
+    assert_eq!(class_of("x"), ["attribute", ""]);
+    assert_eq!(class_of("list"), ["keyword"]);
+    assert_eq!(class_of("of"), ["keyword"]);
+    assert_eq!(class_of("my"), ["type"]);
+    assert_eq!(class_of("I32"), ["type"]);
+}
+
+#[test]
+fn test_mark_errors() {
+    let content = "data Rect {\n    x: i32\n};";
+    let diagnostics = ssd::diagnostics_web(content);
+    let spans = mark_errors(highlight(content), &diagnostics);
+    let marked = spans
+        .iter()
+        .filter(|(class, _)| class.ends_with("error"))
+        .map(|(class, token)| (class.as_str(), *token))
+        .collect::<Vec<_>>();
+    assert_eq!(marked, [(" error", "}")]);
+    assert!(mark_errors(highlight(content), &[])
+        .iter()
+        .all(|(class, _)| !class.contains("error")));
+}
@@ -1,7 +1,13 @@
 use dioxus::prelude::*;
+use editor::Editor;
+use ssd::WebGenerator;
 use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
 use web_sys::window;
 
+mod editor;
+
 fn main() {
     dioxus_web::launch(app);
 }
@@ -111,7 +117,7 @@ a:hover {
     font-weight: bold;
 }
 
-.playground input[type='text'], .playground textarea {
+.playground input[type='text'], .playground textarea, .playground select {
     width: 100%;
     padding: 10px;
     margin-bottom: 10px;
@@ -136,20 +142,174 @@ a:hover {
     padding: 10px;
     border: 1px solid #ddd;
 }
+
+.playground .tabs {
+    margin-top: 10px;
+}
+
+.playground .tabs button {
+    background: #ddd;
+    color: #333;
+    margin-right: 5px;
+}
+
+.playground .tabs button.selected, .playground .tabs button:hover {
+    background: #0056b3;
+    color: white;
+}
+
+.playground .actions {
+    margin: 10px 0;
+}
+
+.playground .actions a, .playground .actions button {
+    display: inline-block;
+    background: #6c757d;
+    color: white;
+    padding: 5px 10px;
+    margin-right: 5px;
+    font-size: 14px;
+}
 "#;
 
+/// A curated pair of data file and generator, bundled into the playground at build time.
+struct Example {
+    name: &'static str,
+    namespace: &'static str,
+    data: &'static str,
+    generator: WebGenerator,
+    source: &'static str,
+    typemap: &'static str,
+    /// The extension of the file the output gets downloaded as.
+    extension: &'static str,
+}
+
+const EXAMPLES: &[Example] = &[
+    Example {
+        name: "C++-like header (rhai)",
+        namespace: "my::namespace",
+        data: include_str!("../data/test.svc"),
+        generator: WebGenerator::Rhai,
+        source: include_str!("../example-generators/cpp-like.rhai"),
+        typemap: include_str!("../example-generators/cpp-like.tym"),
+        extension: "h",
+    },
+    Example {
+        name: "Markdown docs (handlebars)",
+        namespace: "shop",
+        data: include_str!("../data/shop.svc"),
+        generator: WebGenerator::Handlebars,
+        source: include_str!("../example-generators/markdown.hbs"),
+        typemap: "",
+        extension: "md",
+    },
+    Example {
+        name: "JSON schema (tera)",
+        namespace: "shop",
+        data: include_str!("../data/shop.svc"),
+        generator: WebGenerator::Tera,
+        source: include_str!("../example-generators/json-schema.tera"),
+        typemap: include_str!("../example-generators/json-schema.tym"),
+        extension: "json",
+    },
+];
+
+/// The source the playground starts with for the generator.
+fn example_source(generator: WebGenerator) -> &'static str {
+    EXAMPLES
+        .iter()
+        .find(|example| example.generator == generator)
+        .map(|example| example.source)
+        .unwrap_or_default()
+}
+
+/// Downloads a wasm plugin, the server has to allow cross-origin requests.
+async fn fetch_plugin(url: &str) -> Result<Vec<u8>, String> {
+    let window = window().ok_or("There is no window to fetch the plugin with.")?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .and_then(|response| response.dyn_into())
+        .map_err(|e| format!("Fetching {url} failed: {e:?}"))?;
+    if !response.ok() {
+        return Err(format!(
+            "Fetching {url} failed with status {}.",
+            response.status()
+        ));
+    }
+    let buffer = response
+        .array_buffer()
+        .map(JsFuture::from)
+        .map_err(|e| format!("Reading {url} failed: {e:?}"))?
+        .await
+        .map_err(|e| format!("Reading {url} failed: {e:?}"))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// The name the output gets downloaded as, e.g. `my_namespace.h` for `my::namespace`.
+fn output_name(namespace: &str, extension: &str) -> String {
+    let name = namespace
+        .split("::")
+        .map(str::trim)
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    let name = if name.is_empty() { "output" } else { &name };
+    format!("{name}.{extension}")
+}
+
+async fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    use js_sys::{Function, Promise, Reflect};
+
+    let window = window().ok_or("There is no window to copy with.")?;
+    let navigator = Reflect::get(&window, &"navigator".into()).map_err(|e| format!("{e:?}"))?;
+    let clipboard = Reflect::get(&navigator, &"clipboard".into()).map_err(|e| format!("{e:?}"))?;
+    let write_text: Function = Reflect::get(&clipboard, &"writeText".into())
+        .and_then(|f| f.dyn_into())
+        .map_err(|_| "The browser doesn't allow to copy to the clipboard.")?;
+    let promise: Promise = write_text
+        .call1(&clipboard, &text.into())
+        .and_then(|promise| promise.dyn_into())
+        .map_err(|e| format!("{e:?}"))?;
+    JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Copying failed: {e:?}"))
+}
+
+fn source_label(generator: WebGenerator) -> &'static str {
+    match generator {
+        WebGenerator::Rhai => "Rhai Code: ",
+        WebGenerator::Handlebars => "Handlebars Template: ",
+        WebGenerator::Tera => "Tera Template: ",
+    }
+}
+
 fn app(cx: Scope) -> Element {
-    let result = use_state(cx, || Option::<String>::None);
-    let namespace = use_state(&cx, || "my::namespace".to_string());
-    let data = use_state(&cx, || include_str!("../data/test.svc").to_string());
-    let rhai_code = use_state(&cx, || {
-        include_str!("../example-generators/cpp-like.rhai").to_string()
-    });
-    let type_mappings = use_state(&cx, || {
-        include_str!("../example-generators/cpp-like.tym").to_string()
+    let result = use_state(cx, || Option::<Result<ssd::WebOutput, String>>::None);
+    // the emitted file which is shown, the output of the generator if none
+    let tab = use_state(cx, || Option::<String>::None);
+    let copied = use_state(cx, || false);
+    let extension = use_state(cx, || EXAMPLES[0].extension);
+    let example = use_state(cx, || EXAMPLES[0].name);
+    let namespace = use_state(cx, || EXAMPLES[0].namespace.to_string());
+    let data = use_state(cx, || EXAMPLES[0].data.to_string());
+    let generator = use_state(cx, || EXAMPLES[0].generator);
+    // every generator keeps its own source, so switching back and forth doesn't lose edits
+    let sources = use_state(cx, || {
+        WebGenerator::ALL
+            .into_iter()
+            .map(|generator| (generator, example_source(generator).to_string()))
+            .collect::<HashMap<_, _>>()
     });
+    let type_mappings = use_state(cx, || EXAMPLES[0].typemap.to_string());
 
-    let debug_mode = use_state(&cx, || false);
+    // the playground runs wasm plugins instead of the selected generator
+    let use_plugin = use_state(cx, || false);
+    let plugin = use_state(cx, || Option::<(String, Vec<u8>)>::None);
+    let plugin_url = use_state(cx, String::new);
+    let plugin_function = use_state(cx, || "generate".to_string());
+
+    let debug_mode = use_state(cx, || false);
 
     let scroll_to_section = |id: String| {
         if let Some(window) = window() {
@@ -166,6 +326,7 @@ fn app(cx: Scope) -> Element {
 
     cx.render(rsx!(
         style {{ STYLE }}
+        style {{ editor::STYLE }}
         nav { class: "navbar",
             div {
                 button { onclick: move |_| scroll_to_section("history".to_string()), "History" }
@@ -265,6 +426,30 @@ natural choice, as the whole thing is written in rust itself as well."#r }
             section { id: "playground", class: "playground",
                 h2 { "Playground" }
                 form {
+                    label { "Example: " }
+                    select {
+                        onchange: |e| {
+                            if let Some(selected) = EXAMPLES.iter().find(|example| example.name == e.value) {
+                                example.set(selected.name);
+                                namespace.set(selected.namespace.to_string());
+                                data.set(selected.data.to_string());
+                                generator.set(selected.generator);
+                                use_plugin.set(false);
+                                sources.make_mut().insert(selected.generator, selected.source.to_string());
+                                type_mappings.set(selected.typemap.to_string());
+                                extension.set(selected.extension);
+                                result.set(None);
+                            }
+                        },
+                        for option in EXAMPLES {
+                            option {
+                                value: "{option.name}",
+                                selected: *example.get() == option.name,
+                                "{option.name}"
+                            }
+                        }
+                    }
+
                     label { "Namespace: " }
                     input {
                         r#type: "text",
@@ -273,36 +458,135 @@ natural choice, as the whole thing is written in rust itself as well."#r }
                     }
 
                     label { "Custom Data Format: " }
-                    textarea {
-                        rows: "25",
-                        value: "{data}",
-                        oninput: |e| data.set(e.value.clone()),
+                    Editor {
+                        value: data.get(),
+                        ssd: true,
+                        oninput: move |value| data.set(value),
                     }
 
-                    label { "Rhai Code: " }
-                    textarea {
-                        rows: "25",
-                        value: "{rhai_code}",
-                        oninput: |e| rhai_code.set(e.value.clone()),
+                    label { "Generator: " }
+                    select {
+                        onchange: |e| {
+                            use_plugin.set(e.value == "wasm");
+                            if let Ok(selected) = e.value.parse() {
+                                generator.set(selected);
+                            }
+                        },
+                        for option in WebGenerator::ALL {
+                            option {
+                                value: "{option.name()}",
+                                selected: !*use_plugin.get() && *generator.get() == option,
+                                "{option.name()}"
+                            }
+                        }
+                        option { value: "wasm", selected: *use_plugin.get(), "wasm" }
+                    }
+
+                    if *use_plugin.get() {
+                        rsx! {
+                            label { "Wasm Plugin (extism): " }
+                            input {
+                                r#type: "file",
+                                accept: ".wasm",
+                                onchange: move |e| {
+                                    if let Some(files) = e.files.clone() {
+                                        to_owned![plugin];
+                                        cx.spawn(async move {
+                                            for name in files.files() {
+                                                if let Some(bytes) = files.read_file(&name).await {
+                                                    plugin.set(Some((name, bytes)));
+                                                }
+                                            }
+                                        });
+                                    }
+                                },
+                            }
+                            input {
+                                r#type: "text",
+                                placeholder: "or fetch it from a url",
+                                value: "{plugin_url}",
+                                oninput: |e| plugin_url.set(e.value.clone()),
+                            }
+                            button {
+                                prevent_default: "onclick",
+                                onclick: move |_| {
+                                    to_owned![plugin, plugin_url, result];
+                                    cx.spawn(async move {
+                                        let url = plugin_url.get().trim().to_string();
+                                        match fetch_plugin(&url).await {
+                                            Ok(bytes) => plugin.set(Some((url, bytes))),
+                                            Err(e) => result.set(Some(Err(e))),
+                                        }
+                                    });
+                                },
+                                "Fetch"
+                            }
+                            p {
+                                match plugin.get() {
+                                    Some((name, bytes)) => format!("Loaded {name} ({} bytes)", bytes.len()),
+                                    None => "No plugin loaded yet.".to_string(),
+                                }
+                            }
+                            label { "Function: " }
+                            input {
+                                r#type: "text",
+                                value: "{plugin_function}",
+                                oninput: |e| plugin_function.set(e.value.clone()),
+                            }
+                        }
+                    } else {
+                        rsx! {
+                            label { source_label(*generator.get()) }
+                            Editor {
+                                value: &sources.get()[generator.get()],
+                                oninput: move |value| {
+                                    sources.make_mut().insert(*generator.get(), value);
+                                },
+                            }
+                        }
                     }
 
                     label { "Type Mappings: " }
-                    textarea {
-                        rows: "5",
-                        value: "{type_mappings}",
-                        oninput: |e| type_mappings.set(e.value.clone()),
+                    Editor {
+                        value: type_mappings.get(),
+                        oninput: move |value| type_mappings.set(value),
                     }
 
                     button {
                         prevent_default: "onclick",
-                        onclick: |_| {
+                        onclick: move |_| {
+                            tab.set(None);
+                            copied.set(false);
+                            if *use_plugin.get() {
+                                let Some((_, bytes)) = plugin.get().clone() else {
+                                    result.set(Some(Err("Upload or fetch a plugin first.".to_string())));
+                                    return;
+                                };
+                                to_owned![data, namespace, type_mappings, plugin_function, result];
+                                cx.spawn(async move {
+                                    let output = ssd::generate_wasm_web(
+                                        &bytes,
+                                        plugin_function.get().trim(),
+                                        HashMap::default(),
+                                        namespace.get().trim(),
+                                        type_mappings.get().trim(),
+                                        data.get().trim(),
+                                    )
+                                    .await;
+                                    match output {
+                                        Ok(r) => result.set(Some(Ok(r.into()))),
+                                        Err(e) => result.set(Some(Err(format!("{e}")))),
+                                    }
+                                });
+                                return;
+                            }
                             let data = data.get().trim();
                             let namespace = namespace.get().trim();
                             let typemap = type_mappings.get().trim();
-                            let rhai = rhai_code.get().trim();
-                            match ssd::generate_web(HashMap::default(), &namespace, &rhai, &typemap, &data, *debug_mode.get()) {
-                                Ok(r) => result.set(Some(dbg!(r))),
-                                Err(e) => result.set(Some(format!("{e}"))),
+                            let source = sources.get()[generator.get()].trim();
+                            match ssd::generate_web(*generator.get(), HashMap::default(), namespace, source, typemap, data, *debug_mode.get()) {
+                                Ok(r) => result.set(Some(Ok(r))),
+                                Err(e) => result.set(Some(Err(format!("{e}")))),
                             }
                         },
                         "Run"
@@ -316,10 +600,126 @@ natural choice, as the whole thing is written in rust itself as well."#r }
                     //     }
                     // }
 
-                    // Placeholder for the output box, initially hidden
-                    div { id: "output", style: if result.is_none() { "display: none;" } else { "" }, pre { result.get().clone().unwrap_or_default()} }
+                    match result.get() {
+                        None => rsx! { "" },
+                        Some(Err(e)) => rsx! { div { id: "output", pre { "{e}" } } },
+                        Some(Ok(output)) => {
+                            let main_name = output_name(namespace.get(), extension.get());
+                            // the output of the generator gets a tab as well, unless it's empty
+                            let mut tabs = Vec::new();
+                            if !output.output.is_empty() || output.files.is_empty() {
+                                tabs.push((None, main_name.clone()));
+                            }
+                            tabs.extend(output.files.keys().map(|path| (Some(path.clone()), path.clone())));
+                            let selected = tab
+                                .get()
+                                .clone()
+                                .filter(|path| output.files.contains_key(path))
+                                .or_else(|| tabs[0].0.clone());
+                            let (name, content) = match &selected {
+                                Some(path) => (
+                                    path.rsplit('/').next().unwrap_or(path).to_string(),
+                                    output.files[path].clone(),
+                                ),
+                                None => (main_name, output.output.clone()),
+                            };
+                            let href = String::from(js_sys::encode_uri_component(&content));
+                            rsx! {
+                                if tabs.len() > 1 {
+                                    rsx! {
+                                        div { class: "tabs",
+                                            for (path, label) in tabs {
+                                                button {
+                                                    class: if path == selected { "selected" } else { "" },
+                                                    prevent_default: "onclick",
+                                                    onclick: move |_| {
+                                                        tab.set(path.clone());
+                                                        copied.set(false);
+                                                    },
+                                                    "{label}"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                div { class: "actions",
+                                    a {
+                                        href: "data:text/plain;charset=utf-8,{href}",
+                                        download: "{name}",
+                                        "Download"
+                                    }
+                                    button {
+                                        prevent_default: "onclick",
+                                        onclick: move |_| {
+                                            to_owned![content, copied, result];
+                                            cx.spawn(async move {
+                                                match copy_to_clipboard(&content).await {
+                                                    Ok(()) => copied.set(true),
+                                                    Err(e) => result.set(Some(Err(e))),
+                                                }
+                                            });
+                                        },
+                                        if *copied.get() { "Copied" } else { "Copy" }
+                                    }
+                                }
+                                div { id: "output", pre { "{content}" } }
+                            }
+                        }
+                    }
                 }
             }
         }
     ))
 }
+
+#[test]
+fn test_examples() {
+    for example in EXAMPLES {
+        let output = ssd::generate_web(
+            example.generator,
+            HashMap::default(),
+            example.namespace,
+            example.source,
+            example.typemap,
+            example.data,
+            false,
+        )
+        .unwrap_or_else(|e| panic!("{}: {e}", example.name));
+        // every example documents the first data type of its description
+        let first = example.data.split("data ").nth(1).unwrap();
+        let first = first.split_whitespace().next().unwrap();
+        assert!(output.output.contains(first), "{}", example.name);
+        assert!(ssd::diagnostics_web(example.data).is_empty());
+    }
+    for generator in WebGenerator::ALL {
+        assert!(!example_source(generator).is_empty());
+    }
+}
+
+#[test]
+fn test_output_name() {
+    assert_eq!(output_name("my::namespace", "h"), "my_namespace.h");
+    assert_eq!(output_name(" shop ", "md"), "shop.md");
+    assert_eq!(output_name("::a:: ::b", "rs"), "a_b.rs");
+    assert_eq!(output_name("", "json"), "output.json");
+
+    // files emitted by rhai scripts get their own tabs, in the order of their paths
+    let output = ssd::generate_web(
+        WebGenerator::Rhai,
+        HashMap::default(),
+        "shop",
+        r#"emit("src/b.rs", "b"); emit("a.rs", "a"); emit("src/b.rs", "!");"#,
+        "",
+        "data Product {};",
+        false,
+    )
+    .unwrap();
+    assert_eq!(output.output, "");
+    assert_eq!(
+        output.files.into_iter().collect::<Vec<_>>(),
+        [
+            ("a.rs".to_owned(), "a".to_owned()),
+            ("src/b.rs".to_owned(), "b!".to_owned())
+        ]
+    );
+}